@@ -0,0 +1,182 @@
+// Compares the occupancy bitmap layout of SecondaryMap with the enum slot
+// layout it replaced, which is reproduced below as `EnumSecondaryMap`.
+//
+// Run with `cargo bench --bench secondary`.
+
+#![feature(test)]
+
+extern crate test;
+
+use std::mem;
+
+use pi_key_alloter::{is_older_version, Key, KeyData};
+use pi_null::Null;
+use pi_slotmap::{DefaultKey, SecondaryMap, SlotMap};
+use test::{black_box, Bencher};
+
+const N: usize = 100_000;
+
+// The previous layout: one tagged slot per primary slot, iterated one slot at
+// a time. Insertion and lookup follow the code it was replaced by.
+enum Slot<V> {
+    Occupied { value: V, version: u32 },
+    Vacant,
+}
+
+impl<V> Slot<V> {
+    fn version(&self) -> u32 {
+        match self {
+            Slot::Occupied { version, .. } => *version,
+            Slot::Vacant => u32::MAX,
+        }
+    }
+}
+
+struct EnumSecondaryMap<V> {
+    slots: Vec<Slot<V>>,
+    num_elems: usize,
+}
+
+impl<V> EnumSecondaryMap<V> {
+    fn new() -> Self {
+        Self {
+            slots: vec![Slot::Vacant],
+            num_elems: 0,
+        }
+    }
+
+    fn insert(&mut self, key: DefaultKey, value: V) -> Option<V> {
+        if key.is_null() {
+            return None;
+        }
+
+        let kd = key.data();
+        self.slots
+            .extend((self.slots.len()..=kd.index() as usize).map(|_| Slot::Vacant));
+
+        let slot = &mut self.slots[kd.index() as usize];
+        if let Slot::Occupied { value: old, version } = slot {
+            if *version == kd.version() {
+                return Some(mem::replace(old, value));
+            }
+            if is_older_version(kd.version(), *version) {
+                return None;
+            }
+        } else {
+            self.num_elems += 1;
+        }
+
+        *slot = Slot::Occupied {
+            value,
+            version: kd.version(),
+        };
+        None
+    }
+
+    fn get(&self, key: DefaultKey) -> Option<&V> {
+        let kd = key.data();
+        match self.slots.get(kd.index() as usize) {
+            Some(slot @ Slot::Occupied { value, .. }) if slot.version() == kd.version() => Some(value),
+            _ => None,
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (DefaultKey, &V)> {
+        self.slots.iter().enumerate().filter_map(|(idx, slot)| match slot {
+            Slot::Occupied { value, version } => {
+                let kd: KeyData = unsafe { pi_key_alloter::key_data(idx as u32, *version) };
+                Some((kd.into(), value))
+            },
+            Slot::Vacant => None,
+        })
+    }
+}
+
+fn primary_keys() -> Vec<DefaultKey> {
+    let mut sm = SlotMap::new();
+    (0..N as u64).map(|i| sm.insert(i)).collect()
+}
+
+// Every key, or only about one in a hundred.
+fn filled<M, F: FnMut(&mut M, DefaultKey, u64)>(mut map: M, sparse: bool, mut insert: F) -> M {
+    for (i, key) in primary_keys().into_iter().enumerate() {
+        if !sparse || i % 101 == 0 {
+            insert(&mut map, key, i as u64);
+        }
+    }
+    map
+}
+
+#[bench]
+fn insert_bitmap(b: &mut Bencher) {
+    let keys = primary_keys();
+    b.iter(|| {
+        let mut sec = SecondaryMap::new();
+        for (i, &key) in keys.iter().enumerate() {
+            sec.insert(key, i as u64);
+        }
+        black_box(sec)
+    });
+}
+
+#[bench]
+fn insert_enum(b: &mut Bencher) {
+    let keys = primary_keys();
+    b.iter(|| {
+        let mut sec = EnumSecondaryMap::new();
+        for (i, &key) in keys.iter().enumerate() {
+            sec.insert(key, i as u64);
+        }
+        black_box(sec)
+    });
+}
+
+#[bench]
+fn get_bitmap(b: &mut Bencher) {
+    let keys = primary_keys();
+    let sec = filled(SecondaryMap::new(), false, |m, k, v| {
+        m.insert(k, v);
+    });
+    b.iter(|| keys.iter().map(|&key| sec.get(key).copied().unwrap_or(0)).sum::<u64>());
+}
+
+#[bench]
+fn get_enum(b: &mut Bencher) {
+    let keys = primary_keys();
+    let sec = filled(EnumSecondaryMap::new(), false, |m, k, v| {
+        m.insert(k, v);
+    });
+    b.iter(|| keys.iter().map(|&key| sec.get(key).copied().unwrap_or(0)).sum::<u64>());
+}
+
+#[bench]
+fn iter_dense_bitmap(b: &mut Bencher) {
+    let sec = filled(SecondaryMap::new(), false, |m, k, v| {
+        m.insert(k, v);
+    });
+    b.iter(|| sec.iter().map(|(_, v)| *v).sum::<u64>());
+}
+
+#[bench]
+fn iter_dense_enum(b: &mut Bencher) {
+    let sec = filled(EnumSecondaryMap::new(), false, |m, k, v| {
+        m.insert(k, v);
+    });
+    b.iter(|| sec.iter().map(|(_, v)| *v).sum::<u64>());
+}
+
+#[bench]
+fn iter_sparse_bitmap(b: &mut Bencher) {
+    let sec = filled(SecondaryMap::new(), true, |m, k, v| {
+        m.insert(k, v);
+    });
+    b.iter(|| sec.iter().map(|(_, v)| *v).sum::<u64>());
+}
+
+#[bench]
+fn iter_sparse_enum(b: &mut Bencher) {
+    let sec = filled(EnumSecondaryMap::new(), true, |m, k, v| {
+        m.insert(k, v);
+    });
+    b.iter(|| sec.iter().map(|(_, v)| *v).sum::<u64>());
+}
@@ -1,10 +1,10 @@
 fn main() {
     let is_nightly = version_check::is_feature_flaggable() == Some(true);
-    let is_at_least_1_49 = version_check::is_min_version("1.49.0").unwrap_or(false);
+    let is_at_least_1_65 = version_check::is_min_version("1.65.0").unwrap_or(false);
     let is_at_least_1_51 = version_check::is_min_version("1.51.0").unwrap_or(false);
 
-    if !is_at_least_1_49 {
-        println!("cargo:warning=slotmap requires rustc => 1.49.0");
+    if !is_at_least_1_65 {
+        println!("cargo:warning=slotmap requires rustc => 1.65.0");
     }
 
     if is_at_least_1_51 || is_nightly {
@@ -7,13 +7,13 @@
 use alloc::collections::TryReserveError;
 use alloc::vec::Vec;
 use core::fmt;
-use core::iter::{Enumerate, FusedIterator};
+use core::iter::{DoubleEndedIterator, Enumerate, FusedIterator};
 use core::marker::PhantomData;
 #[allow(unused_imports)] // MaybeUninit is only used on nightly at the moment.
 use core::mem::{ManuallyDrop, MaybeUninit};
-use core::ops::{Index, IndexMut};
+use core::ops::{Index, IndexMut, RangeBounds};
 
-use crate::util::{Never, UnwrapUnchecked};
+use crate::util::{slot_range, Never, UnwrapUnchecked};
 use pi_key_alloter::{DefaultKey, Key, key_data};
 
 // Storage inside a slot or metadata for the freelist when vacant.
@@ -567,7 +567,11 @@ impl<K: Key, V> SlotMap<K, V> {
     /// assert_eq!(v, vec![(k, 0)]);
     /// ```
     pub fn drain(&mut self) -> Drain<K, V> {
-        Drain { cur: 0, sm: self }
+        Drain {
+            cur: 0,
+            end: self.slots.len(),
+            sm: self,
+        }
     }
 
     /// Returns a reference to the value corresponding to the key.
@@ -658,6 +662,21 @@ impl<K: Key, V> SlotMap<K, V> {
         &mut self.slots.get_unchecked_mut(key.data().index() as usize).u.value
     }
 
+    // Like `contains_key` and `get_unchecked_mut`, but through a raw pointer
+    // and without creating a reference to the slots, so that references
+    // previously handed out to other values stay valid. Used by mutable joins.
+    pub(crate) unsafe fn raw_contains_key(this: *const Self, key: K) -> bool {
+        let kd = key.data();
+        let slots = &(*this).slots;
+        let idx = kd.index() as usize;
+        idx < slots.len() && (*slots.as_ptr().add(idx)).version == kd.version()
+    }
+
+    pub(crate) unsafe fn raw_get_unchecked_mut(this: *mut Self, key: K) -> *mut V {
+        let slot = (*this).slots.as_mut_ptr().add(key.data().index() as usize);
+        core::ptr::addr_of_mut!((*slot).u.value).cast()
+    }
+
     /// Returns mutable references to the values corresponding to the given
     /// keys. All keys must be valid and disjoint, otherwise None is returned.
     ///
@@ -892,6 +911,123 @@ impl<K: Key, V> SlotMap<K, V> {
             inner: self.iter_mut(),
         }
     }
+
+    /// An iterator visiting the key-value pairs stored in the slots with an
+    /// index in `range`, in slot order. The iterator element type is
+    /// `(K, &'a V)`.
+    ///
+    /// The slot index of a key is `key.data().index()`. Out of bounds parts of
+    /// `range` are ignored. Use [`Range::position`] to find where to resume
+    /// iteration later, even if the slot map was modified in the meantime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k0 = sm.insert(0);
+    /// let k1 = sm.insert(1);
+    /// let k2 = sm.insert(2);
+    ///
+    /// let idx = k1.data().index() as usize;
+    /// let v: Vec<_> = sm.range(idx..).collect();
+    /// assert_eq!(v, vec![(k1, &1), (k2, &2)]);
+    /// ```
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> Range<K, V> {
+        let (start, end) = slot_range(range, self.slots.len());
+        Range {
+            cur: start,
+            end,
+            slots: &self.slots[..],
+            _k: PhantomData,
+        }
+    }
+
+    /// An iterator visiting the key-value pairs stored in the slots with an
+    /// index in `range`, in slot order, with mutable references to the values.
+    /// The iterator element type is `(K, &'a mut V)`.
+    ///
+    /// See [`range`](Self::range) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    ///
+    /// let idx = k1.data().index() as usize;
+    /// sm.range_mut(idx..=idx).for_each(|(_, v)| *v *= -1);
+    /// assert_eq!(sm[k0], 10);
+    /// assert_eq!(sm[k1], -20);
+    /// ```
+    pub fn range_mut<R: RangeBounds<usize>>(&mut self, range: R) -> RangeMut<K, V> {
+        let (start, end) = slot_range(range, self.slots.len());
+        RangeMut {
+            cur: start,
+            end,
+            slots: &mut self.slots[..],
+            _k: PhantomData,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs stored at or after slot index
+    /// `position`, in slot order. The iterator element type is `(K, &'a V)`.
+    ///
+    /// This allows processing a slot map in chunks, resuming where the previous
+    /// chunk left off through [`Range::position`]. Elements inserted or
+    /// removed in between are handled gracefully: every element that stays in
+    /// the slot map is visited exactly once per pass.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// for i in 0..5 {
+    ///     sm.insert(i);
+    /// }
+    ///
+    /// let mut it = sm.iter_from(0);
+    /// let first: Vec<_> = it.by_ref().take(3).map(|(_, v)| *v).collect();
+    /// let position = it.position();
+    ///
+    /// sm.insert(5);
+    /// let rest: Vec<_> = sm.iter_from(position).map(|(_, v)| *v).collect();
+    /// assert_eq!(first, vec![0, 1, 2]);
+    /// assert_eq!(rest, vec![3, 4, 5]);
+    /// ```
+    pub fn iter_from(&self, position: usize) -> Range<K, V> {
+        self.range(position..)
+    }
+
+    /// An iterator visiting all key-value pairs stored at or after slot index
+    /// `position`, in slot order, with mutable references to the values. The
+    /// iterator element type is `(K, &'a mut V)`.
+    ///
+    /// See [`iter_from`](Self::iter_from) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// for i in 0..4 {
+    ///     sm.insert(i);
+    /// }
+    ///
+    /// let mut it = sm.iter_mut_from(0);
+    /// it.by_ref().take(2).for_each(|(_, v)| *v *= 10);
+    /// let position = it.position();
+    /// sm.iter_mut_from(position).for_each(|(_, v)| *v *= 100);
+    ///
+    /// let values: Vec<_> = sm.values().copied().collect();
+    /// assert_eq!(values, vec![0, 10, 200, 300]);
+    /// ```
+    pub fn iter_mut_from(&mut self, position: usize) -> RangeMut<K, V> {
+        self.range_mut(position..)
+    }
 }
 
 impl<K: Key, V> Clone for SlotMap<K, V>
@@ -946,6 +1082,7 @@ impl<K: Key, V> IndexMut<K> for SlotMap<K, V> {
 pub struct Drain<'a, K: 'a + Key, V: 'a> {
     sm: &'a mut SlotMap<K, V>,
     cur: usize,
+    end: usize,
 }
 
 /// An iterator that moves key-value pairs out of a [`SlotMap`].
@@ -1029,12 +1166,62 @@ pub struct ValuesMut<'a, K: 'a + Key, V: 'a> {
     inner: IterMut<'a, K, V>,
 }
 
+/// An iterator over the key-value pairs in a range of slots of a [`SlotMap`].
+///
+/// This iterator is created by [`SlotMap::range`] and [`SlotMap::iter_from`].
+#[derive(Debug)]
+pub struct Range<'a, K: 'a + Key, V: 'a> {
+    cur: usize,
+    end: usize,
+    slots: &'a [Slot<V>],
+    _k: PhantomData<fn(K) -> K>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Range<'a, K, V> {
+    fn clone(&self) -> Self {
+        Range {
+            cur: self.cur,
+            end: self.end,
+            slots: self.slots,
+            _k: self._k,
+        }
+    }
+}
+
+impl<'a, K: 'a + Key, V: 'a> Range<'a, K, V> {
+    /// Returns the slot index from which iteration continues. Pass it to
+    /// [`SlotMap::iter_from`] to resume iterating later.
+    pub fn position(&self) -> usize {
+        self.cur
+    }
+}
+
+/// A mutable iterator over the key-value pairs in a range of slots of a
+/// [`SlotMap`].
+///
+/// This iterator is created by [`SlotMap::range_mut`] and
+/// [`SlotMap::iter_mut_from`].
+#[derive(Debug)]
+pub struct RangeMut<'a, K: 'a + Key, V: 'a> {
+    cur: usize,
+    end: usize,
+    slots: &'a mut [Slot<V>],
+    _k: PhantomData<fn(K) -> K>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> RangeMut<'a, K, V> {
+    /// Returns the slot index from which iteration continues. Pass it to
+    /// [`SlotMap::iter_mut_from`] to resume iterating later.
+    pub fn position(&self) -> usize {
+        self.cur
+    }
+}
+
 impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
     type Item = (K, V);
 
     fn next(&mut self) -> Option<(K, V)> {
-        let len = self.sm.slots.len();
-        while self.cur < len {
+        while self.cur < self.end {
             let idx = self.cur;
             self.cur += 1;
 
@@ -1056,6 +1243,26 @@ impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for Drain<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        while self.cur < self.end {
+            self.end -= 1;
+            let idx = self.end;
+
+            // This is safe because removing doesn't shrink slots.
+            unsafe {
+                let slot = self.sm.slots.get_unchecked(idx);
+                if slot.occupied() {
+                    let kd = key_data(idx as u32, slot.version);
+                    return Some((kd.into(), self.sm.remove_from_slot(idx)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
 impl<'a, K: Key, V> Drop for Drain<'a, K, V> {
     fn drop(&mut self) {
         self.for_each(|_drop| {});
@@ -1089,6 +1296,27 @@ impl<K: Key, V> Iterator for IntoIter<K, V> {
     }
 }
 
+impl<K: Key, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        while let Some((idx, mut slot)) = self.slots.next_back() {
+            if slot.occupied() {
+                let kd = unsafe { key_data(idx as u32, slot.version) };
+
+                // Prevent dropping after extracting the value.
+                slot.version = 0;
+
+                // This is safe because we know the slot was occupied.
+                let value = unsafe { ManuallyDrop::take(&mut slot.u.value) };
+
+                self.num_left -= 1;
+                return Some((kd.into(), value));
+            }
+        }
+
+        None
+    }
+}
+
 impl<'a, K: Key, V> Iterator for Iter<'a, K, V> {
     type Item = (K, &'a V);
 
@@ -1109,6 +1337,20 @@ impl<'a, K: Key, V> Iterator for Iter<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a V)> {
+        while let Some((idx, slot)) = self.slots.next_back() {
+            if let Occupied(value) = slot.get() {
+                let kd = unsafe { key_data(idx as u32, slot.version) };
+                self.num_left -= 1;
+                return Some((kd.into(), value));
+            }
+        }
+
+        None
+    }
+}
+
 impl<'a, K: Key, V> Iterator for IterMut<'a, K, V> {
     type Item = (K, &'a mut V);
 
@@ -1130,6 +1372,21 @@ impl<'a, K: Key, V> Iterator for IterMut<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a mut V)> {
+        while let Some((idx, slot)) = self.slots.next_back() {
+            let version = slot.version;
+            if let OccupiedMut(value) = slot.get_mut() {
+                let kd = unsafe { key_data(idx as u32, version) };
+                self.num_left -= 1;
+                return Some((kd.into(), value));
+            }
+        }
+
+        None
+    }
+}
+
 impl<'a, K: Key, V> Iterator for Keys<'a, K, V> {
     type Item = K;
 
@@ -1142,6 +1399,12 @@ impl<'a, K: Key, V> Iterator for Keys<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<K> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
 impl<'a, K: Key, V> Iterator for Values<'a, K, V> {
     type Item = &'a V;
 
@@ -1154,6 +1417,12 @@ impl<'a, K: Key, V> Iterator for Values<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
 impl<'a, K: Key, V> Iterator for ValuesMut<'a, K, V> {
     type Item = &'a mut V;
 
@@ -1166,6 +1435,66 @@ impl<'a, K: Key, V> Iterator for ValuesMut<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Range<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        while self.cur < self.end {
+            let idx = self.cur;
+            self.cur += 1;
+
+            // This is safe because end never exceeds the number of slots.
+            let slot = unsafe { self.slots.get_unchecked(idx) };
+            if let Occupied(value) = slot.get() {
+                let kd = unsafe { key_data(idx as u32, slot.version) };
+                return Some((kd.into(), value));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.cur))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for RangeMut<'a, K, V> {
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<(K, &'a mut V)> {
+        while self.cur < self.end {
+            let idx = self.cur;
+            self.cur += 1;
+
+            // Unsafe necessary because Rust can't deduce that we won't
+            // return multiple references to the same value.
+            let slot = unsafe { self.slots.get_unchecked_mut(idx) };
+            let version = slot.version;
+            if let OccupiedMut(value) = slot.get_mut() {
+                let kd = unsafe { key_data(idx as u32, version) };
+                let value_ref = unsafe {
+                    let ptr: *mut V = value;
+                    &mut *ptr
+                };
+                return Some((kd.into(), value_ref));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.cur))
+    }
+}
+
 impl<'a, K: Key, V> IntoIterator for &'a SlotMap<K, V> {
     type Item = (K, &'a V);
     type IntoIter = Iter<'a, K, V>;
@@ -1207,6 +1536,8 @@ impl<'a, K: Key, V> FusedIterator for Values<'a, K, V> {}
 impl<'a, K: Key, V> FusedIterator for ValuesMut<'a, K, V> {}
 impl<'a, K: Key, V> FusedIterator for Drain<'a, K, V> {}
 impl<K: Key, V> FusedIterator for IntoIter<K, V> {}
+impl<'a, K: Key, V> FusedIterator for Range<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for RangeMut<'a, K, V> {}
 
 impl<'a, K: Key, V> ExactSizeIterator for Iter<'a, K, V> {}
 impl<'a, K: Key, V> ExactSizeIterator for IterMut<'a, K, V> {}
@@ -1216,6 +1547,115 @@ impl<'a, K: Key, V> ExactSizeIterator for ValuesMut<'a, K, V> {}
 impl<'a, K: Key, V> ExactSizeIterator for Drain<'a, K, V> {}
 impl<K: Key, V> ExactSizeIterator for IntoIter<K, V> {}
 
+// Parallel iteration with rayon.
+#[cfg(feature = "rayon")]
+mod parallel {
+    use rayon::prelude::*;
+
+    use super::*;
+    use crate::par::{ParSlots, ParSlotsMut};
+
+    impl<K: Key, V> SlotMap<K, V> {
+        /// A parallel iterator visiting all key-value pairs in arbitrary order.
+        /// The work is split such that every thread gets about the same number
+        /// of elements, regardless of how the vacant slots are spread out.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// let sum: u64 = sm.par_iter().map(|(_, &v)| v).sum();
+        /// assert_eq!(sum, 500 * 999);
+        /// ```
+        pub fn par_iter(&self) -> impl ParallelIterator<Item = (K, &V)>
+        where
+            K: Send,
+            V: Sync,
+        {
+            ParSlots::new(&self.slots[1..], 1, Slot::occupied).map(|(idx, slot)| {
+                let key = unsafe { key_data(idx as u32, slot.version).into() };
+                (key, unsafe { &*slot.u.value })
+            })
+        }
+
+        /// A parallel iterator visiting all key-value pairs in arbitrary order,
+        /// with mutable references to the values.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// sm.par_iter_mut().for_each(|(_, v)| *v *= 2);
+        /// assert_eq!(sm.values().sum::<u64>(), 1000 * 999);
+        /// ```
+        pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (K, &mut V)>
+        where
+            K: Send,
+            V: Send,
+        {
+            // Slot::occupied only reads the version, see ParSlotsMut::new.
+            let slots = unsafe { ParSlotsMut::new(&mut self.slots[1..], 1, Slot::occupied) };
+            slots.map(|(idx, slot)| {
+                let key = unsafe { key_data(idx as u32, slot.version).into() };
+                (key, unsafe { &mut *slot.u.value })
+            })
+        }
+
+        /// A parallel iterator visiting all keys in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// assert_eq!(sm.par_keys().count(), 1000);
+        /// ```
+        pub fn par_keys(&self) -> impl ParallelIterator<Item = K> + '_
+        where
+            K: Send,
+            V: Sync,
+        {
+            self.par_iter().map(|(key, _)| key)
+        }
+
+        /// A parallel iterator visiting all values mutably in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// sm.par_values_mut().for_each(|v| *v += 1);
+        /// assert_eq!(sm.values().sum::<u64>(), 500 * 1001);
+        /// ```
+        pub fn par_values_mut(&mut self) -> impl ParallelIterator<Item = &mut V>
+        where
+            K: Send,
+            V: Send,
+        {
+            self.par_iter_mut().map(|(_, value)| value)
+        }
+    }
+}
+
 // Serialization with serde.
 #[cfg(feature = "serde")]
 mod serialize {
@@ -1422,6 +1862,119 @@ mod tests {
         }
     }
 
+    #[test]
+    fn resumable_range() {
+        let mut sm = SlotMap::new();
+        let keys: Vec<_> = (0..100).map(|i| sm.insert(i)).collect();
+        for k in keys.iter().step_by(3) {
+            sm.remove(*k);
+        }
+
+        // Visit everything in chunks, removing and inserting in between.
+        let mut seen = Vec::new();
+        let mut position = 0;
+        loop {
+            let mut it = sm.iter_mut_from(position);
+            let chunk: Vec<_> = it.by_ref().take(7).map(|(k, v)| (k, *v)).collect();
+            position = it.position();
+            if chunk.is_empty() {
+                break;
+            }
+            seen.extend(chunk.iter().map(|&(_, v)| v));
+
+            // Removing behind the position must not disturb iteration.
+            sm.remove(chunk[0].0);
+        }
+        let expected: Vec<_> = (0..100).filter(|i| i % 3 != 0).collect();
+        assert_eq!(seen, expected);
+
+        let ranged: Vec<_> = sm.range(20..40).map(|(k, _)| k.data().index()).collect();
+        assert!(ranged.iter().all(|&i| (20..40).contains(&i)));
+        let filtered: Vec<_> = sm
+            .keys()
+            .map(|k| k.data().index())
+            .filter(|i| (20..40).contains(i))
+            .collect();
+        assert_eq!(ranged, filtered);
+        assert_eq!(sm.range(..).count(), sm.len());
+        assert_eq!(sm.range(1000..).count(), 0);
+    }
+
+    #[test]
+    fn double_ended() {
+        let mut sm = SlotMap::new();
+        let keys: Vec<_> = (0..20).map(|i| sm.insert(i)).collect();
+        for &i in &[0, 3, 4, 5, 10, 17, 18, 19] {
+            sm.remove(keys[i]);
+        }
+
+        let forward: Vec<_> = sm.iter().map(|(k, &v)| (k, v)).collect();
+        let mut backward: Vec<_> = sm.iter().rev().map(|(k, &v)| (k, v)).collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert!(sm.values().rev().eq(forward.iter().rev().map(|(_, v)| v)));
+        assert!(sm.keys().rev().eq(forward.iter().rev().map(|&(k, _)| k)));
+
+        // Alternating ends visits every element exactly once.
+        let mut it = sm.iter_mut();
+        let mut seen = Vec::new();
+        while let Some((_, v)) = it.next() {
+            seen.push(*v);
+            if let Some((_, v)) = it.next_back() {
+                seen.push(*v);
+            }
+        }
+        seen.sort();
+        assert!(seen.iter().eq(sm.values()));
+
+        let mut drained: Vec<_> = sm.clone().into_iter().rev().collect();
+        drained.reverse();
+        assert_eq!(drained, forward);
+
+        let mut drained = Vec::new();
+        let mut drain = sm.drain();
+        while let Some(back) = drain.next_back() {
+            drained.push(back);
+            if let Some(front) = drain.next() {
+                drained.push(front);
+            }
+        }
+        drop(drain);
+        assert!(sm.is_empty());
+        drained.sort_by_key(|&(_, v)| v);
+        assert_eq!(drained, forward);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_iter() {
+        use rayon::prelude::*;
+
+        let mut sm = SlotMap::new();
+        let keys: Vec<_> = (0..10_000).map(|i| sm.insert(i)).collect();
+        for &k in &keys[..9_000] {
+            sm.remove(k);
+        }
+
+        let mut par: Vec<_> = sm.par_iter().map(|(k, &v)| (k, v)).collect();
+        par.sort();
+        let seq: Vec<_> = sm.iter().map(|(k, &v)| (k, v)).collect();
+        assert_eq!(par, seq);
+
+        sm.par_values_mut().for_each(|v| *v *= 2);
+        sm.par_iter_mut().for_each(|(_, v)| *v += 1);
+        assert!((9_000..10_000).all(|i| sm[keys[i]] == 2 * i + 1));
+        assert_eq!(sm.par_keys().count(), 1_000);
+
+        // Mutable iteration only needs the values to be Send.
+        let mut cells = SlotMap::new();
+        for i in 0..100 {
+            cells.insert(core::cell::Cell::new(i));
+        }
+        cells.par_values_mut().for_each(|c| *c.get_mut() += 1);
+        assert_eq!(cells.values().map(|c| c.get()).sum::<i32>(), 50 * 101);
+    }
+
     quickcheck! {
         fn qc_slotmap_equiv_hashmap(operations: Vec<(u8, u32)>) -> bool {
             let mut hm = HashMap::new();
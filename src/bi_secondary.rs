@@ -0,0 +1,505 @@
+//! Contains the bidirectional secondary map implementation, a one-to-one
+//! mapping between the keys of two slot maps.
+
+use core::iter::FusedIterator;
+
+use crate::secondary::{self, SecondaryMap};
+use pi_key_alloter::{is_older_version, Key};
+
+/// Bidirectional secondary map, a one-to-one mapping between the keys of two
+/// slot maps with O(1) lookup in both directions.
+///
+/// Both directions are stored like a [`SecondaryMap`], indexed by the slot
+/// index of the key. Every left key is paired with at most one right key and
+/// vice versa; inserting a pair removes any other pair containing either key.
+///
+/// Like a [`SecondaryMap`], lookups with a key of a different version than the
+/// stored one find nothing, and a pair is evicted once a newer key for the
+/// same slot is inserted on either side.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// new_key_type! {
+///     struct RemoteKey;
+/// }
+///
+/// let mut local = SlotMap::new();
+/// let mut remote: SlotMap<RemoteKey, _> = SlotMap::with_key();
+/// let mut links = BiSecondaryMap::new();
+///
+/// let player = local.insert("player");
+/// let ghost = remote.insert("ghost");
+/// links.insert(player, ghost);
+/// assert_eq!(links.get_by_left(player), Some(ghost));
+/// assert_eq!(links.get_by_right(ghost), Some(player));
+///
+/// remote.remove(ghost);
+/// let respawned = remote.insert("ghost");
+/// links.insert(player, respawned);
+/// assert_eq!(links.get_by_right(ghost), None);
+/// assert_eq!(links.get_by_right(respawned), Some(player));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BiSecondaryMap<K1: Key, K2: Key> {
+    left: SecondaryMap<K1, K2>,
+    right: SecondaryMap<K2, K1>,
+}
+
+impl<K1: Key, K2: Key> BiSecondaryMap<K1, K2> {
+    /// Constructs a new, empty [`BiSecondaryMap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut links: BiSecondaryMap<DefaultKey, DefaultKey> = BiSecondaryMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty [`BiSecondaryMap`] with room for keys of the given
+    /// number of slots on each side.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut links: BiSecondaryMap<DefaultKey, DefaultKey> = BiSecondaryMap::with_capacity(10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            left: SecondaryMap::with_capacity(capacity),
+            right: SecondaryMap::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of pairs in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(a, b);
+    /// links.insert(b, a);
+    /// assert_eq!(links.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.left.len()
+    }
+
+    /// Returns if the map is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let links: BiSecondaryMap<DefaultKey, DefaultKey> = BiSecondaryMap::new();
+    /// assert!(links.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.left.is_empty()
+    }
+
+    /// Returns [`true`] if `left` is paired with a right key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(a, b);
+    /// assert!(links.contains_left(a));
+    /// assert!(!links.contains_left(b));
+    /// ```
+    pub fn contains_left(&self, left: K1) -> bool {
+        self.left.contains_key(left)
+    }
+
+    /// Returns [`true`] if `right` is paired with a left key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(a, b);
+    /// assert!(links.contains_right(b));
+    /// assert!(!links.contains_right(a));
+    /// ```
+    pub fn contains_right(&self, right: K2) -> bool {
+        self.right.contains_key(right)
+    }
+
+    /// Pairs `left` with `right`, removing any pairs either key was part of.
+    /// Can silently fail and return [`false`] if either key was removed from
+    /// its originating slot map, and a newer key of the same slot was
+    /// inserted into this map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b, c) = (sm.insert(()), sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// assert!(links.insert(a, b));
+    /// assert!(links.insert(c, b));
+    /// assert_eq!(links.get_by_left(a), None);
+    /// assert_eq!(links.get_by_right(b), Some(c));
+    ///
+    /// sm.remove(a);
+    /// let d = sm.insert(());
+    /// assert!(links.insert(d, a));
+    /// assert!(!links.insert(a, c));
+    /// ```
+    pub fn insert(&mut self, left: K1, right: K2) -> bool {
+        if left.is_null() || right.is_null() {
+            return false;
+        }
+        if is_superseded(&self.left, left) || is_superseded(&self.right, right) {
+            return false;
+        }
+
+        if let Some(old) = self.left.occupant(left) {
+            self.remove_by_left(old);
+        }
+        if let Some(old) = self.right.occupant(right) {
+            self.remove_by_right(old);
+        }
+        self.left.insert(left, right);
+        self.right.insert(right, left);
+        true
+    }
+
+    /// Returns the right key paired with `left`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(a, b);
+    /// assert_eq!(links.get_by_left(a), Some(b));
+    /// assert_eq!(links.get_by_left(b), None);
+    /// ```
+    pub fn get_by_left(&self, left: K1) -> Option<K2> {
+        self.left.get(left).copied()
+    }
+
+    /// Returns the left key paired with `right`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(a, b);
+    /// assert_eq!(links.get_by_right(b), Some(a));
+    /// assert_eq!(links.get_by_right(a), None);
+    /// ```
+    pub fn get_by_right(&self, right: K2) -> Option<K1> {
+        self.right.get(right).copied()
+    }
+
+    /// Removes the pair containing `left`, returning the right key it was
+    /// paired with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(a, b);
+    /// assert_eq!(links.remove_by_left(a), Some(b));
+    /// assert_eq!(links.get_by_right(b), None);
+    /// assert_eq!(links.remove_by_left(a), None);
+    /// ```
+    pub fn remove_by_left(&mut self, left: K1) -> Option<K2> {
+        let right = self.left.remove(left)?;
+        self.right.remove(right);
+        Some(right)
+    }
+
+    /// Removes the pair containing `right`, returning the left key it was
+    /// paired with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(a, b);
+    /// assert_eq!(links.remove_by_right(b), Some(a));
+    /// assert_eq!(links.get_by_left(a), None);
+    /// assert_eq!(links.remove_by_right(b), None);
+    /// ```
+    pub fn remove_by_right(&mut self, right: K2) -> Option<K1> {
+        let left = self.right.remove(right)?;
+        self.left.remove(left);
+        Some(left)
+    }
+
+    /// Retains only the pairs specified by the predicate.
+    ///
+    /// In other words, remove all pairs `(l, r)` such that `f(l, r)` returns
+    /// false.
+    ///
+    /// This function must iterate over all left slots, empty or not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let keys: Vec<_> = (0..4).map(|i| sm.insert(i)).collect();
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(keys[0], keys[1]);
+    /// links.insert(keys[2], keys[3]);
+    ///
+    /// links.retain(|l, _| sm[l] != 2);
+    /// assert_eq!(links.len(), 1);
+    /// assert_eq!(links.get_by_right(keys[3]), None);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K1, K2) -> bool,
+    {
+        let right = &mut self.right;
+        self.left.retain(|l, r| {
+            let keep = f(l, *r);
+            if !keep {
+                right.remove(*r);
+            }
+            keep
+        });
+    }
+
+    /// Clears the map. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(a, b);
+    /// links.clear();
+    /// assert!(links.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.left.clear();
+        self.right.clear();
+    }
+
+    /// An iterator visiting all pairs in order of the slot index of their
+    /// left key. The iterator element type is `(K1, K2)`.
+    ///
+    /// This function must iterate over all left slots, empty or not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b, c) = (sm.insert(()), sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(c, a);
+    /// links.insert(a, b);
+    /// assert_eq!(links.iter().collect::<Vec<_>>(), [(a, b), (c, a)]);
+    /// ```
+    pub fn iter(&self) -> Iter<K1, K2> {
+        Iter {
+            inner: self.left.iter(),
+        }
+    }
+
+    /// Returns the map from left keys to right keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(a, b);
+    /// assert_eq!(links.left_map()[a], b);
+    /// ```
+    pub fn left_map(&self) -> &SecondaryMap<K1, K2> {
+        &self.left
+    }
+
+    /// Returns the map from right keys to left keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut links = BiSecondaryMap::new();
+    /// links.insert(a, b);
+    /// assert_eq!(links.right_map()[b], a);
+    /// ```
+    pub fn right_map(&self) -> &SecondaryMap<K2, K1> {
+        &self.right
+    }
+}
+
+// Returns if the slot of `key` holds a newer version of `key`.
+fn is_superseded<K: Key, V>(map: &SecondaryMap<K, V>, key: K) -> bool {
+    map.occupant(key).map_or(false, |old| {
+        is_older_version(key.data().version(), old.data().version())
+    })
+}
+
+impl<K1: Key, K2: Key> Default for BiSecondaryMap<K1, K2> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K1: Key, K2: Key> PartialEq for BiSecondaryMap<K1, K2> {
+    fn eq(&self, other: &Self) -> bool {
+        self.left == other.left
+    }
+}
+
+impl<K1: Key, K2: Key> Eq for BiSecondaryMap<K1, K2> {}
+
+impl<K1: Key, K2: Key> Extend<(K1, K2)> for BiSecondaryMap<K1, K2> {
+    fn extend<I: IntoIterator<Item = (K1, K2)>>(&mut self, iter: I) {
+        for (left, right) in iter {
+            self.insert(left, right);
+        }
+    }
+}
+
+impl<K1: Key, K2: Key> FromIterator<(K1, K2)> for BiSecondaryMap<K1, K2> {
+    fn from_iter<I: IntoIterator<Item = (K1, K2)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+/// An iterator over the pairs in a [`BiSecondaryMap`].
+///
+/// This iterator is created by [`BiSecondaryMap::iter`].
+#[derive(Debug)]
+pub struct Iter<'a, K1: Key + 'a, K2: Key + 'a> {
+    inner: secondary::Iter<'a, K1, K2>,
+}
+
+impl<'a, K1: Key, K2: Key> Clone for Iter<'a, K1, K2> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K1: Key, K2: Key> Iterator for Iter<'a, K1, K2> {
+    type Item = (K1, K2);
+
+    fn next(&mut self) -> Option<(K1, K2)> {
+        self.inner.next().map(|(l, r)| (l, *r))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K1: Key, K2: Key> DoubleEndedIterator for Iter<'a, K1, K2> {
+    fn next_back(&mut self) -> Option<(K1, K2)> {
+        self.inner.next_back().map(|(l, r)| (l, *r))
+    }
+}
+
+impl<'a, K1: Key, K2: Key> IntoIterator for &'a BiSecondaryMap<K1, K2> {
+    type Item = (K1, K2);
+    type IntoIter = Iter<'a, K1, K2>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K1: Key, K2: Key> FusedIterator for Iter<'a, K1, K2> {}
+
+impl<'a, K1: Key, K2: Key> ExactSizeIterator for Iter<'a, K1, K2> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    new_key_type! {
+        struct RemoteKey;
+    }
+
+    #[test]
+    fn stays_consistent() {
+        let mut local = SlotMap::new();
+        let mut remote: SlotMap<RemoteKey, ()> = SlotMap::with_key();
+        let mut links = BiSecondaryMap::new();
+        let l: Vec<_> = (0..4).map(|_| local.insert(())).collect();
+        let r: Vec<_> = (0..4).map(|_| remote.insert(())).collect();
+
+        for i in 0..4 {
+            assert!(links.insert(l[i], r[i]));
+        }
+        // Overwrite on both sides at once.
+        assert!(links.insert(l[0], r[1]));
+        assert_eq!(links.len(), 3);
+        assert_eq!(links.get_by_right(r[0]), None);
+        assert_eq!(links.get_by_left(l[1]), None);
+
+        // Stale keys on the left and the right.
+        local.remove(l[2]);
+        let l2 = local.insert(());
+        assert!(links.insert(l2, r[0]));
+        assert_eq!(links.get_by_right(r[2]), None);
+        assert_eq!(links.get_by_left(l[2]), None);
+        assert!(!links.insert(l[2], r[2]));
+
+        remote.remove(r[3]);
+        let r3 = remote.insert(());
+        assert!(links.insert(l[1], r3));
+        assert_eq!(links.get_by_left(l[3]), None);
+        assert!(!links.insert(l[3], r[3]));
+        assert_eq!(links.remove_by_right(r[3]), None);
+
+        let pairs: Vec<_> = links.iter().collect();
+        assert_eq!(pairs, [(l[0], r[1]), (l[1], r3), (l2, r[0])]);
+        for &(a, b) in &pairs {
+            assert_eq!(links.get_by_left(a), Some(b));
+            assert_eq!(links.get_by_right(b), Some(a));
+        }
+        assert_eq!(links.left_map().len(), links.right_map().len());
+
+        links.retain(|a, _| a != l2);
+        assert_eq!(links.get_by_right(r[0]), None);
+        assert_eq!(links.remove_by_left(l[0]), Some(r[1]));
+        assert_eq!(links.iter().collect::<Vec<_>>(), [(l[1], r3)]);
+    }
+}
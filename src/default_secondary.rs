@@ -0,0 +1,552 @@
+//! Contains the default secondary map implementation, a secondary map that
+//! holds a default value for every key it has no entry for.
+
+use core::ops::{Index, IndexMut};
+
+use pi_key_alloter::Key;
+
+use crate::primary::PrimaryMap;
+use crate::secondary::{self, Entry, SecondaryMap};
+
+/// Secondary map that returns a default value for keys without an entry.
+///
+/// A [`DefaultSecondaryMap`] is a [`SecondaryMap`] for data every key of a
+/// slot map conceptually has, such as a velocity that is zero unless set.
+/// Indexing with a key that has no entry, or a stale key, returns a shared
+/// default value instead of panicking. Indexing mutably inserts the default
+/// value for the key on demand, like [`Entry::or_default`].
+///
+/// Iteration and [`len`](Self::len) only cover the entries that were
+/// explicitly set, by [`insert`](Self::insert), by indexing mutably or by
+/// [`get_or_insert_default`](Self::get_or_insert_default).
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut sm = SlotMap::new();
+/// let mut velocity = DefaultSecondaryMap::new();
+///
+/// let rock = sm.insert("rock");
+/// let ball = sm.insert("ball");
+/// velocity[ball] += 2.5;
+///
+/// assert_eq!(velocity[rock], 0.0);
+/// assert_eq!(velocity[ball], 2.5);
+/// assert_eq!(velocity.iter().collect::<Vec<_>>(), [(ball, &2.5)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefaultSecondaryMap<K: Key, V> {
+    map: SecondaryMap<K, V>,
+    default: V,
+}
+
+impl<K: Key, V: Default> DefaultSecondaryMap<K, V> {
+    /// Constructs a new, empty [`DefaultSecondaryMap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sec: DefaultSecondaryMap<DefaultKey, i32> = DefaultSecondaryMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty [`DefaultSecondaryMap`] with room for keys of the
+    /// given number of slots.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sec: DefaultSecondaryMap<DefaultKey, i32> = DefaultSecondaryMap::with_capacity(10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            map: SecondaryMap::with_capacity(capacity),
+            default: V::default(),
+        }
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key,
+    /// inserting the default value if the key has no entry.
+    ///
+    /// Returns [`None`] if the key was removed from its originating slot map
+    /// and a newer key of the same slot has an entry in this map, or if the
+    /// key is null.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// let key = sm.insert(());
+    ///
+    /// *sec.get_or_insert_default(key).unwrap() += 5;
+    /// assert_eq!(sec[key], 5);
+    ///
+    /// sm.remove(key);
+    /// let reused = sm.insert(());
+    /// sec.insert(reused, 1);
+    /// assert_eq!(sec.get_or_insert_default(key), None);
+    /// ```
+    pub fn get_or_insert_default(&mut self, key: K) -> Option<&mut V> {
+        self.map.entry(key).map(Entry::or_default)
+    }
+}
+
+impl<K: Key, V> DefaultSecondaryMap<K, V> {
+    /// Returns the number of explicitly set entries in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(4);
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// assert_eq!(sec[k], 0);
+    /// assert_eq!(sec.len(), 0);
+    /// sec[k] = 1;
+    /// assert_eq!(sec.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns if the map has no explicitly set entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let sec: DefaultSecondaryMap<DefaultKey, i32> = DefaultSecondaryMap::new();
+    /// assert!(sec.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns [`true`] if the key has an explicitly set entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(4);
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// assert!(!sec.contains_key(k));
+    /// sec.insert(k, 2);
+    /// assert!(sec.contains_key(k));
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Inserts a value into the map at the given key, returning the value of
+    /// its previous entry. See [`SecondaryMap::insert`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(4);
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// assert_eq!(sec.insert(k, 10), None);
+    /// assert_eq!(sec.insert(k, 20), Some(10));
+    /// assert_eq!(sec[k], 20);
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map.insert(key, value)
+    }
+
+    /// Removes the entry of a key from the map, returning its value. The key
+    /// maps to the default value afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(4);
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// sec.insert(k, 10);
+    /// assert_eq!(sec.remove(k), Some(10));
+    /// assert_eq!(sec.remove(k), None);
+    /// assert_eq!(sec[k], 0);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        self.map.remove(key)
+    }
+
+    /// Retains only the entries specified by the predicate. See
+    /// [`SecondaryMap::retain`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// let k1 = sm.insert(0); sec.insert(k1, 10);
+    /// let k2 = sm.insert(1); sec.insert(k2, 11);
+    ///
+    /// sec.retain(|_, val| *val == 11);
+    /// assert!(!sec.contains_key(k1));
+    /// assert_eq!(sec.len(), 1);
+    /// ```
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        self.map.retain(f)
+    }
+
+    /// Removes every entry whose key is no longer valid in `primary`. See
+    /// [`SecondaryMap::retain_live`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// let a = sm.insert(1);
+    /// sec.insert(a, 5);
+    ///
+    /// sm.remove(a);
+    /// sec.retain_live(&sm);
+    /// assert!(sec.is_empty());
+    /// ```
+    pub fn retain_live<P: PrimaryMap<K>>(&mut self, primary: &P) {
+        self.map.retain_live(primary)
+    }
+
+    /// Removes every entry from the map, so that all keys map to the default
+    /// value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// let k = sm.insert(1);
+    /// sec.insert(k, 10);
+    /// sec.clear();
+    /// assert!(sec.is_empty());
+    /// assert_eq!(sec[k], 0);
+    /// ```
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Returns a reference to the value corresponding to the key, or to the
+    /// default value if the key has no entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// sec.insert(a, "set");
+    /// assert_eq!(*sec.get(a), "set");
+    /// assert_eq!(*sec.get(b), "");
+    /// ```
+    pub fn get(&self, key: K) -> &V {
+        self.map.get(key).unwrap_or(&self.default)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key, or
+    /// [`None`] if the key has no entry. Unlike indexing mutably, this never
+    /// inserts the default value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// sec.insert(a, 1);
+    /// *sec.get_mut(a).unwrap() += 1;
+    /// assert_eq!(sec[a], 2);
+    /// assert_eq!(sec.get_mut(b), None);
+    /// assert!(!sec.contains_key(b));
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.map.get_mut(key)
+    }
+
+    /// Returns the default value returned for keys without an entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let sec: DefaultSecondaryMap<DefaultKey, Option<i32>> = DefaultSecondaryMap::new();
+    /// assert_eq!(*sec.default_value(), None);
+    /// ```
+    pub fn default_value(&self) -> &V {
+        &self.default
+    }
+
+    /// An iterator visiting the explicitly set entries in increasing slot
+    /// order. The iterator element type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// sec.insert(b, 2);
+    /// assert_eq!(sec[a], 0);
+    /// assert_eq!(sec.iter().collect::<Vec<_>>(), [(b, &2)]);
+    /// ```
+    pub fn iter(&self) -> secondary::Iter<K, V> {
+        self.map.iter()
+    }
+
+    /// An iterator visiting the explicitly set entries in increasing slot
+    /// order, with mutable references to the values. The iterator element
+    /// type is `(K, &'a mut V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// sec.insert(a, 1);
+    /// sec.insert(b, 2);
+    /// for (_, val) in sec.iter_mut() {
+    ///     *val *= 10;
+    /// }
+    /// assert_eq!(sec[b], 20);
+    /// ```
+    pub fn iter_mut(&mut self) -> secondary::IterMut<K, V> {
+        self.map.iter_mut()
+    }
+
+    /// An iterator visiting the keys of the explicitly set entries in
+    /// increasing slot order. The iterator element type is `K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// sec[b] = 1;
+    /// assert_eq!(sec[a], 0);
+    /// assert_eq!(sec.keys().collect::<Vec<_>>(), [b]);
+    /// ```
+    pub fn keys(&self) -> secondary::Keys<K, V> {
+        self.map.keys()
+    }
+
+    /// An iterator visiting the values of the explicitly set entries in
+    /// increasing slot order. The iterator element type is `&'a V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// sec.insert(a, 1);
+    /// sec.insert(b, 2);
+    /// assert_eq!(sec.values().sum::<i32>(), 3);
+    /// ```
+    pub fn values(&self) -> secondary::Values<K, V> {
+        self.map.values()
+    }
+
+    /// An iterator visiting the values of the explicitly set entries in
+    /// increasing slot order, with mutable references. The iterator element
+    /// type is `&'a mut V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// sec.insert(a, 1);
+    /// sec.values_mut().for_each(|val| *val += 1);
+    /// assert_eq!(sec[a], 2);
+    /// ```
+    pub fn values_mut(&mut self) -> secondary::ValuesMut<K, V> {
+        self.map.values_mut()
+    }
+
+    /// Returns the underlying [`SecondaryMap`] of explicitly set entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// sec.insert(a, 1);
+    /// assert_eq!(sec[b], 0);
+    /// assert_eq!(sec.as_secondary_map().get(b), None);
+    /// ```
+    pub fn as_secondary_map(&self) -> &SecondaryMap<K, V> {
+        &self.map
+    }
+
+    /// Converts the map into the underlying [`SecondaryMap`] of explicitly
+    /// set entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let mut sec = DefaultSecondaryMap::new();
+    /// sec[a] = 3;
+    /// let sec = sec.into_secondary_map();
+    /// assert_eq!(sec[a], 3);
+    /// ```
+    pub fn into_secondary_map(self) -> SecondaryMap<K, V> {
+        self.map
+    }
+}
+
+impl<K: Key, V: Default> Default for DefaultSecondaryMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Key, V: Default> From<SecondaryMap<K, V>> for DefaultSecondaryMap<K, V> {
+    fn from(map: SecondaryMap<K, V>) -> Self {
+        Self {
+            map,
+            default: V::default(),
+        }
+    }
+}
+
+impl<K: Key, V> Index<K> for DefaultSecondaryMap<K, V> {
+    type Output = V;
+
+    fn index(&self, key: K) -> &V {
+        self.get(key)
+    }
+}
+
+impl<K: Key, V: Default> IndexMut<K> for DefaultSecondaryMap<K, V> {
+    fn index_mut(&mut self, key: K) -> &mut V {
+        match self.get_or_insert_default(key) {
+            Some(r) => r,
+            None => panic!("invalid DefaultSecondaryMap key used"),
+        }
+    }
+}
+
+impl<K: Key, V: Default> FromIterator<(K, V)> for DefaultSecondaryMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut sec = Self::new();
+        sec.extend(iter);
+        sec
+    }
+}
+
+impl<K: Key, V> Extend<(K, V)> for DefaultSecondaryMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.map.extend(iter)
+    }
+}
+
+impl<'a, K: Key, V> IntoIterator for &'a DefaultSecondaryMap<K, V> {
+    type Item = (K, &'a V);
+    type IntoIter = secondary::Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Key, V> IntoIterator for &'a mut DefaultSecondaryMap<K, V> {
+    type Item = (K, &'a mut V);
+    type IntoIter = secondary::IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K: Key, V> IntoIterator for DefaultSecondaryMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = secondary::IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn missing_and_stale_keys_read_default() {
+        let mut sm = SlotMap::new();
+        let keys: Vec<_> = (0..10).map(|i| sm.insert(i)).collect();
+        let mut sec: DefaultSecondaryMap<_, i32> = DefaultSecondaryMap::new();
+
+        for &key in &keys {
+            assert_eq!(sec[key], 0);
+        }
+        assert!(sec.is_empty());
+
+        for &key in keys.iter().step_by(3) {
+            sec[key] += sm[key];
+        }
+        assert_eq!(sec.len(), 4);
+        assert_eq!(sec.iter().map(|(k, &v)| (k, v)).collect::<Vec<_>>(), [
+            (keys[0], 0),
+            (keys[3], 3),
+            (keys[6], 6),
+            (keys[9], 9)
+        ]);
+
+        // A stale key reads the default, but can no longer be written.
+        sm.remove(keys[3]);
+        let reused = sm.insert(30);
+        assert_eq!(sec[reused], 0);
+        sec[reused] = 30;
+        assert_eq!(sec[keys[3]], 0);
+        assert_eq!(sec.get_or_insert_default(keys[3]), None);
+        assert!(sec.insert(keys[3], 3).is_none());
+        assert_eq!(sec[reused], 30);
+
+        // A key of the removed entry reads the default again.
+        assert_eq!(sec.remove(keys[6]), Some(6));
+        assert_eq!(sec[keys[6]], 0);
+        assert_eq!(sec.get_mut(keys[6]), None);
+        assert!(!sec.contains_key(keys[6]));
+        assert_eq!(*sec.get_or_insert_default(keys[6]).unwrap(), 0);
+        assert!(sec.contains_key(keys[6]));
+
+        let total: i32 = sec.into_iter().map(|(_, v)| v).sum();
+        assert_eq!(total, 39);
+    }
+}
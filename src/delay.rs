@@ -11,7 +11,7 @@ use std::convert::TryFrom;
 use alloc::collections::TryReserveError;
 use alloc::collections::VecDeque;
 use alloc::vec::Vec;
-use core::iter::FusedIterator;
+use core::iter::{DoubleEndedIterator, FusedIterator};
 #[allow(unused_imports)] // MaybeUninit is only used on nightly at the moment.
 use core::mem::MaybeUninit;
 use core::ops::{Index, IndexMut};
@@ -539,8 +539,8 @@ impl<K: Key, V> DelaySlotMap<K, V> {
     ///
     /// When the iterator is dropped all elements in the slot map are removed,
     /// even if the iterator was not fully consumed. If the iterator is not
-    /// dropped (using e.g. [`std::mem::forget`]), only the elements that were
-    /// iterated over are removed.
+    /// dropped (using e.g. [`std::mem::forget`]), the slot map is still
+    /// cleared but the elements that were not iterated over are leaked.
     ///
     /// # Examples
     ///
@@ -553,7 +553,17 @@ impl<K: Key, V> DelaySlotMap<K, V> {
     /// assert_eq!(v, vec![(k, 0)]);
     /// ```
     pub fn drain(&mut self) -> Drain<K, V> {
-        Drain { sm: self }
+        // Free all slots up front, last element first, so the freelist ends up
+        // the same as when the elements are popped one by one.
+        for i in (0..self.keys.len()).rev() {
+            let slot_idx = self.keys[i].data().index() as usize;
+            self.free_slot(slot_idx);
+        }
+
+        Drain {
+            inner_keys: self.keys.drain(..),
+            inner_values: self.values.drain(..),
+        }
     }
 
     /// Returns a reference to the value corresponding to the key.
@@ -870,6 +880,103 @@ impl<K: Key, V> DelaySlotMap<K, V> {
             inner: self.iter_mut(),
         }
     }
+
+    /// Returns the values as a contiguous slice, in the same order as
+    /// [`keys_as_slice`](Self::keys_as_slice). The order is arbitrary and
+    /// changes when elements are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DelaySlotMap::new();
+    /// sm.insert(1);
+    /// sm.insert(2);
+    /// sm.insert(3);
+    /// assert_eq!(sm.values_as_slice().iter().sum::<i32>(), 6);
+    /// ```
+    pub fn values_as_slice(&self) -> &[V] {
+        &self.values
+    }
+
+    /// Returns the values as a contiguous mutable slice, in the same order as
+    /// [`keys_as_slice`](Self::keys_as_slice). Reordering the slice does not
+    /// move the keys along with the values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DelaySlotMap::new();
+    /// let k = sm.insert(1);
+    /// sm.insert(2);
+    /// sm.values_as_mut_slice().iter_mut().for_each(|v| *v *= 10);
+    /// assert_eq!(sm[k], 10);
+    /// ```
+    pub fn values_as_mut_slice(&mut self) -> &mut [V] {
+        &mut self.values
+    }
+
+    /// Returns the keys as a contiguous slice. The key at position `i`
+    /// belongs to the value at position `i` of
+    /// [`values_as_slice`](Self::values_as_slice).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DelaySlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    /// for (k, v) in sm.keys_as_slice().iter().zip(sm.values_as_slice()) {
+    ///     assert_eq!(sm[*k], *v);
+    /// }
+    /// assert!(sm.keys_as_slice().contains(&k0) && sm.keys_as_slice().contains(&k1));
+    /// ```
+    pub fn keys_as_slice(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// Returns the position of the value corresponding to the key in
+    /// [`values_as_slice`](Self::values_as_slice), or [`None`] if the key is
+    /// not in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DelaySlotMap::new();
+    /// let k0 = sm.insert("foo");
+    /// let k1 = sm.insert("bar");
+    /// assert_eq!(sm.index_of(k1).map(|i| sm.values_as_slice()[i]), Some("bar"));
+    /// sm.remove(k0);
+    /// assert_eq!(sm.index_of(k0), None);
+    /// assert_eq!(sm.index_of(k1), Some(0));
+    /// ```
+    pub fn index_of(&self, key: K) -> Option<usize> {
+        let kd = key.data();
+        self.slots
+            .get(kd.index() as usize)
+            .filter(|slot| slot.version == kd.version())
+            .map(|slot| slot.idx as usize)
+    }
+
+    /// Returns the key of the value at position `dense_index` in
+    /// [`values_as_slice`](Self::values_as_slice), or [`None`] if it is out of
+    /// bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DelaySlotMap::new();
+    /// let k = sm.insert(5);
+    /// assert_eq!(sm.key_at(0), Some(k));
+    /// assert_eq!(sm.key_at(1), None);
+    /// ```
+    pub fn key_at(&self, dense_index: usize) -> Option<K> {
+        self.keys.get(dense_index).copied()
+    }
 }
 
 impl<K: Key, V> Clone for DelaySlotMap<K, V>
@@ -927,7 +1034,8 @@ impl<K: Key, V> IndexMut<K> for DelaySlotMap<K, V> {
 /// This iterator is created by [`DenseSlotMap::drain`].
 #[derive(Debug)]
 pub struct Drain<'a, K: 'a + Key, V: 'a> {
-    sm: &'a mut DelaySlotMap<K, V>,
+    inner_keys: alloc::vec::Drain<'a, K>,
+    inner_values: alloc::vec::Drain<'a, V>,
 }
 
 /// An iterator that moves key-value pairs out of a [`DenseSlotMap`].
@@ -1012,11 +1120,10 @@ impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
 
     fn next(&mut self) -> Option<(K, V)> {
         // We make no iteration order guarantees, so we just repeatedly pop.
-        let key = self.sm.keys.pop();
-        let value = self.sm.values.pop();
+        let key = self.inner_keys.next_back();
+        let value = self.inner_values.next_back();
 
         if let (Some(k), Some(v)) = (key, value) {
-            self.sm.free_slot(k.data().index() as usize);
             Some((k, v))
         } else {
             None
@@ -1024,14 +1131,20 @@ impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = self.sm.keys.len();
-        (len, Some(len))
+        self.inner_keys.size_hint()
     }
 }
 
-impl<'a, K: Key, V> Drop for Drain<'a, K, V> {
-    fn drop(&mut self) {
-        self.for_each(|_drop| {});
+impl<'a, K: Key, V> DoubleEndedIterator for Drain<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        let key = self.inner_keys.next();
+        let value = self.inner_values.next();
+
+        if let (Some(k), Some(v)) = (key, value) {
+            Some((k, v))
+        } else {
+            None
+        }
     }
 }
 
@@ -1054,6 +1167,19 @@ impl<K: Key, V> Iterator for IntoIter<K, V> {
     }
 }
 
+impl<K: Key, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        let key = self.inner_keys.next_back();
+        let value = self.inner_values.next_back();
+
+        if let (Some(k), Some(v)) = (key, value) {
+            Some((k, v))
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a, K: 'a + Key, V> Iterator for Iter<'a, K, V> {
     type Item = (K, &'a V);
 
@@ -1073,6 +1199,19 @@ impl<'a, K: 'a + Key, V> Iterator for Iter<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a + Key, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a V)> {
+        let key = self.inner_keys.next_back();
+        let value = self.inner_values.next_back();
+
+        if let (Some(k), Some(v)) = (key, value) {
+            Some((*k, v))
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a, K: 'a + Key, V> Iterator for IterMut<'a, K, V> {
     type Item = (K, &'a mut V);
 
@@ -1092,6 +1231,19 @@ impl<'a, K: 'a + Key, V> Iterator for IterMut<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a + Key, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a mut V)> {
+        let key = self.inner_keys.next_back();
+        let value = self.inner_values.next_back();
+
+        if let (Some(k), Some(v)) = (key, value) {
+            Some((*k, v))
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a, K: 'a + Key, V> Iterator for Keys<'a, K, V> {
     type Item = K;
 
@@ -1104,6 +1256,12 @@ impl<'a, K: 'a + Key, V> Iterator for Keys<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a + Key, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<K> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
 impl<'a, K: 'a + Key, V> Iterator for Values<'a, K, V> {
     type Item = &'a V;
 
@@ -1116,6 +1274,12 @@ impl<'a, K: 'a + Key, V> Iterator for Values<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a + Key, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
 impl<'a, K: 'a + Key, V> Iterator for ValuesMut<'a, K, V> {
     type Item = &'a mut V;
 
@@ -1128,6 +1292,12 @@ impl<'a, K: 'a + Key, V> Iterator for ValuesMut<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a + Key, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
 impl<'a, K: 'a + Key, V> IntoIterator for &'a DelaySlotMap<K, V> {
     type Item = (K, &'a V);
     type IntoIter = Iter<'a, K, V>;
@@ -1174,6 +1344,102 @@ impl<'a, K: 'a + Key, V> ExactSizeIterator for ValuesMut<'a, K, V> {}
 impl<'a, K: 'a + Key, V> ExactSizeIterator for Drain<'a, K, V> {}
 impl<K: Key, V> ExactSizeIterator for IntoIter<K, V> {}
 
+// Parallel iteration with rayon.
+#[cfg(feature = "rayon")]
+mod parallel {
+    use rayon::prelude::*;
+
+    use super::*;
+
+    impl<K: Key, V> DelaySlotMap<K, V> {
+        /// A parallel iterator visiting all key-value pairs in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = DelaySlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// let sum: u64 = sm.par_iter().map(|(_, &v)| v).sum();
+        /// assert_eq!(sum, 500 * 999);
+        /// ```
+        pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = (K, &V)>
+        where
+            K: Send + Sync,
+            V: Sync,
+        {
+            self.keys.par_iter().copied().zip(self.values.par_iter())
+        }
+
+        /// A parallel iterator visiting all key-value pairs in arbitrary order,
+        /// with mutable references to the values.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = DelaySlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// sm.par_iter_mut().for_each(|(_, v)| *v *= 2);
+        /// assert_eq!(sm.values().sum::<u64>(), 1000 * 999);
+        /// ```
+        pub fn par_iter_mut(&mut self) -> impl IndexedParallelIterator<Item = (K, &mut V)>
+        where
+            K: Send + Sync,
+            V: Send,
+        {
+            self.keys.par_iter().copied().zip(self.values.par_iter_mut())
+        }
+
+        /// A parallel iterator visiting all keys in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = DelaySlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// assert_eq!(sm.par_keys().count(), 1000);
+        /// ```
+        pub fn par_keys(&self) -> impl IndexedParallelIterator<Item = K> + '_
+        where
+            K: Send + Sync,
+        {
+            self.keys.par_iter().copied()
+        }
+
+        /// A parallel iterator visiting all values mutably in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = DelaySlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// sm.par_values_mut().for_each(|v| *v += 1);
+        /// assert_eq!(sm.values().sum::<u64>(), 500 * 1001);
+        /// ```
+        pub fn par_values_mut(&mut self) -> impl IndexedParallelIterator<Item = &mut V>
+        where
+            V: Send,
+        {
+            self.values.par_iter_mut()
+        }
+    }
+}
+
 // Serialization with serde.
 #[cfg(feature = "serde")]
 mod serialize {
@@ -9,7 +9,8 @@
 #[cfg(all(nightly, any(doc, feature = "unstable")))]
 use alloc::collections::TryReserveError;
 use alloc::vec::Vec;
-use core::iter::FusedIterator;
+use core::cmp::Ordering;
+use core::iter::{DoubleEndedIterator, FusedIterator};
 #[allow(unused_imports)] // MaybeUninit is only used on nightly at the moment.
 use core::mem::MaybeUninit;
 use core::ops::{Index, IndexMut};
@@ -454,8 +455,8 @@ impl<K: Key, V> DenseSlotMap<K, V> {
     ///
     /// When the iterator is dropped all elements in the slot map are removed,
     /// even if the iterator was not fully consumed. If the iterator is not
-    /// dropped (using e.g. [`std::mem::forget`]), only the elements that were
-    /// iterated over are removed.
+    /// dropped (using e.g. [`std::mem::forget`]), the slot map is still
+    /// cleared but the elements that were not iterated over are leaked.
     ///
     /// # Examples
     ///
@@ -468,7 +469,17 @@ impl<K: Key, V> DenseSlotMap<K, V> {
     /// assert_eq!(v, vec![(k, 0)]);
     /// ```
     pub fn drain(&mut self) -> Drain<K, V> {
-        Drain { sm: self }
+        // Free all slots up front, last element first, so the freelist ends up
+        // the same as when the elements are popped one by one.
+        for i in (0..self.keys.len()).rev() {
+            let slot_idx = self.keys[i].data().index() as usize;
+            self.free_slot(slot_idx);
+        }
+
+        Drain {
+            inner_keys: self.keys.drain(..),
+            inner_values: self.values.drain(..),
+        }
     }
 
     /// Returns a reference to the value corresponding to the key.
@@ -569,6 +580,20 @@ impl<K: Key, V> DenseSlotMap<K, V> {
         self.values.get_unchecked_mut(idx as usize)
     }
 
+    // Like `contains_key` and `get_unchecked_mut`, but through a raw pointer
+    // and without creating a reference to the values, so that references
+    // previously handed out to other values stay valid. Used by mutable joins.
+    pub(crate) unsafe fn raw_contains_key(this: *const Self, key: K) -> bool {
+        // The slots are stored apart from the values.
+        (*this).contains_key(key)
+    }
+
+    pub(crate) unsafe fn raw_get_unchecked_mut(this: *mut Self, key: K) -> *mut V {
+        let slots = &(*this).slots;
+        let idx = slots.get_unchecked(key.data().index() as usize).idx_or_free;
+        (*this).values.as_mut_ptr().add(idx as usize)
+    }
+
     /// Returns mutable references to the values corresponding to the given
     /// keys. All keys must be valid and disjoint, otherwise [`None`] is
     /// returned.
@@ -785,6 +810,306 @@ impl<K: Key, V> DenseSlotMap<K, V> {
             inner: self.iter_mut(),
         }
     }
+
+    /// Returns the values as a contiguous slice, in the same order as
+    /// [`keys_as_slice`](Self::keys_as_slice). The order is arbitrary and
+    /// changes when elements are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DenseSlotMap::new();
+    /// sm.insert(1);
+    /// sm.insert(2);
+    /// sm.insert(3);
+    /// assert_eq!(sm.values_as_slice().iter().sum::<i32>(), 6);
+    /// ```
+    pub fn values_as_slice(&self) -> &[V] {
+        &self.values
+    }
+
+    /// Returns the values as a contiguous mutable slice, in the same order as
+    /// [`keys_as_slice`](Self::keys_as_slice). Reordering the slice does not
+    /// move the keys along with the values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DenseSlotMap::new();
+    /// let k = sm.insert(1);
+    /// sm.insert(2);
+    /// sm.values_as_mut_slice().iter_mut().for_each(|v| *v *= 10);
+    /// assert_eq!(sm[k], 10);
+    /// ```
+    pub fn values_as_mut_slice(&mut self) -> &mut [V] {
+        &mut self.values
+    }
+
+    /// Returns the keys as a contiguous slice. The key at position `i`
+    /// belongs to the value at position `i` of
+    /// [`values_as_slice`](Self::values_as_slice).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DenseSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    /// for (k, v) in sm.keys_as_slice().iter().zip(sm.values_as_slice()) {
+    ///     assert_eq!(sm[*k], *v);
+    /// }
+    /// assert!(sm.keys_as_slice().contains(&k0) && sm.keys_as_slice().contains(&k1));
+    /// ```
+    pub fn keys_as_slice(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// Returns the position of the value corresponding to the key in
+    /// [`values_as_slice`](Self::values_as_slice), or [`None`] if the key is
+    /// not in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DenseSlotMap::new();
+    /// let k0 = sm.insert("foo");
+    /// let k1 = sm.insert("bar");
+    /// assert_eq!(sm.index_of(k1).map(|i| sm.values_as_slice()[i]), Some("bar"));
+    /// sm.remove(k0);
+    /// assert_eq!(sm.index_of(k0), None);
+    /// assert_eq!(sm.index_of(k1), Some(0));
+    /// ```
+    pub fn index_of(&self, key: K) -> Option<usize> {
+        let kd = key.data();
+        self.slots
+            .get(kd.index() as usize)
+            .filter(|slot| slot.version == kd.version())
+            .map(|slot| slot.idx_or_free as usize)
+    }
+
+    /// Returns the key of the value at position `dense_index` in
+    /// [`values_as_slice`](Self::values_as_slice), or [`None`] if it is out of
+    /// bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DenseSlotMap::new();
+    /// let k = sm.insert(5);
+    /// assert_eq!(sm.key_at(0), Some(k));
+    /// assert_eq!(sm.key_at(1), None);
+    /// ```
+    pub fn key_at(&self, dense_index: usize) -> Option<K> {
+        self.keys.get(dense_index).copied()
+    }
+
+    /// Sorts the values with a comparator function, keeping all keys valid.
+    /// Iteration and [`values_as_slice`](Self::values_as_slice) follow the
+    /// sorted order until the next removal.
+    ///
+    /// This sort is stable. It allocates a temporary buffer of indices, the
+    /// values themselves are moved in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DenseSlotMap::new();
+    /// let k3 = sm.insert(3);
+    /// let k1 = sm.insert(1);
+    /// let k2 = sm.insert(2);
+    /// sm.sort_by(|a, b| a.cmp(b));
+    /// assert_eq!(sm.values_as_slice(), [1, 2, 3]);
+    /// assert_eq!(sm.keys_as_slice(), [k1, k2, k3]);
+    /// assert_eq!(sm[k3], 3);
+    /// ```
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&V, &V) -> Ordering,
+    {
+        let mut order: Vec<usize> = (0..self.values.len()).collect();
+        order.sort_by(|&a, &b| compare(&self.values[a], &self.values[b]));
+        self.reorder(order);
+    }
+
+    /// Sorts the values with a key extraction function, keeping all keys
+    /// valid. See [`sort_by`](Self::sort_by).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DenseSlotMap::new();
+    /// let ka = sm.insert(("a", 2));
+    /// let kb = sm.insert(("b", 1));
+    /// sm.sort_by_key(|&(_, material)| material);
+    /// assert_eq!(sm.keys_as_slice(), [kb, ka]);
+    /// assert_eq!(sm[ka], ("a", 2));
+    /// ```
+    pub fn sort_by_key<T, F>(&mut self, mut f: F)
+    where
+        T: Ord,
+        F: FnMut(&V) -> T,
+    {
+        self.sort_by(|a, b| f(a).cmp(&f(b)))
+    }
+
+    /// Sorts the values with a comparator function, keeping all keys valid.
+    /// See [`sort_by`](Self::sort_by), but this sort is not stable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DenseSlotMap::new();
+    /// let k = sm.insert(5);
+    /// sm.insert(-2);
+    /// sm.insert(0);
+    /// sm.sort_unstable_by(|a, b| a.cmp(b));
+    /// assert_eq!(sm.values_as_slice(), [-2, 0, 5]);
+    /// assert_eq!(sm.index_of(k), Some(2));
+    /// ```
+    pub fn sort_unstable_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&V, &V) -> Ordering,
+    {
+        let mut order: Vec<usize> = (0..self.values.len()).collect();
+        order.sort_unstable_by(|&a, &b| compare(&self.values[a], &self.values[b]));
+        self.reorder(order);
+    }
+
+    /// Swaps the positions of the values of two keys in
+    /// [`values_as_slice`](Self::values_as_slice), keeping both keys valid.
+    /// Returns `false` and does nothing if either key is not in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = DenseSlotMap::new();
+    /// let k0 = sm.insert("foo");
+    /// let k1 = sm.insert("bar");
+    /// assert!(sm.swap_positions(k0, k1));
+    /// assert_eq!(sm.values_as_slice(), ["bar", "foo"]);
+    /// assert_eq!(sm[k0], "foo");
+    /// ```
+    pub fn swap_positions(&mut self, k1: K, k2: K) -> bool {
+        let (i1, i2) = match (self.index_of(k1), self.index_of(k2)) {
+            (Some(i1), Some(i2)) => (i1, i2),
+            _ => return false,
+        };
+
+        self.swap_dense(i1, i2);
+        true
+    }
+
+    // Swaps the elements at two dense indices and their slot back-pointers.
+    // Both must be in bounds.
+    pub(crate) fn swap_dense(&mut self, i1: usize, i2: usize) {
+        self.keys.swap(i1, i2);
+        self.values.swap(i1, i2);
+        self.fix_slots(i1..=i1);
+        self.fix_slots(i2..=i2);
+    }
+
+    // Iterates over the elements in a range of dense indices.
+    pub(crate) fn iter_dense(&self, range: core::ops::Range<usize>) -> Iter<K, V> {
+        Iter {
+            inner_keys: self.keys[range.clone()].iter(),
+            inner_values: self.values[range].iter(),
+        }
+    }
+
+    // Iterates mutably over the elements in a range of dense indices.
+    pub(crate) fn iter_dense_mut(&mut self, range: core::ops::Range<usize>) -> IterMut<K, V> {
+        IterMut {
+            inner_keys: self.keys[range.clone()].iter(),
+            inner_values: self.values[range].iter_mut(),
+        }
+    }
+
+    // Moves the element at dense index order[i] to dense index i for all i,
+    // then points the slots at the new positions. The order must be a
+    // permutation of 0..len.
+    pub(crate) fn reorder(&mut self, mut order: Vec<usize>) {
+        for start in 0..order.len() {
+            // Follow the cycle through start, marking visited positions by
+            // making them fixed points.
+            let mut cur = start;
+            loop {
+                let next = order[cur];
+                order[cur] = cur;
+                if next == start || next == cur {
+                    break;
+                }
+                self.keys.swap(cur, next);
+                self.values.swap(cur, next);
+                cur = next;
+            }
+        }
+
+        for (idx, key) in self.keys.iter().enumerate() {
+            self.slots[key.data().index() as usize].idx_or_free = idx as u32;
+        }
+    }
+
+    // Points the slots of the elements in the given dense index range at their
+    // current positions.
+    fn fix_slots(&mut self, range: core::ops::RangeInclusive<usize>) {
+        for idx in range {
+            let slot_idx = self.keys[idx].data().index() as usize;
+            self.slots[slot_idx].idx_or_free = idx as u32;
+        }
+    }
+
+    // Moves the element at dense index from to dense index to, shifting the
+    // elements in between by one position. Both must be in bounds.
+    pub(crate) fn move_position(&mut self, from: usize, to: usize) {
+        if from < to {
+            self.keys[from..=to].rotate_left(1);
+            self.values[from..=to].rotate_left(1);
+            self.fix_slots(from..=to);
+        } else if to < from {
+            self.keys[to..=from].rotate_right(1);
+            self.values[to..=from].rotate_right(1);
+            self.fix_slots(to..=from);
+        }
+    }
+
+    // Like remove, but shifts all elements after the removed one down instead
+    // of swapping the last element into its place. This is O(n).
+    pub(crate) fn shift_remove(&mut self, key: K) -> Option<V> {
+        let idx = self.index_of(key)?;
+        self.move_position(idx, self.keys.len() - 1);
+        self.remove(key)
+    }
+
+    // Like retain, but keeps the retained elements in their relative order.
+    pub(crate) fn retain_ordered<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        // Retained elements are swapped down to the front, the removed ones
+        // collect at the back. All slots stay correct after every step.
+        let mut kept = 0;
+        for i in 0..self.keys.len() {
+            let key = self.keys[i];
+            if f(key, &mut self.values[i]) {
+                self.swap_dense(kept, i);
+                kept += 1;
+            }
+        }
+
+        while self.keys.len() > kept {
+            let slot_idx = self.keys[self.keys.len() - 1].data().index() as usize;
+            self.remove_from_slot(slot_idx);
+        }
+    }
 }
 
 impl<K: Key, V> Clone for DenseSlotMap<K, V>
@@ -840,7 +1165,8 @@ impl<K: Key, V> IndexMut<K> for DenseSlotMap<K, V> {
 /// This iterator is created by [`DenseSlotMap::drain`].
 #[derive(Debug)]
 pub struct Drain<'a, K: 'a + Key, V: 'a> {
-    sm: &'a mut DenseSlotMap<K, V>,
+    inner_keys: alloc::vec::Drain<'a, K>,
+    inner_values: alloc::vec::Drain<'a, V>,
 }
 
 /// An iterator that moves key-value pairs out of a [`DenseSlotMap`].
@@ -925,11 +1251,10 @@ impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
 
     fn next(&mut self) -> Option<(K, V)> {
         // We make no iteration order guarantees, so we just repeatedly pop.
-        let key = self.sm.keys.pop();
-        let value = self.sm.values.pop();
+        let key = self.inner_keys.next_back();
+        let value = self.inner_values.next_back();
 
         if let (Some(k), Some(v)) = (key, value) {
-            self.sm.free_slot(k.data().index() as usize);
             Some((k, v))
         } else {
             None
@@ -937,14 +1262,20 @@ impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = self.sm.keys.len();
-        (len, Some(len))
+        self.inner_keys.size_hint()
     }
 }
 
-impl<'a, K: Key, V> Drop for Drain<'a, K, V> {
-    fn drop(&mut self) {
-        self.for_each(|_drop| {});
+impl<'a, K: Key, V> DoubleEndedIterator for Drain<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        let key = self.inner_keys.next();
+        let value = self.inner_values.next();
+
+        if let (Some(k), Some(v)) = (key, value) {
+            Some((k, v))
+        } else {
+            None
+        }
     }
 }
 
@@ -967,6 +1298,19 @@ impl<K: Key, V> Iterator for IntoIter<K, V> {
     }
 }
 
+impl<K: Key, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        let key = self.inner_keys.next_back();
+        let value = self.inner_values.next_back();
+
+        if let (Some(k), Some(v)) = (key, value) {
+            Some((k, v))
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a, K: 'a + Key, V> Iterator for Iter<'a, K, V> {
     type Item = (K, &'a V);
 
@@ -986,6 +1330,19 @@ impl<'a, K: 'a + Key, V> Iterator for Iter<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a + Key, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a V)> {
+        let key = self.inner_keys.next_back();
+        let value = self.inner_values.next_back();
+
+        if let (Some(k), Some(v)) = (key, value) {
+            Some((*k, v))
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a, K: 'a + Key, V> Iterator for IterMut<'a, K, V> {
     type Item = (K, &'a mut V);
 
@@ -1005,6 +1362,19 @@ impl<'a, K: 'a + Key, V> Iterator for IterMut<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a + Key, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a mut V)> {
+        let key = self.inner_keys.next_back();
+        let value = self.inner_values.next_back();
+
+        if let (Some(k), Some(v)) = (key, value) {
+            Some((*k, v))
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a, K: 'a + Key, V> Iterator for Keys<'a, K, V> {
     type Item = K;
 
@@ -1017,6 +1387,12 @@ impl<'a, K: 'a + Key, V> Iterator for Keys<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a + Key, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<K> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
 impl<'a, K: 'a + Key, V> Iterator for Values<'a, K, V> {
     type Item = &'a V;
 
@@ -1029,6 +1405,12 @@ impl<'a, K: 'a + Key, V> Iterator for Values<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a + Key, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
 impl<'a, K: 'a + Key, V> Iterator for ValuesMut<'a, K, V> {
     type Item = &'a mut V;
 
@@ -1041,6 +1423,12 @@ impl<'a, K: 'a + Key, V> Iterator for ValuesMut<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a + Key, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
 impl<'a, K: 'a + Key, V> IntoIterator for &'a DenseSlotMap<K, V> {
     type Item = (K, &'a V);
     type IntoIter = Iter<'a, K, V>;
@@ -1087,6 +1475,102 @@ impl<'a, K: 'a + Key, V> ExactSizeIterator for ValuesMut<'a, K, V> {}
 impl<'a, K: 'a + Key, V> ExactSizeIterator for Drain<'a, K, V> {}
 impl<K: Key, V> ExactSizeIterator for IntoIter<K, V> {}
 
+// Parallel iteration with rayon.
+#[cfg(feature = "rayon")]
+mod parallel {
+    use rayon::prelude::*;
+
+    use super::*;
+
+    impl<K: Key, V> DenseSlotMap<K, V> {
+        /// A parallel iterator visiting all key-value pairs in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = DenseSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// let sum: u64 = sm.par_iter().map(|(_, &v)| v).sum();
+        /// assert_eq!(sum, 500 * 999);
+        /// ```
+        pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = (K, &V)>
+        where
+            K: Send + Sync,
+            V: Sync,
+        {
+            self.keys.par_iter().copied().zip(self.values.par_iter())
+        }
+
+        /// A parallel iterator visiting all key-value pairs in arbitrary order,
+        /// with mutable references to the values.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = DenseSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// sm.par_iter_mut().for_each(|(_, v)| *v *= 2);
+        /// assert_eq!(sm.values().sum::<u64>(), 1000 * 999);
+        /// ```
+        pub fn par_iter_mut(&mut self) -> impl IndexedParallelIterator<Item = (K, &mut V)>
+        where
+            K: Send + Sync,
+            V: Send,
+        {
+            self.keys.par_iter().copied().zip(self.values.par_iter_mut())
+        }
+
+        /// A parallel iterator visiting all keys in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = DenseSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// assert_eq!(sm.par_keys().count(), 1000);
+        /// ```
+        pub fn par_keys(&self) -> impl IndexedParallelIterator<Item = K> + '_
+        where
+            K: Send + Sync,
+        {
+            self.keys.par_iter().copied()
+        }
+
+        /// A parallel iterator visiting all values mutably in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = DenseSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// sm.par_values_mut().for_each(|v| *v += 1);
+        /// assert_eq!(sm.values().sum::<u64>(), 500 * 1001);
+        /// ```
+        pub fn par_values_mut(&mut self) -> impl IndexedParallelIterator<Item = &mut V>
+        where
+            V: Send,
+        {
+            self.values.par_iter_mut()
+        }
+    }
+}
+
 // Serialization with serde.
 #[cfg(feature = "serde")]
 mod serialize {
@@ -1311,6 +1795,87 @@ mod tests {
         }
     }
 
+    #[test]
+    fn double_ended() {
+        let mut sm = DenseSlotMap::new();
+        let keys: Vec<_> = (0..10).map(|i| sm.insert(i)).collect();
+        sm.remove(keys[2]);
+
+        let forward: Vec<_> = sm.iter().map(|(k, &v)| (k, v)).collect();
+        assert!(sm.iter().rev().map(|(k, &v)| (k, v)).eq(forward.iter().rev().cloned()));
+        assert!(sm.keys().rev().eq(forward.iter().rev().map(|&(k, _)| k)));
+        sm.values_mut().rev().for_each(|v| *v *= 2);
+        assert!(sm.values().rev().eq(forward.iter().rev().map(|&(_, v)| v * 2).collect::<Vec<_>>().iter()));
+
+        let mut drain = sm.drain();
+        let back = drain.next_back();
+        let front = drain.next();
+        assert_eq!(drain.len(), 7);
+        drop(drain);
+        assert_eq!(back, Some((forward[0].0, forward[0].1 * 2)));
+        assert_eq!(front, Some((forward[8].0, forward[8].1 * 2)));
+        assert!(sm.is_empty());
+        assert!(keys.iter().all(|&k| !sm.contains_key(k)));
+
+        // Slots are reused like before.
+        let k = sm.insert(42);
+        assert_eq!(k.data().index(), keys[0].data().index());
+    }
+
+    #[test]
+    fn slice_access() {
+        let mut sm = DenseSlotMap::new();
+        let keys: Vec<_> = (0..10).map(|i| sm.insert(i)).collect();
+        for &k in &[keys[0], keys[4], keys[9]] {
+            sm.remove(k);
+        }
+
+        assert_eq!(sm.keys_as_slice().len(), sm.values_as_slice().len());
+        for (i, (&k, &v)) in sm.keys_as_slice().iter().zip(sm.values_as_slice()).enumerate() {
+            assert_eq!(sm.index_of(k), Some(i));
+            assert_eq!(sm.key_at(i), Some(k));
+            assert_eq!(sm[k], v);
+        }
+        assert_eq!(sm.index_of(keys[4]), None);
+        assert_eq!(sm.key_at(sm.len()), None);
+
+        sm.values_as_mut_slice().sort_unstable();
+        assert_eq!(sm.values_as_slice(), [1, 2, 3, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn sort_keeps_keys_valid() {
+        let mut sm = DenseSlotMap::new();
+        let keys: Vec<_> = (0..50).map(|i| sm.insert((i * 37) % 50)).collect();
+        for &k in keys.iter().step_by(7) {
+            sm.remove(k);
+        }
+
+        sm.sort_by_key(|&v| v);
+        assert!(sm.values_as_slice().windows(2).all(|w| w[0] <= w[1]));
+        for (i, &k) in keys.iter().enumerate() {
+            if i % 7 != 0 {
+                assert_eq!(sm[k], (i * 37) % 50);
+            }
+        }
+
+        sm.sort_unstable_by(|a, b| b.cmp(a));
+        assert!(sm.values_as_slice().windows(2).all(|w| w[0] >= w[1]));
+        for (i, &k) in sm.keys_as_slice().iter().enumerate() {
+            assert_eq!(sm.index_of(k), Some(i));
+        }
+
+        let (first, last) = (sm.key_at(0).unwrap(), sm.key_at(sm.len() - 1).unwrap());
+        assert!(sm.swap_positions(first, last));
+        assert_eq!(sm.key_at(0), Some(last));
+        assert_eq!(sm.index_of(first), Some(sm.len() - 1));
+        assert!(!sm.swap_positions(first, keys[0]));
+
+        // Removal still swap-removes correctly after sorting.
+        sm.remove(last);
+        assert!(sm.keys().all(|k| sm.contains_key(k)));
+    }
+
     quickcheck! {
         fn qc_slotmap_equiv_hashmap(operations: Vec<(u8, u32)>) -> bool {
             let mut hm = HashMap::new();
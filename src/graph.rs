@@ -0,0 +1,945 @@
+//! Contains the slot graph implementation, a directed graph whose nodes and
+//! edges are addressed by slot map keys.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::{basic, SecondaryMap, SlotMap};
+use pi_key_alloter::{DefaultKey, Key};
+
+// A node together with the heads of its outgoing and incoming edge lists.
+#[derive(Debug, Clone)]
+struct Node<EK, N> {
+    value: N,
+    first_out: EK,
+    first_in: EK,
+}
+
+// An edge together with its endpoints and its links in the outgoing edge list
+// of its source and the incoming edge list of its target. Null keys mark the
+// ends of the lists.
+#[derive(Debug, Clone)]
+struct Edge<NK, EK, E> {
+    value: E,
+    source: NK,
+    target: NK,
+    prev_out: EK,
+    next_out: EK,
+    prev_in: EK,
+    next_in: EK,
+}
+
+/// Slot graph, a directed graph with nodes and edges stored in two
+/// [`SlotMap`]s.
+///
+/// Nodes are addressed by keys of type `NK` and edges by keys of type `EK`,
+/// both generational, so keys of removed nodes and edges are rejected rather
+/// than pointing at reused storage. Using distinct key types for the two
+/// (see [`new_key_type!`](crate::new_key_type)) prevents mixing them up.
+///
+/// Every node keeps linked lists of its outgoing and incoming edges, so
+/// adding and removing an edge is O(1), and removing a node is linear in the
+/// number of its edges, which are removed along with it. Parallel edges and
+/// self loops are allowed. Adjacent edges are visited from the most recently
+/// added one.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut graph: SlotGraph<_, _, &str, u32> = SlotGraph::new();
+/// let a = graph.add_node("a");
+/// let b = graph.add_node("b");
+/// let c = graph.add_node("c");
+/// let ab = graph.add_edge(a, b, 1).unwrap();
+/// graph.add_edge(b, c, 2);
+///
+/// assert_eq!(graph.endpoints(ab), Some((a, b)));
+/// assert_eq!(graph.neighbors(b).collect::<Vec<_>>(), [c]);
+/// assert_eq!(graph.toposort(), Some(vec![a, b, c]));
+///
+/// graph.remove_node(b);
+/// assert_eq!(graph.edge_count(), 0);
+/// assert_eq!(graph.edge(ab), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlotGraph<NK: Key, EK: Key, N, E> {
+    nodes: SlotMap<NK, Node<EK, N>>,
+    edges: SlotMap<EK, Edge<NK, EK, E>>,
+}
+
+impl<N, E> SlotGraph<DefaultKey, DefaultKey, N, E> {
+    /// Constructs a new, empty [`SlotGraph`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let graph: SlotGraph<_, _, i32, ()> = SlotGraph::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity_and_key(0, 0)
+    }
+
+    /// Creates an empty [`SlotGraph`] with room for the given number of nodes
+    /// and edges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let graph: SlotGraph<_, _, i32, ()> = SlotGraph::with_capacity(10, 20);
+    /// ```
+    pub fn with_capacity(nodes: usize, edges: usize) -> Self {
+        Self::with_capacity_and_key(nodes, edges)
+    }
+}
+
+impl<NK: Key, EK: Key, N, E> SlotGraph<NK, EK, N, E> {
+    /// Constructs a new, empty [`SlotGraph`] with custom key types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct NodeKey;
+    ///     struct EdgeKey;
+    /// }
+    /// let graph: SlotGraph<NodeKey, EdgeKey, i32, ()> = SlotGraph::with_key();
+    /// ```
+    pub fn with_key() -> Self {
+        Self::with_capacity_and_key(0, 0)
+    }
+
+    /// Creates an empty [`SlotGraph`] with room for the given number of nodes
+    /// and edges, and custom key types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct NodeKey;
+    ///     struct EdgeKey;
+    /// }
+    /// let graph: SlotGraph<NodeKey, EdgeKey, i32, ()> =
+    ///     SlotGraph::with_capacity_and_key(10, 20);
+    /// ```
+    pub fn with_capacity_and_key(nodes: usize, edges: usize) -> Self {
+        Self {
+            nodes: SlotMap::with_capacity_and_key(nodes),
+            edges: SlotMap::with_capacity_and_key(edges),
+        }
+    }
+
+    /// Returns the number of nodes in the graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, i32, ()> = SlotGraph::new();
+    /// graph.add_node(0);
+    /// assert_eq!(graph.node_count(), 1);
+    /// ```
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the number of edges in the graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, i32, ()> = SlotGraph::new();
+    /// let a = graph.add_node(0);
+    /// graph.add_edge(a, a, ());
+    /// assert_eq!(graph.edge_count(), 1);
+    /// ```
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Returns if the graph has no nodes, and hence no edges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, i32, ()> = SlotGraph::new();
+    /// assert!(graph.is_empty());
+    /// graph.add_node(0);
+    /// assert!(!graph.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Removes all nodes and edges. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, i32, ()> = SlotGraph::new();
+    /// let a = graph.add_node(0);
+    /// graph.add_edge(a, a, ());
+    /// graph.clear();
+    /// assert_eq!((graph.node_count(), graph.edge_count()), (0, 0));
+    /// ```
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.edges.clear();
+    }
+
+    /// Returns [`true`] if the graph contains the node of `node`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, i32, ()> = SlotGraph::new();
+    /// let a = graph.add_node(0);
+    /// assert!(graph.contains_node(a));
+    /// graph.remove_node(a);
+    /// assert!(!graph.contains_node(a));
+    /// ```
+    pub fn contains_node(&self, node: NK) -> bool {
+        self.nodes.contains_key(node)
+    }
+
+    /// Returns [`true`] if the graph contains the edge of `edge`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, i32, ()> = SlotGraph::new();
+    /// let a = graph.add_node(0);
+    /// let e = graph.add_edge(a, a, ()).unwrap();
+    /// assert!(graph.contains_edge(e));
+    /// graph.remove_edge(e);
+    /// assert!(!graph.contains_edge(e));
+    /// ```
+    pub fn contains_edge(&self, edge: EK) -> bool {
+        self.edges.contains_key(edge)
+    }
+
+    /// Adds a node to the graph, returning its key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of nodes in the graph equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, &str, ()> = SlotGraph::new();
+    /// let a = graph.add_node("a");
+    /// assert_eq!(graph.node(a), Some(&"a"));
+    /// ```
+    pub fn add_node(&mut self, value: N) -> NK {
+        self.nodes.insert(Node {
+            value,
+            first_out: EK::null(),
+            first_in: EK::null(),
+        })
+    }
+
+    /// Adds an edge from `source` to `target`, returning its key. Returns
+    /// [`None`] and drops the value if either node is not in the graph.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of edges in the graph equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), &str> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let b = graph.add_node(());
+    /// let e = graph.add_edge(a, b, "a to b").unwrap();
+    /// assert_eq!(graph.edge(e), Some(&"a to b"));
+    /// graph.remove_node(b);
+    /// assert_eq!(graph.add_edge(a, b, "dangling"), None);
+    /// ```
+    pub fn add_edge(&mut self, source: NK, target: NK, value: E) -> Option<EK> {
+        let next_out = self.nodes.get(source)?.first_out;
+        let next_in = self.nodes.get(target)?.first_in;
+        let edge = self.edges.insert(Edge {
+            value,
+            source,
+            target,
+            prev_out: EK::null(),
+            next_out,
+            prev_in: EK::null(),
+            next_in,
+        });
+
+        if let Some(next) = self.edges.get_mut(next_out) {
+            next.prev_out = edge;
+        }
+        if let Some(next) = self.edges.get_mut(next_in) {
+            next.prev_in = edge;
+        }
+        self.nodes[source].first_out = edge;
+        self.nodes[target].first_in = edge;
+        Some(edge)
+    }
+
+    /// Removes a node and all of its incident edges from the graph, returning
+    /// the value of the node if it was not previously removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, &str, ()> = SlotGraph::new();
+    /// let a = graph.add_node("a");
+    /// let b = graph.add_node("b");
+    /// graph.add_edge(a, b, ());
+    /// graph.add_edge(b, a, ());
+    /// assert_eq!(graph.remove_node(a), Some("a"));
+    /// assert_eq!(graph.remove_node(a), None);
+    /// assert_eq!(graph.edge_count(), 0);
+    /// ```
+    pub fn remove_node(&mut self, node: NK) -> Option<N> {
+        loop {
+            let first_out = self.nodes.get(node)?.first_out;
+            if self.remove_edge(first_out).is_none() {
+                break;
+            }
+        }
+        loop {
+            let first_in = self.nodes[node].first_in;
+            if self.remove_edge(first_in).is_none() {
+                break;
+            }
+        }
+        self.nodes.remove(node).map(|node| node.value)
+    }
+
+    /// Removes an edge from the graph, returning its value if it was not
+    /// previously removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), i32> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let e = graph.add_edge(a, a, 7).unwrap();
+    /// assert_eq!(graph.remove_edge(e), Some(7));
+    /// assert_eq!(graph.remove_edge(e), None);
+    /// ```
+    pub fn remove_edge(&mut self, edge: EK) -> Option<E> {
+        let removed = self.edges.remove(edge)?;
+
+        match self.edges.get_mut(removed.prev_out) {
+            Some(prev) => prev.next_out = removed.next_out,
+            None => self.nodes[removed.source].first_out = removed.next_out,
+        }
+        if let Some(next) = self.edges.get_mut(removed.next_out) {
+            next.prev_out = removed.prev_out;
+        }
+        match self.edges.get_mut(removed.prev_in) {
+            Some(prev) => prev.next_in = removed.next_in,
+            None => self.nodes[removed.target].first_in = removed.next_in,
+        }
+        if let Some(next) = self.edges.get_mut(removed.next_in) {
+            next.prev_in = removed.prev_in;
+        }
+        Some(removed.value)
+    }
+
+    /// Returns a reference to the value of a node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, &str, ()> = SlotGraph::new();
+    /// let a = graph.add_node("a");
+    /// assert_eq!(graph.node(a), Some(&"a"));
+    /// ```
+    pub fn node(&self, node: NK) -> Option<&N> {
+        self.nodes.get(node).map(|node| &node.value)
+    }
+
+    /// Returns a mutable reference to the value of a node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, i32, ()> = SlotGraph::new();
+    /// let a = graph.add_node(1);
+    /// *graph.node_mut(a).unwrap() += 1;
+    /// assert_eq!(graph.node(a), Some(&2));
+    /// ```
+    pub fn node_mut(&mut self, node: NK) -> Option<&mut N> {
+        self.nodes.get_mut(node).map(|node| &mut node.value)
+    }
+
+    /// Returns a reference to the value of an edge.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), f32> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let e = graph.add_edge(a, a, 0.5).unwrap();
+    /// assert_eq!(graph.edge(e), Some(&0.5));
+    /// ```
+    pub fn edge(&self, edge: EK) -> Option<&E> {
+        self.edges.get(edge).map(|edge| &edge.value)
+    }
+
+    /// Returns a mutable reference to the value of an edge.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), f32> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let e = graph.add_edge(a, a, 0.5).unwrap();
+    /// *graph.edge_mut(e).unwrap() *= 4.0;
+    /// assert_eq!(graph.edge(e), Some(&2.0));
+    /// ```
+    pub fn edge_mut(&mut self, edge: EK) -> Option<&mut E> {
+        self.edges.get_mut(edge).map(|edge| &mut edge.value)
+    }
+
+    /// Returns the source and target node of an edge.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), ()> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let b = graph.add_node(());
+    /// let e = graph.add_edge(a, b, ()).unwrap();
+    /// assert_eq!(graph.endpoints(e), Some((a, b)));
+    /// ```
+    pub fn endpoints(&self, edge: EK) -> Option<(NK, NK)> {
+        self.edges.get(edge).map(|edge| (edge.source, edge.target))
+    }
+
+    /// Returns the key of an edge from `source` to `target`, if there is one.
+    /// This is linear in the number of outgoing edges of `source`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), ()> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let b = graph.add_node(());
+    /// let e = graph.add_edge(a, b, ()).unwrap();
+    /// assert_eq!(graph.find_edge(a, b), Some(e));
+    /// assert_eq!(graph.find_edge(b, a), None);
+    /// ```
+    pub fn find_edge(&self, source: NK, target: NK) -> Option<EK> {
+        self.outgoing(source)
+            .find(|&(_, node, _)| node == target)
+            .map(|(edge, _, _)| edge)
+    }
+
+    /// An iterator visiting all nodes in arbitrary order. The iterator element
+    /// type is `(NK, &'a N)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, i32, ()> = SlotGraph::new();
+    /// graph.add_node(1);
+    /// graph.add_node(2);
+    /// assert_eq!(graph.nodes().map(|(_, v)| v).sum::<i32>(), 3);
+    /// ```
+    pub fn nodes(&self) -> Nodes<NK, EK, N> {
+        Nodes {
+            inner: self.nodes.iter(),
+        }
+    }
+
+    /// An iterator visiting all edges in arbitrary order. The iterator element
+    /// type is `(EK, &'a E)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), i32> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// graph.add_edge(a, a, 1);
+    /// graph.add_edge(a, a, 2);
+    /// assert_eq!(graph.edges().map(|(_, v)| v).sum::<i32>(), 3);
+    /// ```
+    pub fn edges(&self) -> Edges<NK, EK, E> {
+        Edges {
+            inner: self.edges.iter(),
+        }
+    }
+
+    /// An iterator visiting the outgoing edges of a node, most recently added
+    /// first. The iterator element type is `(EK, NK, &'a E)`, with the target
+    /// node of each edge. It is empty if the node is not in the graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), &str> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let b = graph.add_node(());
+    /// let e = graph.add_edge(a, b, "e").unwrap();
+    /// assert_eq!(graph.outgoing(a).collect::<Vec<_>>(), [(e, b, &"e")]);
+    /// ```
+    pub fn outgoing(&self, node: NK) -> Adjacent<NK, EK, E> {
+        let first = self.nodes.get(node).map_or_else(EK::null, |n| n.first_out);
+        Adjacent {
+            edges: &self.edges,
+            cur: first,
+            outgoing: true,
+        }
+    }
+
+    /// An iterator visiting the incoming edges of a node, most recently added
+    /// first. The iterator element type is `(EK, NK, &'a E)`, with the source
+    /// node of each edge. It is empty if the node is not in the graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), &str> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let b = graph.add_node(());
+    /// let e = graph.add_edge(a, b, "e").unwrap();
+    /// assert_eq!(graph.incoming(b).collect::<Vec<_>>(), [(e, a, &"e")]);
+    /// ```
+    pub fn incoming(&self, node: NK) -> Adjacent<NK, EK, E> {
+        let first = self.nodes.get(node).map_or_else(EK::null, |n| n.first_in);
+        Adjacent {
+            edges: &self.edges,
+            cur: first,
+            outgoing: false,
+        }
+    }
+
+    /// An iterator visiting the targets of the outgoing edges of a node. A
+    /// node is visited once per edge leading to it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), ()> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let b = graph.add_node(());
+    /// graph.add_edge(a, b, ());
+    /// assert_eq!(graph.neighbors(a).collect::<Vec<_>>(), [b]);
+    /// assert_eq!(graph.neighbors(b).count(), 0);
+    /// ```
+    pub fn neighbors(&self, node: NK) -> impl Iterator<Item = NK> + '_ {
+        self.outgoing(node).map(|(_, target, _)| target)
+    }
+
+    /// An iterator visiting the sources of the incoming edges of a node. A
+    /// node is visited once per edge coming from it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), ()> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let b = graph.add_node(());
+    /// graph.add_edge(a, b, ());
+    /// assert_eq!(graph.predecessors(b).collect::<Vec<_>>(), [a]);
+    /// ```
+    pub fn predecessors(&self, node: NK) -> impl Iterator<Item = NK> + '_ {
+        self.incoming(node).map(|(_, source, _)| source)
+    }
+
+    /// A breadth-first iterator over the nodes reachable from `start`
+    /// following outgoing edges, starting with `start` itself. It is empty if
+    /// `start` is not in the graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), ()> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let b = graph.add_node(());
+    /// let c = graph.add_node(());
+    /// let d = graph.add_node(());
+    /// graph.add_edge(a, b, ());
+    /// graph.add_edge(b, c, ());
+    /// graph.add_edge(c, a, ());
+    /// assert_eq!(graph.bfs(a).collect::<Vec<_>>(), [a, b, c]);
+    /// assert_eq!(graph.bfs(d).collect::<Vec<_>>(), [d]);
+    /// ```
+    pub fn bfs(&self, start: NK) -> Bfs<NK, EK, N, E> {
+        let mut visited = SecondaryMap::new();
+        let mut queue = VecDeque::new();
+        if self.contains_node(start) {
+            visited.insert(start, ());
+            queue.push_back(start);
+        }
+        Bfs {
+            graph: self,
+            queue,
+            visited,
+        }
+    }
+
+    /// A depth-first pre-order iterator over the nodes reachable from `start`
+    /// following outgoing edges, starting with `start` itself. It is empty if
+    /// `start` is not in the graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, (), ()> = SlotGraph::new();
+    /// let a = graph.add_node(());
+    /// let b = graph.add_node(());
+    /// let c = graph.add_node(());
+    /// let d = graph.add_node(());
+    /// graph.add_edge(a, c, ());
+    /// graph.add_edge(a, b, ());
+    /// graph.add_edge(b, d, ());
+    /// assert_eq!(graph.dfs(a).collect::<Vec<_>>(), [a, b, d, c]);
+    /// ```
+    pub fn dfs(&self, start: NK) -> Dfs<NK, EK, N, E> {
+        let mut stack = Vec::new();
+        if self.contains_node(start) {
+            stack.push(start);
+        }
+        Dfs {
+            graph: self,
+            stack,
+            visited: SecondaryMap::new(),
+        }
+    }
+
+    /// Returns all nodes ordered such that every edge points from an earlier
+    /// to a later node, or [`None`] if the graph has a cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut graph: SlotGraph<_, _, &str, ()> = SlotGraph::new();
+    /// let shirt = graph.add_node("shirt");
+    /// let tie = graph.add_node("tie");
+    /// let jacket = graph.add_node("jacket");
+    /// graph.add_edge(tie, jacket, ());
+    /// graph.add_edge(shirt, tie, ());
+    /// assert_eq!(graph.toposort(), Some(vec![shirt, tie, jacket]));
+    ///
+    /// graph.add_edge(jacket, shirt, ());
+    /// assert_eq!(graph.toposort(), None);
+    /// ```
+    pub fn toposort(&self) -> Option<Vec<NK>> {
+        // Kahn's algorithm: repeatedly emit a node without remaining incoming
+        // edges.
+        let mut in_degree = SecondaryMap::with_capacity(self.nodes.capacity());
+        let mut order = Vec::with_capacity(self.node_count());
+        for (node, _) in self.nodes.iter() {
+            let degree = self.incoming(node).count();
+            in_degree.insert(node, degree);
+            if degree == 0 {
+                order.push(node);
+            }
+        }
+
+        let mut next = 0;
+        while let Some(&node) = order.get(next) {
+            next += 1;
+            for target in self.neighbors(node) {
+                let degree = &mut in_degree[target];
+                *degree -= 1;
+                if *degree == 0 {
+                    order.push(target);
+                }
+            }
+        }
+
+        if order.len() == self.node_count() {
+            Some(order)
+        } else {
+            None
+        }
+    }
+}
+
+impl<NK: Key, EK: Key, N, E> Default for SlotGraph<NK, EK, N, E> {
+    fn default() -> Self {
+        Self::with_key()
+    }
+}
+
+// Iterators.
+/// An iterator over the nodes in a [`SlotGraph`], in arbitrary order.
+///
+/// This iterator is created by [`SlotGraph::nodes`].
+#[derive(Debug)]
+pub struct Nodes<'a, NK: Key + 'a, EK: 'a, N: 'a> {
+    inner: basic::Iter<'a, NK, Node<EK, N>>,
+}
+
+impl<'a, NK: 'a + Key, EK: 'a, N: 'a> Clone for Nodes<'a, NK, EK, N> {
+    fn clone(&self) -> Self {
+        Nodes {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// An iterator over the edges in a [`SlotGraph`], in arbitrary order.
+///
+/// This iterator is created by [`SlotGraph::edges`].
+#[derive(Debug)]
+pub struct Edges<'a, NK: 'a, EK: Key + 'a, E: 'a> {
+    inner: basic::Iter<'a, EK, Edge<NK, EK, E>>,
+}
+
+impl<'a, NK: 'a, EK: 'a + Key, E: 'a> Clone for Edges<'a, NK, EK, E> {
+    fn clone(&self) -> Self {
+        Edges {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// An iterator over the outgoing or incoming edges of a node in a
+/// [`SlotGraph`].
+///
+/// This iterator is created by [`SlotGraph::outgoing`] and
+/// [`SlotGraph::incoming`].
+#[derive(Debug)]
+pub struct Adjacent<'a, NK: 'a, EK: Key + 'a, E: 'a> {
+    edges: &'a SlotMap<EK, Edge<NK, EK, E>>,
+    cur: EK,
+    outgoing: bool,
+}
+
+impl<'a, NK: 'a, EK: 'a + Key, E: 'a> Clone for Adjacent<'a, NK, EK, E> {
+    fn clone(&self) -> Self {
+        Adjacent {
+            edges: self.edges,
+            cur: self.cur,
+            outgoing: self.outgoing,
+        }
+    }
+}
+
+/// A breadth-first iterator over the nodes in a [`SlotGraph`].
+///
+/// This iterator is created by [`SlotGraph::bfs`].
+#[derive(Debug, Clone)]
+pub struct Bfs<'a, NK: Key + 'a, EK: Key + 'a, N: 'a, E: 'a> {
+    graph: &'a SlotGraph<NK, EK, N, E>,
+    queue: VecDeque<NK>,
+    visited: SecondaryMap<NK, ()>,
+}
+
+/// A depth-first iterator over the nodes in a [`SlotGraph`].
+///
+/// This iterator is created by [`SlotGraph::dfs`].
+#[derive(Debug, Clone)]
+pub struct Dfs<'a, NK: Key + 'a, EK: Key + 'a, N: 'a, E: 'a> {
+    graph: &'a SlotGraph<NK, EK, N, E>,
+    stack: Vec<NK>,
+    visited: SecondaryMap<NK, ()>,
+}
+
+impl<'a, NK: Key, EK, N> Iterator for Nodes<'a, NK, EK, N> {
+    type Item = (NK, &'a N);
+
+    fn next(&mut self) -> Option<(NK, &'a N)> {
+        self.inner.next().map(|(key, node)| (key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, NK, EK: Key, E> Iterator for Edges<'a, NK, EK, E> {
+    type Item = (EK, &'a E);
+
+    fn next(&mut self) -> Option<(EK, &'a E)> {
+        self.inner.next().map(|(key, edge)| (key, &edge.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, NK: Copy, EK: Key, E> Iterator for Adjacent<'a, NK, EK, E> {
+    type Item = (EK, NK, &'a E);
+
+    fn next(&mut self) -> Option<(EK, NK, &'a E)> {
+        let key = self.cur;
+        let edge = self.edges.get(key)?;
+        if self.outgoing {
+            self.cur = edge.next_out;
+            Some((key, edge.target, &edge.value))
+        } else {
+            self.cur = edge.next_in;
+            Some((key, edge.source, &edge.value))
+        }
+    }
+}
+
+impl<'a, NK: Key, EK: Key, N, E> Iterator for Bfs<'a, NK, EK, N, E> {
+    type Item = NK;
+
+    fn next(&mut self) -> Option<NK> {
+        let node = self.queue.pop_front()?;
+        for target in self.graph.neighbors(node) {
+            if self.visited.insert(target, ()).is_none() {
+                self.queue.push_back(target);
+            }
+        }
+        Some(node)
+    }
+}
+
+impl<'a, NK: Key, EK: Key, N, E> Iterator for Dfs<'a, NK, EK, N, E> {
+    type Item = NK;
+
+    fn next(&mut self) -> Option<NK> {
+        while let Some(node) = self.stack.pop() {
+            if self.visited.insert(node, ()).is_some() {
+                continue;
+            }
+
+            // Push the neighbours reversed, so they are visited in order.
+            let pushed = self.stack.len();
+            self.stack.extend(
+                self.graph
+                    .neighbors(node)
+                    .filter(|&target| !self.visited.contains_key(target)),
+            );
+            self.stack[pushed..].reverse();
+            return Some(node);
+        }
+        None
+    }
+}
+
+impl<'a, NK: Key, EK, N> FusedIterator for Nodes<'a, NK, EK, N> {}
+impl<'a, NK, EK: Key, E> FusedIterator for Edges<'a, NK, EK, E> {}
+impl<'a, NK: Copy, EK: Key, E> FusedIterator for Adjacent<'a, NK, EK, E> {}
+impl<'a, NK: Key, EK: Key, N, E> FusedIterator for Bfs<'a, NK, EK, N, E> {}
+impl<'a, NK: Key, EK: Key, N, E> FusedIterator for Dfs<'a, NK, EK, N, E> {}
+
+impl<'a, NK: Key, EK, N> ExactSizeIterator for Nodes<'a, NK, EK, N> {}
+impl<'a, NK, EK: Key, E> ExactSizeIterator for Edges<'a, NK, EK, E> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    new_key_type! {
+        struct NodeKey;
+        struct EdgeKey;
+    }
+
+    fn check<N, E>(graph: &SlotGraph<NodeKey, EdgeKey, N, E>) {
+        let mut num_out = 0;
+        let mut num_in = 0;
+        for (node, _) in graph.nodes() {
+            for (edge, target, _) in graph.outgoing(node) {
+                assert_eq!(graph.endpoints(edge), Some((node, target)));
+                num_out += 1;
+            }
+            for (edge, source, _) in graph.incoming(node) {
+                assert_eq!(graph.endpoints(edge), Some((source, node)));
+                num_in += 1;
+            }
+        }
+        assert_eq!(num_out, graph.edge_count());
+        assert_eq!(num_in, graph.edge_count());
+    }
+
+    #[test]
+    fn remove_node_removes_edges() {
+        let mut graph: SlotGraph<NodeKey, EdgeKey, u32, u32> = SlotGraph::with_key();
+        let nodes: Vec<_> = (0..5).map(|i| graph.add_node(i)).collect();
+        let mut edges = Vec::new();
+        for (i, &a) in nodes.iter().enumerate() {
+            for (j, &b) in nodes.iter().enumerate() {
+                if (i + j) % 2 == 0 {
+                    edges.push(graph.add_edge(a, b, (10 * i + j) as u32).unwrap());
+                }
+            }
+        }
+        check(&graph);
+        assert_eq!(graph.edge_count(), 13);
+
+        assert_eq!(graph.remove_edge(edges[4]), Some(13));
+        assert_eq!(graph.remove_node(nodes[2]), Some(2));
+        check(&graph);
+        assert_eq!(graph.edge_count(), 7);
+        assert!(graph.edges().all(|(e, _)| {
+            let (a, b) = graph.endpoints(e).unwrap();
+            a != nodes[2] && b != nodes[2]
+        }));
+        assert_eq!(graph.neighbors(nodes[0]).count(), 2);
+        assert_eq!(graph.predecessors(nodes[4]).count(), 2);
+        assert_eq!(graph.find_edge(nodes[0], nodes[2]), None);
+        assert!(graph.find_edge(nodes[4], nodes[4]).is_some());
+
+        // Stale keys stay stale after their storage is reused.
+        let n = graph.add_node(9);
+        assert!(graph.add_edge(nodes[2], n, 0).is_none());
+        assert_eq!(graph.outgoing(nodes[2]).count(), 0);
+        assert_eq!(graph.remove_node(nodes[2]), None);
+        check(&graph);
+    }
+
+    #[test]
+    fn traversals() {
+        let mut graph: SlotGraph<NodeKey, EdgeKey, usize, ()> = SlotGraph::with_key();
+        let n: Vec<_> = (0..6).map(|i| graph.add_node(i)).collect();
+        for &(a, b) in &[(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)] {
+            graph.add_edge(n[a], n[b], ());
+        }
+
+        let bfs: Vec<_> = graph.bfs(n[0]).map(|k| *graph.node(k).unwrap()).collect();
+        assert_eq!(bfs.len(), 5);
+        assert_eq!((bfs[0], bfs[3], bfs[4]), (0, 3, 4));
+        let dfs: Vec<_> = graph.dfs(n[0]).map(|k| *graph.node(k).unwrap()).collect();
+        assert_eq!(dfs, [0, 2, 3, 4, 1]);
+
+        let order = graph.toposort().unwrap();
+        let pos = |k| order.iter().position(|&o| o == k).unwrap();
+        assert_eq!(order.len(), 6);
+        for (e, _) in graph.edges() {
+            let (a, b) = graph.endpoints(e).unwrap();
+            assert!(pos(a) < pos(b));
+        }
+
+        let back = graph.add_edge(n[4], n[1], ()).unwrap();
+        assert_eq!(graph.toposort(), None);
+        graph.remove_edge(back);
+        assert!(graph.toposort().is_some());
+    }
+}
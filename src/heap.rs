@@ -0,0 +1,650 @@
+//! Contains the keyed heap implementation, a priority queue whose entries can
+//! be looked up, reprioritized and removed by key.
+
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+use core::ops::{Index, IndexMut};
+
+use crate::{basic, SecondaryMap, SlotMap};
+use pi_key_alloter::{DefaultKey, Key};
+
+// A heap entry together with its position in the heap array.
+#[derive(Debug, Clone)]
+struct Entry<P, V> {
+    priority: P,
+    value: V,
+    pos: usize,
+}
+
+/// Keyed heap, a binary min-heap backed by a [`SlotMap`].
+///
+/// Pushing an entry returns a key, which can later be used to read the entry,
+/// change its priority or remove it, all in O(log n). Keys of popped or
+/// removed entries are rejected through their version, like any slot map key.
+/// This makes it a good fit for algorithms that need a decrease-key
+/// operation, such as Dijkstra's shortest paths or timer queues.
+///
+/// The entry with the lowest priority is at the top. Wrap priorities in
+/// [`core::cmp::Reverse`] to get a max-heap instead. Entries with equal
+/// priorities are popped in an unspecified order.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut heap = KeyedHeap::new();
+/// let a = heap.push(3, "a");
+/// let b = heap.push(5, "b");
+/// heap.push(4, "c");
+///
+/// heap.change_priority(b, 1);
+/// assert_eq!(heap.pop(), Some((b, 1, "b")));
+/// assert_eq!(heap.remove(a), Some((3, "a")));
+/// assert_eq!(heap.change_priority(a, 0), None);
+/// assert_eq!(heap.peek().map(|(_, p, v)| (*p, *v)), Some((4, "c")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeyedHeap<K: Key, P, V> {
+    sm: SlotMap<K, Entry<P, V>>,
+    heap: Vec<K>,
+}
+
+impl<P, V> KeyedHeap<DefaultKey, P, V> {
+    /// Constructs a new, empty [`KeyedHeap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap: KeyedHeap<_, u32, &str> = KeyedHeap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`KeyedHeap`] with the given capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap: KeyedHeap<_, u32, &str> = KeyedHeap::with_capacity(10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_key(capacity)
+    }
+}
+
+impl<K: Key, P, V> KeyedHeap<K, P, V> {
+    /// Constructs a new, empty [`KeyedHeap`] with a custom key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct TaskKey;
+    /// }
+    /// let mut heap: KeyedHeap<TaskKey, u32, &str> = KeyedHeap::with_key();
+    /// ```
+    pub fn with_key() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`KeyedHeap`] with the given capacity and a custom key
+    /// type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct TaskKey;
+    /// }
+    /// let mut heap: KeyedHeap<TaskKey, u32, &str> = KeyedHeap::with_capacity_and_key(10);
+    /// ```
+    pub fn with_capacity_and_key(capacity: usize) -> Self {
+        Self {
+            sm: SlotMap::with_capacity_and_key(capacity),
+            heap: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of entries in the heap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// heap.push(1, ());
+    /// heap.push(1, ());
+    /// assert_eq!(heap.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Returns if the heap is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// let key = heap.push(1, "dummy");
+    /// assert_eq!(heap.is_empty(), false);
+    /// heap.remove(key);
+    /// assert_eq!(heap.is_empty(), true);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Reserves capacity for at least `additional` more entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new allocation size overflows [`usize`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// heap.push(1, "foo");
+    /// heap.reserve(32);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.sm.reserve(additional);
+        self.heap.reserve(additional);
+    }
+
+    /// Returns [`true`] if the heap contains `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// let key = heap.push(1, 42);
+    /// assert_eq!(heap.contains_key(key), true);
+    /// heap.pop();
+    /// assert_eq!(heap.contains_key(key), false);
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.sm.contains_key(key)
+    }
+
+    /// Clears the heap. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// let key = heap.push(1, 0);
+    /// heap.clear();
+    /// assert_eq!(heap.len(), 0);
+    /// assert_eq!(heap.get(key), None);
+    /// ```
+    pub fn clear(&mut self) {
+        self.sm.clear();
+        self.heap.clear();
+    }
+
+    /// Returns the key, priority and value of the entry with the lowest
+    /// priority, or [`None`] if the heap is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// assert!(heap.peek().is_none());
+    /// heap.push(2, 'b');
+    /// let a = heap.push(1, 'a');
+    /// assert_eq!(heap.peek(), Some((a, &1, &'a')));
+    /// ```
+    pub fn peek(&self) -> Option<(K, &P, &V)> {
+        let key = *self.heap.first()?;
+        let entry = &self.sm[key];
+        Some((key, &entry.priority, &entry.value))
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// let key = heap.push(1, "bar");
+    /// assert_eq!(heap.get(key), Some(&"bar"));
+    /// heap.remove(key);
+    /// assert_eq!(heap.get(key), None);
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.sm.get(key).map(|entry| &entry.value)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key. The
+    /// priority can only be changed through
+    /// [`change_priority`](Self::change_priority).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// let key = heap.push(1, 3.5);
+    /// if let Some(x) = heap.get_mut(key) {
+    ///     *x += 3.0;
+    /// }
+    /// assert_eq!(heap[key], 6.5);
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.sm.get_mut(key).map(|entry| &mut entry.value)
+    }
+
+    /// Returns a reference to the priority of the entry of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// let key = heap.push(7, ());
+    /// assert_eq!(heap.priority(key), Some(&7));
+    /// ```
+    pub fn priority(&self, key: K) -> Option<&P> {
+        self.sm.get(key).map(|entry| &entry.priority)
+    }
+
+    /// An iterator visiting all entries in arbitrary order. The iterator
+    /// element type is `(K, &'a P, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// heap.push(2, 'b');
+    /// heap.push(1, 'a');
+    /// let mut entries: Vec<_> = heap.iter().map(|(_, p, v)| (*p, *v)).collect();
+    /// entries.sort();
+    /// assert_eq!(entries, [(1, 'a'), (2, 'b')]);
+    /// ```
+    pub fn iter(&self) -> Iter<K, P, V> {
+        Iter {
+            inner: self.sm.iter(),
+        }
+    }
+
+    // Puts the entry of key at pos in the heap array, keeping its position up
+    // to date.
+    fn place(&mut self, pos: usize, key: K) {
+        self.heap[pos] = key;
+        self.sm[key].pos = pos;
+    }
+}
+
+impl<K: Key, P: Ord, V> KeyedHeap<K, P, V> {
+    /// Pushes an entry onto the heap, returning its key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of entries in the heap equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// let key = heap.push(10, "ten");
+    /// assert_eq!(heap[key], "ten");
+    /// ```
+    pub fn push(&mut self, priority: P, value: V) -> K {
+        let pos = self.heap.len();
+        let key = self.sm.insert(Entry {
+            priority,
+            value,
+            pos,
+        });
+        self.heap.push(key);
+        self.sift_up(pos);
+        key
+    }
+
+    /// Removes the entry with the lowest priority and returns its key,
+    /// priority and value, or [`None`] if the heap is empty. The returned key
+    /// is no longer valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// heap.push(2, 'b');
+    /// let a = heap.push(1, 'a');
+    /// assert_eq!(heap.pop(), Some((a, 1, 'a')));
+    /// assert_eq!(heap.pop().map(|(_, p, v)| (p, v)), Some((2, 'b')));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    pub fn pop(&mut self) -> Option<(K, P, V)> {
+        let key = *self.heap.first()?;
+        self.remove(key).map(|(priority, value)| (key, priority, value))
+    }
+
+    /// Removes a key from the heap, returning the priority and value of the
+    /// entry if the key was not previously removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// let key = heap.push(1, 'a');
+    /// assert_eq!(heap.remove(key), Some((1, 'a')));
+    /// assert_eq!(heap.remove(key), None);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<(P, V)> {
+        let entry = self.sm.remove(key)?;
+        let last = self.heap.pop().unwrap();
+        if entry.pos < self.heap.len() {
+            self.place(entry.pos, last);
+            self.restore(entry.pos);
+        }
+        Some((entry.priority, entry.value))
+    }
+
+    /// Changes the priority of the entry of `key`, returning the old priority,
+    /// or [`None`] if the key is not in the heap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// heap.push(1, 'a');
+    /// let b = heap.push(2, 'b');
+    /// assert_eq!(heap.change_priority(b, 0), Some(2));
+    /// assert_eq!(heap.peek(), Some((b, &0, &'b')));
+    /// ```
+    pub fn change_priority(&mut self, key: K, priority: P) -> Option<P> {
+        let entry = self.sm.get_mut(key)?;
+        let old = core::mem::replace(&mut entry.priority, priority);
+        let pos = entry.pos;
+        self.restore(pos);
+        Some(old)
+    }
+
+    /// Moves all entries of `other` into this heap.
+    ///
+    /// The entries get new keys in this heap. The returned map translates the
+    /// keys of `other` to them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut heap = KeyedHeap::new();
+    /// heap.push(2, 'b');
+    /// let mut other = KeyedHeap::new();
+    /// let a = other.push(1, 'a');
+    ///
+    /// let moved = heap.meld(other);
+    /// assert_eq!(heap.len(), 2);
+    /// assert_eq!(heap.peek(), Some((moved[a], &1, &'a')));
+    /// ```
+    pub fn meld(&mut self, other: Self) -> SecondaryMap<K, K> {
+        let mut moved = SecondaryMap::with_capacity(other.sm.capacity());
+        self.reserve(other.len());
+        for (old_key, entry) in other.sm {
+            let pos = self.heap.len();
+            let key = self.sm.insert(Entry { pos, ..entry });
+            self.heap.push(key);
+            moved.insert(old_key, key);
+        }
+
+        // Heapify bottom-up, which is linear rather than O(m log(n + m)).
+        for pos in (0..self.heap.len() / 2).rev() {
+            self.sift_down(pos);
+        }
+        moved
+    }
+
+    // Moves the entry at pos up or down until the heap property holds again.
+    fn restore(&mut self, pos: usize) {
+        if pos > 0 && self.less(pos, (pos - 1) / 2) {
+            self.sift_up(pos);
+        } else {
+            self.sift_down(pos);
+        }
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        let key = self.heap[pos];
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if self.sm[self.heap[parent]].priority <= self.sm[key].priority {
+                break;
+            }
+            self.place(pos, self.heap[parent]);
+            pos = parent;
+        }
+        self.place(pos, key);
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        let key = self.heap[pos];
+        loop {
+            let mut child = 2 * pos + 1;
+            if child >= self.heap.len() {
+                break;
+            }
+            if child + 1 < self.heap.len() && self.less(child + 1, child) {
+                child += 1;
+            }
+            if self.sm[key].priority <= self.sm[self.heap[child]].priority {
+                break;
+            }
+            self.place(pos, self.heap[child]);
+            pos = child;
+        }
+        self.place(pos, key);
+    }
+
+    fn less(&self, a: usize, b: usize) -> bool {
+        self.sm[self.heap[a]].priority < self.sm[self.heap[b]].priority
+    }
+}
+
+impl<K: Key, P, V> Default for KeyedHeap<K, P, V> {
+    fn default() -> Self {
+        Self::with_key()
+    }
+}
+
+impl<K: Key, P, V> Index<K> for KeyedHeap<K, P, V> {
+    type Output = V;
+
+    fn index(&self, key: K) -> &V {
+        match self.get(key) {
+            Some(r) => r,
+            None => panic!("invalid KeyedHeap key used"),
+        }
+    }
+}
+
+impl<K: Key, P, V> IndexMut<K> for KeyedHeap<K, P, V> {
+    fn index_mut(&mut self, key: K) -> &mut V {
+        match self.get_mut(key) {
+            Some(r) => r,
+            None => panic!("invalid KeyedHeap key used"),
+        }
+    }
+}
+
+impl<K: Key, P: Ord, V> Extend<(P, V)> for KeyedHeap<K, P, V> {
+    fn extend<I: IntoIterator<Item = (P, V)>>(&mut self, iter: I) {
+        for (priority, value) in iter {
+            self.push(priority, value);
+        }
+    }
+}
+
+// Iterators.
+/// An iterator over the entries in a [`KeyedHeap`], in arbitrary order.
+///
+/// This iterator is created by [`KeyedHeap::iter`].
+#[derive(Debug)]
+pub struct Iter<'a, K: Key + 'a, P: 'a, V: 'a> {
+    inner: basic::Iter<'a, K, Entry<P, V>>,
+}
+
+impl<'a, K: 'a + Key, P: 'a, V: 'a> Clone for Iter<'a, K, P, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K: Key, P, V> Iterator for Iter<'a, K, P, V> {
+    type Item = (K, &'a P, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(key, entry)| (key, &entry.priority, &entry.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K: Key, P, V> IntoIterator for &'a KeyedHeap<K, P, V> {
+    type Item = (K, &'a P, &'a V);
+    type IntoIter = Iter<'a, K, P, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Key, P, V> FusedIterator for Iter<'a, K, P, V> {}
+
+impl<'a, K: Key, P, V> ExactSizeIterator for Iter<'a, K, P, V> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use quickcheck::quickcheck;
+    use std::collections::BTreeSet;
+
+    fn check<K: Key, P: Ord, V>(heap: &KeyedHeap<K, P, V>) {
+        for (pos, &key) in heap.heap.iter().enumerate() {
+            assert_eq!(heap.sm[key].pos, pos);
+            if pos > 0 {
+                let parent = heap.heap[(pos - 1) / 2];
+                assert!(heap.sm[parent].priority <= heap.sm[key].priority);
+            }
+        }
+        assert_eq!(heap.sm.len(), heap.heap.len());
+    }
+
+    #[test]
+    fn stale_keys_rejected() {
+        let mut heap = KeyedHeap::new();
+        let a = heap.push(1, 'a');
+        heap.pop();
+        let b = heap.push(2, 'b');
+        assert_ne!(a, b);
+        assert_eq!(heap.change_priority(a, 0), None);
+        assert_eq!(heap.remove(a), None);
+        assert_eq!(heap.get(a), None);
+        assert_eq!(heap.peek(), Some((b, &2, &'b')));
+    }
+
+    quickcheck! {
+        fn qc_heap_vs_btreeset(operations: Vec<(u8, u32)>) -> bool {
+            let mut heap = KeyedHeap::new();
+            let mut other = KeyedHeap::new();
+            let mut set = BTreeSet::new();
+            let mut keys = Vec::new();
+
+            for (op, val) in operations {
+                match op % 5 {
+                    // Push.
+                    0 | 1 => {
+                        let id = keys.len();
+                        keys.push((heap.push(val, id), val, id));
+                        set.insert((val, id));
+                    }
+
+                    // Pop.
+                    2 => {
+                        let expected = set.iter().next().copied();
+                        let popped = heap.pop().map(|(_, p, v)| (p, v));
+                        if popped.map(|(p, _)| p) != expected.map(|(p, _)| p) {
+                            return false;
+                        }
+                        if let Some(entry) = popped {
+                            set.remove(&entry);
+                        }
+                    }
+
+                    // Change priority.
+                    3 => {
+                        if keys.is_empty() {
+                            continue;
+                        }
+                        let i = val as usize % keys.len();
+                        let (key, prio, id) = keys[i];
+                        if let Some(old) = heap.change_priority(key, val / 2) {
+                            if old != prio || !set.remove(&(old, id)) {
+                                return false;
+                            }
+                            keys[i].1 = val / 2;
+                            set.insert((val / 2, id));
+                        }
+                    }
+
+                    // Remove.
+                    _ => {
+                        if keys.is_empty() {
+                            continue;
+                        }
+                        let (key, _, _) = keys[val as usize % keys.len()];
+                        if let Some(entry) = heap.remove(key) {
+                            if !set.remove(&entry) {
+                                return false;
+                            }
+                        }
+                    }
+                }
+
+                if heap.len() != set.len() {
+                    return false;
+                }
+            }
+            check(&heap);
+
+            // Meld half of the entries into another heap and drain both.
+            for _ in 0..heap.len() / 2 {
+                let (_, p, v) = heap.pop().unwrap();
+                other.push(p, v);
+            }
+            heap.meld(other);
+            check(&heap);
+            let mut drained = Vec::new();
+            while let Some((_, p, v)) = heap.pop() {
+                drained.push((p, v));
+            }
+            drained.sort();
+            drained.into_iter().eq(set)
+        }
+    }
+}
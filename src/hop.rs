@@ -18,14 +18,14 @@
 use alloc::collections::TryReserveError;
 use alloc::vec::Vec;
 use core::fmt;
-use core::iter::FusedIterator;
+use core::iter::{DoubleEndedIterator, FusedIterator};
 use core::marker::PhantomData;
 use core::mem::ManuallyDrop;
 #[allow(unused_imports)] // MaybeUninit is only used on nightly at the moment.
 use core::mem::MaybeUninit;
-use core::ops::{Index, IndexMut};
+use core::ops::{Index, IndexMut, RangeBounds};
 
-use crate::util::{Never, UnwrapUnchecked};
+use crate::util::{slot_range, Never, UnwrapUnchecked};
 use pi_key_alloter::{DefaultKey, Key, key_data};
 
 // Metadata to maintain the freelist.
@@ -91,6 +91,47 @@ impl<T> Slot<T> {
     }
 }
 
+// Returns one past the index of the last occupied slot. If the slots end in a
+// block of vacant slots this is the front of that block.
+fn occupied_end<T>(slots: &[Slot<T>]) -> usize {
+    match slots.last().map(|slot| slot.get()) {
+        Some(Vacant(free)) => free.other_end as usize,
+        _ => slots.len(),
+    }
+}
+
+// Returns the index of the last slot in the block of vacant slots containing
+// the vacant slot idx. Only the ends of a block hold valid freelist entries,
+// so the block is located by walking the freelist from the sentinel. A plain
+// scan runs alongside the walk, which finds the end of short blocks sooner.
+fn vacant_block_back<T>(slots: &[Slot<T>], idx: usize) -> usize {
+    // All unchecked accesses are safe due to the invariants of the freelist.
+    unsafe {
+        // The block at the start of the slots has the sentinel as its front.
+        let sentinel = slots.get_unchecked(0).u.free;
+        if idx <= sentinel.other_end as usize {
+            return sentinel.other_end as usize;
+        }
+
+        let mut front = sentinel.next as usize;
+        let mut scan = idx;
+        loop {
+            if front != 0 {
+                let free = slots.get_unchecked(front).u.free;
+                if front <= idx && idx <= free.other_end as usize {
+                    return free.other_end as usize;
+                }
+                front = free.next as usize;
+            }
+
+            match slots.get(scan + 1) {
+                Some(slot) if !slot.occupied() => scan += 1,
+                _ => return scan,
+            }
+        }
+    }
+}
+
 impl<T> Drop for Slot<T> {
     fn drop(&mut self) {
         if core::mem::needs_drop::<T>() && self.occupied() {
@@ -665,6 +706,7 @@ impl<K: Key, V> HopSlotMap<K, V> {
     pub fn drain(&mut self) -> Drain<K, V> {
         Drain {
             cur: unsafe { self.slots.get_unchecked(0).u.free.other_end as usize + 1 },
+            end: occupied_end(&self.slots),
             sm: self,
         }
     }
@@ -761,6 +803,21 @@ impl<K: Key, V> HopSlotMap<K, V> {
         &mut self.slots.get_unchecked_mut(key.data().index() as usize).u.value
     }
 
+    // Like `contains_key` and `get_unchecked_mut`, but through a raw pointer
+    // and without creating a reference to the slots, so that references
+    // previously handed out to other values stay valid. Used by mutable joins.
+    pub(crate) unsafe fn raw_contains_key(this: *const Self, key: K) -> bool {
+        let kd = key.data();
+        let slots = &(*this).slots;
+        let idx = kd.index() as usize;
+        idx < slots.len() && (*slots.as_ptr().add(idx)).version == kd.version()
+    }
+
+    pub(crate) unsafe fn raw_get_unchecked_mut(this: *mut Self, key: K) -> *mut V {
+        let slot = (*this).slots.as_mut_ptr().add(key.data().index() as usize);
+        core::ptr::addr_of_mut!((*slot).u.value).cast()
+    }
+
     /// Returns mutable references to the values corresponding to the given
     /// keys. All keys must be valid and disjoint, otherwise [`None`] is
     /// returned.
@@ -879,6 +936,7 @@ impl<K: Key, V> HopSlotMap<K, V> {
     pub fn iter(&self) -> Iter<K, V> {
         Iter {
             cur: unsafe { self.slots.get_unchecked(0).u.free.other_end as usize + 1 },
+            end: occupied_end(&self.slots),
             num_left: self.len(),
             slots: &self.slots[..],
             _k: PhantomData,
@@ -911,6 +969,7 @@ impl<K: Key, V> HopSlotMap<K, V> {
     pub fn iter_mut(&mut self) -> IterMut<K, V> {
         IterMut {
             cur: 0,
+            end: occupied_end(&self.slots),
             num_left: self.len(),
             slots: &mut self.slots[..],
             _k: PhantomData,
@@ -979,6 +1038,129 @@ impl<K: Key, V> HopSlotMap<K, V> {
             inner: self.iter_mut(),
         }
     }
+
+    /// An iterator visiting the key-value pairs stored in the slots with an
+    /// index in `range`, in slot order. The iterator element type is
+    /// `(K, &'a V)`.
+    ///
+    /// The slot index of a key is `key.data().index()`. Out of bounds parts of
+    /// `range` are ignored. Use [`Range::position`] to find where to resume
+    /// iteration later, even if the slot map was modified in the meantime.
+    ///
+    /// Contiguous blocks of vacant slots are hopped over. If the range starts
+    /// inside such a block, its end is found through the freelist, so resuming
+    /// in the middle of a long run of vacant slots is cheap as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = HopSlotMap::new();
+    /// let k0 = sm.insert(0);
+    /// let k1 = sm.insert(1);
+    /// let k2 = sm.insert(2);
+    ///
+    /// let idx = k1.data().index() as usize;
+    /// let v: Vec<_> = sm.range(idx..).collect();
+    /// assert_eq!(v, vec![(k1, &1), (k2, &2)]);
+    /// ```
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> Range<K, V> {
+        let (start, end) = slot_range(range, self.slots.len());
+        Range {
+            cur: start,
+            end,
+            prev_occupied: start.checked_sub(1).map_or(false, |i| self.slots[i].occupied()),
+            slots: &self.slots[..],
+            _k: PhantomData,
+        }
+    }
+
+    /// An iterator visiting the key-value pairs stored in the slots with an
+    /// index in `range`, in slot order, with mutable references to the values.
+    /// The iterator element type is `(K, &'a mut V)`.
+    ///
+    /// See [`range`](Self::range) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = HopSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    ///
+    /// let idx = k1.data().index() as usize;
+    /// sm.range_mut(idx..=idx).for_each(|(_, v)| *v *= -1);
+    /// assert_eq!(sm[k0], 10);
+    /// assert_eq!(sm[k1], -20);
+    /// ```
+    pub fn range_mut<R: RangeBounds<usize>>(&mut self, range: R) -> RangeMut<K, V> {
+        let (start, end) = slot_range(range, self.slots.len());
+        RangeMut {
+            cur: start,
+            end,
+            prev_occupied: start.checked_sub(1).map_or(false, |i| self.slots[i].occupied()),
+            slots: &mut self.slots[..],
+            _k: PhantomData,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs stored at or after slot index
+    /// `position`, in slot order. The iterator element type is `(K, &'a V)`.
+    ///
+    /// This allows processing a slot map in chunks, resuming where the previous
+    /// chunk left off through [`Range::position`]. Elements inserted or
+    /// removed in between are handled gracefully: every element that stays in
+    /// the slot map is visited exactly once per pass.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = HopSlotMap::new();
+    /// let keys: Vec<_> = (0..5).map(|i| sm.insert(i)).collect();
+    ///
+    /// let mut it = sm.iter_from(0);
+    /// let first: Vec<_> = it.by_ref().take(2).map(|(_, v)| *v).collect();
+    /// let position = it.position();
+    ///
+    /// sm.remove(keys[2]);
+    /// sm.remove(keys[3]);
+    /// let rest: Vec<_> = sm.iter_from(position).map(|(_, v)| *v).collect();
+    /// assert_eq!(first, vec![0, 1]);
+    /// assert_eq!(rest, vec![4]);
+    /// ```
+    pub fn iter_from(&self, position: usize) -> Range<K, V> {
+        self.range(position..)
+    }
+
+    /// An iterator visiting all key-value pairs stored at or after slot index
+    /// `position`, in slot order, with mutable references to the values. The
+    /// iterator element type is `(K, &'a mut V)`.
+    ///
+    /// See [`iter_from`](Self::iter_from) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = HopSlotMap::new();
+    /// for i in 0..4 {
+    ///     sm.insert(i);
+    /// }
+    ///
+    /// let mut it = sm.iter_mut_from(0);
+    /// it.by_ref().take(2).for_each(|(_, v)| *v *= 10);
+    /// let position = it.position();
+    /// sm.iter_mut_from(position).for_each(|(_, v)| *v *= 100);
+    ///
+    /// let mut values: Vec<_> = sm.values().copied().collect();
+    /// values.sort();
+    /// assert_eq!(values, vec![0, 10, 200, 300]);
+    /// ```
+    pub fn iter_mut_from(&mut self, position: usize) -> RangeMut<K, V> {
+        self.range_mut(position..)
+    }
 }
 
 impl<K: Key, V> Clone for HopSlotMap<K, V>
@@ -1031,6 +1213,7 @@ impl<K: Key, V> IndexMut<K> for HopSlotMap<K, V> {
 #[derive(Debug)]
 pub struct Drain<'a, K: Key + 'a, V: 'a> {
     cur: usize,
+    end: usize,
     sm: &'a mut HopSlotMap<K, V>,
 }
 
@@ -1041,6 +1224,7 @@ pub struct Drain<'a, K: Key + 'a, V: 'a> {
 #[derive(Debug, Clone)]
 pub struct IntoIter<K: Key, V> {
     cur: usize,
+    end: usize,
     num_left: usize,
     slots: Vec<Slot<V>>,
     _k: PhantomData<fn(K) -> K>,
@@ -1052,6 +1236,7 @@ pub struct IntoIter<K: Key, V> {
 #[derive(Debug)]
 pub struct Iter<'a, K: Key + 'a, V: 'a> {
     cur: usize,
+    end: usize,
     num_left: usize,
     slots: &'a [Slot<V>],
     _k: PhantomData<fn(K) -> K>,
@@ -1061,6 +1246,7 @@ impl<'a, K: 'a + Key, V: 'a> Clone for Iter<'a, K, V> {
     fn clone(&self) -> Self {
         Iter {
             cur: self.cur,
+            end: self.end,
             num_left: self.num_left,
             slots: self.slots,
             _k: self._k.clone(),
@@ -1074,6 +1260,7 @@ impl<'a, K: 'a + Key, V: 'a> Clone for Iter<'a, K, V> {
 #[derive(Debug)]
 pub struct IterMut<'a, K: Key + 'a, V: 'a> {
     cur: usize,
+    end: usize,
     num_left: usize,
     slots: &'a mut [Slot<V>],
     _k: PhantomData<fn(K) -> K>,
@@ -1119,6 +1306,64 @@ pub struct ValuesMut<'a, K: Key + 'a, V: 'a> {
     inner: IterMut<'a, K, V>,
 }
 
+/// An iterator over the key-value pairs in a range of slots of a
+/// [`HopSlotMap`].
+///
+/// This iterator is created by [`HopSlotMap::range`] and
+/// [`HopSlotMap::iter_from`].
+#[derive(Debug)]
+pub struct Range<'a, K: Key + 'a, V: 'a> {
+    cur: usize,
+    end: usize,
+    // Whether the slot before cur is occupied, in which case a vacant slot at
+    // cur is the front of a block of vacant slots.
+    prev_occupied: bool,
+    slots: &'a [Slot<V>],
+    _k: PhantomData<fn(K) -> K>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Range<'a, K, V> {
+    fn clone(&self) -> Self {
+        Range {
+            cur: self.cur,
+            end: self.end,
+            prev_occupied: self.prev_occupied,
+            slots: self.slots,
+            _k: self._k,
+        }
+    }
+}
+
+impl<'a, K: 'a + Key, V: 'a> Range<'a, K, V> {
+    /// Returns the slot index from which iteration continues. Pass it to
+    /// [`HopSlotMap::iter_from`] to resume iterating later.
+    pub fn position(&self) -> usize {
+        self.cur
+    }
+}
+
+/// A mutable iterator over the key-value pairs in a range of slots of a
+/// [`HopSlotMap`].
+///
+/// This iterator is created by [`HopSlotMap::range_mut`] and
+/// [`HopSlotMap::iter_mut_from`].
+#[derive(Debug)]
+pub struct RangeMut<'a, K: Key + 'a, V: 'a> {
+    cur: usize,
+    end: usize,
+    prev_occupied: bool,
+    slots: &'a mut [Slot<V>],
+    _k: PhantomData<fn(K) -> K>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> RangeMut<'a, K, V> {
+    /// Returns the slot index from which iteration continues. Pass it to
+    /// [`HopSlotMap::iter_mut_from`] to resume iterating later.
+    pub fn position(&self) -> usize {
+        self.cur
+    }
+}
+
 impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
     type Item = (K, V);
 
@@ -1146,6 +1391,27 @@ impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for Drain<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        // All unchecked indices are safe due to the invariants of the freelist
+        // and that self.sm.len() guarantees there is another element.
+        if self.sm.len() == 0 {
+            return None;
+        }
+
+        // Skip back to previous element. Must do this before removing. The
+        // sentinel guarantees idx - 1 exists.
+        let idx = self.end - 1;
+        self.end = match unsafe { self.sm.slots.get_unchecked(idx - 1).get() } {
+            Occupied(_) => idx,
+            Vacant(free) => free.other_end as usize,
+        };
+
+        let key = unsafe { key_data(idx as u32, self.sm.slots.get_unchecked(idx).version) };
+        Some((key.into(), unsafe { self.sm.remove_from_slot(idx) }))
+    }
+}
+
 impl<'a, K: Key, V> Drop for Drain<'a, K, V> {
     fn drop(&mut self) {
         self.for_each(|_drop| {});
@@ -1156,7 +1422,7 @@ impl<K: Key, V> Iterator for IntoIter<K, V> {
     type Item = (K, V);
 
     fn next(&mut self) -> Option<(K, V)> {
-        if self.cur >= self.slots.len() {
+        if self.num_left == 0 || self.cur >= self.slots.len() {
             return None;
         }
 
@@ -1185,6 +1451,29 @@ impl<K: Key, V> Iterator for IntoIter<K, V> {
     }
 }
 
+impl<K: Key, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        // All indices are valid due to the invariants of the freelist and that
+        // num_left guarantees there is another element.
+        if self.num_left == 0 {
+            return None;
+        }
+
+        let idx = match self.slots[self.end - 1].get() {
+            Occupied(_) => self.end - 1,
+            // Skip block of contiguous vacant slots.
+            Vacant(free) => free.other_end as usize - 1,
+        };
+
+        self.end = idx;
+        self.num_left -= 1;
+        let slot = &mut self.slots[idx];
+        let key = unsafe { key_data(idx as u32, slot.version).into() };
+        slot.version = 0; // Prevent dropping after extracting the value.
+        Some((key, unsafe { ManuallyDrop::take(&mut slot.u.value) }))
+    }
+}
+
 impl<'a, K: Key, V> Iterator for Iter<'a, K, V> {
     type Item = (K, &'a V);
 
@@ -1212,11 +1501,32 @@ impl<'a, K: Key, V> Iterator for Iter<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a V)> {
+        // All unchecked indices are safe due to the invariants of the freelist
+        // and that num_left guarantees there is another element.
+        if self.num_left == 0 {
+            return None;
+        }
+        self.num_left -= 1;
+
+        let idx = match unsafe { self.slots.get_unchecked(self.end - 1).get() } {
+            Occupied(_) => self.end - 1,
+            Vacant(free) => free.other_end as usize - 1,
+        };
+
+        self.end = idx;
+        let slot = unsafe { self.slots.get_unchecked(idx) };
+        let key = unsafe { key_data(idx as u32, slot.version).into() };
+        Some((key, unsafe { &*slot.u.value }))
+    }
+}
+
 impl<'a, K: Key, V> Iterator for IterMut<'a, K, V> {
     type Item = (K, &'a mut V);
 
     fn next(&mut self) -> Option<(K, &'a mut V)> {
-        if self.cur >= self.slots.len() {
+        if self.num_left == 0 || self.cur >= self.slots.len() {
             return None;
         }
 
@@ -1251,6 +1561,35 @@ impl<'a, K: Key, V> Iterator for IterMut<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a mut V)> {
+        // All indices are valid due to the invariants of the freelist and that
+        // num_left guarantees there is another element.
+        if self.num_left == 0 {
+            return None;
+        }
+
+        let idx = match self.slots[self.end - 1].get() {
+            Occupied(_) => self.end - 1,
+            // Skip block of contiguous vacant slots.
+            Vacant(free) => free.other_end as usize - 1,
+        };
+
+        self.end = idx;
+        self.num_left -= 1;
+
+        // Unsafe necessary because Rust can't deduce that we won't
+        // return multiple references to the same value.
+        let slot = &mut self.slots[idx];
+        let version = slot.version;
+        let value_ref = unsafe {
+            let ptr: *mut V = &mut *slot.u.value;
+            &mut *ptr
+        };
+        Some((unsafe { key_data(idx as u32, version).into() }, value_ref))
+    }
+}
+
 impl<'a, K: Key, V> Iterator for Keys<'a, K, V> {
     type Item = K;
 
@@ -1263,6 +1602,12 @@ impl<'a, K: Key, V> Iterator for Keys<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<K> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
 impl<'a, K: Key, V> Iterator for Values<'a, K, V> {
     type Item = &'a V;
 
@@ -1275,6 +1620,12 @@ impl<'a, K: Key, V> Iterator for Values<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
 impl<'a, K: Key, V> Iterator for ValuesMut<'a, K, V> {
     type Item = &'a mut V;
 
@@ -1287,6 +1638,92 @@ impl<'a, K: Key, V> Iterator for ValuesMut<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Range<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        while self.cur < self.end {
+            // This is safe because end never exceeds the number of slots.
+            let idx = self.cur;
+            let slot = unsafe { self.slots.get_unchecked(idx) };
+            match slot.get() {
+                Occupied(value) => {
+                    self.cur = idx + 1;
+                    self.prev_occupied = true;
+                    let key = unsafe { key_data(idx as u32, slot.version).into() };
+                    return Some((key, value));
+                },
+                Vacant(free) if self.prev_occupied => {
+                    // Skip block of contiguous vacant slots.
+                    self.cur = (free.other_end as usize + 1).min(self.end);
+                    self.prev_occupied = false;
+                },
+                // We started inside a vacant block, whose freelist entries
+                // can't be trusted. Find its end through the freelist.
+                Vacant(_) => {
+                    self.cur = (vacant_block_back(self.slots, idx) + 1).min(self.end);
+                    self.prev_occupied = false;
+                },
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.cur))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for RangeMut<'a, K, V> {
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<(K, &'a mut V)> {
+        while self.cur < self.end {
+            // This is safe because end never exceeds the number of slots.
+            let idx = self.cur;
+            let slot = unsafe { self.slots.get_unchecked_mut(idx) };
+            let version = slot.version;
+            match slot.get_mut() {
+                OccupiedMut(value) => {
+                    self.cur = idx + 1;
+                    self.prev_occupied = true;
+
+                    // Unsafe necessary because Rust can't deduce that we won't
+                    // return multiple references to the same value.
+                    let value_ref = unsafe {
+                        let ptr: *mut V = value;
+                        &mut *ptr
+                    };
+                    return Some((unsafe { key_data(idx as u32, version).into() }, value_ref));
+                },
+                VacantMut(free) if self.prev_occupied => {
+                    // Skip block of contiguous vacant slots.
+                    self.cur = (free.other_end as usize + 1).min(self.end);
+                    self.prev_occupied = false;
+                },
+                // See Range::next.
+                VacantMut(_) => {
+                    self.cur = (vacant_block_back(self.slots, idx) + 1).min(self.end);
+                    self.prev_occupied = false;
+                },
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.cur))
+    }
+}
+
 impl<'a, K: Key, V> IntoIterator for &'a HopSlotMap<K, V> {
     type Item = (K, &'a V);
     type IntoIter = Iter<'a, K, V>;
@@ -1312,6 +1749,7 @@ impl<K: Key, V> IntoIterator for HopSlotMap<K, V> {
     fn into_iter(self) -> Self::IntoIter {
         IntoIter {
             cur: 0,
+            end: occupied_end(&self.slots),
             num_left: self.len(),
             slots: self.slots,
             _k: PhantomData,
@@ -1326,6 +1764,8 @@ impl<'a, K: Key, V> FusedIterator for Values<'a, K, V> {}
 impl<'a, K: Key, V> FusedIterator for ValuesMut<'a, K, V> {}
 impl<'a, K: Key, V> FusedIterator for Drain<'a, K, V> {}
 impl<K: Key, V> FusedIterator for IntoIter<K, V> {}
+impl<'a, K: Key, V> FusedIterator for Range<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for RangeMut<'a, K, V> {}
 
 impl<'a, K: Key, V> ExactSizeIterator for Iter<'a, K, V> {}
 impl<'a, K: Key, V> ExactSizeIterator for IterMut<'a, K, V> {}
@@ -1335,6 +1775,115 @@ impl<'a, K: Key, V> ExactSizeIterator for ValuesMut<'a, K, V> {}
 impl<'a, K: Key, V> ExactSizeIterator for Drain<'a, K, V> {}
 impl<K: Key, V> ExactSizeIterator for IntoIter<K, V> {}
 
+// Parallel iteration with rayon.
+#[cfg(feature = "rayon")]
+mod parallel {
+    use rayon::prelude::*;
+
+    use super::*;
+    use crate::par::{ParSlots, ParSlotsMut};
+
+    impl<K: Key, V> HopSlotMap<K, V> {
+        /// A parallel iterator visiting all key-value pairs in arbitrary order.
+        /// The work is split such that every thread gets about the same number
+        /// of elements, regardless of how the vacant slots are spread out.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = HopSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// let sum: u64 = sm.par_iter().map(|(_, &v)| v).sum();
+        /// assert_eq!(sum, 500 * 999);
+        /// ```
+        pub fn par_iter(&self) -> impl ParallelIterator<Item = (K, &V)>
+        where
+            K: Send,
+            V: Sync,
+        {
+            ParSlots::new(&self.slots[1..], 1, Slot::occupied).map(|(idx, slot)| {
+                let key = unsafe { key_data(idx as u32, slot.version).into() };
+                (key, unsafe { &*slot.u.value })
+            })
+        }
+
+        /// A parallel iterator visiting all key-value pairs in arbitrary order,
+        /// with mutable references to the values.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = HopSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// sm.par_iter_mut().for_each(|(_, v)| *v *= 2);
+        /// assert_eq!(sm.values().sum::<u64>(), 1000 * 999);
+        /// ```
+        pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (K, &mut V)>
+        where
+            K: Send,
+            V: Send,
+        {
+            // Slot::occupied only reads the version, see ParSlotsMut::new.
+            let slots = unsafe { ParSlotsMut::new(&mut self.slots[1..], 1, Slot::occupied) };
+            slots.map(|(idx, slot)| {
+                let key = unsafe { key_data(idx as u32, slot.version).into() };
+                (key, unsafe { &mut *slot.u.value })
+            })
+        }
+
+        /// A parallel iterator visiting all keys in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = HopSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// assert_eq!(sm.par_keys().count(), 1000);
+        /// ```
+        pub fn par_keys(&self) -> impl ParallelIterator<Item = K> + '_
+        where
+            K: Send,
+            V: Sync,
+        {
+            self.par_iter().map(|(key, _)| key)
+        }
+
+        /// A parallel iterator visiting all values mutably in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = HopSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// sm.par_values_mut().for_each(|v| *v += 1);
+        /// assert_eq!(sm.values().sum::<u64>(), 500 * 1001);
+        /// ```
+        pub fn par_values_mut(&mut self) -> impl ParallelIterator<Item = &mut V>
+        where
+            K: Send,
+            V: Send,
+        {
+            self.par_iter_mut().map(|(_, value)| value)
+        }
+    }
+}
+
 // Serialization with serde.
 #[cfg(feature = "serde")]
 mod serialize {
@@ -1565,6 +2114,116 @@ mod tests {
         }
     }
 
+    #[test]
+    fn resumable_range() {
+        let mut sm = HopSlotMap::new();
+        let keys: Vec<_> = (0..100).map(|i| sm.insert(i)).collect();
+        for k in keys.iter().step_by(3) {
+            sm.remove(*k);
+        }
+
+        // Visit everything in chunks, removing and inserting in between.
+        let mut seen = Vec::new();
+        let mut position = 0;
+        loop {
+            let mut it = sm.iter_mut_from(position);
+            let chunk: Vec<_> = it.by_ref().take(7).map(|(k, v)| (k, *v)).collect();
+            position = it.position();
+            if chunk.is_empty() {
+                break;
+            }
+            seen.extend(chunk.iter().map(|&(_, v)| v));
+
+            // Removing behind the position must not disturb iteration.
+            sm.remove(chunk[0].0);
+        }
+        let expected: Vec<_> = (0..100).filter(|i| i % 3 != 0).collect();
+        assert_eq!(seen, expected);
+
+        let ranged: Vec<_> = sm.range(20..40).map(|(k, _)| k.data().index()).collect();
+        assert!(ranged.iter().all(|&i| (20..40).contains(&i)));
+        let filtered: Vec<_> = sm
+            .keys()
+            .map(|k| k.data().index())
+            .filter(|i| (20..40).contains(i))
+            .collect();
+        assert_eq!(ranged, filtered);
+        assert_eq!(sm.range(..).count(), sm.len());
+        assert_eq!(sm.range(1000..).count(), 0);
+    }
+
+    #[test]
+    fn resume_inside_vacant_run() {
+        let mut sm = HopSlotMap::new();
+        let keys: Vec<_> = (0..1000).map(|i| sm.insert(i)).collect();
+        // A long run at the front, merged with the sentinel block, a long run
+        // in the middle and a few short runs in between.
+        for (i, k) in keys.iter().enumerate() {
+            if i < 200 || (300..900).contains(&i) || i % 50 == 7 {
+                sm.remove(*k);
+            }
+        }
+
+        for position in [0, 1, 100, 200, 257, 300, 301, 600, 899, 900, 957, 999, 1000, 1001] {
+            let expected: Vec<_> = sm
+                .iter()
+                .filter(|(k, _)| k.data().index() as usize >= position)
+                .map(|(_, v)| *v)
+                .collect();
+            let values: Vec<_> = sm.iter_from(position).map(|(_, v)| *v).collect();
+            assert_eq!(values, expected);
+            let values: Vec<_> = sm.iter_mut_from(position).map(|(_, v)| *v).collect();
+            assert_eq!(values, expected);
+        }
+        assert_eq!(sm.range(400..=600).count(), 0);
+        assert_eq!(sm.range(400..=901).map(|(_, v)| *v).collect::<Vec<_>>(), vec![900]);
+    }
+
+    #[test]
+    fn double_ended() {
+        let mut sm = HopSlotMap::new();
+        let keys: Vec<_> = (0..20).map(|i| sm.insert(i)).collect();
+        for &i in &[0, 3, 4, 5, 10, 17, 18, 19] {
+            sm.remove(keys[i]);
+        }
+
+        let forward: Vec<_> = sm.iter().map(|(k, &v)| (k, v)).collect();
+        let mut backward: Vec<_> = sm.iter().rev().map(|(k, &v)| (k, v)).collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert!(sm.values().rev().eq(forward.iter().rev().map(|(_, v)| v)));
+        assert!(sm.keys().rev().eq(forward.iter().rev().map(|&(k, _)| k)));
+
+        // Alternating ends visits every element exactly once.
+        let mut it = sm.iter_mut();
+        let mut seen = Vec::new();
+        while let Some((_, v)) = it.next() {
+            seen.push(*v);
+            if let Some((_, v)) = it.next_back() {
+                seen.push(*v);
+            }
+        }
+        seen.sort();
+        assert!(seen.iter().eq(sm.values()));
+
+        let mut drained: Vec<_> = sm.clone().into_iter().rev().collect();
+        drained.reverse();
+        assert_eq!(drained, forward);
+
+        let mut drained = Vec::new();
+        let mut drain = sm.drain();
+        while let Some(back) = drain.next_back() {
+            drained.push(back);
+            if let Some(front) = drain.next() {
+                drained.push(front);
+            }
+        }
+        drop(drain);
+        assert!(sm.is_empty());
+        drained.sort_by_key(|&(_, v)| v);
+        assert_eq!(drained, forward);
+    }
+
     quickcheck! {
         fn qc_slotmap_equiv_hashmap(operations: Vec<(u8, u32)>) -> bool {
             let mut hm = HashMap::new();
@@ -0,0 +1,596 @@
+//! Contains the slot interner implementation, a slot map that stores every
+//! distinct value once.
+
+use alloc::borrow::ToOwned;
+use alloc::vec::Vec;
+use std::borrow::Borrow;
+use std::collections::hash_map::{self, HashMap};
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use std::ops::Index;
+
+use crate::{basic, SlotMap};
+use pi_key_alloter::{DefaultKey, Key};
+
+// The keys of the values with a given hash. Almost always a single key.
+#[derive(Debug, Clone)]
+enum Bucket<K> {
+    One(K),
+    Many(Vec<K>),
+}
+
+// Hasher for the index, whose keys already are hashes.
+#[derive(Debug, Clone, Default)]
+struct HashOnly(u64);
+
+impl Hasher for HashOnly {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ u64::from(byte);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = n;
+    }
+}
+
+/// Slot interner, a [`SlotMap`] that deduplicates its values.
+///
+/// [`intern`](Self::intern) returns the key of an equal value if one is
+/// already stored, and inserts the value under a new key otherwise. The key of
+/// a value can be looked up with [`key_of`](Self::key_of), and removing a key
+/// removes the value from both directions. Values can't be mutated in place,
+/// as that would break the deduplication.
+///
+/// Values are stored once, in the slot map. The hash index only stores the
+/// hashes of the values and their keys, and compares against the slot map on
+/// lookup.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut paths = SlotInterner::new();
+/// let a = paths.intern("textures/grass.png".to_string());
+/// let b = paths.intern_ref("textures/stone.png");
+/// assert_eq!(paths.intern_ref("textures/grass.png"), a);
+/// assert_eq!(paths.len(), 2);
+///
+/// assert_eq!(paths.key_of("textures/stone.png"), Some(b));
+/// assert_eq!(paths.remove(b).as_deref(), Some("textures/stone.png"));
+/// assert_eq!(paths.key_of("textures/stone.png"), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlotInterner<K: Key, V, S: BuildHasher = hash_map::RandomState> {
+    sm: SlotMap<K, V>,
+    index: HashMap<u64, Bucket<K>, BuildHasherDefault<HashOnly>>,
+    hash_builder: S,
+}
+
+impl<V> SlotInterner<DefaultKey, V, hash_map::RandomState> {
+    /// Constructs a new, empty [`SlotInterner`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner: SlotInterner<_, String> = SlotInterner::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`SlotInterner`] with the given capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner: SlotInterner<_, String> = SlotInterner::with_capacity(10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_key(capacity)
+    }
+}
+
+impl<K: Key, V> SlotInterner<K, V, hash_map::RandomState> {
+    /// Constructs a new, empty [`SlotInterner`] with a custom key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct PathKey;
+    /// }
+    /// let mut interner: SlotInterner<PathKey, String> = SlotInterner::with_key();
+    /// ```
+    pub fn with_key() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`SlotInterner`] with the given capacity and a custom
+    /// key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct PathKey;
+    /// }
+    /// let mut interner: SlotInterner<PathKey, String> =
+    ///     SlotInterner::with_capacity_and_key(10);
+    /// ```
+    pub fn with_capacity_and_key(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<K: Key, V, S: BuildHasher> SlotInterner<K, V, S> {
+    /// Creates an empty [`SlotInterner`] which will use the given hash builder
+    /// to hash values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// # use std::collections::hash_map::RandomState;
+    /// let mut interner: SlotInterner<DefaultKey, String, _> =
+    ///     SlotInterner::with_hasher(RandomState::new());
+    /// ```
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// Creates an empty [`SlotInterner`] with the given capacity, which will
+    /// use the given hash builder to hash values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// # use std::collections::hash_map::RandomState;
+    /// let mut interner: SlotInterner<DefaultKey, String, _> =
+    ///     SlotInterner::with_capacity_and_hasher(10, RandomState::new());
+    /// ```
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        Self {
+            sm: SlotMap::with_capacity_and_key(capacity),
+            index: HashMap::with_capacity_and_hasher(capacity, Default::default()),
+            hash_builder,
+        }
+    }
+
+    /// Returns the number of distinct values in the interner.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// interner.intern(1);
+    /// interner.intern(1);
+    /// interner.intern(2);
+    /// assert_eq!(interner.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.sm.len()
+    }
+
+    /// Returns if the interner is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let interner: SlotInterner<_, i32> = SlotInterner::new();
+    /// assert!(interner.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.sm.is_empty()
+    }
+
+    /// Returns [`true`] if the interner contains `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// let key = interner.intern(42);
+    /// assert_eq!(interner.contains_key(key), true);
+    /// interner.remove(key);
+    /// assert_eq!(interner.contains_key(key), false);
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.sm.contains_key(key)
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// let key = interner.intern("bar");
+    /// assert_eq!(interner.get(key), Some(&"bar"));
+    /// interner.remove(key);
+    /// assert_eq!(interner.get(key), None);
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.sm.get(key)
+    }
+
+    /// Clears the interner. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// interner.intern("foo");
+    /// interner.clear();
+    /// assert_eq!(interner.key_of("foo"), None);
+    /// ```
+    pub fn clear(&mut self) {
+        self.sm.clear();
+        self.index.clear();
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order. The
+    /// iterator element type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// let k = interner.intern(1);
+    /// interner.intern(1);
+    /// assert_eq!(interner.iter().collect::<Vec<_>>(), [(k, &1)]);
+    /// ```
+    pub fn iter(&self) -> basic::Iter<K, V> {
+        self.sm.iter()
+    }
+
+    /// An iterator visiting all keys in arbitrary order. The iterator element
+    /// type is `K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// let k = interner.intern(1);
+    /// assert_eq!(interner.keys().collect::<Vec<_>>(), [k]);
+    /// ```
+    pub fn keys(&self) -> basic::Keys<K, V> {
+        self.sm.keys()
+    }
+
+    /// An iterator visiting all values in arbitrary order. The iterator
+    /// element type is `&'a V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// interner.intern(1);
+    /// interner.intern(1);
+    /// assert_eq!(interner.values().collect::<Vec<_>>(), [&1]);
+    /// ```
+    pub fn values(&self) -> basic::Values<K, V> {
+        self.sm.values()
+    }
+
+    /// Returns the underlying [`SlotMap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// let k = interner.intern("a");
+    /// assert_eq!(interner.as_slot_map()[k], "a");
+    /// ```
+    pub fn as_slot_map(&self) -> &SlotMap<K, V> {
+        &self.sm
+    }
+}
+
+impl<K, V, S> SlotInterner<K, V, S>
+where
+    K: Key,
+    V: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns the key of a value equal to `value`, inserting `value` under a
+    /// new key if there is none.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values in the interner equals
+    /// 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// let a = interner.intern(vec![1, 2]);
+    /// let b = interner.intern(vec![1, 2]);
+    /// let c = interner.intern(vec![3]);
+    /// assert_eq!(a, b);
+    /// assert_ne!(a, c);
+    /// ```
+    pub fn intern(&mut self, value: V) -> K {
+        let hash = self.hash(&value);
+        match self.find(hash, &value) {
+            Some(key) => key,
+            None => self.insert_new(hash, value),
+        }
+    }
+
+    /// Returns the key of a value equal to `value`, inserting an owned copy
+    /// of `value` under a new key if there is none. Unlike
+    /// [`intern`](Self::intern) this only creates the owned value when it is
+    /// inserted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values in the interner equals
+    /// 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner: SlotInterner<_, String> = SlotInterner::new();
+    /// let a = interner.intern_ref("shader.wgsl");
+    /// assert_eq!(interner.intern("shader.wgsl".to_string()), a);
+    /// ```
+    pub fn intern_ref<Q>(&mut self, value: &Q) -> K
+    where
+        V: Borrow<Q>,
+        Q: ?Sized + Hash + Eq + ToOwned<Owned = V>,
+    {
+        let hash = self.hash(value);
+        match self.find(hash, value) {
+            Some(key) => key,
+            None => self.insert_new(hash, value.to_owned()),
+        }
+    }
+
+    /// Returns the key of the value equal to `value`, or [`None`] if there is
+    /// none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner: SlotInterner<_, String> = SlotInterner::new();
+    /// let k = interner.intern("a".to_string());
+    /// assert_eq!(interner.key_of("a"), Some(k));
+    /// assert_eq!(interner.key_of("b"), None);
+    /// ```
+    pub fn key_of<Q>(&self, value: &Q) -> Option<K>
+    where
+        V: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.find(self.hash(value), value)
+    }
+
+    /// Returns [`true`] if the interner contains a value equal to `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// interner.intern(5);
+    /// assert!(interner.contains(&5));
+    /// assert!(!interner.contains(&6));
+    /// ```
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        V: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.key_of(value).is_some()
+    }
+
+    /// Removes a key from the interner, returning the value at the key if the
+    /// key was not previously removed. Interning an equal value afterwards
+    /// gives a new key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// let k = interner.intern('a');
+    /// assert_eq!(interner.remove(k), Some('a'));
+    /// assert_eq!(interner.remove(k), None);
+    /// assert_ne!(interner.intern('a'), k);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let value = self.sm.remove(key)?;
+        let hash = self.hash(&value);
+        self.unindex(hash, key);
+        Some(value)
+    }
+
+    /// Retains only the values specified by the predicate.
+    ///
+    /// In other words, remove all key-value pairs `(k, v)` such that
+    /// `f(k, &v)` returns false. This method invalidates any removed keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut interner = SlotInterner::new();
+    /// for i in 0..6 {
+    ///     interner.intern(i);
+    /// }
+    /// interner.retain(|_, v| *v % 2 == 0);
+    /// assert!(interner.contains(&2));
+    /// assert!(!interner.contains(&3));
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K, &V) -> bool,
+    {
+        let removed: Vec<K> = self
+            .sm
+            .iter()
+            .filter(|&(key, value)| !f(key, value))
+            .map(|(key, _)| key)
+            .collect();
+        for key in removed {
+            self.remove(key);
+        }
+    }
+
+    fn hash<Q: ?Sized + Hash>(&self, value: &Q) -> u64 {
+        let mut hasher = self.hash_builder.build_hasher();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn find<Q>(&self, hash: u64, value: &Q) -> Option<K>
+    where
+        V: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        let is_equal = |&key: &K| self.sm[key].borrow() == value;
+        match self.index.get(&hash)? {
+            Bucket::One(key) => Some(*key).filter(is_equal),
+            Bucket::Many(keys) => keys.iter().copied().find(is_equal),
+        }
+    }
+
+    fn insert_new(&mut self, hash: u64, value: V) -> K {
+        let key = self.sm.insert(value);
+        match self.index.entry(hash) {
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(Bucket::One(key));
+            }
+            hash_map::Entry::Occupied(mut entry) => match entry.get_mut() {
+                Bucket::One(other) => {
+                    let other = *other;
+                    entry.insert(Bucket::Many(vec![other, key]));
+                }
+                Bucket::Many(keys) => keys.push(key),
+            },
+        }
+        key
+    }
+
+    fn unindex(&mut self, hash: u64, key: K) {
+        if let hash_map::Entry::Occupied(mut entry) = self.index.entry(hash) {
+            match entry.get_mut() {
+                Bucket::One(_) => {
+                    entry.remove();
+                }
+                Bucket::Many(keys) => {
+                    keys.retain(|&k| k != key);
+                    if let [other] = keys[..] {
+                        entry.insert(Bucket::One(other));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<K, V, S> Default for SlotInterner<K, V, S>
+where
+    K: Key,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Self::with_hasher(Default::default())
+    }
+}
+
+impl<K, V, S> Index<K> for SlotInterner<K, V, S>
+where
+    K: Key,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: K) -> &V {
+        match self.get(key) {
+            Some(r) => r,
+            None => panic!("invalid SlotInterner key used"),
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a SlotInterner<K, V, S>
+where
+    K: Key,
+    S: BuildHasher,
+{
+    type Item = (K, &'a V);
+    type IntoIter = basic::Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    // Hashes everything to the same value, to exercise collisions.
+    #[derive(Default)]
+    struct Colliding;
+
+    impl Hasher for Colliding {
+        fn finish(&self) -> u64 {
+            7
+        }
+
+        fn write(&mut self, _: &[u8]) {}
+    }
+
+    #[test]
+    fn colliding_hashes() {
+        let mut interner: SlotInterner<DefaultKey, String, BuildHasherDefault<Colliding>> =
+            SlotInterner::default();
+        let keys: Vec<_> = (0..5).map(|i| interner.intern(i.to_string())).collect();
+        assert_eq!(interner.len(), 5);
+        for (i, &k) in keys.iter().enumerate() {
+            assert_eq!(interner.key_of(&*i.to_string()), Some(k));
+            assert_eq!(interner.intern_ref(&*i.to_string()), k);
+        }
+
+        assert_eq!(interner.remove(keys[1]).as_deref(), Some("1"));
+        interner.retain(|_, v| v != "3");
+        assert_eq!(interner.key_of("1"), None);
+        assert_eq!(interner.key_of("3"), None);
+        assert_eq!(interner.key_of("4"), Some(keys[4]));
+        interner.remove(keys[0]);
+        interner.remove(keys[2]);
+        assert_eq!(interner.key_of("4"), Some(keys[4]));
+
+        let new = interner.intern("1".to_string());
+        assert!(!interner.contains_key(keys[1]));
+        assert_eq!(interner.key_of("1"), Some(new));
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.index.len(), 1);
+    }
+}
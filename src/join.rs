@@ -0,0 +1,540 @@
+//! Contains [`join`], which iterates over the keys present in several maps at
+//! once.
+
+use alloc::vec::Vec;
+use core::iter::{Empty, FusedIterator};
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use pi_key_alloter::Key;
+
+use crate::{basic, dense, hop, secondary, soa};
+use crate::{DenseSlotMap, HopSlotMap, SecondaryMap, SlotMap, SoaSlotMap};
+#[cfg(feature = "std")]
+use crate::util::UnwrapUnchecked;
+#[cfg(feature = "std")]
+use crate::{sparse_secondary, SparseSecondaryMap};
+
+/// A value that can be passed to [`join`] as one of its inputs.
+///
+/// Implemented for shared and mutable references to [`SlotMap`],
+/// [`HopSlotMap`], [`DenseSlotMap`], [`SoaSlotMap`], [`SecondaryMap`] and
+/// [`SparseSecondaryMap`](crate::SparseSecondaryMap), yielding `&V` and
+/// `&mut V` respectively, and for [`Maybe`].
+pub trait IntoJoinable<K: Key> {
+    /// The input this value turns into.
+    type Joinable: Joinable<K>;
+
+    /// Turns this value into an input of a join.
+    fn into_joinable(self) -> Self::Joinable;
+}
+
+/// An input of a [`join`], created by [`IntoJoinable`].
+pub trait Joinable<K: Key> {
+    /// The item yielded for each key of the join.
+    type Item;
+
+    /// An iterator over the keys of this input, used when it drives the join.
+    type Keys: Iterator<Item = K>;
+
+    /// Returns the number of keys of this input, or [`None`] if it does not
+    /// restrict the keys of the join.
+    fn join_len(&self) -> Option<usize>;
+
+    /// Returns an iterator over the keys of this input.
+    fn join_keys(&self) -> Self::Keys;
+
+    /// Returns [`true`] if this input has an item for `key`.
+    fn join_contains(&self, key: K) -> bool;
+
+    /// Returns the item for `key`.
+    ///
+    /// # Safety
+    ///
+    /// `join_contains(key)` must be true, and for inputs yielding mutable
+    /// references this must be called at most once per key.
+    unsafe fn join_get(&mut self, key: K) -> Self::Item;
+}
+
+/// A tuple of [`IntoJoinable`] values that can be passed to [`join`].
+///
+/// Implemented for tuples of up to eight values sharing the same key type.
+pub trait IntoJoin<K: Key> {
+    /// The tuple of inputs this tuple turns into.
+    type Join: Join<K>;
+
+    /// Turns every value of this tuple into an input of a join.
+    fn into_join(self) -> Self::Join;
+}
+
+/// A tuple of [`Joinable`] inputs, created by [`IntoJoin`].
+pub trait Join<K: Key> {
+    /// The item yielded by the join, the key followed by an item of each input.
+    type Item;
+
+    /// The iterator over the keys of the smallest input.
+    type Keys: Iterator<Item = K>;
+
+    /// Returns an iterator over the keys of the smallest input that restricts
+    /// the keys of the join.
+    fn driver(&self) -> Self::Keys;
+
+    /// Returns [`true`] if every input has an item for `key`.
+    fn contains(&self, key: K) -> bool;
+
+    /// Returns the items of every input for `key`.
+    ///
+    /// # Safety
+    ///
+    /// `contains(key)` must be true, and this must be called at most once per
+    /// key.
+    unsafe fn get(&mut self, key: K) -> Self::Item;
+}
+
+/// An optional input of a [`join`].
+///
+/// Yields `Some` with the item of the wrapped input for the keys it contains
+/// and [`None`] for all other keys, so it never excludes a key from the join.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// use slotmap::join::{join, Maybe};
+///
+/// let mut sm = SlotMap::new();
+/// let a = sm.insert("a");
+/// let b = sm.insert("b");
+/// let mut shield = SecondaryMap::new();
+/// shield.insert(b, 5);
+///
+/// let v: Vec<_> = join((&sm, Maybe(&shield))).collect();
+/// assert_eq!(v, vec![(a, &"a", None), (b, &"b", Some(&5))]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Maybe<J>(pub J);
+
+/// Iterates over the keys present in all of the given inputs, yielding each
+/// key with the items of every input.
+///
+/// `parts` is a tuple of shared or mutable references to maps, optionally
+/// wrapped in [`Maybe`], all using the same key type. The join walks the keys
+/// of the smallest input, checks each key against the other inputs once and
+/// then accesses their items without further checks. Inputs wrapped in
+/// [`Maybe`] never drive the join; if every input is optional the join is
+/// empty.
+///
+/// The order of iteration is the order of the keys of the driving input.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// use slotmap::join::join;
+///
+/// let mut players = SlotMap::new();
+/// let alice = players.insert("alice");
+/// let bob = players.insert("bob");
+/// let carol = players.insert("carol");
+///
+/// let mut health = SecondaryMap::new();
+/// let mut ammo = SecondaryMap::new();
+/// health.insert(alice, 100);
+/// health.insert(bob, 80);
+/// ammo.insert(bob, 3);
+/// ammo.insert(carol, 7);
+///
+/// for (_, name, hp, rounds) in join((&players, &health, &mut ammo)) {
+///     assert_eq!(*name, "bob");
+///     assert_eq!(*hp, 80);
+///     *rounds -= 1;
+/// }
+/// assert_eq!(ammo[bob], 2);
+/// assert_eq!(ammo[carol], 7);
+/// ```
+pub fn join<K: Key, J: IntoJoin<K>>(parts: J) -> JoinIter<K, J::Join> {
+    let parts = parts.into_join();
+    JoinIter {
+        keys: parts.driver(),
+        parts,
+        _k: PhantomData,
+    }
+}
+
+/// An iterator over the keys present in all inputs of a join.
+///
+/// This iterator is created by [`join`].
+#[derive(Debug)]
+pub struct JoinIter<K: Key, J: Join<K>> {
+    parts: J,
+    keys: J::Keys,
+    _k: PhantomData<fn(K) -> K>,
+}
+
+impl<K: Key, J: Join<K>> Iterator for JoinIter<K, J> {
+    type Item = J::Item;
+
+    fn next(&mut self) -> Option<J::Item> {
+        for key in self.keys.by_ref() {
+            if self.parts.contains(key) {
+                // This is safe because the driving keys are all distinct.
+                return Some(unsafe { self.parts.get(key) });
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.keys.size_hint().1)
+    }
+}
+
+impl<K: Key, J: Join<K>> FusedIterator for JoinIter<K, J> where J::Keys: FusedIterator {}
+
+/// A mutable input of a [`join`], created from a mutable reference to a map.
+///
+/// Reaches the values through a raw pointer rather than borrowing the map
+/// again for every key, which would invalidate the items already yielded.
+#[derive(Debug)]
+pub struct JoinMut<'a, M> {
+    map: NonNull<M>,
+    _m: PhantomData<&'a mut M>,
+}
+
+// A `JoinMut` is used like the `&mut M` it was created from.
+unsafe impl<'a, M: Send> Send for JoinMut<'a, M> {}
+unsafe impl<'a, M: Sync> Sync for JoinMut<'a, M> {}
+
+/// A mutable input of a [`join`], created from a mutable reference to a
+/// [`SparseSecondaryMap`].
+///
+/// Looking up a key in a hash map borrows the other entries it probes, so
+/// this takes a mutable reference to every value up front and hands out each
+/// of them once.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct SparseJoinMut<'a, K: Key, V> {
+    values: HashMap<K, &'a mut V>,
+}
+
+/// The keys of the driving input of a join, one of the options being
+/// [`Some`].
+#[derive(Debug, Clone)]
+pub struct JoinKeys<T>(T);
+
+impl<K: Key, J: IntoJoinable<K>> IntoJoinable<K> for Maybe<J> {
+    type Joinable = Maybe<J::Joinable>;
+
+    fn into_joinable(self) -> Maybe<J::Joinable> {
+        Maybe(self.0.into_joinable())
+    }
+}
+
+impl<K: Key, J: Joinable<K>> Joinable<K> for Maybe<J> {
+    type Item = Option<J::Item>;
+    type Keys = Empty<K>;
+
+    fn join_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn join_keys(&self) -> Empty<K> {
+        core::iter::empty()
+    }
+
+    fn join_contains(&self, _key: K) -> bool {
+        true
+    }
+
+    unsafe fn join_get(&mut self, key: K) -> Option<J::Item> {
+        match self.0.join_contains(key) {
+            true => Some(self.0.join_get(key)),
+            false => None,
+        }
+    }
+}
+
+macro_rules! impl_joinable {
+    ($module:ident, $map:ident $(<$($gen:ident $(: $bound:path)?),*>)?) => {
+        impl<'a, K: Key, V $($(, $gen $(: $bound)?)*)?> IntoJoinable<K> for &'a $map<K, V $($(, $gen)*)?> {
+            type Joinable = Self;
+
+            fn into_joinable(self) -> Self {
+                self
+            }
+        }
+
+        impl<'a, K: Key, V $($(, $gen $(: $bound)?)*)?> Joinable<K> for &'a $map<K, V $($(, $gen)*)?> {
+            type Item = &'a V;
+            type Keys = $module::Keys<'a, K, V>;
+
+            fn join_len(&self) -> Option<usize> {
+                Some(self.len())
+            }
+
+            fn join_keys(&self) -> Self::Keys {
+                (*self).keys()
+            }
+
+            fn join_contains(&self, key: K) -> bool {
+                self.contains_key(key)
+            }
+
+            unsafe fn join_get(&mut self, key: K) -> &'a V {
+                (*self).get_unchecked(key)
+            }
+        }
+    };
+}
+
+macro_rules! impl_joinable_mut {
+    ($map:ident) => {
+        impl<'a, K: Key, V> IntoJoinable<K> for &'a mut $map<K, V> {
+            type Joinable = JoinMut<'a, $map<K, V>>;
+
+            fn into_joinable(self) -> Self::Joinable {
+                JoinMut {
+                    map: NonNull::from(self),
+                    _m: PhantomData,
+                }
+            }
+        }
+
+        impl<'a, K: Key, V> Joinable<K> for JoinMut<'a, $map<K, V>> {
+            type Item = &'a mut V;
+            type Keys = alloc::vec::IntoIter<K>;
+
+            fn join_len(&self) -> Option<usize> {
+                Some(unsafe { self.map.as_ref() }.len())
+            }
+
+            fn join_keys(&self) -> Self::Keys {
+                // Collected up front, as the slots must not stay borrowed
+                // while the values are handed out.
+                let keys: Vec<K> = unsafe { self.map.as_ref() }.keys().collect();
+                keys.into_iter()
+            }
+
+            fn join_contains(&self, key: K) -> bool {
+                unsafe { $map::raw_contains_key(self.map.as_ptr(), key) }
+            }
+
+            unsafe fn join_get(&mut self, key: K) -> &'a mut V {
+                &mut *$map::raw_get_unchecked_mut(self.map.as_ptr(), key)
+            }
+        }
+    };
+}
+
+impl_joinable!(basic, SlotMap);
+impl_joinable!(hop, HopSlotMap);
+impl_joinable!(dense, DenseSlotMap);
+impl_joinable!(soa, SoaSlotMap);
+impl_joinable!(secondary, SecondaryMap);
+#[cfg(feature = "std")]
+impl_joinable!(sparse_secondary, SparseSecondaryMap<S: std::hash::BuildHasher>);
+
+impl_joinable_mut!(SlotMap);
+impl_joinable_mut!(HopSlotMap);
+impl_joinable_mut!(DenseSlotMap);
+impl_joinable_mut!(SoaSlotMap);
+impl_joinable_mut!(SecondaryMap);
+
+#[cfg(feature = "std")]
+impl<'a, K: Key, V, S: std::hash::BuildHasher> IntoJoinable<K> for &'a mut SparseSecondaryMap<K, V, S> {
+    type Joinable = SparseJoinMut<'a, K, V>;
+
+    fn into_joinable(self) -> SparseJoinMut<'a, K, V> {
+        SparseJoinMut {
+            values: self.iter_mut().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, K: Key, V> Joinable<K> for SparseJoinMut<'a, K, V> {
+    type Item = &'a mut V;
+    type Keys = alloc::vec::IntoIter<K>;
+
+    fn join_len(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+
+    fn join_keys(&self) -> Self::Keys {
+        let keys: Vec<K> = self.values.keys().copied().collect();
+        keys.into_iter()
+    }
+
+    fn join_contains(&self, key: K) -> bool {
+        self.values.contains_key(&key)
+    }
+
+    unsafe fn join_get(&mut self, key: K) -> &'a mut V {
+        self.values.remove(&key).unwrap_unchecked_()
+    }
+}
+
+macro_rules! impl_join {
+    ($($part:ident $keys:ident $idx:tt),+) => {
+        impl<K: Key, $($part: IntoJoinable<K>),+> IntoJoin<K> for ($($part,)+) {
+            type Join = ($($part::Joinable,)+);
+
+            fn into_join(self) -> Self::Join {
+                ($(self.$idx.into_joinable(),)+)
+            }
+        }
+
+        impl<K: Key, $($part: Joinable<K>),+> Join<K> for ($($part,)+) {
+            type Item = (K, $($part::Item,)+);
+            type Keys = JoinKeys<($(Option<$part::Keys>,)+)>;
+
+            fn driver(&self) -> Self::Keys {
+                // Find the smallest input that restricts the keys.
+                let mut smallest = None;
+                $(
+                    if let Some(len) = self.$idx.join_len() {
+                        if smallest.map_or(true, |(_, min)| len < min) {
+                            smallest = Some(($idx, len));
+                        }
+                    }
+                )+
+
+                let driver = smallest.map(|(idx, _)| idx);
+                JoinKeys(($(
+                    match driver == Some($idx) {
+                        true => Some(self.$idx.join_keys()),
+                        false => None,
+                    },
+                )+))
+            }
+
+            fn contains(&self, key: K) -> bool {
+                $(self.$idx.join_contains(key))&&+
+            }
+
+            unsafe fn get(&mut self, key: K) -> Self::Item {
+                (key, $(self.$idx.join_get(key),)+)
+            }
+        }
+
+        impl<K: Key, $($keys: Iterator<Item = K>),+> Iterator for JoinKeys<($(Option<$keys>,)+)> {
+            type Item = K;
+
+            fn next(&mut self) -> Option<K> {
+                $(
+                    if let Some(keys) = &mut (self.0).$idx {
+                        return keys.next();
+                    }
+                )+
+                None
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                $(
+                    if let Some(keys) = &(self.0).$idx {
+                        return keys.size_hint();
+                    }
+                )+
+                (0, Some(0))
+            }
+        }
+
+        impl<K: Key, $($keys: FusedIterator<Item = K>),+> FusedIterator for JoinKeys<($(Option<$keys>,)+)> {}
+    };
+}
+
+impl_join!(A KA 0);
+impl_join!(A KA 0, B KB 1);
+impl_join!(A KA 0, B KB 1, C KC 2);
+impl_join!(A KA 0, B KB 1, C KC 2, D KD 3);
+impl_join!(A KA 0, B KB 1, C KC 2, D KD 3, E KE 4);
+impl_join!(A KA 0, B KB 1, C KC 2, D KD 3, E KE 4, F KF 5);
+impl_join!(A KA 0, B KB 1, C KC 2, D KD 3, E KE 4, F KF 5, G KG 6);
+impl_join!(A KA 0, B KB 1, C KC 2, D KD 3, E KE 4, F KF 5, G KG 6, H KH 7);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn drives_from_smallest() {
+        let mut sm = SlotMap::new();
+        let keys: Vec<_> = (0..100).map(|i| sm.insert(i)).collect();
+        let mut dense = DenseSlotMap::new();
+        for (i, &key) in keys.iter().enumerate() {
+            assert_eq!(dense.insert(i), key);
+        }
+
+        let mut even = SecondaryMap::new();
+        let mut tens = SparseSecondaryMap::new();
+        for (i, &key) in keys.iter().enumerate() {
+            if i % 2 == 0 {
+                even.insert(key, i);
+            }
+            if i % 10 == 0 {
+                tens.insert(key, i);
+            }
+        }
+        sm.remove(keys[20]);
+
+        // The sparse map drives, whatever its position in the tuple.
+        let parts = (&sm, &even, Maybe(&dense), &tens).into_join();
+        assert_eq!(parts.driver().size_hint(), (10, Some(10)));
+        for (key, v, e, d, t) in join((&sm, &mut even, Maybe(&dense), &tens)) {
+            assert_eq!((*v as usize, *d.unwrap()), (*t, *t));
+            *e += 1;
+            assert_ne!(key, keys[20]);
+        }
+        assert_eq!(even.values().filter(|&&e| e % 2 == 1).count(), 9);
+
+        let all: Vec<_> = join((Maybe(&sm), Maybe(&even))).collect();
+        assert!(all.is_empty());
+        assert_eq!(join((&mut sm,)).count(), 99);
+    }
+
+    #[test]
+    fn mutable_items_outlive_later_lookups() {
+        let mut sm = HopSlotMap::new();
+        let keys: Vec<_> = (0..50).map(|i| sm.insert(i)).collect();
+        let mut soa = SoaSlotMap::new();
+        let mut sparse = SparseSecondaryMap::new();
+        for (i, &key) in keys.iter().enumerate() {
+            assert_eq!(soa.insert(i), key);
+            sparse.insert(key, i);
+        }
+
+        // Every item is held until the end, while later keys are looked up.
+        let items: Vec<_> = join((&mut sm, &mut soa, Maybe(&mut sparse))).collect();
+        assert_eq!(items.len(), 50);
+        for (_, h, s, sp) in items {
+            *h += 1;
+            *s += 1;
+            *sp.unwrap() += 1;
+        }
+        for (i, &key) in keys.iter().enumerate() {
+            assert_eq!((sm[key], soa[key], sparse[key]), (i + 1, i + 1, i + 1));
+        }
+
+        // A mutable sparse map, both driving and looked up by key.
+        for &key in &keys[..40] {
+            sparse.remove(key);
+        }
+        let items: Vec<_> = join((&mut soa, &mut sparse)).collect();
+        assert_eq!(items.len(), 10);
+        for (_, s, sp) in items {
+            *s += 1;
+            *sp += 1;
+        }
+        let mut few = SecondaryMap::new();
+        few.insert(keys[45], ());
+        let mut items: Vec<_> = join((&mut sparse, &few)).collect();
+        assert_eq!(items.len(), 1);
+        *items[0].1 += 1;
+        assert_eq!((soa[keys[45]], sparse[keys[45]]), (47, 48));
+        assert_eq!(soa[keys[0]], 1);
+    }
+}
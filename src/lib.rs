@@ -52,7 +52,7 @@
 //! stored in slot maps, without hashing required - it's direct indexing under
 //! the hood.
 //!
-//! The minimum required stable Rust version for this crate is 1.49.
+//! The minimum required stable Rust version for this crate is 1.65.
 //!
 //! # Examples
 //!
@@ -103,6 +103,10 @@
 //! it relies on [`HashMap`]. Finally the `unstable` feature can be defined to
 //! enable the parts of `slotmap` that only work on nightly Rust.
 //!
+//! The `rayon` feature adds parallel iterators through [`rayon`] to all slot
+//! maps and secondary maps: `par_iter`, `par_iter_mut`, `par_keys` and
+//! `par_values_mut`. It requires the `std` feature.
+//!
 //! # Why not index a [`Vec`], or use [`slab`], [`stable-vec`], etc?
 //!
 //! Those solutions either can not reclaim memory from deleted elements or
@@ -132,7 +136,9 @@
 //! The memory usage for each slot in [`SlotMap`] is `4 + max(sizeof(T), 4)`
 //! rounded up to the alignment of `T`. Similarly it is `4 + max(sizeof(T), 12)`
 //! for [`HopSlotMap`]. [`DenseSlotMap`] has an overhead of 8 bytes per element
-//! and 8 bytes per slot.
+//! and 8 bytes per slot. [`SoaSlotMap`] stores the same 4 byte version and
+//! `sizeof(T)` byte value per slot as two separate arrays, plus 4 bytes per
+//! vacant slot for its free list.
 //!
 //! # Choosing [`SlotMap`], [`HopSlotMap`] or [`DenseSlotMap`]
 //!
@@ -150,12 +156,29 @@
 //! [`HopSlotMap`]. The downside is that insertion and removal is roughly twice
 //! as slow. Random access is the same speed for both.
 //!
+//! For large values, [`SoaSlotMap`] keeps the versions of a [`SlotMap`] in
+//! their own array, apart from the values. Checking keys, iterating over keys
+//! and skipping vacant slots then only read the versions, at the cost of a
+//! second cache line per access of a value.
+//!
+//! Both iterate in slot order, which depends on which slots were reused. If
+//! you need to iterate in insertion order, [`OrderedSlotMap`] additionally
+//! links the elements of a [`SlotMap`] together in the order they were
+//! inserted. For any other order, [`SlotList`] is a doubly linked list whose
+//! elements can be inserted, moved and removed anywhere by key.
+//!
 //! [`DenseSlotMap`] goes even further and stores all elements on a contiguous
 //! block of memory. It uses two indirections per random access; the slots
 //! contain indices used to access the contiguous memory. This means random
 //! access is slower than both [`SlotMap`] and [`HopSlotMap`], but iteration is
 //! significantly faster, as fast as a normal [`Vec`].
 //!
+//! A [`DenseSlotMap`] removes elements by moving the last element into the
+//! hole, so its iteration order is arbitrary. If you need the elements to stay
+//! in insertion order, use [`OrderedDenseSlotMap`], which pays for that with
+//! O(n) removal. If you mostly iterate over a few fixed subsets of the
+//! elements, [`PartitionedDenseSlotMap`] keeps each subset contiguous.
+//!
 //! # Choosing [`SecondaryMap`] or [`SparseSecondaryMap`]
 //!
 //! You want to associate extra data with objects stored in a slot map, so you
@@ -173,6 +196,43 @@
 //! reused. You should use this variant if you expect to store some associated
 //! data for only a small portion of the primary slot map.
 //!
+//! To visit the objects that have data in several of these maps, use
+//! [`join`](join::join). It walks the keys of the smallest map and checks each
+//! key against the others once, instead of looking every key up in every map.
+//!
+//! Entries of removed keys stay in a secondary map until their slot is reused.
+//! Call `retain_live` to drop them, or register the secondary maps as
+//! observers of an [`ObservedSlotMap`], which removes their entries along with
+//! the keys.
+//!
+//! # Data structures built on slot maps
+//!
+//! Keys make good handles into linked data structures, because a stale handle
+//! is detected instead of silently pointing at a reused node. This crate
+//! ships a few such structures:
+//!
+//! - [`SlotList`], a doubly linked list with O(1) insertion and removal
+//!   anywhere by key.
+//! - [`KeyedHeap`], a priority queue whose entries can be reprioritized or
+//!   removed by key.
+//! - [`SlotTree`], an ordered hierarchy of nodes with cascading removal, as
+//!   used by scene graphs and UI trees.
+//! - [`SlotGraph`], a directed graph with separately keyed nodes and edges,
+//!   where removing a node removes its edges.
+//! - [`UnionFind`], a disjoint-set forest over the keys of a slot map, for
+//!   tracking connected components.
+//! - [`SlotInterner`], a slot map that stores every distinct value once and
+//!   can look up the key of a value (requires `std`).
+//! - [`BiSecondaryMap`], a one-to-one mapping between the keys of two slot
+//!   maps with lookup in both directions.
+//! - [`SecondaryIndex`], a map from attributes of the values in a slot map to
+//!   the keys that have them (requires `std`).
+//! - [`SecondaryMultiMap`], which associates any number of values with each
+//!   key of a slot map, stored in a single pool.
+//! - [`SecondarySet`], a set of keys of a slot map backed by a bitset.
+//! - [`DefaultSecondaryMap`], a secondary map that returns a default value
+//!   for keys without an entry and inserts it on mutable access.
+//!
 //! # Custom key types
 //!
 //! If you have multiple slot maps it's an error to use the key of one slot map
@@ -199,6 +259,7 @@
 //! [`BTreeMap`]: std::collections::BTreeMap
 //! [`HashMap`]: std::collections::HashMap
 //! [`serde`]: https://github.com/serde-rs/serde
+//! [`rayon`]: https://github.com/rayon-rs/rayon
 //! [`slab`]: https://crates.io/crates/slab
 //! [`stable-vec`]: https://crates.io/crates/stable-vec
 //! [`no_std`]: https://doc.rust-lang.org/1.7.0/book/no-stdlib.html
@@ -215,12 +276,34 @@ pub mod __impl {
 }
 
 pub mod basic;
+pub mod bi_secondary;
+pub mod default_secondary;
 pub mod dense;
 pub mod delay;
+pub mod graph;
+pub mod heap;
 pub mod hop;
+#[cfg(feature = "std")]
+pub mod interner;
+pub mod join;
+pub mod list;
+pub mod multi_secondary;
+pub mod observed;
+pub mod ordered;
+pub mod ordered_dense;
+pub mod partitioned_dense;
+pub mod primary;
+#[cfg(feature = "rayon")]
+pub(crate) mod par;
 pub mod secondary;
 #[cfg(feature = "std")]
+pub mod secondary_index;
+pub mod secondary_set;
+pub mod soa;
+#[cfg(feature = "std")]
 pub mod sparse_secondary;
+pub mod tree;
+pub mod union_find;
 pub(crate) mod util;
 
 
@@ -229,16 +312,50 @@ pub use pi_key_alloter::{new_key_type, Key, DefaultKey, KeyData, is_older_versio
 #[doc(inline)]
 pub use crate::basic::SlotMap;
 #[doc(inline)]
+pub use crate::bi_secondary::BiSecondaryMap;
+#[doc(inline)]
+pub use crate::default_secondary::DefaultSecondaryMap;
+#[doc(inline)]
 pub use crate::dense::DenseSlotMap;
 #[doc(inline)]
 pub use crate::delay::DelaySlotMap;
 #[doc(inline)]
+pub use crate::graph::SlotGraph;
+#[doc(inline)]
+pub use crate::heap::KeyedHeap;
+#[doc(inline)]
 pub use crate::hop::HopSlotMap;
+#[cfg(feature = "std")]
+#[doc(inline)]
+pub use crate::interner::SlotInterner;
+#[doc(inline)]
+pub use crate::list::SlotList;
+#[doc(inline)]
+pub use crate::multi_secondary::SecondaryMultiMap;
+#[doc(inline)]
+pub use crate::observed::ObservedSlotMap;
+#[doc(inline)]
+pub use crate::ordered::OrderedSlotMap;
+#[doc(inline)]
+pub use crate::ordered_dense::OrderedDenseSlotMap;
+#[doc(inline)]
+pub use crate::partitioned_dense::PartitionedDenseSlotMap;
 #[doc(inline)]
 pub use crate::secondary::SecondaryMap;
 #[cfg(feature = "std")]
 #[doc(inline)]
+pub use crate::secondary_index::SecondaryIndex;
+#[doc(inline)]
+pub use crate::secondary_set::SecondarySet;
+#[doc(inline)]
+pub use crate::soa::SoaSlotMap;
+#[cfg(feature = "std")]
+#[doc(inline)]
 pub use crate::sparse_secondary::SparseSecondaryMap;
+#[doc(inline)]
+pub use crate::tree::SlotTree;
+#[doc(inline)]
+pub use crate::union_find::UnionFind;
 
 // Keep Slottable for backwards compatibility, but warn about deprecation
 // and hide from documentation.
@@ -0,0 +1,1608 @@
+//! Contains the slot list implementation, a doubly linked list whose nodes are
+//! addressed by slot map keys.
+
+use core::fmt;
+use core::iter::{FromIterator, FusedIterator};
+use core::marker::PhantomData;
+use core::ops::{Index, IndexMut};
+
+use crate::SlotMap;
+use pi_key_alloter::{DefaultKey, Key};
+
+// A list element together with the links to its neighbours. Null keys mark
+// the ends of the list.
+#[derive(Debug, Clone)]
+struct Node<K, V> {
+    value: V,
+    prev: K,
+    next: K,
+}
+
+/// Slot list, a doubly linked list backed by a [`SlotMap`].
+///
+/// Every element is addressed by the key returned when inserting it. Keys
+/// stay valid while the element is in the list, no matter how the list is
+/// rearranged, and become invalid once it is removed, just like slot map
+/// keys. Pushing, popping, inserting next to a key, removing by key and
+/// moving elements are all O(1).
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut list = SlotList::new();
+/// let b = list.push_back('b');
+/// let a = list.push_front('a');
+/// let c = list.insert_after(b, 'c').unwrap();
+/// assert_eq!(list.values().collect::<String>(), "abc");
+///
+/// list.move_before(c, a);
+/// assert_eq!(list.values().collect::<String>(), "cab");
+/// assert_eq!(list.remove(a), Some('a'));
+/// assert_eq!(list.next(c), Some(b));
+/// ```
+#[derive(Clone)]
+pub struct SlotList<K: Key, V> {
+    sm: SlotMap<K, Node<K, V>>,
+    head: K,
+    tail: K,
+}
+
+impl<V> SlotList<DefaultKey, V> {
+    /// Constructs a new, empty [`SlotList`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list: SlotList<_, i32> = SlotList::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`SlotList`] with the given capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list: SlotList<_, i32> = SlotList::with_capacity(10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_key(capacity)
+    }
+}
+
+impl<K: Key, V> SlotList<K, V> {
+    /// Constructs a new, empty [`SlotList`] with a custom key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct ListKey;
+    /// }
+    /// let mut list: SlotList<ListKey, i32> = SlotList::with_key();
+    /// ```
+    pub fn with_key() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`SlotList`] with the given capacity and a custom key
+    /// type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct ListKey;
+    /// }
+    /// let mut list: SlotList<ListKey, i32> = SlotList::with_capacity_and_key(10);
+    /// ```
+    pub fn with_capacity_and_key(capacity: usize) -> Self {
+        Self {
+            sm: SlotMap::with_capacity_and_key(capacity),
+            head: K::null(),
+            tail: K::null(),
+        }
+    }
+
+    /// Returns the number of elements in the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// list.push_back(1);
+    /// list.push_front(0);
+    /// assert_eq!(list.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.sm.len()
+    }
+
+    /// Returns if the list is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let key = list.push_back("dummy");
+    /// assert_eq!(list.is_empty(), false);
+    /// list.remove(key);
+    /// assert_eq!(list.is_empty(), true);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.sm.is_empty()
+    }
+
+    /// Returns the number of elements the [`SlotList`] can hold without
+    /// reallocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let list: SlotList<_, f64> = SlotList::with_capacity(10);
+    /// assert_eq!(list.capacity(), 10);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.sm.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new allocation size overflows [`usize`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// list.push_back("foo");
+    /// list.reserve(32);
+    /// assert!(list.capacity() >= 33);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.sm.reserve(additional)
+    }
+
+    /// Returns [`true`] if the list contains `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let key = list.push_back(42);
+    /// assert_eq!(list.contains_key(key), true);
+    /// list.remove(key);
+    /// assert_eq!(list.contains_key(key), false);
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.sm.contains_key(key)
+    }
+
+    /// Returns the key of the first element, or [`None`] if the list is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// assert_eq!(list.front(), None);
+    /// let k = list.push_front(1);
+    /// list.push_back(2);
+    /// assert_eq!(list.front(), Some(k));
+    /// ```
+    pub fn front(&self) -> Option<K> {
+        Some(self.head).filter(|k| !k.is_null())
+    }
+
+    /// Returns the key of the last element, or [`None`] if the list is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// list.push_back(1);
+    /// let k = list.push_back(2);
+    /// assert_eq!(list.back(), Some(k));
+    /// ```
+    pub fn back(&self) -> Option<K> {
+        Some(self.tail).filter(|k| !k.is_null())
+    }
+
+    /// Returns the key of the element after the element of `key`, or
+    /// [`None`] if it is the last element or `key` is not in the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let a = list.push_back('a');
+    /// let b = list.push_back('b');
+    /// assert_eq!(list.next(a), Some(b));
+    /// assert_eq!(list.next(b), None);
+    /// ```
+    pub fn next(&self, key: K) -> Option<K> {
+        self.sm.get(key).map(|node| node.next).filter(|k| !k.is_null())
+    }
+
+    /// Returns the key of the element before the element of `key`, or
+    /// [`None`] if it is the first element or `key` is not in the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let a = list.push_back('a');
+    /// let b = list.push_back('b');
+    /// assert_eq!(list.prev(b), Some(a));
+    /// assert_eq!(list.prev(a), None);
+    /// ```
+    pub fn prev(&self, key: K) -> Option<K> {
+        self.sm.get(key).map(|node| node.prev).filter(|k| !k.is_null())
+    }
+
+    /// Inserts a value at the front of the list, returning its key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the list equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// list.push_front(2);
+    /// let k = list.push_front(1);
+    /// assert_eq!(list.front(), Some(k));
+    /// assert_eq!(list.values().collect::<Vec<_>>(), [&1, &2]);
+    /// ```
+    pub fn push_front(&mut self, value: V) -> K {
+        let key = self.sm.insert(Node::unlinked(value));
+        self.link(key, key, K::null(), self.head);
+        key
+    }
+
+    /// Inserts a value at the back of the list, returning its key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the list equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// list.push_back(1);
+    /// let k = list.push_back(2);
+    /// assert_eq!(list.back(), Some(k));
+    /// assert_eq!(list.values().collect::<Vec<_>>(), [&1, &2]);
+    /// ```
+    pub fn push_back(&mut self, value: V) -> K {
+        self.push_back_with_key(|_| value)
+    }
+
+    /// Inserts a value given by `f` at the back of the list. The key where the
+    /// value will be stored is passed into `f`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the list equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let k = list.push_back_with_key(|k| (k, 20));
+    /// assert_eq!(list[k], (k, 20));
+    /// ```
+    pub fn push_back_with_key<F>(&mut self, f: F) -> K
+    where
+        F: FnOnce(K) -> V,
+    {
+        let key = self.sm.insert_with_key(|k| Node::unlinked(f(k)));
+        self.link(key, key, self.tail, K::null());
+        key
+    }
+
+    /// Removes the first element and returns its value, or [`None`] if the
+    /// list is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// list.push_back(1);
+    /// list.push_back(2);
+    /// assert_eq!(list.pop_front(), Some(1));
+    /// assert_eq!(list.pop_front(), Some(2));
+    /// assert_eq!(list.pop_front(), None);
+    /// ```
+    pub fn pop_front(&mut self) -> Option<V> {
+        self.remove(self.head)
+    }
+
+    /// Removes the last element and returns its value, or [`None`] if the list
+    /// is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// list.push_back(1);
+    /// list.push_back(2);
+    /// assert_eq!(list.pop_back(), Some(2));
+    /// assert_eq!(list.pop_back(), Some(1));
+    /// assert_eq!(list.pop_back(), None);
+    /// ```
+    pub fn pop_back(&mut self) -> Option<V> {
+        self.remove(self.tail)
+    }
+
+    /// Inserts a value directly before the element of `anchor`, returning its
+    /// key. Returns [`None`] and drops the value if `anchor` is not in the
+    /// list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the list equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let c = list.push_back('c');
+    /// list.insert_before(c, 'b');
+    /// assert_eq!(list.values().collect::<String>(), "bc");
+    /// ```
+    pub fn insert_before(&mut self, anchor: K, value: V) -> Option<K> {
+        let prev = self.sm.get(anchor)?.prev;
+        let key = self.sm.insert(Node::unlinked(value));
+        self.link(key, key, prev, anchor);
+        Some(key)
+    }
+
+    /// Inserts a value directly after the element of `anchor`, returning its
+    /// key. Returns [`None`] and drops the value if `anchor` is not in the
+    /// list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the list equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let a = list.push_back('a');
+    /// list.insert_after(a, 'b');
+    /// assert_eq!(list.values().collect::<String>(), "ab");
+    /// ```
+    pub fn insert_after(&mut self, anchor: K, value: V) -> Option<K> {
+        let next = self.sm.get(anchor)?.next;
+        let key = self.sm.insert(Node::unlinked(value));
+        self.link(key, key, anchor, next);
+        Some(key)
+    }
+
+    /// Removes a key from the list, returning the value at the key if the key
+    /// was not previously removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let a = list.push_back('a');
+    /// let b = list.push_back('b');
+    /// assert_eq!(list.remove(a), Some('a'));
+    /// assert_eq!(list.remove(a), None);
+    /// assert_eq!(list.front(), Some(b));
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        if !self.sm.contains_key(key) {
+            return None;
+        }
+        self.unlink(key, key);
+        self.sm.remove(key).map(|node| node.value)
+    }
+
+    /// Retains only the elements specified by the predicate, visiting them
+    /// from front to back.
+    ///
+    /// In other words, remove all key-value pairs `(k, v)` such that
+    /// `f(k, &mut v)` returns false. This method invalidates any removed keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list: SlotList<DefaultKey, i32> = (0..6).collect();
+    /// list.retain(|_, v| *v % 2 == 0);
+    /// assert_eq!(list.values().collect::<Vec<_>>(), [&0, &2, &4]);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        let mut cur = self.head;
+        while let Some(node) = self.sm.get_mut(cur) {
+            let (key, next) = (cur, node.next);
+            if !f(key, &mut node.value) {
+                self.remove(key);
+            }
+            cur = next;
+        }
+    }
+
+    /// Clears the list. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// list.push_back(0);
+    /// list.clear();
+    /// assert_eq!(list.len(), 0);
+    /// assert_eq!(list.front(), None);
+    /// ```
+    pub fn clear(&mut self) {
+        self.sm.clear();
+        self.head = K::null();
+        self.tail = K::null();
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let key = list.push_back("bar");
+    /// assert_eq!(list.get(key), Some(&"bar"));
+    /// list.remove(key);
+    /// assert_eq!(list.get(key), None);
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.sm.get(key).map(|node| &node.value)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let key = list.push_back(3.5);
+    /// if let Some(x) = list.get_mut(key) {
+    ///     *x += 3.0;
+    /// }
+    /// assert_eq!(list[key], 6.5);
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.sm.get_mut(key).map(|node| &mut node.value)
+    }
+
+    /// Moves the element of `key` to directly before the element of `anchor`.
+    /// Returns `false` and does nothing if either key is not in the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let a = list.push_back('a');
+    /// let b = list.push_back('b');
+    /// assert!(list.move_before(b, a));
+    /// assert_eq!(list.values().collect::<String>(), "ba");
+    /// ```
+    pub fn move_before(&mut self, key: K, anchor: K) -> bool {
+        self.splice_before(key, key, anchor)
+    }
+
+    /// Moves the element of `key` to directly after the element of `anchor`.
+    /// Returns `false` and does nothing if either key is not in the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let a = list.push_back('a');
+    /// let b = list.push_back('b');
+    /// assert!(list.move_after(a, b));
+    /// assert_eq!(list.values().collect::<String>(), "ba");
+    /// ```
+    pub fn move_after(&mut self, key: K, anchor: K) -> bool {
+        self.splice_after(key, key, anchor)
+    }
+
+    /// Moves the elements from `first` up to and including `last` to directly
+    /// before the element of `anchor`, keeping their order.
+    ///
+    /// Returns `false` and does nothing if any of the keys is not in the list,
+    /// if `last` does not come at or after `first`, or if `anchor` lies within
+    /// the moved range. Checking this walks the range, so splicing is
+    /// O(length of the range).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let keys: Vec<_> = "abcde".chars().map(|c| list.push_back(c)).collect();
+    /// assert!(list.splice_before(keys[2], keys[3], keys[0]));
+    /// assert_eq!(list.values().collect::<String>(), "cdabe");
+    /// assert!(!list.splice_before(keys[0], keys[4], keys[1]));
+    /// ```
+    pub fn splice_before(&mut self, first: K, last: K, anchor: K) -> bool {
+        if !self.is_splicable(first, last, anchor) {
+            return false;
+        }
+        self.unlink(first, last);
+        let prev = self.sm[anchor].prev;
+        self.link(first, last, prev, anchor);
+        true
+    }
+
+    /// Moves the elements from `first` up to and including `last` to directly
+    /// after the element of `anchor`, keeping their order.
+    ///
+    /// See [`splice_before`](Self::splice_before) for when this does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let keys: Vec<_> = "abcde".chars().map(|c| list.push_back(c)).collect();
+    /// assert!(list.splice_after(keys[0], keys[1], keys[4]));
+    /// assert_eq!(list.values().collect::<String>(), "cdeab");
+    /// ```
+    pub fn splice_after(&mut self, first: K, last: K, anchor: K) -> bool {
+        if !self.is_splicable(first, last, anchor) {
+            return false;
+        }
+        self.unlink(first, last);
+        let next = self.sm[anchor].next;
+        self.link(first, last, anchor, next);
+        true
+    }
+
+    /// Returns a cursor pointing at the element of `key`, or [`None`] if the
+    /// key is not in the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// list.push_back(1);
+    /// let k = list.push_back(2);
+    /// let mut cursor = list.cursor(k).unwrap();
+    /// cursor.move_prev();
+    /// assert_eq!(cursor.current(), Some(&1));
+    /// ```
+    pub fn cursor(&self, key: K) -> Option<Cursor<K, V>> {
+        self.sm.get(key).map(|_| Cursor { list: self, cur: key })
+    }
+
+    /// Returns a cursor pointing at the first element. If the list is empty
+    /// it points at the "ghost" non-element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let list: SlotList<DefaultKey, i32> = (1..3).collect();
+    /// assert_eq!(list.cursor_front().current(), Some(&1));
+    /// ```
+    pub fn cursor_front(&self) -> Cursor<K, V> {
+        Cursor {
+            list: self,
+            cur: self.head,
+        }
+    }
+
+    /// Returns a cursor pointing at the last element. If the list is empty
+    /// it points at the "ghost" non-element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let list: SlotList<DefaultKey, i32> = (1..3).collect();
+    /// assert_eq!(list.cursor_back().current(), Some(&2));
+    /// ```
+    pub fn cursor_back(&self) -> Cursor<K, V> {
+        Cursor {
+            list: self,
+            cur: self.tail,
+        }
+    }
+
+    /// Returns a cursor that can edit the list, pointing at the element of
+    /// `key`, or [`None`] if the key is not in the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let k = list.push_back(1);
+    /// let mut cursor = list.cursor_mut(k).unwrap();
+    /// cursor.insert_after(2);
+    /// assert_eq!(list.values().collect::<Vec<_>>(), [&1, &2]);
+    /// ```
+    pub fn cursor_mut(&mut self, key: K) -> Option<CursorMut<K, V>> {
+        if !self.sm.contains_key(key) {
+            return None;
+        }
+        Some(CursorMut { list: self, cur: key })
+    }
+
+    /// Returns a cursor that can edit the list, pointing at the first element.
+    /// If the list is empty it points at the "ghost" non-element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list: SlotList<DefaultKey, i32> = (1..4).collect();
+    /// let mut cursor = list.cursor_front_mut();
+    /// cursor.remove_current();
+    /// assert_eq!(cursor.current(), Some(&mut 2));
+    /// ```
+    pub fn cursor_front_mut(&mut self) -> CursorMut<K, V> {
+        CursorMut {
+            cur: self.head,
+            list: self,
+        }
+    }
+
+    /// Returns a cursor that can edit the list, pointing at the last element.
+    /// If the list is empty it points at the "ghost" non-element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list: SlotList<DefaultKey, i32> = (1..4).collect();
+    /// let mut cursor = list.cursor_back_mut();
+    /// *cursor.current().unwrap() = 30;
+    /// assert_eq!(list.values().collect::<Vec<_>>(), [&1, &2, &30]);
+    /// ```
+    pub fn cursor_back_mut(&mut self) -> CursorMut<K, V> {
+        CursorMut {
+            cur: self.tail,
+            list: self,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs from front to back. The
+    /// iterator element type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let k0 = list.push_back(0);
+    /// let k1 = list.push_back(1);
+    /// assert_eq!(list.iter().collect::<Vec<_>>(), [(k0, &0), (k1, &1)]);
+    /// assert_eq!(list.iter().rev().collect::<Vec<_>>(), [(k1, &1), (k0, &0)]);
+    /// ```
+    pub fn iter(&self) -> Iter<K, V> {
+        Iter {
+            sm: &self.sm,
+            front: self.head,
+            back: self.tail,
+            num_left: self.len(),
+        }
+    }
+
+    /// An iterator visiting all key-value pairs from front to back, with
+    /// mutable references to the values. The iterator element type is
+    /// `(K, &'a mut V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let k0 = list.push_back(10);
+    /// let k1 = list.push_back(20);
+    /// for (k, v) in list.iter_mut() {
+    ///     if k != k1 {
+    ///         *v *= -1;
+    ///     }
+    /// }
+    /// assert_eq!(list[k0], -10);
+    /// assert_eq!(list[k1], 20);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<K, V> {
+        IterMut {
+            front: self.head,
+            back: self.tail,
+            num_left: self.len(),
+            sm: &mut self.sm,
+            _sm: PhantomData,
+        }
+    }
+
+    /// An iterator visiting all keys from front to back. The iterator element
+    /// type is K.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// let k1 = list.push_back(20);
+    /// let k0 = list.push_front(10);
+    /// assert_eq!(list.keys().collect::<Vec<_>>(), [k0, k1]);
+    /// ```
+    pub fn keys(&self) -> Keys<K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    /// An iterator visiting all values from front to back. The iterator
+    /// element type is `&'a V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// list.push_back(10);
+    /// list.push_back(20);
+    /// assert_eq!(list.values().collect::<Vec<_>>(), [&10, &20]);
+    /// ```
+    pub fn values(&self) -> Values<K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// An iterator visiting all values mutably from front to back. The
+    /// iterator element type is `&'a mut V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut list = SlotList::new();
+    /// list.push_back(1);
+    /// list.push_back(2);
+    /// list.values_mut().for_each(|n| *n *= 3);
+    /// assert_eq!(list.values().collect::<Vec<_>>(), [&3, &6]);
+    /// ```
+    pub fn values_mut(&mut self) -> ValuesMut<K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    // Links the chain of nodes from first to last in between prev and next,
+    // which must be neighbours or null. The outer links of first and last are
+    // overwritten.
+    fn link(&mut self, first: K, last: K, prev: K, next: K) {
+        self.sm[first].prev = prev;
+        self.sm[last].next = next;
+        match self.sm.get_mut(prev) {
+            Some(node) => node.next = first,
+            None => self.head = first,
+        }
+        match self.sm.get_mut(next) {
+            Some(node) => node.prev = last,
+            None => self.tail = last,
+        }
+    }
+
+    // Takes the chain of nodes from first to last out of the list, joining its
+    // neighbours. The chain keeps its inner links.
+    fn unlink(&mut self, first: K, last: K) {
+        let prev = self.sm[first].prev;
+        let next = self.sm[last].next;
+        match self.sm.get_mut(prev) {
+            Some(node) => node.next = next,
+            None => self.head = next,
+        }
+        match self.sm.get_mut(next) {
+            Some(node) => node.prev = prev,
+            None => self.tail = prev,
+        }
+    }
+
+    // Checks that first to last is a chain in the list not containing anchor.
+    fn is_splicable(&self, first: K, last: K, anchor: K) -> bool {
+        if !self.sm.contains_key(anchor) {
+            return false;
+        }
+
+        let mut cur = first;
+        while let Some(node) = self.sm.get(cur) {
+            if cur == anchor {
+                return false;
+            }
+            if cur == last {
+                return true;
+            }
+            cur = node.next;
+        }
+        false
+    }
+}
+
+impl<K, V> Node<K, V>
+where
+    K: Key,
+{
+    fn unlinked(value: V) -> Self {
+        Self {
+            value,
+            prev: K::null(),
+            next: K::null(),
+        }
+    }
+}
+
+impl<K: Key, V: fmt::Debug> fmt::Debug for SlotList<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Key, V> Default for SlotList<K, V> {
+    fn default() -> Self {
+        Self::with_key()
+    }
+}
+
+impl<K: Key, V> Index<K> for SlotList<K, V> {
+    type Output = V;
+
+    fn index(&self, key: K) -> &V {
+        match self.get(key) {
+            Some(r) => r,
+            None => panic!("invalid SlotList key used"),
+        }
+    }
+}
+
+impl<K: Key, V> IndexMut<K> for SlotList<K, V> {
+    fn index_mut(&mut self, key: K) -> &mut V {
+        match self.get_mut(key) {
+            Some(r) => r,
+            None => panic!("invalid SlotList key used"),
+        }
+    }
+}
+
+impl<K: Key, V> Extend<V> for SlotList<K, V> {
+    fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<K: Key, V> FromIterator<V> for SlotList<K, V> {
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+        let mut list = Self::with_key();
+        list.extend(iter);
+        list
+    }
+}
+
+// Cursors.
+/// A cursor over a [`SlotList`].
+///
+/// A cursor points at an element of the list, or at a "ghost" non-element
+/// between the back and the front. Moving past either end of the list lands
+/// on the ghost, moving once more wraps around to the other end.
+///
+/// This cursor is created by [`SlotList::cursor`], [`SlotList::cursor_front`]
+/// and [`SlotList::cursor_back`].
+#[derive(Debug)]
+pub struct Cursor<'a, K: Key + 'a, V: 'a> {
+    list: &'a SlotList<K, V>,
+    cur: K,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Cursor<'a, K, V> {
+    fn clone(&self) -> Self {
+        Cursor {
+            list: self.list,
+            cur: self.cur,
+        }
+    }
+}
+
+impl<'a, K: Key, V> Cursor<'a, K, V> {
+    /// Returns the key of the current element, or [`None`] if the cursor
+    /// points at the ghost.
+    pub fn key(&self) -> Option<K> {
+        Some(self.cur).filter(|k| !k.is_null())
+    }
+
+    /// Returns the value of the current element, or [`None`] if the cursor
+    /// points at the ghost.
+    pub fn current(&self) -> Option<&'a V> {
+        self.list.get(self.cur)
+    }
+
+    /// Moves the cursor to the next element.
+    pub fn move_next(&mut self) {
+        self.cur = match self.list.sm.get(self.cur) {
+            Some(node) => node.next,
+            None => self.list.head,
+        };
+    }
+
+    /// Moves the cursor to the previous element.
+    pub fn move_prev(&mut self) {
+        self.cur = match self.list.sm.get(self.cur) {
+            Some(node) => node.prev,
+            None => self.list.tail,
+        };
+    }
+
+    /// Returns the value of the next element without moving the cursor.
+    pub fn peek_next(&self) -> Option<&'a V> {
+        let mut next = self.clone();
+        next.move_next();
+        next.current()
+    }
+
+    /// Returns the value of the previous element without moving the cursor.
+    pub fn peek_prev(&self) -> Option<&'a V> {
+        let mut prev = self.clone();
+        prev.move_prev();
+        prev.current()
+    }
+}
+
+/// A cursor over a [`SlotList`] that can insert and remove elements.
+///
+/// Like [`Cursor`] it points at an element or at the ghost non-element.
+///
+/// This cursor is created by [`SlotList::cursor_mut`],
+/// [`SlotList::cursor_front_mut`] and [`SlotList::cursor_back_mut`].
+#[derive(Debug)]
+pub struct CursorMut<'a, K: Key + 'a, V: 'a> {
+    list: &'a mut SlotList<K, V>,
+    cur: K,
+}
+
+impl<'a, K: Key, V> CursorMut<'a, K, V> {
+    /// Returns the key of the current element, or [`None`] if the cursor
+    /// points at the ghost.
+    pub fn key(&self) -> Option<K> {
+        Some(self.cur).filter(|k| !k.is_null())
+    }
+
+    /// Returns the value of the current element, or [`None`] if the cursor
+    /// points at the ghost.
+    pub fn current(&mut self) -> Option<&mut V> {
+        self.list.get_mut(self.cur)
+    }
+
+    /// Moves the cursor to the next element.
+    pub fn move_next(&mut self) {
+        self.cur = match self.list.sm.get(self.cur) {
+            Some(node) => node.next,
+            None => self.list.head,
+        };
+    }
+
+    /// Moves the cursor to the previous element.
+    pub fn move_prev(&mut self) {
+        self.cur = match self.list.sm.get(self.cur) {
+            Some(node) => node.prev,
+            None => self.list.tail,
+        };
+    }
+
+    /// Inserts a value before the current element, returning its key. If the
+    /// cursor points at the ghost the value is inserted at the back.
+    pub fn insert_before(&mut self, value: V) -> K {
+        match self.list.sm.get(self.cur) {
+            Some(_) => self.list.insert_before(self.cur, value).unwrap(),
+            None => self.list.push_back(value),
+        }
+    }
+
+    /// Inserts a value after the current element, returning its key. If the
+    /// cursor points at the ghost the value is inserted at the front.
+    pub fn insert_after(&mut self, value: V) -> K {
+        match self.list.sm.get(self.cur) {
+            Some(_) => self.list.insert_after(self.cur, value).unwrap(),
+            None => self.list.push_front(value),
+        }
+    }
+
+    /// Removes the current element and returns its value, moving the cursor
+    /// to the next element. Returns [`None`] if the cursor points at the
+    /// ghost.
+    pub fn remove_current(&mut self) -> Option<V> {
+        let next = self.list.sm.get(self.cur)?.next;
+        let value = self.list.remove(self.cur);
+        self.cur = next;
+        value
+    }
+
+    /// Returns a read-only cursor pointing at the current element.
+    pub fn as_cursor(&self) -> Cursor<K, V> {
+        Cursor {
+            list: self.list,
+            cur: self.cur,
+        }
+    }
+}
+
+// Iterators.
+/// An iterator that moves key-value pairs out of a [`SlotList`].
+///
+/// This iterator is created by calling the `into_iter` method on
+/// [`SlotList`], provided by the [`IntoIterator`] trait.
+#[derive(Debug, Clone)]
+pub struct IntoIter<K: Key, V> {
+    list: SlotList<K, V>,
+}
+
+/// An iterator over the key-value pairs in a [`SlotList`].
+///
+/// This iterator is created by [`SlotList::iter`].
+#[derive(Debug)]
+pub struct Iter<'a, K: Key + 'a, V: 'a> {
+    sm: &'a SlotMap<K, Node<K, V>>,
+    front: K,
+    back: K,
+    num_left: usize,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Iter<'a, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            sm: self.sm,
+            front: self.front,
+            back: self.back,
+            num_left: self.num_left,
+        }
+    }
+}
+
+/// A mutable iterator over the key-value pairs in a [`SlotList`].
+///
+/// This iterator is created by [`SlotList::iter_mut`].
+///
+/// The nodes are reached through a raw pointer rather than borrowing the slot
+/// map again for every step, which would invalidate the values already
+/// yielded.
+#[derive(Debug)]
+pub struct IterMut<'a, K: Key + 'a, V: 'a> {
+    sm: *mut SlotMap<K, Node<K, V>>,
+    front: K,
+    back: K,
+    num_left: usize,
+    _sm: PhantomData<&'a mut SlotMap<K, Node<K, V>>>,
+}
+
+// An `IterMut` is used like the `&mut SlotMap` it was created from.
+unsafe impl<'a, K: Key, V: Send> Send for IterMut<'a, K, V> {}
+unsafe impl<'a, K: Key, V: Sync> Sync for IterMut<'a, K, V> {}
+
+/// An iterator over the keys in a [`SlotList`].
+///
+/// This iterator is created by [`SlotList::keys`].
+#[derive(Debug)]
+pub struct Keys<'a, K: Key + 'a, V: 'a> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Keys<'a, K, V> {
+    fn clone(&self) -> Self {
+        Keys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// An iterator over the values in a [`SlotList`].
+///
+/// This iterator is created by [`SlotList::values`].
+#[derive(Debug)]
+pub struct Values<'a, K: Key + 'a, V: 'a> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Values<'a, K, V> {
+    fn clone(&self) -> Self {
+        Values {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// A mutable iterator over the values in a [`SlotList`].
+///
+/// This iterator is created by [`SlotList::values_mut`].
+#[derive(Debug)]
+pub struct ValuesMut<'a, K: Key + 'a, V: 'a> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<K: Key, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        let key = self.list.front()?;
+        self.list.remove(key).map(|value| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len(), Some(self.list.len()))
+    }
+}
+
+impl<K: Key, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        let key = self.list.back()?;
+        self.list.remove(key).map(|value| (key, value))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Iter<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        if self.num_left == 0 {
+            return None;
+        }
+
+        let key = self.front;
+        let node = self.sm.get(key)?;
+        self.front = node.next;
+        self.num_left -= 1;
+        Some((key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.num_left, Some(self.num_left))
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a V)> {
+        if self.num_left == 0 {
+            return None;
+        }
+
+        let key = self.back;
+        let node = self.sm.get(key)?;
+        self.back = node.prev;
+        self.num_left -= 1;
+        Some((key, &node.value))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for IterMut<'a, K, V> {
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<(K, &'a mut V)> {
+        if self.num_left == 0 {
+            return None;
+        }
+
+        // This is safe because every key linked into the list is valid and
+        // the remaining count stops the ends from passing each other, so each
+        // value is handed out once.
+        let key = self.front;
+        unsafe {
+            let node = SlotMap::raw_get_unchecked_mut(self.sm, key);
+            self.front = (*node).next;
+            self.num_left -= 1;
+            Some((key, &mut (*node).value))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.num_left, Some(self.num_left))
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a mut V)> {
+        if self.num_left == 0 {
+            return None;
+        }
+
+        // This is safe because every key linked into the list is valid and
+        // the remaining count stops the ends from passing each other, so each
+        // value is handed out once.
+        let key = self.back;
+        unsafe {
+            let node = SlotMap::raw_get_unchecked_mut(self.sm, key);
+            self.back = (*node).prev;
+            self.num_left -= 1;
+            Some((key, &mut (*node).value))
+        }
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Keys<'a, K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<K> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K: Key, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K: Key, V> IntoIterator for &'a SlotList<K, V> {
+    type Item = (K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Key, V> IntoIterator for &'a mut SlotList<K, V> {
+    type Item = (K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K: Key, V> IntoIterator for SlotList<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, K: Key, V> FusedIterator for Iter<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for IterMut<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for Keys<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for Values<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for ValuesMut<'a, K, V> {}
+impl<K: Key, V> FusedIterator for IntoIter<K, V> {}
+
+impl<'a, K: Key, V> ExactSizeIterator for Iter<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for IterMut<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for Keys<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for Values<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for ValuesMut<'a, K, V> {}
+impl<K: Key, V> ExactSizeIterator for IntoIter<K, V> {}
+
+// Serialization with serde.
+#[cfg(feature = "serde")]
+mod serialize {
+    use pi_key_alloter::KeyData;
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct SerdeNode<'a, V> {
+        value: &'a V,
+        prev: KeyData,
+        next: KeyData,
+    }
+
+    #[derive(Deserialize)]
+    struct SerdeNodeOwned<V> {
+        value: V,
+        prev: KeyData,
+        next: KeyData,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct SerdeList<S> {
+        nodes: S,
+        head: KeyData,
+        tail: KeyData,
+    }
+
+    impl<K: Key, V: Serialize> Serialize for Node<K, V> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let serde_node = SerdeNode {
+                value: &self.value,
+                prev: self.prev.data(),
+                next: self.next.data(),
+            };
+            serde_node.serialize(serializer)
+        }
+    }
+
+    impl<'de, K: Key, V: Deserialize<'de>> Deserialize<'de> for Node<K, V> {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let serde_node: SerdeNodeOwned<V> = Deserialize::deserialize(deserializer)?;
+            Ok(Self {
+                value: serde_node.value,
+                prev: serde_node.prev.into(),
+                next: serde_node.next.into(),
+            })
+        }
+    }
+
+    impl<K: Key, V: Serialize> Serialize for SlotList<K, V> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let serde_list = SerdeList {
+                nodes: &self.sm,
+                head: self.head.data(),
+                tail: self.tail.data(),
+            };
+            serde_list.serialize(serializer)
+        }
+    }
+
+    impl<'de, K: Key, V: Deserialize<'de>> Deserialize<'de> for SlotList<K, V> {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let serde_list: SerdeList<SlotMap<K, Node<K, V>>> =
+                Deserialize::deserialize(deserializer)?;
+            let list = SlotList {
+                sm: serde_list.nodes,
+                head: serde_list.head.into(),
+                tail: serde_list.tail.into(),
+            };
+
+            // The links come from an untrusted source, so check that they
+            // form a single chain through every element.
+            let mut num_linked = 0;
+            let (mut prev, mut cur) = (K::null(), list.head);
+            while let Some(node) = list.sm.get(cur) {
+                if node.prev != prev || num_linked == list.len() {
+                    return Err(de::Error::custom(&"invalid list links"));
+                }
+                num_linked += 1;
+                prev = cur;
+                cur = node.next;
+            }
+            if !cur.is_null() || prev != list.tail || num_linked != list.len() {
+                return Err(de::Error::custom(&"invalid list links"));
+            }
+
+            Ok(list)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn check<K: Key, V>(list: &SlotList<K, V>) {
+        let forward: Vec<_> = list.keys().collect();
+        let mut backward: Vec<_> = list.keys().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!(forward.len(), list.len());
+        for w in forward.windows(2) {
+            assert_eq!(list.next(w[0]), Some(w[1]));
+            assert_eq!(list.prev(w[1]), Some(w[0]));
+        }
+        assert_eq!(list.front(), forward.first().copied());
+        assert_eq!(list.back(), forward.last().copied());
+    }
+
+    fn string<K: Key>(list: &SlotList<K, char>) -> String {
+        list.values().collect()
+    }
+
+    #[test]
+    fn push_pop_insert_remove() {
+        let mut list = SlotList::new();
+        let c = list.push_back('c');
+        let b = list.push_front('b');
+        let a = list.insert_before(b, 'a').unwrap();
+        let d = list.insert_after(c, 'd').unwrap();
+        check(&list);
+        assert_eq!(string(&list), "abcd");
+
+        assert_eq!(list.remove(b), Some('b'));
+        assert_eq!(list.remove(b), None);
+        assert_eq!(list.insert_after(b, 'x'), None);
+        check(&list);
+        assert_eq!(string(&list), "acd");
+
+        assert_eq!(list.pop_front(), Some('a'));
+        assert_eq!(list.pop_back(), Some('d'));
+        check(&list);
+        assert!(!list.contains_key(a) && !list.contains_key(d));
+        assert_eq!(list.pop_back(), Some('c'));
+        assert_eq!(list.pop_front(), None);
+        check(&list);
+    }
+
+    #[test]
+    fn splice_and_move() {
+        let mut list = SlotList::new();
+        let k: Vec<_> = "abcdef".chars().map(|c| list.push_back(c)).collect();
+
+        assert!(list.splice_after(k[0], k[1], k[5]));
+        assert_eq!(string(&list), "cdefab");
+        check(&list);
+        assert!(list.splice_before(k[4], k[1], k[2]));
+        assert_eq!(string(&list), "efabcd");
+        check(&list);
+
+        // Invalid ranges and anchors inside the range are rejected.
+        assert!(!list.splice_before(k[2], k[0], k[4]));
+        assert!(!list.splice_before(k[0], k[2], k[1]));
+        assert!(!list.splice_before(k[0], k[0], k[0]));
+        assert_eq!(string(&list), "efabcd");
+
+        assert!(list.move_before(k[3], k[4]));
+        assert!(list.move_after(k[4], k[2]));
+        assert_eq!(string(&list), "dfabce");
+        check(&list);
+    }
+
+    #[test]
+    fn cursors() {
+        let mut list: SlotList<DefaultKey, char> = "abc".chars().collect();
+        let mut cursor = list.cursor_back();
+        cursor.move_next();
+        assert_eq!(cursor.key(), None);
+        assert_eq!(cursor.peek_next(), Some(&'a'));
+        assert_eq!(cursor.peek_prev(), Some(&'c'));
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&'a'));
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some('b'));
+        assert_eq!(cursor.current(), Some(&mut 'c'));
+        cursor.insert_before('x');
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        cursor.insert_before('y');
+        cursor.insert_after('z');
+        assert_eq!(cursor.as_cursor().peek_next(), Some(&'z'));
+        assert_eq!(string(&list), "zaxcy");
+        check(&list);
+
+        let mut seen = String::new();
+        let mut cursor = list.cursor_front_mut();
+        while let Some(&mut c) = cursor.current() {
+            if c == 'x' {
+                cursor.remove_current();
+            } else {
+                seen.push(c);
+                cursor.move_next();
+            }
+        }
+        assert_eq!(seen, "zacy");
+        assert_eq!(string(&list), "zacy");
+    }
+
+    #[test]
+    fn iterators() {
+        let mut list: SlotList<DefaultKey, i32> = (0..10).collect();
+        list.retain(|_, v| *v % 3 != 0);
+        assert!(list.values().copied().eq([1, 2, 4, 5, 7, 8]));
+        assert!(list.values().rev().copied().eq([8, 7, 5, 4, 2, 1]));
+
+        let mut it = list.iter_mut();
+        *it.next().unwrap().1 = 0;
+        *it.next_back().unwrap().1 = 0;
+        assert_eq!(it.len(), 4);
+        assert!(list.values().copied().eq([0, 2, 4, 5, 7, 0]));
+        let values: Vec<_> = list.values_mut().collect();
+        for v in values {
+            *v += 1;
+        }
+        assert!(list.values().copied().eq([1, 3, 5, 6, 8, 1]));
+
+        let owned: Vec<_> = list.clone().into_iter().rev().map(|(_, v)| v).collect();
+        assert_eq!(owned, [1, 8, 6, 5, 3, 1]);
+        assert_eq!(format!("{:?}", list.values().next()), "Some(1)");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn list_serde() {
+        let mut list: SlotList<DefaultKey, char> = "abcd".chars().collect();
+        let b = list.keys().nth(1).unwrap();
+        list.remove(b);
+        list.push_front('e');
+
+        let ser = serde_json::to_string(&list).unwrap();
+        let de: SlotList<DefaultKey, char> = serde_json::from_str(&ser).unwrap();
+        assert!(de.iter().eq(list.iter()));
+
+        // Broken links are rejected.
+        let mut other = SlotList::new();
+        other.push_back('a');
+        other.push_back('b');
+        let ser = serde_json::to_string(&other).unwrap();
+        let cycle = ser.replace("\"tail\":{\"idx\":2", "\"tail\":{\"idx\":1");
+        assert_ne!(cycle, ser);
+        assert!(serde_json::from_str::<SlotList<DefaultKey, char>>(&cycle).is_err());
+    }
+}
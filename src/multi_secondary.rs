@@ -0,0 +1,592 @@
+//! Contains the secondary multimap implementation, which associates any number
+//! of values with each key of a slot map.
+
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+use crate::{SecondaryMap, SlotMap};
+use pi_null::Null;
+use pi_key_alloter::{is_older_version, DefaultKey, Key};
+
+// A value in the pool and the next value of the same key.
+#[derive(Debug, Clone)]
+struct Node<V> {
+    value: V,
+    next: DefaultKey,
+}
+
+// The values of a key, as a singly linked list through the pool.
+#[derive(Debug, Clone, Copy)]
+struct List {
+    head: DefaultKey,
+    tail: DefaultKey,
+    len: usize,
+}
+
+/// Secondary multimap, associates any number of values with each key of a
+/// slot map.
+///
+/// Unlike a [`SecondaryMap<K, Vec<V>>`](SecondaryMap), which allocates a
+/// [`Vec`](alloc::vec::Vec) per key, all values are stored in a single pool
+/// and linked into a list per key. The lists are indexed by the slot index of
+/// the key like a [`SecondaryMap`], and the values of a key are dropped once
+/// a newer key for its slot is pushed to.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut entities = SlotMap::new();
+/// let mut events = SecondaryMultiMap::new();
+///
+/// let player = entities.insert("player");
+/// let enemy = entities.insert("enemy");
+/// events.push(player, "jump");
+/// events.push(enemy, "spawn");
+/// events.push(player, "land");
+///
+/// assert_eq!(events.iter_key(player).collect::<Vec<_>>(), [&"jump", &"land"]);
+/// assert_eq!(events.remove_key(enemy).collect::<Vec<_>>(), ["spawn"]);
+/// assert_eq!(events.values_len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SecondaryMultiMap<K: Key, V> {
+    lists: SecondaryMap<K, List>,
+    pool: SlotMap<DefaultKey, Node<V>>,
+}
+
+impl<K: Key, V> SecondaryMultiMap<K, V> {
+    /// Constructs a new, empty [`SecondaryMultiMap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut events: SecondaryMultiMap<DefaultKey, i32> = SecondaryMultiMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty [`SecondaryMultiMap`] with room for the given number
+    /// of values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut events: SecondaryMultiMap<DefaultKey, i32> = SecondaryMultiMap::with_capacity(10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            lists: SecondaryMap::new(),
+            pool: SlotMap::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of keys with at least one value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut events = SecondaryMultiMap::new();
+    /// events.push(k, 1);
+    /// events.push(k, 2);
+    /// assert_eq!(events.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.lists.len()
+    }
+
+    /// Returns the number of values of all keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut events = SecondaryMultiMap::new();
+    /// events.push(k, 1);
+    /// events.push(k, 2);
+    /// assert_eq!(events.values_len(), 2);
+    /// ```
+    pub fn values_len(&self) -> usize {
+        self.pool.len()
+    }
+
+    /// Returns if the multimap is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let events: SecondaryMultiMap<DefaultKey, i32> = SecondaryMultiMap::new();
+    /// assert!(events.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.lists.is_empty()
+    }
+
+    /// Returns [`true`] if `key` has at least one value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut events = SecondaryMultiMap::new();
+    /// assert!(!events.contains_key(k));
+    /// events.push(k, 1);
+    /// assert!(events.contains_key(k));
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.lists.contains_key(key)
+    }
+
+    /// Returns the number of values of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut events = SecondaryMultiMap::new();
+    /// events.push(k, 1);
+    /// events.push(k, 1);
+    /// assert_eq!(events.key_len(k), 2);
+    /// ```
+    pub fn key_len(&self, key: K) -> usize {
+        self.lists.get(key).map_or(0, |list| list.len)
+    }
+
+    /// Appends `value` to the values of `key`. Can silently fail and return
+    /// [`false`] if `key` was removed from the originating slot map, and a
+    /// newer key of the same slot was pushed to. Pushing to a newer key drops
+    /// the values of older keys of the same slot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut events = SecondaryMultiMap::new();
+    /// assert!(events.push(k, 1));
+    ///
+    /// sm.remove(k);
+    /// let new_k = sm.insert(());
+    /// assert!(events.push(new_k, 2));
+    /// assert!(!events.contains_key(k));
+    /// assert!(!events.push(k, 3));
+    /// assert_eq!(events.values_len(), 1);
+    /// ```
+    pub fn push(&mut self, key: K, value: V) -> bool {
+        if key.is_null() {
+            return false;
+        }
+        if let Some(old) = self.lists.occupant(key) {
+            if old != key {
+                if is_older_version(key.data().version(), old.data().version()) {
+                    return false;
+                }
+                self.remove_key(old);
+            }
+        }
+
+        let node = self.pool.insert(Node {
+            value,
+            next: DefaultKey::null(),
+        });
+        match self.lists.get_mut(key) {
+            Some(list) => {
+                self.pool[list.tail].next = node;
+                list.tail = node;
+                list.len += 1;
+            }
+            None => {
+                self.lists.insert(
+                    key,
+                    List {
+                        head: node,
+                        tail: node,
+                        len: 1,
+                    },
+                );
+            }
+        }
+        true
+    }
+
+    /// An iterator visiting the values of `key` in the order they were pushed.
+    /// The iterator element type is `&'a V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut events = SecondaryMultiMap::new();
+    /// events.push(a, 1);
+    /// events.push(b, 2);
+    /// events.push(a, 3);
+    /// assert_eq!(events.iter_key(a).collect::<Vec<_>>(), [&1, &3]);
+    /// ```
+    pub fn iter_key(&self, key: K) -> IterKey<V> {
+        let list = self.list(key);
+        IterKey {
+            pool: &self.pool,
+            cur: list.head,
+            num_left: list.len,
+        }
+    }
+
+    /// An iterator visiting the values of `key` in the order they were
+    /// pushed, with mutable references to the values. The iterator element
+    /// type is `&'a mut V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut events = SecondaryMultiMap::new();
+    /// events.push(k, 1);
+    /// events.push(k, 2);
+    /// for v in events.iter_key_mut(k) {
+    ///     *v *= 10;
+    /// }
+    /// assert_eq!(events.iter_key(k).collect::<Vec<_>>(), [&10, &20]);
+    /// ```
+    pub fn iter_key_mut(&mut self, key: K) -> IterKeyMut<V> {
+        let list = self.list(key);
+        IterKeyMut {
+            pool: &mut self.pool,
+            cur: list.head,
+            num_left: list.len,
+            _pool: PhantomData,
+        }
+    }
+
+    /// Removes all values of `key`, returning them in the order they were
+    /// pushed as an iterator.
+    ///
+    /// When the iterator is dropped all remaining values of `key` are dropped,
+    /// even if the iterator was not fully consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut events = SecondaryMultiMap::new();
+    /// events.push(k, 'a');
+    /// events.push(k, 'b');
+    /// assert_eq!(events.remove_key(k).collect::<String>(), "ab");
+    /// assert!(!events.contains_key(k));
+    /// assert_eq!(events.values_len(), 0);
+    /// ```
+    pub fn remove_key(&mut self, key: K) -> DrainKey<V> {
+        let list = self.lists.remove(key).unwrap_or_else(empty_list);
+        DrainKey {
+            pool: &mut self.pool,
+            cur: list.head,
+            num_left: list.len,
+        }
+    }
+
+    /// Removes the first value of `key` for which `pred` returns [`true`],
+    /// returning it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut events = SecondaryMultiMap::new();
+    /// for i in 1..=4 {
+    ///     events.push(k, i);
+    /// }
+    /// assert_eq!(events.remove_value(k, |v| v % 2 == 0), Some(2));
+    /// assert_eq!(events.remove_value(k, |v| *v > 10), None);
+    /// assert_eq!(events.iter_key(k).collect::<Vec<_>>(), [&1, &3, &4]);
+    /// ```
+    pub fn remove_value<F>(&mut self, key: K, mut pred: F) -> Option<V>
+    where
+        F: FnMut(&V) -> bool,
+    {
+        let list = self.lists.get_mut(key)?;
+        let mut prev = DefaultKey::null();
+        let mut cur = list.head;
+        while !cur.is_null() {
+            let node = &self.pool[cur];
+            if pred(&node.value) {
+                break;
+            }
+            prev = cur;
+            cur = node.next;
+        }
+        if cur.is_null() {
+            return None;
+        }
+
+        let node = self.pool.remove(cur).unwrap();
+        if prev.is_null() {
+            list.head = node.next;
+        } else {
+            self.pool[prev].next = node.next;
+        }
+        if list.tail == cur {
+            list.tail = prev;
+        }
+        list.len -= 1;
+        if list.len == 0 {
+            self.lists.remove(key);
+        }
+        Some(node.value)
+    }
+
+    /// Clears the multimap. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut events = SecondaryMultiMap::new();
+    /// events.push(k, 1);
+    /// events.clear();
+    /// assert!(events.is_empty());
+    /// assert_eq!(events.values_len(), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        self.lists.clear();
+        self.pool.clear();
+    }
+
+    /// An iterator visiting all keys with at least one value in order of
+    /// their slot index. The iterator element type is `K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut events = SecondaryMultiMap::new();
+    /// events.push(b, 1);
+    /// events.push(a, 2);
+    /// events.push(b, 3);
+    /// assert_eq!(events.keys().collect::<Vec<_>>(), [a, b]);
+    /// ```
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        self.lists.keys()
+    }
+
+    fn list(&self, key: K) -> List {
+        self.lists.get(key).copied().unwrap_or_else(empty_list)
+    }
+}
+
+fn empty_list() -> List {
+    List {
+        head: DefaultKey::null(),
+        tail: DefaultKey::null(),
+        len: 0,
+    }
+}
+
+impl<K: Key, V> Default for SecondaryMultiMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Key, V> Extend<(K, V)> for SecondaryMultiMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.push(key, value);
+        }
+    }
+}
+
+impl<K: Key, V> FromIterator<(K, V)> for SecondaryMultiMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+// Iterators.
+/// An iterator over the values of a key in a [`SecondaryMultiMap`].
+///
+/// This iterator is created by [`SecondaryMultiMap::iter_key`].
+#[derive(Debug)]
+pub struct IterKey<'a, V: 'a> {
+    pool: &'a SlotMap<DefaultKey, Node<V>>,
+    cur: DefaultKey,
+    num_left: usize,
+}
+
+impl<'a, V: 'a> Clone for IterKey<'a, V> {
+    fn clone(&self) -> Self {
+        IterKey {
+            pool: self.pool,
+            cur: self.cur,
+            num_left: self.num_left,
+        }
+    }
+}
+
+/// A mutable iterator over the values of a key in a [`SecondaryMultiMap`].
+///
+/// This iterator is created by [`SecondaryMultiMap::iter_key_mut`].
+///
+/// The pool is walked through a raw pointer rather than borrowing it again
+/// for every value, which would invalidate the values already yielded.
+#[derive(Debug)]
+pub struct IterKeyMut<'a, V: 'a> {
+    pool: *mut SlotMap<DefaultKey, Node<V>>,
+    cur: DefaultKey,
+    num_left: usize,
+    _pool: PhantomData<&'a mut SlotMap<DefaultKey, Node<V>>>,
+}
+
+// An `IterKeyMut` is used like the `&mut SlotMap` it was created from.
+unsafe impl<'a, V: Send> Send for IterKeyMut<'a, V> {}
+unsafe impl<'a, V: Sync> Sync for IterKeyMut<'a, V> {}
+
+/// A draining iterator over the values of a key in a [`SecondaryMultiMap`].
+///
+/// This iterator is created by [`SecondaryMultiMap::remove_key`].
+#[derive(Debug)]
+pub struct DrainKey<'a, V: 'a> {
+    pool: &'a mut SlotMap<DefaultKey, Node<V>>,
+    cur: DefaultKey,
+    num_left: usize,
+}
+
+impl<'a, V> Iterator for IterKey<'a, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        let node = self.pool.get(self.cur)?;
+        self.cur = node.next;
+        self.num_left -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.num_left, Some(self.num_left))
+    }
+}
+
+impl<'a, V> Iterator for IterKeyMut<'a, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        if self.num_left == 0 {
+            return None;
+        }
+
+        // This is safe because the first `num_left` nodes of the list are
+        // valid and distinct, so each value is handed out once.
+        unsafe {
+            let node = SlotMap::raw_get_unchecked_mut(self.pool, self.cur);
+            self.cur = (*node).next;
+            self.num_left -= 1;
+            Some(&mut (*node).value)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.num_left, Some(self.num_left))
+    }
+}
+
+impl<'a, V> Iterator for DrainKey<'a, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        let node = self.pool.remove(self.cur)?;
+        self.cur = node.next;
+        self.num_left -= 1;
+        Some(node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.num_left, Some(self.num_left))
+    }
+}
+
+impl<'a, V> Drop for DrainKey<'a, V> {
+    fn drop(&mut self) {
+        self.for_each(|_drop| {});
+    }
+}
+
+impl<'a, V> FusedIterator for IterKey<'a, V> {}
+impl<'a, V> FusedIterator for IterKeyMut<'a, V> {}
+impl<'a, V> FusedIterator for DrainKey<'a, V> {}
+
+impl<'a, V> ExactSizeIterator for IterKey<'a, V> {}
+impl<'a, V> ExactSizeIterator for IterKeyMut<'a, V> {}
+impl<'a, V> ExactSizeIterator for DrainKey<'a, V> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn pooled_lists() {
+        let mut sm = SlotMap::new();
+        let keys: Vec<_> = (0..3).map(|_| sm.insert(())).collect();
+        let mut map = SecondaryMultiMap::new();
+        for i in 0..12 {
+            assert!(map.push(keys[i % 3], i));
+        }
+        assert_eq!(map.values_len(), 12);
+        assert_eq!(map.iter_key(keys[1]).copied().collect::<Vec<_>>(), [1, 4, 7, 10]);
+        let values: Vec<_> = map.iter_key_mut(keys[2]).collect();
+        for v in values {
+            *v += 100;
+        }
+        assert_eq!(map.iter_key(keys[2]).copied().collect::<Vec<_>>(), [102, 105, 108, 111]);
+
+        // Removing the head, a middle value and the tail.
+        assert_eq!(map.remove_value(keys[1], |&v| v == 1), Some(1));
+        assert_eq!(map.remove_value(keys[1], |&v| v == 7), Some(7));
+        assert_eq!(map.remove_value(keys[1], |&v| v == 10), Some(10));
+        map.push(keys[1], 13);
+        assert_eq!(map.iter_key(keys[1]).copied().collect::<Vec<_>>(), [4, 13]);
+        assert_eq!(map.remove_value(keys[1], |_| true), Some(4));
+        assert_eq!(map.remove_value(keys[1], |_| true), Some(13));
+        assert!(!map.contains_key(keys[1]));
+        assert_eq!(map.key_len(keys[1]), 0);
+
+        // A partially consumed drain still removes everything.
+        assert_eq!(map.remove_key(keys[0]).next(), Some(0));
+        assert_eq!(map.values_len(), 4);
+        assert_eq!(map.keys().collect::<Vec<_>>(), [keys[2]]);
+
+        // A newer key drops the values of the older one.
+        sm.remove(keys[2]);
+        let new = sm.insert(());
+        assert!(map.push(new, 20));
+        assert!(!map.push(keys[2], 21));
+        assert_eq!(map.iter_key(keys[2]).len(), 0);
+        assert_eq!(map.iter_key(new).collect::<Vec<_>>(), [&20]);
+        assert_eq!(map.values_len(), 1);
+    }
+}
@@ -0,0 +1,1069 @@
+//! Contains the observed slot map implementation, which notifies observers of
+//! removed keys.
+
+use alloc::boxed::Box;
+use core::any::Any;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::ops::{Index, IndexMut};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::primary::PrimaryMapMut;
+use crate::{basic, dense, hop, soa};
+use crate::{
+    DefaultSecondaryMap, DenseSlotMap, HopSlotMap, OrderedSlotMap, SecondaryMap, SecondarySet,
+    SlotMap, SoaSlotMap,
+};
+use pi_key_alloter::{new_key_type, DefaultKey, Key};
+
+new_key_type! {
+    /// A handle to an observer registered with [`ObservedSlotMap::observe`].
+    pub struct ObserverKey;
+}
+
+// A registered observer, which can be downcast to its own type.
+trait Observer<K: Key>: RemovalObserver<K> + Send {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<K: Key, O: RemovalObserver<K> + Send + 'static> Observer<K> for O {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// The registered observers, in order of registration.
+type Observers<K> = OrderedSlotMap<ObserverKey, Box<dyn Observer<K>>>;
+
+/// An observer of the keys removed from an [`ObservedSlotMap`].
+///
+/// Implemented for [`SecondaryMap`], [`SparseSecondaryMap`],
+/// [`DefaultSecondaryMap`] and [`SecondarySet`], which remove their entry for
+/// every removed key, for closures taking the removed key, and for
+/// `Arc<Mutex<O>>` so an observer can be shared with code outside of the slot
+/// map.
+///
+/// [`SparseSecondaryMap`]: crate::SparseSecondaryMap
+pub trait RemovalObserver<K: Key> {
+    /// Called with every key removed from the slot map.
+    fn on_remove(&mut self, key: K);
+
+    /// Called when the slot map is cleared, with the keys it held. Calls
+    /// [`on_remove`](Self::on_remove) for each key by default.
+    fn on_clear(&mut self, keys: &mut dyn Iterator<Item = K>) {
+        for key in keys {
+            self.on_remove(key);
+        }
+    }
+}
+
+/// A slot map that can be wrapped by an [`ObservedSlotMap`].
+///
+/// Implemented for [`SlotMap`], [`HopSlotMap`], [`DenseSlotMap`] and
+/// [`SoaSlotMap`].
+pub trait ObservableMap<K: Key, V>: PrimaryMapMut<K, V> {
+    /// An iterator over the key-value pairs of the map.
+    type Iter<'a>: Iterator<Item = (K, &'a V)> + FusedIterator + ExactSizeIterator
+    where
+        Self: 'a,
+        V: 'a;
+
+    /// A mutable iterator over the key-value pairs of the map.
+    type IterMut<'a>: Iterator<Item = (K, &'a mut V)> + FusedIterator + ExactSizeIterator
+    where
+        Self: 'a,
+        V: 'a;
+
+    /// An iterator over the values of the map.
+    type Values<'a>: Iterator<Item = &'a V> + FusedIterator + ExactSizeIterator
+    where
+        Self: 'a,
+        V: 'a;
+
+    /// A mutable iterator over the values of the map.
+    type ValuesMut<'a>: Iterator<Item = &'a mut V> + FusedIterator + ExactSizeIterator
+    where
+        Self: 'a,
+        V: 'a;
+
+    /// A draining iterator over the key-value pairs of the map.
+    type Drain<'a>: Iterator<Item = (K, V)> + FusedIterator + ExactSizeIterator
+    where
+        Self: 'a;
+
+    /// Creates an empty map with the given capacity.
+    fn with_capacity_and_key(capacity: usize) -> Self;
+
+    /// Returns the number of elements the map can hold without reallocating.
+    fn capacity(&self) -> usize;
+
+    /// Reserves capacity for at least `additional` more elements.
+    fn reserve(&mut self, additional: usize);
+
+    /// Inserts the value given by `f`, which is passed the key of the value.
+    fn insert_with_key<F: FnOnce(K) -> V>(&mut self, f: F) -> K;
+
+    /// Returns a reference to the value of `key`.
+    fn get(&self, key: K) -> Option<&V>;
+
+    /// Retains only the elements specified by the predicate.
+    fn retain<F: FnMut(K, &mut V) -> bool>(&mut self, f: F);
+
+    /// Removes all elements.
+    fn clear(&mut self);
+
+    /// Removes all elements, returning them as an iterator.
+    fn drain(&mut self) -> Self::Drain<'_>;
+
+    /// An iterator visiting all key-value pairs.
+    fn iter(&self) -> Self::Iter<'_>;
+
+    /// An iterator visiting all key-value pairs, with mutable references to
+    /// the values.
+    fn iter_mut(&mut self) -> Self::IterMut<'_>;
+
+    /// An iterator visiting all values.
+    fn values(&self) -> Self::Values<'_>;
+
+    /// An iterator visiting all values mutably.
+    fn values_mut(&mut self) -> Self::ValuesMut<'_>;
+}
+
+/// Observed slot map, a slot map that notifies observers of the keys it
+/// removes.
+///
+/// Wraps a [`SlotMap`] by default, or any other [`ObservableMap`] such as a
+/// [`HopSlotMap`], [`DenseSlotMap`] or [`SoaSlotMap`] given as `M`.
+///
+/// Observers are registered with [`observe`](Self::observe), unregistered with
+/// [`unobserve`](Self::unobserve) and implement [`RemovalObserver`]. Every key
+/// removed through [`remove`](Self::remove), [`retain`](Self::retain) or
+/// [`drain`](Self::drain) is passed to every observer, and
+/// [`clear`](Self::clear) passes all keys at once. Registering the secondary
+/// maps of a slot map as observers keeps them free of the entries of removed
+/// keys without any bookkeeping. Registered observers stay accessible through
+/// [`observer`](Self::observer) and [`observer_mut`](Self::observer_mut).
+///
+/// Mutable access to the elements goes through the methods of this type, so
+/// no key can be removed without the observers seeing it. Use
+/// [`as_slot_map`](Self::as_slot_map) for the read only methods of the
+/// wrapped map not forwarded here.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut entities = ObservedSlotMap::new();
+/// let health = entities.observe(SecondaryMap::<_, i32>::new());
+///
+/// let player = entities.insert("player");
+/// let monster = entities.insert("monster");
+/// let hp = entities.observer_mut::<SecondaryMap<_, i32>>(health).unwrap();
+/// hp.insert(player, 100);
+/// hp.insert(monster, 30);
+///
+/// entities.remove(monster);
+/// let hp = entities.observer::<SecondaryMap<_, i32>>(health).unwrap();
+/// assert_eq!(hp.len(), 1);
+/// assert!(!hp.contains_key(monster));
+/// ```
+///
+/// Any other slot map is wrapped by naming it:
+///
+/// ```
+/// # use slotmap::*;
+/// let mut entities: ObservedSlotMap<_, _, HopSlotMap<_, _>> = ObservedSlotMap::with_key();
+/// let removed = entities.observe(SecondarySet::<DefaultKey>::new());
+/// let key = entities.insert("player");
+/// entities.observer_mut::<SecondarySet<_>>(removed).unwrap().insert(key);
+/// entities.clear();
+/// assert!(entities.observer::<SecondarySet<_>>(removed).unwrap().is_empty());
+/// ```
+pub struct ObservedSlotMap<K: Key, V, M: ObservableMap<K, V> = SlotMap<K, V>> {
+    map: M,
+    observers: Observers<K>,
+    _v: PhantomData<fn(V) -> V>,
+}
+
+impl<V> ObservedSlotMap<DefaultKey, V> {
+    /// Constructs a new, empty [`ObservedSlotMap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm: ObservedSlotMap<_, i32> = ObservedSlotMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`ObservedSlotMap`] with the given capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm: ObservedSlotMap<_, i32> = ObservedSlotMap::with_capacity(10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_key(capacity)
+    }
+}
+
+impl<K: Key, V, M: ObservableMap<K, V>> ObservedSlotMap<K, V, M> {
+    /// Constructs a new, empty [`ObservedSlotMap`] with a custom key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct EntityKey;
+    /// }
+    /// let mut entities: ObservedSlotMap<EntityKey, i32> = ObservedSlotMap::with_key();
+    /// ```
+    pub fn with_key() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`ObservedSlotMap`] with the given capacity and a
+    /// custom key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct EntityKey;
+    /// }
+    /// let mut entities: ObservedSlotMap<EntityKey, i32> = ObservedSlotMap::with_capacity_and_key(5);
+    /// ```
+    pub fn with_capacity_and_key(capacity: usize) -> Self {
+        Self {
+            map: M::with_capacity_and_key(capacity),
+            observers: OrderedSlotMap::with_key(),
+            _v: PhantomData,
+        }
+    }
+
+    /// Registers an observer, which is notified of every key removed from now
+    /// on, and returns a key to unregister it with. Observers are notified in
+    /// the order they were registered.
+    ///
+    /// Observers must be [`Send`], so the slot map can be sent to another
+    /// thread. An `Arc<Mutex<O>>` observer is locked while it is notified, so
+    /// it must not be locked elsewhere on the same thread when a key is
+    /// removed. It is still notified if the mutex was poisoned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let mut sm = ObservedSlotMap::new();
+    /// let log = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = log.clone();
+    /// sm.observe(move |key: DefaultKey| sink.lock().unwrap().push(key));
+    ///
+    /// let key = sm.insert(1);
+    /// sm.remove(key);
+    /// assert_eq!(*log.lock().unwrap(), [key]);
+    /// ```
+    pub fn observe<O: RemovalObserver<K> + Send + 'static>(&mut self, observer: O) -> ObserverKey {
+        self.observers.insert(Box::new(observer))
+    }
+
+    /// Unregisters the observer registered under `key`, which is no longer
+    /// notified of removed keys. Returns [`true`] if the observer was still
+    /// registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let mut sm = ObservedSlotMap::new();
+    /// let flags = Arc::new(Mutex::new(SecondarySet::new()));
+    /// let observer = sm.observe(flags.clone());
+    /// let key = sm.insert(1);
+    /// flags.lock().unwrap().insert(key);
+    ///
+    /// assert!(sm.unobserve(observer));
+    /// assert!(!sm.unobserve(observer));
+    /// sm.remove(key);
+    /// assert!(flags.lock().unwrap().contains(key));
+    /// ```
+    pub fn unobserve(&mut self, key: ObserverKey) -> bool {
+        self.observers.remove(key).is_some()
+    }
+
+    /// Returns a reference to the observer registered under `key`, or
+    /// [`None`] if it is no longer registered or is not an `O`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let names = sm.observe(SparseSecondaryMap::<_, &str>::new());
+    /// let key = sm.insert(1);
+    /// sm.observer_mut::<SparseSecondaryMap<_, &str>>(names).unwrap().insert(key, "one");
+    ///
+    /// assert_eq!(sm.observer::<SparseSecondaryMap<_, &str>>(names).unwrap()[key], "one");
+    /// assert!(sm.observer::<SecondaryMap<_, &str>>(names).is_none());
+    /// ```
+    pub fn observer<O: RemovalObserver<K> + 'static>(&self, key: ObserverKey) -> Option<&O> {
+        self.observers.get(key)?.as_any().downcast_ref()
+    }
+
+    /// Returns a mutable reference to the observer registered under `key`,
+    /// or [`None`] if it is no longer registered or is not an `O`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let flags = sm.observe(SecondarySet::new());
+    /// let key = sm.insert(1);
+    /// sm.observer_mut::<SecondarySet<_>>(flags).unwrap().insert(key);
+    ///
+    /// sm.remove(key);
+    /// assert!(!sm.observer::<SecondarySet<_>>(flags).unwrap().contains(key));
+    /// ```
+    pub fn observer_mut<O: RemovalObserver<K> + 'static>(
+        &mut self,
+        key: ObserverKey,
+    ) -> Option<&mut O> {
+        self.observers.get_mut(key)?.as_any_mut().downcast_mut()
+    }
+
+    /// Returns the number of registered observers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm: ObservedSlotMap<_, i32> = ObservedSlotMap::new();
+    /// sm.observe(|_key: DefaultKey| {});
+    /// assert_eq!(sm.num_observers(), 1);
+    /// ```
+    pub fn num_observers(&self) -> usize {
+        self.observers.len()
+    }
+
+    /// Returns the number of elements in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::with_capacity(10);
+    /// sm.insert("len() counts actual elements, not capacity");
+    /// assert_eq!(sm.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns if the slot map is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let key = sm.insert("dummy");
+    /// assert_eq!(sm.is_empty(), false);
+    /// sm.remove(key);
+    /// assert_eq!(sm.is_empty(), true);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the number of elements the [`ObservedSlotMap`] can hold without
+    /// reallocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let sm: ObservedSlotMap<_, f64> = ObservedSlotMap::with_capacity(10);
+    /// assert_eq!(sm.capacity(), 10);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more elements to be inserted
+    /// in the [`ObservedSlotMap`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new allocation size overflows [`usize`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// sm.insert("foo");
+    /// sm.reserve(32);
+    /// assert!(sm.capacity() >= 33);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.map.reserve(additional)
+    }
+
+    /// Returns [`true`] if the slot map contains `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let key = sm.insert(42);
+    /// assert_eq!(sm.contains_key(key), true);
+    /// sm.remove(key);
+    /// assert_eq!(sm.contains_key(key), false);
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Inserts a value into the slot map. Returns a unique key that can be used
+    /// to access this value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the slot map equals
+    /// 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let key = sm.insert(42);
+    /// assert_eq!(sm[key], 42);
+    /// ```
+    pub fn insert(&mut self, value: V) -> K {
+        self.map.insert(value)
+    }
+
+    /// Inserts a value given by `f` into the slot map. The key where the
+    /// value will be stored is passed into `f`. This is useful to store values
+    /// that contain their own key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the slot map equals
+    /// 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let key = sm.insert_with_key(|k| (k, 20));
+    /// assert_eq!(sm[key], (key, 20));
+    /// ```
+    pub fn insert_with_key<F>(&mut self, f: F) -> K
+    where
+        F: FnOnce(K) -> V,
+    {
+        self.map.insert_with_key(f)
+    }
+
+    /// Removes a key from the slot map, returning the value at the key if the
+    /// key was not previously removed. The observers are notified if the key
+    /// was removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let key = sm.insert(42);
+    /// assert_eq!(sm.remove(key), Some(42));
+    /// assert_eq!(sm.remove(key), None);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let value = self.map.remove(key)?;
+        notify(&mut self.observers, key);
+        Some(value)
+    }
+
+    /// Retains only the elements specified by the predicate, notifying the
+    /// observers of every removed key.
+    ///
+    /// In other words, remove all key-value pairs `(k, v)` such that
+    /// `f(k, &mut v)` returns false. This method invalidates any removed keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let mut flags = SecondarySet::new();
+    /// for i in 0..6 {
+    ///     flags.insert(sm.insert(i));
+    /// }
+    /// let flags = std::sync::Arc::new(std::sync::Mutex::new(flags));
+    /// sm.observe(flags.clone());
+    ///
+    /// sm.retain(|_, v| *v % 2 == 0);
+    /// assert_eq!(sm.len(), 3);
+    /// assert_eq!(flags.lock().unwrap().len(), 3);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        let observers = &mut self.observers;
+        self.map.retain(|key, value| {
+            let keep = f(key, value);
+            if !keep {
+                notify(observers, key);
+            }
+            keep
+        })
+    }
+
+    /// Clears the slot map. Keeps the allocated memory for reuse.
+    ///
+    /// Every observer receives all keys of the slot map through
+    /// [`RemovalObserver::on_clear`], which secondary maps handle by clearing
+    /// themselves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let mut sm = ObservedSlotMap::new();
+    /// let names = Arc::new(Mutex::new(SparseSecondaryMap::new()));
+    /// sm.observe(names.clone());
+    /// for i in 0..10 {
+    ///     names.lock().unwrap().insert(sm.insert(i), i.to_string());
+    /// }
+    ///
+    /// sm.clear();
+    /// assert_eq!(sm.len(), 0);
+    /// assert_eq!(names.lock().unwrap().len(), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        for observer in self.observers.values_mut() {
+            observer.on_clear(&mut self.map.keys());
+        }
+        self.map.clear()
+    }
+
+    /// Clears the slot map, returning all key-value pairs in arbitrary order as
+    /// an iterator. The observers are notified of each key as it is removed.
+    ///
+    /// When the iterator is dropped all elements in the slot map are removed,
+    /// even if the iterator was not fully consumed, and the observers are
+    /// notified of them. If the iterator is not dropped (using e.g.
+    /// [`std::mem::forget`]), only the elements that were iterated over are
+    /// removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let k = sm.insert(0);
+    /// let v: Vec<_> = sm.drain().collect();
+    /// assert_eq!(sm.len(), 0);
+    /// assert_eq!(v, vec![(k, 0)]);
+    /// ```
+    pub fn drain(&mut self) -> Drain<K, V, M> {
+        Drain {
+            inner: self.map.drain(),
+            observers: &mut self.observers,
+        }
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let key = sm.insert("bar");
+    /// assert_eq!(sm.get(key), Some(&"bar"));
+    /// sm.remove(key);
+    /// assert_eq!(sm.get(key), None);
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let key = sm.insert(3.5);
+    /// if let Some(x) = sm.get_mut(key) {
+    ///     *x += 3.0;
+    /// }
+    /// assert_eq!(sm[key], 6.5);
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.map.get_mut(key)
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order. The
+    /// iterator element type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let k0 = sm.insert(0);
+    /// let k1 = sm.insert(1);
+    /// assert_eq!(sm.iter().collect::<Vec<_>>(), [(k0, &0), (k1, &1)]);
+    /// ```
+    pub fn iter(&self) -> M::Iter<'_> {
+        self.map.iter()
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order, with
+    /// mutable references to the values. The iterator element type is
+    /// `(K, &'a mut V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    /// for (k, v) in sm.iter_mut() {
+    ///     if k != k1 {
+    ///         *v *= -1;
+    ///     }
+    /// }
+    /// assert_eq!(sm[k0], -10);
+    /// assert_eq!(sm[k1], 20);
+    /// ```
+    pub fn iter_mut(&mut self) -> M::IterMut<'_> {
+        self.map.iter_mut()
+    }
+
+    /// An iterator visiting all keys in arbitrary order. The iterator element
+    /// type is `K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    /// assert_eq!(sm.keys().collect::<Vec<_>>(), [k0, k1]);
+    /// ```
+    pub fn keys(&self) -> M::Keys<'_> {
+        self.map.keys()
+    }
+
+    /// An iterator visiting all values in arbitrary order. The iterator element
+    /// type is `&'a V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// sm.insert(10);
+    /// sm.insert(20);
+    /// assert_eq!(sm.values().sum::<i32>(), 30);
+    /// ```
+    pub fn values(&self) -> M::Values<'_> {
+        self.map.values()
+    }
+
+    /// An iterator visiting all values mutably in arbitrary order. The iterator
+    /// element type is `&'a mut V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// sm.insert(1);
+    /// sm.insert(2);
+    /// sm.values_mut().for_each(|n| *n *= 3);
+    /// assert_eq!(sm.values().sum::<i32>(), 9);
+    /// ```
+    pub fn values_mut(&mut self) -> M::ValuesMut<'_> {
+        self.map.values_mut()
+    }
+
+    /// Returns the wrapped slot map, for the read only methods not forwarded
+    /// by this type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = ObservedSlotMap::new();
+    /// let key = sm.insert(1);
+    /// let idx = key.data().index() as usize;
+    /// assert_eq!(sm.as_slot_map().range(idx..).count(), 1);
+    /// ```
+    pub fn as_slot_map(&self) -> &M {
+        &self.map
+    }
+}
+
+// Notifies every observer of a removed key.
+fn notify<K: Key>(observers: &mut Observers<K>, key: K) {
+    for observer in observers.values_mut() {
+        observer.on_remove(key);
+    }
+}
+
+impl<K: Key, V, M: ObservableMap<K, V> + fmt::Debug> fmt::Debug for ObservedSlotMap<K, V, M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ObservedSlotMap")
+            .field("map", &self.map)
+            .field("observers", &self.observers.len())
+            .finish()
+    }
+}
+
+impl<K: Key, V, M: ObservableMap<K, V>> Default for ObservedSlotMap<K, V, M> {
+    fn default() -> Self {
+        Self::with_key()
+    }
+}
+
+impl<K: Key, V, M: ObservableMap<K, V>> Index<K> for ObservedSlotMap<K, V, M> {
+    type Output = V;
+
+    fn index(&self, key: K) -> &V {
+        match self.get(key) {
+            Some(r) => r,
+            None => panic!("invalid ObservedSlotMap key used"),
+        }
+    }
+}
+
+impl<K: Key, V, M: ObservableMap<K, V>> IndexMut<K> for ObservedSlotMap<K, V, M> {
+    fn index_mut(&mut self, key: K) -> &mut V {
+        match self.get_mut(key) {
+            Some(r) => r,
+            None => panic!("invalid ObservedSlotMap key used"),
+        }
+    }
+}
+
+impl<'a, K: Key, V, M: ObservableMap<K, V>> IntoIterator for &'a ObservedSlotMap<K, V, M> {
+    type Item = (K, &'a V);
+    type IntoIter = M::Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Key, V, M: ObservableMap<K, V>> IntoIterator for &'a mut ObservedSlotMap<K, V, M> {
+    type Item = (K, &'a mut V);
+    type IntoIter = M::IterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// A draining iterator for [`ObservedSlotMap`].
+///
+/// This iterator is created by [`ObservedSlotMap::drain`].
+pub struct Drain<'a, K: 'a + Key, V: 'a, M: ObservableMap<K, V> + 'a = SlotMap<K, V>> {
+    inner: M::Drain<'a>,
+    observers: &'a mut Observers<K>,
+}
+
+impl<'a, K: Key, V, M: ObservableMap<K, V>> fmt::Debug for Drain<'a, K, V, M>
+where
+    M::Drain<'a>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Drain").field("inner", &self.inner).finish()
+    }
+}
+
+impl<'a, K: Key, V, M: ObservableMap<K, V>> Iterator for Drain<'a, K, V, M> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.inner.next()?;
+        notify(self.observers, key);
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K: Key, V, M: ObservableMap<K, V>> Drop for Drain<'a, K, V, M> {
+    fn drop(&mut self) {
+        self.for_each(|_drop| {});
+    }
+}
+
+impl<'a, K: Key, V, M: ObservableMap<K, V>> FusedIterator for Drain<'a, K, V, M> {}
+impl<'a, K: Key, V, M: ObservableMap<K, V>> ExactSizeIterator for Drain<'a, K, V, M> {}
+
+macro_rules! impl_observable_map {
+    ($module:ident, $map:ident) => {
+        impl<K: Key, V> ObservableMap<K, V> for $map<K, V> {
+            type Iter<'a> = $module::Iter<'a, K, V> where V: 'a;
+            type IterMut<'a> = $module::IterMut<'a, K, V> where V: 'a;
+            type Values<'a> = $module::Values<'a, K, V> where V: 'a;
+            type ValuesMut<'a> = $module::ValuesMut<'a, K, V> where V: 'a;
+            type Drain<'a> = $module::Drain<'a, K, V> where V: 'a;
+
+            fn with_capacity_and_key(capacity: usize) -> Self {
+                Self::with_capacity_and_key(capacity)
+            }
+
+            fn capacity(&self) -> usize {
+                self.capacity()
+            }
+
+            fn reserve(&mut self, additional: usize) {
+                self.reserve(additional)
+            }
+
+            fn insert_with_key<F: FnOnce(K) -> V>(&mut self, f: F) -> K {
+                self.insert_with_key(f)
+            }
+
+            fn get(&self, key: K) -> Option<&V> {
+                self.get(key)
+            }
+
+            fn retain<F: FnMut(K, &mut V) -> bool>(&mut self, f: F) {
+                self.retain(f)
+            }
+
+            fn clear(&mut self) {
+                self.clear()
+            }
+
+            fn drain(&mut self) -> Self::Drain<'_> {
+                self.drain()
+            }
+
+            fn iter(&self) -> Self::Iter<'_> {
+                self.iter()
+            }
+
+            fn iter_mut(&mut self) -> Self::IterMut<'_> {
+                self.iter_mut()
+            }
+
+            fn values(&self) -> Self::Values<'_> {
+                self.values()
+            }
+
+            fn values_mut(&mut self) -> Self::ValuesMut<'_> {
+                self.values_mut()
+            }
+        }
+    };
+}
+
+impl_observable_map!(basic, SlotMap);
+impl_observable_map!(hop, HopSlotMap);
+impl_observable_map!(dense, DenseSlotMap);
+impl_observable_map!(soa, SoaSlotMap);
+
+impl<K: Key, F: FnMut(K)> RemovalObserver<K> for F {
+    fn on_remove(&mut self, key: K) {
+        self(key)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Key, O: RemovalObserver<K>> RemovalObserver<K> for Arc<Mutex<O>> {
+    fn on_remove(&mut self, key: K) {
+        self.lock().unwrap_or_else(PoisonError::into_inner).on_remove(key)
+    }
+
+    fn on_clear(&mut self, keys: &mut dyn Iterator<Item = K>) {
+        self.lock().unwrap_or_else(PoisonError::into_inner).on_clear(keys)
+    }
+}
+
+impl<K: Key, V> RemovalObserver<K> for SecondaryMap<K, V> {
+    fn on_remove(&mut self, key: K) {
+        self.remove(key);
+    }
+
+    fn on_clear(&mut self, _keys: &mut dyn Iterator<Item = K>) {
+        self.clear()
+    }
+}
+
+impl<K: Key, V> RemovalObserver<K> for DefaultSecondaryMap<K, V> {
+    fn on_remove(&mut self, key: K) {
+        self.remove(key);
+    }
+
+    fn on_clear(&mut self, _keys: &mut dyn Iterator<Item = K>) {
+        self.clear()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Key, V, S: std::hash::BuildHasher> RemovalObserver<K> for crate::SparseSecondaryMap<K, V, S> {
+    fn on_remove(&mut self, key: K) {
+        self.remove(key);
+    }
+
+    fn on_clear(&mut self, _keys: &mut dyn Iterator<Item = K>) {
+        self.clear()
+    }
+}
+
+impl<K: Key> RemovalObserver<K> for SecondarySet<K> {
+    fn on_remove(&mut self, key: K) {
+        self.remove(key);
+    }
+
+    fn on_clear(&mut self, _keys: &mut dyn Iterator<Item = K>) {
+        self.clear()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex, PoisonError};
+
+    use super::ObservableMap;
+    use crate::*;
+
+    #[test]
+    fn every_removal_is_observed() {
+        let mut sm = ObservedSlotMap::new();
+        let sec = Arc::new(Mutex::new(SecondaryMap::new()));
+        let sparse = Arc::new(Mutex::new(SparseSecondaryMap::new()));
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        sm.observe(sec.clone());
+        sm.observe(sparse.clone());
+        let log = removed.clone();
+        let logger = sm.observe(move |key: DefaultKey| log.lock().unwrap().push(key));
+
+        let keys: Vec<_> = (0..20).map(|i| sm.insert(i)).collect();
+        for &key in &keys {
+            sec.lock().unwrap().insert(key, ());
+            sparse.lock().unwrap().insert(key, ());
+        }
+        let consistent = |sm: &ObservedSlotMap<DefaultKey, i32>| {
+            sm.len() == sec.lock().unwrap().len()
+                && sm.len() == sparse.lock().unwrap().len()
+                && sm.keys().all(|key| sec.lock().unwrap().contains_key(key))
+        };
+
+        sm.remove(keys[0]);
+        sm.remove(keys[0]);
+        sm.retain(|_, v| *v % 3 != 0);
+        assert!(consistent(&sm));
+        assert_eq!(removed.lock().unwrap().len(), 7);
+
+        let mut drain = sm.drain();
+        drain.next();
+        drop(drain);
+        assert!(consistent(&sm));
+        assert_eq!(removed.lock().unwrap().len(), 20);
+
+        for &key in &keys[..5] {
+            sparse.lock().unwrap().insert(key, ());
+        }
+        let key = sm.insert(0);
+        sec.lock().unwrap().insert(key, ());
+        sm.clear();
+        assert!(consistent(&sm) && sparse.lock().unwrap().is_empty());
+        assert_eq!(removed.lock().unwrap().last(), Some(&key));
+
+        // Unregistered observers are no longer notified.
+        assert!(sm.unobserve(logger));
+        assert!(!sm.unobserve(logger));
+        let key = sm.insert(0);
+        sec.lock().unwrap().insert(key, ());
+        std::thread::spawn(move || sm.remove(key)).join().unwrap();
+        assert!(sec.lock().unwrap().is_empty());
+        assert_eq!(removed.lock().unwrap().len(), 21);
+    }
+
+    #[test]
+    fn every_slot_map_can_be_observed() {
+        fn check<M: ObservableMap<DefaultKey, i32>>() {
+            let mut sm: ObservedSlotMap<DefaultKey, i32, M> = ObservedSlotMap::with_key();
+            let sec = sm.observe(SecondaryMap::<DefaultKey, i32>::new());
+            let keys: Vec<_> = (0..10).map(|i| sm.insert(i)).collect();
+            for &key in &keys {
+                let value = sm[key];
+                sm.observer_mut::<SecondaryMap<_, i32>>(sec).unwrap().insert(key, value);
+            }
+
+            sm.remove(keys[0]);
+            sm.retain(|_, v| *v % 2 == 0);
+            let values = sm.observer::<SecondaryMap<_, i32>>(sec).unwrap();
+            assert_eq!(values.len(), 4);
+            assert!(sm.iter().all(|(key, v)| values.get(key) == Some(v)));
+
+            sm.drain().next();
+            assert!(sm.is_empty());
+            assert!(sm.observer::<SecondaryMap<_, i32>>(sec).unwrap().is_empty());
+        }
+
+        check::<SlotMap<_, _>>();
+        check::<HopSlotMap<_, _>>();
+        check::<DenseSlotMap<_, _>>();
+        check::<SoaSlotMap<_, _>>();
+    }
+
+    #[test]
+    fn poisoned_observers_are_notified() {
+        let mut sm = ObservedSlotMap::new();
+        let sec = Arc::new(Mutex::new(SecondaryMap::new()));
+        sm.observe(sec.clone());
+        let key = sm.insert(1);
+        sec.lock().unwrap().insert(key, ());
+
+        let poison = sec.clone();
+        std::thread::spawn(move || {
+            let _guard = poison.lock().unwrap();
+            panic!("poisoning the observer");
+        })
+        .join()
+        .unwrap_err();
+        assert!(sec.is_poisoned());
+
+        sm.remove(key);
+        assert!(sec.lock().unwrap_or_else(PoisonError::into_inner).is_empty());
+    }
+}
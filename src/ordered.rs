@@ -0,0 +1,895 @@
+//! Contains the insertion ordered slot map implementation.
+
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::ops::{Index, IndexMut};
+
+use crate::SlotMap;
+use pi_key_alloter::{DefaultKey, Key};
+
+// An element together with the links to the elements inserted right before
+// and after it. Null keys mark the ends of the order.
+#[derive(Debug, Clone)]
+struct Node<K, V> {
+    value: V,
+    prev: K,
+    next: K,
+}
+
+/// Ordered slot map, a [`SlotMap`](crate::SlotMap) that iterates in insertion order.
+///
+/// The iteration order of [`SlotMap`](crate::SlotMap) and
+/// [`HopSlotMap`](crate::HopSlotMap) follows the slots, so it depends on
+/// which slots were reused. This map additionally links its elements in the
+/// order they were inserted, which makes iteration deterministic with respect
+/// to creation. Removal stays O(1), and the neighbours of an element can be
+/// looked up with [`next`](Self::next) and [`prev`](Self::prev).
+///
+/// Every element costs two extra keys of memory for the links, and iteration
+/// follows the links rather than walking the slots.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut sm = OrderedSlotMap::new();
+/// let a = sm.insert("a");
+/// let b = sm.insert("b");
+/// sm.remove(a);
+/// let c = sm.insert("c"); // Reuses the slot of "a".
+/// assert_eq!(sm.values().collect::<Vec<_>>(), [&"b", &"c"]);
+/// assert_eq!(sm.first(), Some(b));
+/// assert_eq!(sm.next(b), Some(c));
+/// ```
+#[derive(Clone)]
+pub struct OrderedSlotMap<K: Key, V> {
+    sm: SlotMap<K, Node<K, V>>,
+    head: K,
+    tail: K,
+}
+
+impl<V> OrderedSlotMap<DefaultKey, V> {
+    /// Constructs a new, empty [`OrderedSlotMap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm: OrderedSlotMap<_, i32> = OrderedSlotMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`OrderedSlotMap`] with the given capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm: OrderedSlotMap<_, i32> = OrderedSlotMap::with_capacity(10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_key(capacity)
+    }
+}
+
+impl<K: Key, V> OrderedSlotMap<K, V> {
+    /// Constructs a new, empty [`OrderedSlotMap`] with a custom key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct EventKey;
+    /// }
+    /// let mut events: OrderedSlotMap<EventKey, i32> = OrderedSlotMap::with_key();
+    /// ```
+    pub fn with_key() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`OrderedSlotMap`] with the given capacity and a
+    /// custom key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct EventKey;
+    /// }
+    /// let mut events: OrderedSlotMap<EventKey, i32> = OrderedSlotMap::with_capacity_and_key(5);
+    /// ```
+    pub fn with_capacity_and_key(capacity: usize) -> Self {
+        Self {
+            sm: SlotMap::with_capacity_and_key(capacity),
+            head: K::null(),
+            tail: K::null(),
+        }
+    }
+
+    /// Returns the number of elements in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::with_capacity(10);
+    /// sm.insert("len() counts actual elements, not capacity");
+    /// assert_eq!(sm.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.sm.len()
+    }
+
+    /// Returns if the slot map is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let key = sm.insert("dummy");
+    /// assert_eq!(sm.is_empty(), false);
+    /// sm.remove(key);
+    /// assert_eq!(sm.is_empty(), true);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.sm.is_empty()
+    }
+
+    /// Returns the number of elements the [`OrderedSlotMap`] can hold without
+    /// reallocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let sm: OrderedSlotMap<_, f64> = OrderedSlotMap::with_capacity(10);
+    /// assert_eq!(sm.capacity(), 10);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.sm.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more elements to be
+    /// inserted in the [`OrderedSlotMap`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new allocation size overflows [`usize`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// sm.insert("foo");
+    /// sm.reserve(32);
+    /// assert!(sm.capacity() >= 33);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.sm.reserve(additional)
+    }
+
+    /// Returns [`true`] if the slot map contains `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let key = sm.insert(42);
+    /// assert_eq!(sm.contains_key(key), true);
+    /// sm.remove(key);
+    /// assert_eq!(sm.contains_key(key), false);
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.sm.contains_key(key)
+    }
+
+    /// Inserts a value after all other elements, returning its key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the slot map equals
+    /// 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let key = sm.insert(42);
+    /// assert_eq!(sm[key], 42);
+    /// assert_eq!(sm.last(), Some(key));
+    /// ```
+    pub fn insert(&mut self, value: V) -> K {
+        self.insert_with_key(|_| value)
+    }
+
+    /// Inserts a value given by `f` after all other elements. The key where
+    /// the value will be stored is passed into `f`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the slot map equals
+    /// 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let key = sm.insert_with_key(|k| (k, 20));
+    /// assert_eq!(sm[key], (key, 20));
+    /// ```
+    pub fn insert_with_key<F>(&mut self, f: F) -> K
+    where
+        F: FnOnce(K) -> V,
+    {
+        let key = self.sm.insert_with_key(|k| Node {
+            value: f(k),
+            prev: self.tail,
+            next: K::null(),
+        });
+        match self.sm.get_mut(self.tail) {
+            Some(node) => node.next = key,
+            None => self.head = key,
+        }
+        self.tail = key;
+        key
+    }
+
+    /// Removes a key from the slot map, returning the value at the key if the
+    /// key was not previously removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let k0 = sm.insert(0);
+    /// let k1 = sm.insert(1);
+    /// assert_eq!(sm.remove(k0), Some(0));
+    /// assert_eq!(sm.remove(k0), None);
+    /// assert_eq!(sm.first(), Some(k1));
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let node = self.sm.remove(key)?;
+        match self.sm.get_mut(node.prev) {
+            Some(prev) => prev.next = node.next,
+            None => self.head = node.next,
+        }
+        match self.sm.get_mut(node.next) {
+            Some(next) => next.prev = node.prev,
+            None => self.tail = node.prev,
+        }
+        Some(node.value)
+    }
+
+    /// Retains only the elements specified by the predicate, visiting them in
+    /// insertion order.
+    ///
+    /// In other words, remove all key-value pairs `(k, v)` such that
+    /// `f(k, &mut v)` returns false. This method invalidates any removed keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// for i in 0..6 {
+    ///     sm.insert(i);
+    /// }
+    /// sm.retain(|_, v| *v % 2 == 0);
+    /// assert_eq!(sm.values().collect::<Vec<_>>(), [&0, &2, &4]);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        let mut cur = self.head;
+        while let Some(node) = self.sm.get_mut(cur) {
+            let (key, next) = (cur, node.next);
+            if !f(key, &mut node.value) {
+                self.remove(key);
+            }
+            cur = next;
+        }
+    }
+
+    /// Clears the slot map. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// sm.insert(0);
+    /// sm.clear();
+    /// assert_eq!(sm.len(), 0);
+    /// assert_eq!(sm.first(), None);
+    /// ```
+    pub fn clear(&mut self) {
+        self.sm.clear();
+        self.head = K::null();
+        self.tail = K::null();
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let key = sm.insert("bar");
+    /// assert_eq!(sm.get(key), Some(&"bar"));
+    /// sm.remove(key);
+    /// assert_eq!(sm.get(key), None);
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.sm.get(key).map(|node| &node.value)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let key = sm.insert(3.5);
+    /// if let Some(x) = sm.get_mut(key) {
+    ///     *x += 3.0;
+    /// }
+    /// assert_eq!(sm[key], 6.5);
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.sm.get_mut(key).map(|node| &mut node.value)
+    }
+
+    /// Returns the key of the oldest element, or [`None`] if the slot map is
+    /// empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// assert_eq!(sm.first(), None);
+    /// let k = sm.insert(1);
+    /// sm.insert(2);
+    /// assert_eq!(sm.first(), Some(k));
+    /// ```
+    pub fn first(&self) -> Option<K> {
+        Some(self.head).filter(|k| !k.is_null())
+    }
+
+    /// Returns the key of the newest element, or [`None`] if the slot map is
+    /// empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// sm.insert(1);
+    /// let k = sm.insert(2);
+    /// assert_eq!(sm.last(), Some(k));
+    /// ```
+    pub fn last(&self) -> Option<K> {
+        Some(self.tail).filter(|k| !k.is_null())
+    }
+
+    /// Returns the key of the element inserted after the element of `key`, or
+    /// [`None`] if `key` is the newest element or not in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let a = sm.insert('a');
+    /// let b = sm.insert('b');
+    /// assert_eq!(sm.next(a), Some(b));
+    /// assert_eq!(sm.next(b), None);
+    /// ```
+    pub fn next(&self, key: K) -> Option<K> {
+        self.sm.get(key).map(|node| node.next).filter(|k| !k.is_null())
+    }
+
+    /// Returns the key of the element inserted before the element of `key`,
+    /// or [`None`] if `key` is the oldest element or not in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let a = sm.insert('a');
+    /// let b = sm.insert('b');
+    /// assert_eq!(sm.prev(b), Some(a));
+    /// assert_eq!(sm.prev(a), None);
+    /// ```
+    pub fn prev(&self, key: K) -> Option<K> {
+        self.sm.get(key).map(|node| node.prev).filter(|k| !k.is_null())
+    }
+
+    /// An iterator visiting all key-value pairs in insertion order. The
+    /// iterator element type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let k0 = sm.insert(0);
+    /// let k1 = sm.insert(1);
+    /// assert_eq!(sm.iter().collect::<Vec<_>>(), [(k0, &0), (k1, &1)]);
+    /// assert_eq!(sm.iter().rev().collect::<Vec<_>>(), [(k1, &1), (k0, &0)]);
+    /// ```
+    pub fn iter(&self) -> Iter<K, V> {
+        Iter {
+            sm: &self.sm,
+            front: self.head,
+            back: self.tail,
+            num_left: self.len(),
+        }
+    }
+
+    /// An iterator visiting all key-value pairs in insertion order, with
+    /// mutable references to the values. The iterator element type is
+    /// `(K, &'a mut V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    /// for (k, v) in sm.iter_mut() {
+    ///     if k != k1 {
+    ///         *v *= -1;
+    ///     }
+    /// }
+    /// assert_eq!(sm[k0], -10);
+    /// assert_eq!(sm[k1], 20);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<K, V> {
+        IterMut {
+            front: self.head,
+            back: self.tail,
+            num_left: self.len(),
+            sm: &mut self.sm,
+            _sm: PhantomData,
+        }
+    }
+
+    /// An iterator visiting all keys in insertion order. The iterator element
+    /// type is K.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    /// assert_eq!(sm.keys().collect::<Vec<_>>(), [k0, k1]);
+    /// ```
+    pub fn keys(&self) -> Keys<K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    /// An iterator visiting all values in insertion order. The iterator
+    /// element type is `&'a V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// sm.insert(10);
+    /// sm.insert(20);
+    /// assert_eq!(sm.values().collect::<Vec<_>>(), [&10, &20]);
+    /// ```
+    pub fn values(&self) -> Values<K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// An iterator visiting all values mutably in insertion order. The
+    /// iterator element type is `&'a mut V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedSlotMap::new();
+    /// sm.insert(1);
+    /// sm.insert(2);
+    /// sm.values_mut().for_each(|n| *n *= 3);
+    /// assert_eq!(sm.values().collect::<Vec<_>>(), [&3, &6]);
+    /// ```
+    pub fn values_mut(&mut self) -> ValuesMut<K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+}
+
+impl<K: Key, V: fmt::Debug> fmt::Debug for OrderedSlotMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Key, V> Default for OrderedSlotMap<K, V> {
+    fn default() -> Self {
+        Self::with_key()
+    }
+}
+
+impl<K: Key, V> Index<K> for OrderedSlotMap<K, V> {
+    type Output = V;
+
+    fn index(&self, key: K) -> &V {
+        match self.get(key) {
+            Some(r) => r,
+            None => panic!("invalid OrderedSlotMap key used"),
+        }
+    }
+}
+
+impl<K: Key, V> IndexMut<K> for OrderedSlotMap<K, V> {
+    fn index_mut(&mut self, key: K) -> &mut V {
+        match self.get_mut(key) {
+            Some(r) => r,
+            None => panic!("invalid OrderedSlotMap key used"),
+        }
+    }
+}
+
+// Iterators.
+/// An iterator that moves key-value pairs out of an [`OrderedSlotMap`].
+///
+/// This iterator is created by calling the `into_iter` method on
+/// [`OrderedSlotMap`], provided by the [`IntoIterator`] trait.
+#[derive(Debug, Clone)]
+pub struct IntoIter<K: Key, V> {
+    sm: OrderedSlotMap<K, V>,
+}
+
+/// An iterator over the key-value pairs in an [`OrderedSlotMap`].
+///
+/// This iterator is created by [`OrderedSlotMap::iter`].
+#[derive(Debug)]
+pub struct Iter<'a, K: Key + 'a, V: 'a> {
+    sm: &'a SlotMap<K, Node<K, V>>,
+    front: K,
+    back: K,
+    num_left: usize,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Iter<'a, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            sm: self.sm,
+            front: self.front,
+            back: self.back,
+            num_left: self.num_left,
+        }
+    }
+}
+
+/// A mutable iterator over the key-value pairs in an [`OrderedSlotMap`].
+///
+/// This iterator is created by [`OrderedSlotMap::iter_mut`].
+///
+/// The nodes are reached through a raw pointer rather than borrowing the slot
+/// map again for every step, which would invalidate the values already
+/// yielded.
+#[derive(Debug)]
+pub struct IterMut<'a, K: Key + 'a, V: 'a> {
+    sm: *mut SlotMap<K, Node<K, V>>,
+    front: K,
+    back: K,
+    num_left: usize,
+    _sm: PhantomData<&'a mut SlotMap<K, Node<K, V>>>,
+}
+
+// An `IterMut` is used like the `&mut SlotMap` it was created from.
+unsafe impl<'a, K: Key, V: Send> Send for IterMut<'a, K, V> {}
+unsafe impl<'a, K: Key, V: Sync> Sync for IterMut<'a, K, V> {}
+
+/// An iterator over the keys in an [`OrderedSlotMap`].
+///
+/// This iterator is created by [`OrderedSlotMap::keys`].
+#[derive(Debug)]
+pub struct Keys<'a, K: Key + 'a, V: 'a> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Keys<'a, K, V> {
+    fn clone(&self) -> Self {
+        Keys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// An iterator over the values in an [`OrderedSlotMap`].
+///
+/// This iterator is created by [`OrderedSlotMap::values`].
+#[derive(Debug)]
+pub struct Values<'a, K: Key + 'a, V: 'a> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Values<'a, K, V> {
+    fn clone(&self) -> Self {
+        Values {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// A mutable iterator over the values in an [`OrderedSlotMap`].
+///
+/// This iterator is created by [`OrderedSlotMap::values_mut`].
+#[derive(Debug)]
+pub struct ValuesMut<'a, K: Key + 'a, V: 'a> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<K: Key, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        let key = self.sm.first()?;
+        self.sm.remove(key).map(|value| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.sm.len(), Some(self.sm.len()))
+    }
+}
+
+impl<K: Key, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        let key = self.sm.last()?;
+        self.sm.remove(key).map(|value| (key, value))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Iter<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        if self.num_left == 0 {
+            return None;
+        }
+
+        let key = self.front;
+        let node = self.sm.get(key)?;
+        self.front = node.next;
+        self.num_left -= 1;
+        Some((key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.num_left, Some(self.num_left))
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a V)> {
+        if self.num_left == 0 {
+            return None;
+        }
+
+        let key = self.back;
+        let node = self.sm.get(key)?;
+        self.back = node.prev;
+        self.num_left -= 1;
+        Some((key, &node.value))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for IterMut<'a, K, V> {
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<(K, &'a mut V)> {
+        if self.num_left == 0 {
+            return None;
+        }
+
+        // This is safe because every linked key is valid and the remaining
+        // count stops the ends from passing each other, so each value is
+        // handed out once.
+        let key = self.front;
+        unsafe {
+            let node = SlotMap::raw_get_unchecked_mut(self.sm, key);
+            self.front = (*node).next;
+            self.num_left -= 1;
+            Some((key, &mut (*node).value))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.num_left, Some(self.num_left))
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a mut V)> {
+        if self.num_left == 0 {
+            return None;
+        }
+
+        // This is safe because every linked key is valid and the remaining
+        // count stops the ends from passing each other, so each value is
+        // handed out once.
+        let key = self.back;
+        unsafe {
+            let node = SlotMap::raw_get_unchecked_mut(self.sm, key);
+            self.back = (*node).prev;
+            self.num_left -= 1;
+            Some((key, &mut (*node).value))
+        }
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Keys<'a, K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<K> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K: Key, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K: Key, V> IntoIterator for &'a OrderedSlotMap<K, V> {
+    type Item = (K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Key, V> IntoIterator for &'a mut OrderedSlotMap<K, V> {
+    type Item = (K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K: Key, V> IntoIterator for OrderedSlotMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { sm: self }
+    }
+}
+
+impl<'a, K: Key, V> FusedIterator for Iter<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for IterMut<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for Keys<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for Values<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for ValuesMut<'a, K, V> {}
+impl<K: Key, V> FusedIterator for IntoIter<K, V> {}
+
+impl<'a, K: Key, V> ExactSizeIterator for Iter<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for IterMut<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for Keys<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for Values<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for ValuesMut<'a, K, V> {}
+impl<K: Key, V> ExactSizeIterator for IntoIter<K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn follows_insertion_order() {
+        let mut sm = OrderedSlotMap::new();
+        let mut expected = Vec::new();
+        for i in 0..30 {
+            let k = sm.insert(i as i32);
+            expected.push((k, i as i32));
+            if i % 4 == 3 {
+                let (k, v) = expected.remove(i % 5);
+                assert_eq!(sm.remove(k), Some(v));
+            }
+        }
+
+        let forward: Vec<_> = sm.iter().map(|(k, &v)| (k, v)).collect();
+        assert_eq!(forward, expected);
+        let mut backward: Vec<_> = sm.iter().rev().map(|(k, &v)| (k, v)).collect();
+        backward.reverse();
+        assert_eq!(backward, expected);
+
+        let mut cur = sm.first();
+        for &(k, _) in &expected {
+            assert_eq!(cur, Some(k));
+            cur = sm.next(k);
+        }
+        assert_eq!(cur, None);
+        let mut cur = sm.last();
+        for &(k, _) in expected.iter().rev() {
+            assert_eq!(cur, Some(k));
+            cur = sm.prev(k);
+        }
+        assert_eq!(cur, None);
+
+        sm.retain(|_, v| *v % 3 != 0);
+        expected.retain(|&(_, v)| v % 3 != 0);
+        let mut it = sm.iter_mut();
+        *it.next_back().unwrap().1 = -1;
+        *it.next().unwrap().1 = -1;
+        assert_eq!(sm[expected[0].0], -1);
+        assert_eq!(sm[expected[expected.len() - 1].0], -1);
+        let values: Vec<_> = sm.values_mut().collect();
+        for v in values {
+            *v += 1;
+        }
+        assert_eq!(sm[expected[0].0], 0);
+
+        let owned: Vec<_> = sm.into_iter().map(|(k, _)| k).collect();
+        assert!(owned.iter().eq(expected.iter().map(|(k, _)| k)));
+    }
+}
@@ -0,0 +1,757 @@
+//! Contains the ordered dense slot map implementation.
+
+use core::iter::Rev;
+use core::ops::{Index, IndexMut};
+
+use crate::dense::{DenseSlotMap, Drain, IntoIter, Iter, IterMut, Keys, Values, ValuesMut};
+use pi_key_alloter::{DefaultKey, Key};
+
+/// Ordered dense slot map, a [`DenseSlotMap`] that keeps its elements in
+/// insertion order.
+///
+/// A [`DenseSlotMap`] removes elements by swapping the last element into the
+/// hole, which destroys the order of the elements. This map shifts the
+/// following elements down instead, so iteration always follows insertion
+/// order, unless elements are explicitly reordered with
+/// [`move_before`](Self::move_before) or [`move_after`](Self::move_after).
+///
+/// The price is that removal and reordering are O(n) in the number of
+/// elements after the affected position. Insertion, lookup and iteration are
+/// as fast as in a [`DenseSlotMap`].
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut sm = OrderedDenseSlotMap::new();
+/// let a = sm.insert("a");
+/// let b = sm.insert("b");
+/// let c = sm.insert("c");
+/// sm.remove(a);
+/// sm.insert("d");
+/// assert_eq!(sm.values().collect::<Vec<_>>(), [&"b", &"c", &"d"]);
+/// sm.move_after(b, c);
+/// assert_eq!(sm.values().collect::<Vec<_>>(), [&"c", &"b", &"d"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct OrderedDenseSlotMap<K: Key, V> {
+    inner: DenseSlotMap<K, V>,
+}
+
+impl<V> OrderedDenseSlotMap<DefaultKey, V> {
+    /// Constructs a new, empty [`OrderedDenseSlotMap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm: OrderedDenseSlotMap<_, i32> = OrderedDenseSlotMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`OrderedDenseSlotMap`] with the given capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm: OrderedDenseSlotMap<_, i32> = OrderedDenseSlotMap::with_capacity(10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> OrderedDenseSlotMap<DefaultKey, V> {
+        Self::with_capacity_and_key(capacity)
+    }
+}
+
+impl<K: Key, V> OrderedDenseSlotMap<K, V> {
+    /// Constructs a new, empty [`OrderedDenseSlotMap`] with a custom key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct RowKey;
+    /// }
+    /// let mut rows: OrderedDenseSlotMap<RowKey, i32> = OrderedDenseSlotMap::with_key();
+    /// ```
+    pub fn with_key() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`OrderedDenseSlotMap`] with the given capacity and a
+    /// custom key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct RowKey;
+    /// }
+    /// let mut rows: OrderedDenseSlotMap<RowKey, i32> =
+    ///     OrderedDenseSlotMap::with_capacity_and_key(10);
+    /// ```
+    pub fn with_capacity_and_key(capacity: usize) -> Self {
+        Self {
+            inner: DenseSlotMap::with_capacity_and_key(capacity),
+        }
+    }
+
+    /// Returns the number of elements in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::with_capacity(10);
+    /// sm.insert("len() counts actual elements, not capacity");
+    /// assert_eq!(sm.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns if the slot map is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let key = sm.insert("dummy");
+    /// assert_eq!(sm.is_empty(), false);
+    /// sm.remove(key);
+    /// assert_eq!(sm.is_empty(), true);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns the number of elements the [`OrderedDenseSlotMap`] can hold
+    /// without reallocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let sm: OrderedDenseSlotMap<_, f64> = OrderedDenseSlotMap::with_capacity(10);
+    /// assert_eq!(sm.capacity(), 10);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more elements to be
+    /// inserted in the [`OrderedDenseSlotMap`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new allocation size overflows [`usize`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// sm.insert("foo");
+    /// sm.reserve(32);
+    /// assert!(sm.capacity() >= 33);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+
+    /// Returns [`true`] if the slot map contains `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let key = sm.insert(42);
+    /// assert_eq!(sm.contains_key(key), true);
+    /// sm.remove(key);
+    /// assert_eq!(sm.contains_key(key), false);
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.inner.contains_key(key)
+    }
+
+    /// Inserts a value at the end of the slot map, returning its key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the slot map equals
+    /// 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let key = sm.insert(42);
+    /// assert_eq!(sm[key], 42);
+    /// assert_eq!(sm.key_at(0), Some(key));
+    /// ```
+    pub fn insert(&mut self, value: V) -> K {
+        self.inner.insert(value)
+    }
+
+    /// Inserts a value given by `f` at the end of the slot map. The key where
+    /// the value will be stored is passed into `f`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the slot map equals
+    /// 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let key = sm.insert_with_key(|k| (k, 20));
+    /// assert_eq!(sm[key], (key, 20));
+    /// ```
+    pub fn insert_with_key<F>(&mut self, f: F) -> K
+    where
+        F: FnOnce(K) -> V,
+    {
+        self.inner.insert_with_key(f)
+    }
+
+    /// Removes a key from the slot map, returning the value at the key if the
+    /// key was not previously removed. The elements after it keep their order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let k0 = sm.insert(0);
+    /// let k1 = sm.insert(1);
+    /// let k2 = sm.insert(2);
+    /// assert_eq!(sm.remove(k0), Some(0));
+    /// assert_eq!(sm.remove(k0), None);
+    /// assert_eq!(sm.keys().collect::<Vec<_>>(), [k1, k2]);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        self.inner.shift_remove(key)
+    }
+
+    /// Retains only the elements specified by the predicate, visiting them in
+    /// order. The retained elements keep their order.
+    ///
+    /// In other words, remove all key-value pairs `(k, v)` such that
+    /// `f(k, &mut v)` returns false. This method operates in place and
+    /// invalidates any removed keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// for i in 0..6 {
+    ///     sm.insert(i);
+    /// }
+    /// sm.retain(|_, v| *v % 2 == 1);
+    /// assert_eq!(sm.values_as_slice(), [1, 3, 5]);
+    /// ```
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        self.inner.retain_ordered(f)
+    }
+
+    /// Clears the slot map. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// sm.insert(0);
+    /// sm.clear();
+    /// assert_eq!(sm.len(), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    /// Clears the slot map, returning all key-value pairs in order as an
+    /// iterator. Keeps the allocated memory for reuse.
+    ///
+    /// See [`DenseSlotMap::drain`] for what happens if the iterator is not
+    /// dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let k0 = sm.insert(0);
+    /// let k1 = sm.insert(1);
+    /// let v: Vec<_> = sm.drain().collect();
+    /// assert_eq!(sm.len(), 0);
+    /// assert_eq!(v, vec![(k0, 0), (k1, 1)]);
+    /// ```
+    pub fn drain(&mut self) -> Rev<Drain<K, V>> {
+        // Drain pops from the back.
+        self.inner.drain().rev()
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let key = sm.insert("bar");
+    /// assert_eq!(sm.get(key), Some(&"bar"));
+    /// sm.remove(key);
+    /// assert_eq!(sm.get(key), None);
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.inner.get(key)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let key = sm.insert(3.5);
+    /// if let Some(x) = sm.get_mut(key) {
+    ///     *x += 3.0;
+    /// }
+    /// assert_eq!(sm[key], 6.5);
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.inner.get_mut(key)
+    }
+
+    /// Moves the element of `key` to directly before the element of `anchor`.
+    /// Returns `false` and does nothing if either key is not in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let a = sm.insert('a');
+    /// let b = sm.insert('b');
+    /// let c = sm.insert('c');
+    /// assert!(sm.move_before(c, a));
+    /// assert_eq!(sm.values_as_slice(), ['c', 'a', 'b']);
+    /// ```
+    pub fn move_before(&mut self, key: K, anchor: K) -> bool {
+        match (self.inner.index_of(key), self.inner.index_of(anchor)) {
+            (Some(from), Some(to)) => {
+                let to = if from < to { to - 1 } else { to };
+                self.inner.move_position(from, to);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Moves the element of `key` to directly after the element of `anchor`.
+    /// Returns `false` and does nothing if either key is not in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let a = sm.insert('a');
+    /// let b = sm.insert('b');
+    /// let c = sm.insert('c');
+    /// assert!(sm.move_after(a, c));
+    /// assert_eq!(sm.values_as_slice(), ['b', 'c', 'a']);
+    /// ```
+    pub fn move_after(&mut self, key: K, anchor: K) -> bool {
+        match (self.inner.index_of(key), self.inner.index_of(anchor)) {
+            (Some(from), Some(to)) => {
+                let to = if from > to { to + 1 } else { to };
+                self.inner.move_position(from, to);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs in order. The iterator element
+    /// type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let k0 = sm.insert(0);
+    /// let k1 = sm.insert(1);
+    /// assert_eq!(sm.iter().collect::<Vec<_>>(), [(k0, &0), (k1, &1)]);
+    /// ```
+    pub fn iter(&self) -> Iter<K, V> {
+        self.inner.iter()
+    }
+
+    /// An iterator visiting all key-value pairs in order, with mutable
+    /// references to the values. The iterator element type is
+    /// `(K, &'a mut V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    /// for (k, v) in sm.iter_mut() {
+    ///     if k != k1 {
+    ///         *v *= -1;
+    ///     }
+    /// }
+    /// assert_eq!(sm[k0], -10);
+    /// assert_eq!(sm[k1], 20);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<K, V> {
+        self.inner.iter_mut()
+    }
+
+    /// An iterator visiting all keys in order. The iterator element type is K.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    /// assert_eq!(sm.keys().collect::<Vec<_>>(), [k0, k1]);
+    /// ```
+    pub fn keys(&self) -> Keys<K, V> {
+        self.inner.keys()
+    }
+
+    /// An iterator visiting all values in order. The iterator element type is
+    /// `&'a V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// sm.insert(10);
+    /// sm.insert(20);
+    /// assert_eq!(sm.values().collect::<Vec<_>>(), [&10, &20]);
+    /// ```
+    pub fn values(&self) -> Values<K, V> {
+        self.inner.values()
+    }
+
+    /// An iterator visiting all values mutably in order. The iterator element
+    /// type is `&'a mut V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// sm.insert(1);
+    /// sm.insert(2);
+    /// sm.values_mut().for_each(|n| *n *= 3);
+    /// assert_eq!(sm.values_as_slice(), [3, 6]);
+    /// ```
+    pub fn values_mut(&mut self) -> ValuesMut<K, V> {
+        self.inner.values_mut()
+    }
+
+    /// Returns the values as a contiguous slice, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// sm.insert(1);
+    /// sm.insert(2);
+    /// assert_eq!(sm.values_as_slice(), [1, 2]);
+    /// ```
+    pub fn values_as_slice(&self) -> &[V] {
+        self.inner.values_as_slice()
+    }
+
+    /// Returns the values as a contiguous mutable slice, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let k = sm.insert(1);
+    /// sm.values_as_mut_slice()[0] = 5;
+    /// assert_eq!(sm[k], 5);
+    /// ```
+    pub fn values_as_mut_slice(&mut self) -> &mut [V] {
+        self.inner.values_as_mut_slice()
+    }
+
+    /// Returns the keys as a contiguous slice, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let k0 = sm.insert(1);
+    /// let k1 = sm.insert(2);
+    /// assert_eq!(sm.keys_as_slice(), [k0, k1]);
+    /// ```
+    pub fn keys_as_slice(&self) -> &[K] {
+        self.inner.keys_as_slice()
+    }
+
+    /// Returns the position of the key in the order of the slot map, or
+    /// [`None`] if the key is not in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let k0 = sm.insert(1);
+    /// let k1 = sm.insert(2);
+    /// sm.remove(k0);
+    /// assert_eq!(sm.index_of(k1), Some(0));
+    /// ```
+    pub fn index_of(&self, key: K) -> Option<usize> {
+        self.inner.index_of(key)
+    }
+
+    /// Returns the key at the given position in the order of the slot map,
+    /// or [`None`] if it is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let k = sm.insert(1);
+    /// assert_eq!(sm.key_at(0), Some(k));
+    /// assert_eq!(sm.key_at(1), None);
+    /// ```
+    pub fn key_at(&self, index: usize) -> Option<K> {
+        self.inner.key_at(index)
+    }
+
+    /// Returns the underlying [`DenseSlotMap`], whose order matches this map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = OrderedDenseSlotMap::new();
+    /// let k = sm.insert(1);
+    /// assert_eq!(sm.as_dense()[k], 1);
+    /// ```
+    pub fn as_dense(&self) -> &DenseSlotMap<K, V> {
+        &self.inner
+    }
+}
+
+impl<K: Key, V> Default for OrderedDenseSlotMap<K, V> {
+    fn default() -> Self {
+        Self::with_key()
+    }
+}
+
+impl<K: Key, V> Index<K> for OrderedDenseSlotMap<K, V> {
+    type Output = V;
+
+    fn index(&self, key: K) -> &V {
+        match self.get(key) {
+            Some(r) => r,
+            None => panic!("invalid OrderedDenseSlotMap key used"),
+        }
+    }
+}
+
+impl<K: Key, V> IndexMut<K> for OrderedDenseSlotMap<K, V> {
+    fn index_mut(&mut self, key: K) -> &mut V {
+        match self.get_mut(key) {
+            Some(r) => r,
+            None => panic!("invalid OrderedDenseSlotMap key used"),
+        }
+    }
+}
+
+impl<'a, K: 'a + Key, V> IntoIterator for &'a OrderedDenseSlotMap<K, V> {
+    type Item = (K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: 'a + Key, V> IntoIterator for &'a mut OrderedDenseSlotMap<K, V> {
+    type Item = (K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K: Key, V> IntoIterator for OrderedDenseSlotMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serialize {
+    use alloc::vec::Vec;
+
+    use pi_key_alloter::KeyData;
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    #[derive(Serialize)]
+    #[serde(bound(serialize = "V: Serialize"))]
+    struct SerdeOrdered<'a, K: Key, V> {
+        map: &'a DenseSlotMap<K, V>,
+        order: Vec<KeyData>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(bound(deserialize = "V: Deserialize<'de>"))]
+    struct SerdeOrderedOwned<K: Key, V> {
+        map: DenseSlotMap<K, V>,
+        order: Vec<KeyData>,
+    }
+
+    impl<K: Key, V: Serialize> Serialize for OrderedDenseSlotMap<K, V> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let serde_ordered = SerdeOrdered {
+                map: &self.inner,
+                order: self.inner.keys_as_slice().iter().map(|k| k.data()).collect(),
+            };
+            serde_ordered.serialize(serializer)
+        }
+    }
+
+    impl<'de, K: Key, V: Deserialize<'de>> Deserialize<'de> for OrderedDenseSlotMap<K, V> {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let SerdeOrderedOwned { mut map, order } = Deserialize::deserialize(deserializer)?;
+            if order.len() != map.len() {
+                return Err(de::Error::custom(&"order does not match elements"));
+            }
+
+            // Every element must appear exactly once in the order.
+            let mut seen = alloc::vec![false; map.len()];
+            let mut positions = Vec::with_capacity(order.len());
+            for kd in order {
+                match map.index_of(kd.into()) {
+                    Some(idx) if !seen[idx] => {
+                        seen[idx] = true;
+                        positions.push(idx);
+                    },
+                    _ => return Err(de::Error::custom(&"order does not match elements")),
+                }
+            }
+
+            map.reorder(positions);
+            Ok(Self { inner: map })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn keeps_insertion_order() {
+        let mut sm = OrderedDenseSlotMap::new();
+        let keys: Vec<_> = (0..20).map(|i| sm.insert(i)).collect();
+        for &i in &[0, 5, 6, 19, 11] {
+            assert_eq!(sm.remove(keys[i]), Some(i));
+        }
+        let expected: Vec<_> = (0..20).filter(|i| ![0, 5, 6, 19, 11].contains(i)).collect();
+        assert_eq!(sm.values_as_slice(), &expected[..]);
+        for (i, k) in sm.keys().enumerate() {
+            assert_eq!(sm.index_of(k), Some(i));
+        }
+
+        sm.retain(|_, v| *v % 3 != 0);
+        let expected: Vec<_> = expected.into_iter().filter(|v| v % 3 != 0).collect();
+        assert_eq!(sm.values_as_slice(), &expected[..]);
+        for (k, &v) in &sm {
+            assert_eq!(sm[keys[v]], v);
+            assert_eq!(k, keys[v]);
+        }
+
+        let drained: Vec<_> = sm.clone().drain().map(|(_, v)| v).collect();
+        assert_eq!(drained, expected);
+        let owned: Vec<_> = sm.into_iter().map(|(_, v)| v).collect();
+        assert_eq!(owned, expected);
+    }
+
+    #[test]
+    fn move_before_after() {
+        let mut sm = OrderedDenseSlotMap::new();
+        let k: Vec<_> = (0..5).map(|i| sm.insert(i)).collect();
+
+        assert!(sm.move_before(k[4], k[1]));
+        assert_eq!(sm.values_as_slice(), [0, 4, 1, 2, 3]);
+        assert!(sm.move_before(k[0], k[3]));
+        assert_eq!(sm.values_as_slice(), [4, 1, 2, 0, 3]);
+        assert!(sm.move_after(k[3], k[4]));
+        assert_eq!(sm.values_as_slice(), [4, 3, 1, 2, 0]);
+        assert!(sm.move_after(k[4], k[0]));
+        assert_eq!(sm.values_as_slice(), [3, 1, 2, 0, 4]);
+        assert!(sm.move_after(k[2], k[2]));
+        assert_eq!(sm.values_as_slice(), [3, 1, 2, 0, 4]);
+
+        for (i, &key) in k.iter().enumerate() {
+            assert_eq!(sm[key], i);
+        }
+
+        sm.remove(k[1]);
+        assert!(!sm.move_before(k[1], k[0]));
+        assert!(!sm.move_after(k[0], k[1]));
+        assert_eq!(sm.values_as_slice(), [3, 2, 0, 4]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ordered_serde() {
+        let mut sm = OrderedDenseSlotMap::new();
+        let k: Vec<_> = (0..5).map(|i| sm.insert(i)).collect();
+        sm.remove(k[1]);
+        sm.move_before(k[4], k[0]);
+
+        let ser = serde_json::to_string(&sm).unwrap();
+        let de: OrderedDenseSlotMap<DefaultKey, i32> = serde_json::from_str(&ser).unwrap();
+        assert_eq!(de.keys_as_slice(), sm.keys_as_slice());
+        assert_eq!(de.values_as_slice(), [4, 0, 2, 3]);
+    }
+}
@@ -0,0 +1,500 @@
+//! Parallel iteration over the slots of the sparse slot maps.
+//!
+//! Splitting a slice of slots in half by index balances badly when the
+//! occupied slots are unevenly spread, e.g. after removing most of the front
+//! of a slot map. Instead the slots are first counted in fixed size chunks and
+//! producers are split at the chunk boundary that halves the number of
+//! occupied slots. Slots whose occupancy is kept in a bitmap are counted the
+//! same way, with `count_ones` on the words of the bitmap.
+
+use alloc::vec::Vec;
+
+use rayon::iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer};
+use rayon::prelude::*;
+
+// Number of slots whose occupancy is counted together. Producers are only
+// split at multiples of this.
+const CHUNK: usize = 256;
+
+// Number of occupancy bitmap words covering a chunk of slots.
+const CHUNK_WORDS: usize = CHUNK / u64::BITS as usize;
+
+fn count_occupied<S: Sync, F>(slots: &[S], occupied: F) -> Vec<usize>
+where
+    F: Fn(&S) -> bool + Sync,
+{
+    slots
+        .par_chunks(CHUNK)
+        .map(|chunk| chunk.iter().filter(|slot| occupied(slot)).count())
+        .collect()
+}
+
+fn count_bits(bits: &[u64]) -> Vec<usize> {
+    bits.par_chunks(CHUNK_WORDS)
+        .map(|chunk| chunk.iter().map(|word| word.count_ones() as usize).sum())
+        .collect()
+}
+
+// A slot shared between the threads counting occupied slots, even if it isn't
+// Sync itself. See `ParSlotsMut::new` for why this is fine.
+#[repr(transparent)]
+struct CountedSlot<S>(S);
+
+unsafe impl<S> Sync for CountedSlot<S> {}
+
+// Returns the chunk index at which to split so both halves hold about the same
+// number of occupied slots, or None if splitting isn't worth it.
+fn split_chunk(counts: &[usize]) -> Option<usize> {
+    let total: usize = counts.iter().sum();
+    if counts.len() < 2 || total < 2 {
+        return None;
+    }
+
+    let mut seen = 0;
+    let mid = counts
+        .iter()
+        .position(|&count| {
+            seen += count;
+            2 * seen >= total
+        })
+        .unwrap_or(0);
+    Some((mid + 1).min(counts.len() - 1))
+}
+
+/// A parallel iterator over the occupied slots in a slice of slots, yielding
+/// the index of each slot along with it.
+pub(crate) struct ParSlots<'a, S> {
+    slots: &'a [S],
+    offset: usize,
+    occupied: fn(&S) -> bool,
+}
+
+impl<'a, S: Sync> ParSlots<'a, S> {
+    /// Iterates over `slots`, reporting indices shifted by `offset`.
+    pub(crate) fn new(slots: &'a [S], offset: usize, occupied: fn(&S) -> bool) -> Self {
+        Self {
+            slots,
+            offset,
+            occupied,
+        }
+    }
+}
+
+impl<'a, S: Sync> ParallelIterator for ParSlots<'a, S> {
+    type Item = (usize, &'a S);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let counts = count_occupied(self.slots, self.occupied);
+        let producer = SlotsProducer {
+            slots: self.slots,
+            offset: self.offset,
+            counts: &counts,
+            occupied: self.occupied,
+        };
+        bridge_unindexed(producer, consumer)
+    }
+}
+
+struct SlotsProducer<'a, 'c, S> {
+    slots: &'a [S],
+    offset: usize,
+    counts: &'c [usize],
+    occupied: fn(&S) -> bool,
+}
+
+impl<'a, 'c, S: Sync> UnindexedProducer for SlotsProducer<'a, 'c, S> {
+    type Item = (usize, &'a S);
+
+    fn split(self) -> (Self, Option<Self>) {
+        let mid = match split_chunk(self.counts) {
+            Some(mid) => mid,
+            None => return (self, None),
+        };
+
+        let (left_slots, right_slots) = self.slots.split_at(mid * CHUNK);
+        let (left_counts, right_counts) = self.counts.split_at(mid);
+        let right = SlotsProducer {
+            slots: right_slots,
+            offset: self.offset + mid * CHUNK,
+            counts: right_counts,
+            occupied: self.occupied,
+        };
+        let left = SlotsProducer {
+            slots: left_slots,
+            offset: self.offset,
+            counts: left_counts,
+            occupied: self.occupied,
+        };
+        (left, Some(right))
+    }
+
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        let (offset, occupied) = (self.offset, self.occupied);
+        folder.consume_iter(
+            self.slots
+                .iter()
+                .enumerate()
+                .filter(|(_, slot)| occupied(slot))
+                .map(|(idx, slot)| (offset + idx, slot)),
+        )
+    }
+}
+
+/// A parallel iterator over mutable references to the occupied slots in a
+/// slice of slots, yielding the index of each slot along with it.
+pub(crate) struct ParSlotsMut<'a, S> {
+    slots: &'a mut [S],
+    offset: usize,
+    occupied: fn(&S) -> bool,
+}
+
+impl<'a, S: Send> ParSlotsMut<'a, S> {
+    /// Iterates over `slots`, reporting indices shifted by `offset`.
+    ///
+    /// # Safety
+    ///
+    /// The slots are counted on several threads before they are handed out,
+    /// even if `S` is not `Sync`. `occupied` must therefore only read plain
+    /// data of a slot, such as its version, and never its value.
+    pub(crate) unsafe fn new(slots: &'a mut [S], offset: usize, occupied: fn(&S) -> bool) -> Self {
+        Self {
+            slots,
+            offset,
+            occupied,
+        }
+    }
+}
+
+impl<'a, S: Send> ParallelIterator for ParSlotsMut<'a, S> {
+    type Item = (usize, &'a mut S);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        // Counted before the slots are split up and handed out mutably. This
+        // is safe by the contract of new, nothing else accesses the slots yet.
+        let slots: *const [S] = &*self.slots;
+        let shared = unsafe { &*(slots as *const [CountedSlot<S>]) };
+        let occupied = self.occupied;
+        let counts = count_occupied(shared, |slot| occupied(&slot.0));
+        let producer = SlotsProducerMut {
+            slots: self.slots,
+            offset: self.offset,
+            counts: &counts,
+            occupied: self.occupied,
+        };
+        bridge_unindexed(producer, consumer)
+    }
+}
+
+struct SlotsProducerMut<'a, 'c, S> {
+    slots: &'a mut [S],
+    offset: usize,
+    counts: &'c [usize],
+    occupied: fn(&S) -> bool,
+}
+
+impl<'a, 'c, S: Send> UnindexedProducer for SlotsProducerMut<'a, 'c, S> {
+    type Item = (usize, &'a mut S);
+
+    fn split(self) -> (Self, Option<Self>) {
+        let mid = match split_chunk(self.counts) {
+            Some(mid) => mid,
+            None => return (self, None),
+        };
+
+        let (left_slots, right_slots) = self.slots.split_at_mut(mid * CHUNK);
+        let (left_counts, right_counts) = self.counts.split_at(mid);
+        let right = SlotsProducerMut {
+            slots: right_slots,
+            offset: self.offset + mid * CHUNK,
+            counts: right_counts,
+            occupied: self.occupied,
+        };
+        let left = SlotsProducerMut {
+            slots: left_slots,
+            offset: self.offset,
+            counts: left_counts,
+            occupied: self.occupied,
+        };
+        (left, Some(right))
+    }
+
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        let (offset, occupied) = (self.offset, self.occupied);
+        folder.consume_iter(
+            self.slots
+                .iter_mut()
+                .enumerate()
+                .filter(|(_, slot)| occupied(slot))
+                .map(|(idx, slot)| (offset + idx, slot)),
+        )
+    }
+}
+
+/// A parallel iterator over the items of a slice whose bit is set in an
+/// occupancy bitmap, yielding the index of each item along with it.
+pub(crate) struct ParBits<'a, T> {
+    bits: &'a [u64],
+    items: &'a [T],
+}
+
+impl<'a, T: Sync> ParBits<'a, T> {
+    /// Iterates over the items in `items` whose bit is set in `bits`.
+    pub(crate) fn new(bits: &'a [u64], items: &'a [T]) -> Self {
+        Self { bits, items }
+    }
+}
+
+impl<'a, T: Sync> ParallelIterator for ParBits<'a, T> {
+    type Item = (usize, &'a T);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let counts = count_bits(self.bits);
+        let producer = BitsProducer {
+            bits: self.bits,
+            items: self.items,
+            offset: 0,
+            counts: &counts,
+        };
+        bridge_unindexed(producer, consumer)
+    }
+}
+
+struct BitsProducer<'a, 'c, T> {
+    bits: &'a [u64],
+    items: &'a [T],
+    offset: usize,
+    counts: &'c [usize],
+}
+
+impl<'a, 'c, T: Sync> UnindexedProducer for BitsProducer<'a, 'c, T> {
+    type Item = (usize, &'a T);
+
+    fn split(self) -> (Self, Option<Self>) {
+        let mid = match split_chunk(self.counts) {
+            Some(mid) => mid,
+            None => return (self, None),
+        };
+
+        // The bitmap may have more words than needed to cover the items.
+        let (left_bits, right_bits) = self.bits.split_at(mid * CHUNK_WORDS);
+        let (left_items, right_items) = self.items.split_at((mid * CHUNK).min(self.items.len()));
+        let (left_counts, right_counts) = self.counts.split_at(mid);
+        let right = BitsProducer {
+            bits: right_bits,
+            items: right_items,
+            offset: self.offset + mid * CHUNK,
+            counts: right_counts,
+        };
+        let left = BitsProducer {
+            bits: left_bits,
+            items: left_items,
+            offset: self.offset,
+            counts: left_counts,
+        };
+        (left, Some(right))
+    }
+
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        let offset = self.offset;
+        folder.consume_iter(
+            self.bits
+                .iter()
+                .zip(self.items.chunks(u64::BITS as usize))
+                .enumerate()
+                .filter(|(_, (&word, _))| word != 0)
+                .flat_map(move |(w, (&word, items))| {
+                    items
+                        .iter()
+                        .enumerate()
+                        .filter(move |&(bit, _)| word & (1 << bit) != 0)
+                        .map(move |(bit, item)| (offset + w * u64::BITS as usize + bit, item))
+                }),
+        )
+    }
+}
+
+/// A parallel iterator over mutable references to the items of a slice whose
+/// bit is set in an occupancy bitmap, yielding the index of each item along
+/// with it.
+pub(crate) struct ParBitsMut<'a, T> {
+    bits: &'a [u64],
+    items: &'a mut [T],
+}
+
+impl<'a, T: Send> ParBitsMut<'a, T> {
+    /// Iterates over the items in `items` whose bit is set in `bits`.
+    pub(crate) fn new(bits: &'a [u64], items: &'a mut [T]) -> Self {
+        Self { bits, items }
+    }
+}
+
+impl<'a, T: Send> ParallelIterator for ParBitsMut<'a, T> {
+    type Item = (usize, &'a mut T);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let counts = count_bits(self.bits);
+        let producer = BitsProducerMut {
+            bits: self.bits,
+            items: self.items,
+            offset: 0,
+            counts: &counts,
+        };
+        bridge_unindexed(producer, consumer)
+    }
+}
+
+struct BitsProducerMut<'a, 'c, T> {
+    bits: &'a [u64],
+    items: &'a mut [T],
+    offset: usize,
+    counts: &'c [usize],
+}
+
+impl<'a, 'c, T: Send> UnindexedProducer for BitsProducerMut<'a, 'c, T> {
+    type Item = (usize, &'a mut T);
+
+    fn split(self) -> (Self, Option<Self>) {
+        let mid = match split_chunk(self.counts) {
+            Some(mid) => mid,
+            None => return (self, None),
+        };
+
+        let (left_bits, right_bits) = self.bits.split_at(mid * CHUNK_WORDS);
+        let item_mid = (mid * CHUNK).min(self.items.len());
+        let (left_items, right_items) = self.items.split_at_mut(item_mid);
+        let (left_counts, right_counts) = self.counts.split_at(mid);
+        let right = BitsProducerMut {
+            bits: right_bits,
+            items: right_items,
+            offset: self.offset + mid * CHUNK,
+            counts: right_counts,
+        };
+        let left = BitsProducerMut {
+            bits: left_bits,
+            items: left_items,
+            offset: self.offset,
+            counts: left_counts,
+        };
+        (left, Some(right))
+    }
+
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        let offset = self.offset;
+        folder.consume_iter(
+            self.bits
+                .iter()
+                .zip(self.items.chunks_mut(u64::BITS as usize))
+                .enumerate()
+                .filter(|(_, (&word, _))| word != 0)
+                .flat_map(move |(w, (&word, items))| {
+                    items
+                        .iter_mut()
+                        .enumerate()
+                        .filter(move |&(bit, _)| word & (1 << bit) != 0)
+                        .map(move |(bit, item)| (offset + w * u64::BITS as usize + bit, item))
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_balances_occupied() {
+        assert_eq!(split_chunk(&[]), None);
+        assert_eq!(split_chunk(&[100]), None);
+        assert_eq!(split_chunk(&[0, 0, 1]), None);
+        assert_eq!(split_chunk(&[0, 0, 0, 10, 10]), Some(4));
+        assert_eq!(split_chunk(&[10, 0, 0, 0, 10]), Some(1));
+        assert_eq!(split_chunk(&[0, 0, 0, 0, 10, 10]), Some(5));
+        assert_eq!(split_chunk(&[20, 1, 1]), Some(1));
+    }
+
+    #[test]
+    fn split_balances_set_bits() {
+        // Only the last eighth of the slots is occupied.
+        let len = 64 * 1024;
+        let items: Vec<usize> = (0..len).collect();
+        let mut bits = vec![0u64; len / 64];
+        for word in &mut bits[len / 64 * 7 / 8..] {
+            *word = u64::MAX;
+        }
+        let occupied = len / 8;
+
+        let counts = count_bits(&bits);
+        let producer = BitsProducer {
+            bits: &bits,
+            items: &items,
+            offset: 0,
+            counts: &counts,
+        };
+        let (left, right) = producer.split();
+        let right = right.unwrap();
+        let left_count: usize = left.counts.iter().sum();
+        let right_count: usize = right.counts.iter().sum();
+        assert_eq!(left_count + right_count, occupied);
+        assert!(left_count.abs_diff(right_count) <= CHUNK);
+        assert_eq!(right.offset, left.items.len());
+
+        let mut seen: Vec<_> = ParBits::new(&bits, &items).map(|(idx, &item)| (idx, item)).collect();
+        seen.sort();
+        let expected: Vec<_> = (len - occupied..len).map(|i| (i, i)).collect();
+        assert_eq!(seen, expected);
+
+        let mut incremented = items.clone();
+        ParBitsMut::new(&bits, &mut incremented).for_each(|(_, item)| *item += 1);
+        assert!(items
+            .iter()
+            .zip(&incremented)
+            .all(|(&i, &inc)| inc == if i >= len - occupied { i + 1 } else { i }));
+    }
+
+    #[test]
+    fn visits_every_occupied_slot() {
+        let slots: Vec<u32> = (0..10_000)
+            .map(|i| if i > 9000 || i % 7 == 0 { i } else { 0 })
+            .collect();
+        let mut seen: Vec<_> = ParSlots::new(&slots, 5, |&s| s != 0)
+            .map(|(idx, &s)| (idx, s))
+            .collect();
+        seen.sort();
+        let expected: Vec<_> = (0..10_000)
+            .filter(|&i| i > 9000 || (i % 7 == 0 && i != 0))
+            .map(|i| (i as usize + 5, i))
+            .collect();
+        assert_eq!(seen, expected);
+
+        let mut incremented = slots.clone();
+        unsafe { ParSlotsMut::new(&mut incremented, 0, |&s| s != 0) }.for_each(|(_, s)| *s += 1);
+        assert!(slots
+            .iter()
+            .zip(&incremented)
+            .all(|(&s, &inc)| inc == if s != 0 { s + 1 } else { 0 }));
+    }
+}
@@ -0,0 +1,603 @@
+//! Contains the partitioned dense slot map implementation.
+
+use alloc::vec::Vec;
+use core::ops::{Index, IndexMut, Range};
+
+use crate::dense::{DenseSlotMap, IntoIter, Iter, IterMut};
+use pi_key_alloter::{DefaultKey, Key};
+
+/// Partitioned dense slot map, a [`DenseSlotMap`] whose elements are divided
+/// into a fixed number of groups.
+///
+/// The elements of each partition are kept contiguous in the value array,
+/// partition 0 first. Iterating over a single partition is therefore as fast
+/// as walking a slice, which makes this map a good fit for e.g. only visiting
+/// the enabled entities of a much larger set.
+///
+/// Moving an element to a neighbouring partition is a single swap across the
+/// boundary, so changing the partition of an element costs O(d) where d is
+/// the distance between the partitions. Insertion and removal cost
+/// O(partitions). All keys stay valid through these moves.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// const ENABLED: usize = 0;
+/// const DISABLED: usize = 1;
+///
+/// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+/// let a = sm.insert_into(ENABLED, "a");
+/// let b = sm.insert_into(DISABLED, "b");
+/// let c = sm.insert_into(ENABLED, "c");
+/// sm.set_partition(a, DISABLED);
+/// assert_eq!(sm.partition_values(ENABLED), ["c"]);
+/// assert_eq!(sm.partition_of(a), Some(DISABLED));
+/// assert_eq!(sm[b], "b");
+/// # let _ = c;
+/// ```
+#[derive(Debug, Clone)]
+pub struct PartitionedDenseSlotMap<K: Key, V> {
+    inner: DenseSlotMap<K, V>,
+    // The dense index one past the last element of each partition.
+    ends: Vec<usize>,
+}
+
+impl<V> PartitionedDenseSlotMap<DefaultKey, V> {
+    /// Constructs a new, empty [`PartitionedDenseSlotMap`] with the given
+    /// number of partitions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partitions` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let sm: PartitionedDenseSlotMap<_, i32> = PartitionedDenseSlotMap::with_partitions(3);
+    /// assert_eq!(sm.partitions(), 3);
+    /// ```
+    pub fn with_partitions(partitions: usize) -> Self {
+        Self::with_partitions_and_key(partitions)
+    }
+}
+
+impl<K: Key, V> PartitionedDenseSlotMap<K, V> {
+    /// Constructs a new, empty [`PartitionedDenseSlotMap`] with the given
+    /// number of partitions and a custom key type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partitions` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct EntityKey;
+    /// }
+    /// let sm: PartitionedDenseSlotMap<EntityKey, i32> =
+    ///     PartitionedDenseSlotMap::with_partitions_and_key(2);
+    /// ```
+    pub fn with_partitions_and_key(partitions: usize) -> Self {
+        assert!(partitions > 0, "PartitionedDenseSlotMap needs at least one partition");
+        Self {
+            inner: DenseSlotMap::with_key(),
+            ends: alloc::vec![0; partitions],
+        }
+    }
+
+    /// Returns the number of partitions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let sm: PartitionedDenseSlotMap<_, ()> = PartitionedDenseSlotMap::with_partitions(4);
+    /// assert_eq!(sm.partitions(), 4);
+    /// ```
+    pub fn partitions(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Returns the number of elements in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// sm.insert_into(0, 'a');
+    /// sm.insert_into(1, 'b');
+    /// assert_eq!(sm.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns if the slot map is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// let key = sm.insert_into(1, "dummy");
+    /// assert_eq!(sm.is_empty(), false);
+    /// sm.remove(key);
+    /// assert_eq!(sm.is_empty(), true);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns the number of elements in the given partition.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partition` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// sm.insert_into(1, 'a');
+    /// assert_eq!(sm.partition_len(0), 0);
+    /// assert_eq!(sm.partition_len(1), 1);
+    /// ```
+    pub fn partition_len(&self, partition: usize) -> usize {
+        self.partition_range(partition).len()
+    }
+
+    /// Returns [`true`] if the slot map contains `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(1);
+    /// let key = sm.insert_into(0, 42);
+    /// assert_eq!(sm.contains_key(key), true);
+    /// sm.remove(key);
+    /// assert_eq!(sm.contains_key(key), false);
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.inner.contains_key(key)
+    }
+
+    /// Inserts a value into the given partition, returning its key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partition` is out of bounds or if the number of elements in
+    /// the slot map equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// let key = sm.insert_into(1, 42);
+    /// assert_eq!(sm[key], 42);
+    /// assert_eq!(sm.partition_of(key), Some(1));
+    /// ```
+    pub fn insert_into(&mut self, partition: usize, value: V) -> K {
+        assert!(partition < self.partitions(), "partition index out of bounds");
+        let key = self.inner.insert(value);
+
+        // The new element is at the end of the last partition. Move it down
+        // by swapping it with the first element of every partition above the
+        // target, growing the partition below each time.
+        let mut cur = self.inner.len() - 1;
+        *self.ends.last_mut().unwrap() += 1;
+        for p in (partition + 1..self.partitions()).rev() {
+            let first = self.ends[p - 1];
+            self.inner.swap_dense(cur, first);
+            self.ends[p - 1] += 1;
+            cur = first;
+        }
+
+        key
+    }
+
+    /// Removes a key from the slot map, returning the value at the key if the
+    /// key was not previously removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// let key = sm.insert_into(0, 42);
+    /// assert_eq!(sm.remove(key), Some(42));
+    /// assert_eq!(sm.remove(key), None);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let idx = self.inner.index_of(key)?;
+        let partition = self.partition_at(idx);
+
+        // Move the element up to the end of the last partition by swapping it
+        // with the last element of its own partition and every partition
+        // above, shrinking each of them, so the final removal is a pop.
+        let mut cur = idx;
+        for p in partition..self.partitions() {
+            let last = self.ends[p] - 1;
+            self.inner.swap_dense(cur, last);
+            self.ends[p] -= 1;
+            cur = last;
+        }
+
+        self.inner.remove(key)
+    }
+
+    /// Clears the slot map. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// sm.insert_into(0, 0);
+    /// sm.insert_into(1, 1);
+    /// sm.clear();
+    /// assert_eq!(sm.len(), 0);
+    /// assert_eq!(sm.partition_len(1), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        self.inner.clear();
+        self.ends.iter_mut().for_each(|end| *end = 0);
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(1);
+    /// let key = sm.insert_into(0, "bar");
+    /// assert_eq!(sm.get(key), Some(&"bar"));
+    /// sm.remove(key);
+    /// assert_eq!(sm.get(key), None);
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.inner.get(key)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(1);
+    /// let key = sm.insert_into(0, 3.5);
+    /// if let Some(x) = sm.get_mut(key) {
+    ///     *x += 3.0;
+    /// }
+    /// assert_eq!(sm[key], 6.5);
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.inner.get_mut(key)
+    }
+
+    /// Returns the partition of the element corresponding to the key, or
+    /// [`None`] if the key is not in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(3);
+    /// let key = sm.insert_into(2, ());
+    /// assert_eq!(sm.partition_of(key), Some(2));
+    /// sm.remove(key);
+    /// assert_eq!(sm.partition_of(key), None);
+    /// ```
+    pub fn partition_of(&self, key: K) -> Option<usize> {
+        self.inner.index_of(key).map(|idx| self.partition_at(idx))
+    }
+
+    /// Moves the element corresponding to the key into another partition.
+    /// Returns `false` and does nothing if the key is not in the slot map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partition` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// let a = sm.insert_into(0, 'a');
+    /// let b = sm.insert_into(0, 'b');
+    /// assert!(sm.set_partition(a, 1));
+    /// assert_eq!(sm.partition_values(0), ['b']);
+    /// assert_eq!(sm.partition_values(1), ['a']);
+    /// ```
+    pub fn set_partition(&mut self, key: K, partition: usize) -> bool {
+        assert!(partition < self.partitions(), "partition index out of bounds");
+        let mut cur = match self.inner.index_of(key) {
+            Some(idx) => idx,
+            None => return false,
+        };
+
+        let mut p = self.partition_at(cur);
+        while p < partition {
+            // Swap with the last element of p, which then ends before it.
+            let last = self.ends[p] - 1;
+            self.inner.swap_dense(cur, last);
+            self.ends[p] -= 1;
+            cur = last;
+            p += 1;
+        }
+        while p > partition {
+            // Swap with the first element of p, which then starts after it.
+            let first = self.ends[p - 1];
+            self.inner.swap_dense(cur, first);
+            self.ends[p - 1] += 1;
+            cur = first;
+            p -= 1;
+        }
+
+        true
+    }
+
+    /// An iterator visiting all key-value pairs, partition by partition. The
+    /// iterator element type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// sm.insert_into(1, 1);
+    /// sm.insert_into(0, 0);
+    /// assert!(sm.iter().map(|(_, &v)| v).eq(0..2));
+    /// ```
+    pub fn iter(&self) -> Iter<K, V> {
+        self.inner.iter()
+    }
+
+    /// An iterator visiting all key-value pairs, partition by partition, with
+    /// mutable references to the values. The iterator element type is
+    /// `(K, &'a mut V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// let k = sm.insert_into(1, 10);
+    /// sm.iter_mut().for_each(|(_, v)| *v += 1);
+    /// assert_eq!(sm[k], 11);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<K, V> {
+        self.inner.iter_mut()
+    }
+
+    /// An iterator visiting the key-value pairs in a single partition. The
+    /// iterator element type is `(K, &'a V)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partition` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// let k = sm.insert_into(1, "disabled");
+    /// sm.insert_into(0, "enabled");
+    /// assert_eq!(sm.iter_partition(1).collect::<Vec<_>>(), [(k, &"disabled")]);
+    /// ```
+    pub fn iter_partition(&self, partition: usize) -> Iter<K, V> {
+        self.inner.iter_dense(self.partition_range(partition))
+    }
+
+    /// An iterator visiting the key-value pairs in a single partition, with
+    /// mutable references to the values. The iterator element type is
+    /// `(K, &'a mut V)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partition` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// let a = sm.insert_into(0, 1);
+    /// let b = sm.insert_into(1, 1);
+    /// sm.iter_partition_mut(0).for_each(|(_, v)| *v = 0);
+    /// assert_eq!((sm[a], sm[b]), (0, 1));
+    /// ```
+    pub fn iter_partition_mut(&mut self, partition: usize) -> IterMut<K, V> {
+        let range = self.partition_range(partition);
+        self.inner.iter_dense_mut(range)
+    }
+
+    /// Returns the keys of a single partition as a slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partition` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// let k = sm.insert_into(1, ());
+    /// assert_eq!(sm.partition_keys(1), [k]);
+    /// ```
+    pub fn partition_keys(&self, partition: usize) -> &[K] {
+        &self.inner.keys_as_slice()[self.partition_range(partition)]
+    }
+
+    /// Returns the values of a single partition as a slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partition` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// sm.insert_into(1, 5);
+    /// assert_eq!(sm.partition_values(1), [5]);
+    /// ```
+    pub fn partition_values(&self, partition: usize) -> &[V] {
+        &self.inner.values_as_slice()[self.partition_range(partition)]
+    }
+
+    /// Returns the values of a single partition as a mutable slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partition` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// let k = sm.insert_into(1, 5);
+    /// sm.partition_values_mut(1)[0] = 6;
+    /// assert_eq!(sm[k], 6);
+    /// ```
+    pub fn partition_values_mut(&mut self, partition: usize) -> &mut [V] {
+        let range = self.partition_range(partition);
+        &mut self.inner.values_as_mut_slice()[range]
+    }
+
+    /// Returns the underlying [`DenseSlotMap`]. Its values are ordered by
+    /// partition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = PartitionedDenseSlotMap::with_partitions(2);
+    /// let k = sm.insert_into(1, 1);
+    /// assert_eq!(sm.as_dense()[k], 1);
+    /// ```
+    pub fn as_dense(&self) -> &DenseSlotMap<K, V> {
+        &self.inner
+    }
+
+    // Returns the dense index range of a partition.
+    fn partition_range(&self, partition: usize) -> Range<usize> {
+        let end = self.ends[partition];
+        let start = if partition == 0 { 0 } else { self.ends[partition - 1] };
+        start..end
+    }
+
+    // Returns the partition containing the given dense index.
+    fn partition_at(&self, idx: usize) -> usize {
+        self.ends.partition_point(|&end| end <= idx)
+    }
+}
+
+impl<K: Key, V> Index<K> for PartitionedDenseSlotMap<K, V> {
+    type Output = V;
+
+    fn index(&self, key: K) -> &V {
+        match self.get(key) {
+            Some(r) => r,
+            None => panic!("invalid PartitionedDenseSlotMap key used"),
+        }
+    }
+}
+
+impl<K: Key, V> IndexMut<K> for PartitionedDenseSlotMap<K, V> {
+    fn index_mut(&mut self, key: K) -> &mut V {
+        match self.get_mut(key) {
+            Some(r) => r,
+            None => panic!("invalid PartitionedDenseSlotMap key used"),
+        }
+    }
+}
+
+impl<'a, K: 'a + Key, V> IntoIterator for &'a PartitionedDenseSlotMap<K, V> {
+    type Item = (K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: 'a + Key, V> IntoIterator for &'a mut PartitionedDenseSlotMap<K, V> {
+    type Item = (K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K: Key, V> IntoIterator for PartitionedDenseSlotMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn check<K: Key, V>(sm: &PartitionedDenseSlotMap<K, V>) {
+        let mut total = 0;
+        for p in 0..sm.partitions() {
+            for (k, _) in sm.iter_partition(p) {
+                assert_eq!(sm.partition_of(k), Some(p));
+                assert!(sm.as_dense().contains_key(k));
+            }
+            total += sm.partition_len(p);
+        }
+        assert_eq!(total, sm.len());
+    }
+
+    #[test]
+    fn partitions_stay_contiguous() {
+        let mut sm = PartitionedDenseSlotMap::with_partitions(3);
+        let mut keys = Vec::new();
+        for i in 0..60 {
+            keys.push((sm.insert_into(i % 3, i), i % 3));
+            check(&sm);
+        }
+
+        for (i, entry) in keys.iter_mut().enumerate().step_by(4) {
+            let p = (i * 7) % 3;
+            assert!(sm.set_partition(entry.0, p));
+            entry.1 = p;
+            check(&sm);
+        }
+
+        for &(k, _) in keys.iter().step_by(5) {
+            assert!(sm.remove(k).is_some());
+            check(&sm);
+        }
+        assert!(!sm.set_partition(keys[0].0, 1));
+
+        for (i, &(k, p)) in keys.iter().enumerate() {
+            if i % 5 != 0 {
+                assert_eq!(sm[k], i);
+                assert_eq!(sm.partition_of(k), Some(p));
+                assert!(sm.partition_keys(p).contains(&k));
+            }
+        }
+    }
+}
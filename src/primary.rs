@@ -0,0 +1,201 @@
+//! Contains the [`PrimaryMap`] trait, implemented by the slot maps that issue
+//! keys.
+
+use core::iter::Map;
+
+use pi_key_alloter::Key;
+
+use crate::observed::ObservableMap;
+use crate::{basic, delay, dense, hop, list, ordered, soa};
+use crate::{
+    DelaySlotMap, DenseSlotMap, HopSlotMap, ObservedSlotMap, OrderedDenseSlotMap, OrderedSlotMap,
+    PartitionedDenseSlotMap, SlotList, SlotMap, SoaSlotMap,
+};
+
+/// A map that issues keys, which secondary maps can be checked against.
+///
+/// Implemented for all slot maps of this crate, so methods such as
+/// [`SecondaryMap::retain_live`](crate::SecondaryMap::retain_live) work with
+/// any of them.
+pub trait PrimaryMap<K: Key> {
+    /// An iterator over the keys of the map.
+    type Keys<'a>: Iterator<Item = K>
+    where
+        Self: 'a;
+
+    /// Returns the number of elements in the map.
+    fn len(&self) -> usize;
+
+    /// Returns [`true`] if the map is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns [`true`] if `key` is valid in the map.
+    fn contains_key(&self, key: K) -> bool;
+
+    /// An iterator visiting all keys of the map.
+    fn keys(&self) -> Self::Keys<'_>;
+}
+
+/// A [`PrimaryMap`] whose values can be inserted, changed and removed by
+/// key.
+///
+/// Implemented for the slot maps of this crate that insert a value without
+/// further arguments, so companions such as
+/// [`SecondaryIndex`](crate::SecondaryIndex) can update any of them.
+pub trait PrimaryMapMut<K: Key, V>: PrimaryMap<K> {
+    /// Inserts a value into the map, returning its key.
+    fn insert(&mut self, value: V) -> K;
+
+    /// Returns a mutable reference to the value of `key`.
+    fn get_mut(&mut self, key: K) -> Option<&mut V>;
+
+    /// Removes `key` from the map, returning its value.
+    fn remove(&mut self, key: K) -> Option<V>;
+}
+
+macro_rules! impl_primary_map {
+    ($module:ident, $map:ident) => {
+        impl<K: Key, V> PrimaryMap<K> for $map<K, V> {
+            type Keys<'a> = $module::Keys<'a, K, V> where Self: 'a;
+
+            fn len(&self) -> usize {
+                self.len()
+            }
+
+            fn contains_key(&self, key: K) -> bool {
+                self.contains_key(key)
+            }
+
+            fn keys(&self) -> Self::Keys<'_> {
+                self.keys()
+            }
+        }
+    };
+}
+
+impl_primary_map!(basic, SlotMap);
+impl_primary_map!(hop, HopSlotMap);
+impl_primary_map!(dense, DenseSlotMap);
+impl_primary_map!(soa, SoaSlotMap);
+impl_primary_map!(ordered, OrderedSlotMap);
+impl_primary_map!(dense, OrderedDenseSlotMap);
+impl_primary_map!(delay, DelaySlotMap);
+impl_primary_map!(list, SlotList);
+
+macro_rules! impl_primary_map_mut {
+    ($map:ident) => {
+        impl<K: Key, V> PrimaryMapMut<K, V> for $map<K, V> {
+            fn insert(&mut self, value: V) -> K {
+                self.insert(value)
+            }
+
+            fn get_mut(&mut self, key: K) -> Option<&mut V> {
+                self.get_mut(key)
+            }
+
+            fn remove(&mut self, key: K) -> Option<V> {
+                self.remove(key)
+            }
+        }
+    };
+}
+
+impl_primary_map_mut!(SlotMap);
+impl_primary_map_mut!(HopSlotMap);
+impl_primary_map_mut!(DenseSlotMap);
+impl_primary_map_mut!(SoaSlotMap);
+impl_primary_map_mut!(OrderedSlotMap);
+impl_primary_map_mut!(OrderedDenseSlotMap);
+impl_primary_map_mut!(DelaySlotMap);
+
+impl<K: Key, V, M: ObservableMap<K, V>> PrimaryMap<K> for ObservedSlotMap<K, V, M> {
+    type Keys<'a> = M::Keys<'a> where Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn contains_key(&self, key: K) -> bool {
+        self.contains_key(key)
+    }
+
+    fn keys(&self) -> Self::Keys<'_> {
+        self.keys()
+    }
+}
+
+impl<K: Key, V, M: ObservableMap<K, V>> PrimaryMapMut<K, V> for ObservedSlotMap<K, V, M> {
+    fn insert(&mut self, value: V) -> K {
+        self.insert(value)
+    }
+
+    fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.get_mut(key)
+    }
+
+    fn remove(&mut self, key: K) -> Option<V> {
+        self.remove(key)
+    }
+}
+
+impl<K: Key, V> PrimaryMap<K> for PartitionedDenseSlotMap<K, V> {
+    type Keys<'a> = Map<dense::Iter<'a, K, V>, fn((K, &'a V)) -> K> where Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn contains_key(&self, key: K) -> bool {
+        self.contains_key(key)
+    }
+
+    fn keys(&self) -> Self::Keys<'_> {
+        self.iter().map(|(key, _)| key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn retain_live_from_either_side() {
+        let mut sm = SlotMap::new();
+        let keys: Vec<_> = (0..200).map(|i| sm.insert(i)).collect();
+
+        let mut sec = SecondaryMap::new();
+        let mut sparse = SparseSecondaryMap::new();
+        for &key in keys.iter().step_by(4) {
+            sec.insert(key, sm[key]);
+            sparse.insert(key, sm[key]);
+        }
+
+        // Fewer entries than the primary: the entries are checked.
+        for &key in keys.iter().step_by(3) {
+            sm.remove(key);
+        }
+        sec.retain_live(&sm);
+        sparse.retain_live(&sm);
+        let expected: Vec<_> = sm.iter().filter(|(_, &v)| v % 4 == 0).map(|(k, &v)| (k, v)).collect();
+        assert_eq!(sec.iter().map(|(k, &v)| (k, v)).collect::<Vec<_>>(), expected);
+        let mut sparse_entries: Vec<_> = sparse.iter().map(|(k, &v)| (k, v)).collect();
+        sparse_entries.sort_by_key(|&(_, v)| v);
+        assert_eq!(sparse_entries, expected);
+
+        // More entries than the primary: the primary keys are walked.
+        sm.retain(|_, v| *v < 40);
+        let hop: HopSlotMap<_, ()> = HopSlotMap::new();
+        sec.retain_live(&sm);
+        sparse.retain_live(&sm);
+        assert_eq!(sec.len(), expected.iter().filter(|&&(_, v)| v < 40).count());
+        assert!(sec.keys().all(|key| sm.contains_key(key)));
+        assert_eq!(sparse.len(), sec.len());
+        assert!(sparse.keys().all(|key| sec.contains_key(key)));
+
+        sec.retain_live(&hop);
+        sparse.retain_live(&hop);
+        assert!(sec.is_empty() && sparse.is_empty());
+    }
+}
@@ -4,76 +4,117 @@
 #[cfg(all(nightly, any(doc, feature = "unstable")))]
 use alloc::collections::TryReserveError;
 use alloc::vec::Vec;
-use core::hint::unreachable_unchecked;
-use core::iter::{Enumerate, Extend, FromIterator, FusedIterator};
+use core::fmt;
+use core::iter::{DoubleEndedIterator, Extend, FromIterator, FusedIterator};
 use core::marker::PhantomData;
-use core::mem::replace;
-#[allow(unused_imports)] // MaybeUninit is only used on nightly at the moment.
-use core::mem::MaybeUninit;
-use core::ops::{Index, IndexMut};
+use core::mem::{self, replace, MaybeUninit};
+use core::ops::{Index, IndexMut, RangeBounds};
 
+use crate::primary::PrimaryMap;
+use crate::util::slot_range;
 use pi_key_alloter::{is_older_version, Key, KeyData, key_data};
 
-// This representation works because we don't have to store the versions
-// of removed elements.
-#[derive(Debug, Clone)]
-enum Slot<T> {
-    Occupied { value: T, version: u32 },
+const BITS: usize = u64::BITS as usize;
 
-    Vacant,
+// Walks the set bits of an occupancy bitmap from both ends, skipping a whole
+// word at a time while it's empty. The bitmap isn't borrowed, so bits the
+// cursor has passed may be cleared in between. The front and back each keep
+// a copy of their current word, and `num_left` stops them from yielding the
+// same bit twice when they meet.
+#[derive(Debug, Clone)]
+struct BitCursor {
+    front: usize,
+    front_word: u64,
+    back: usize,
+    back_word: u64,
+    num_left: usize,
 }
 
-use self::Slot::{Occupied, Vacant};
-
-impl<T> Slot<T> {
-    pub fn new_occupied(version: u32, value: T) -> Self {
-        Occupied {
-            value,
-            version: version,
+impl BitCursor {
+    fn new(bits: &[u64], num_left: usize) -> Self {
+        Self {
+            front: 0,
+            front_word: bits.first().copied().unwrap_or(0),
+            back: bits.len().saturating_sub(1),
+            back_word: bits.last().copied().unwrap_or(0),
+            num_left,
         }
     }
 
-    pub fn new_vacant() -> Self {
-        Vacant
-    }
-
-    // Is this slot occupied?
     #[inline(always)]
-    pub fn occupied(&self) -> bool {
-        match self {
-            Occupied { .. } => true,
-            Vacant => false,
+    fn next(&mut self, bits: &[u64]) -> Option<usize> {
+        if self.num_left == 0 {
+            return None;
+        }
+        while self.front_word == 0 {
+            self.front += 1;
+            self.front_word = bits[self.front];
         }
+
+        let bit = self.front_word.trailing_zeros() as usize;
+        self.front_word &= self.front_word - 1;
+        self.num_left -= 1;
+        Some(self.front * BITS + bit)
     }
 
     #[inline(always)]
-    pub fn version(&self) -> u32 {
-        match self {
-            Occupied { version, .. } => *version,
-            Vacant => std::u32::MAX,
+    fn next_back(&mut self, bits: &[u64]) -> Option<usize> {
+        if self.num_left == 0 {
+            return None;
+        }
+        while self.back_word == 0 {
+            self.back -= 1;
+            self.back_word = bits[self.back];
         }
+
+        let bit = BITS - 1 - self.back_word.leading_zeros() as usize;
+        self.back_word &= !(1 << bit);
+        self.num_left -= 1;
+        Some(self.back * BITS + bit)
     }
 
-    pub unsafe fn get_unchecked(&self) -> &T {
-        match self {
-            Occupied { value, .. } => value,
-            Vacant => unreachable_unchecked(),
+    // Calls `f` with every remaining index in order, a word at a time.
+    #[inline(always)]
+    fn fold<B, F: FnMut(B, usize) -> B>(self, bits: &[u64], init: B, mut f: F) -> B {
+        let mut acc = init;
+        if self.num_left == 0 {
+            return acc;
         }
-    }
 
-    pub unsafe fn get_unchecked_mut(&mut self) -> &mut T {
-        match self {
-            Occupied { value, .. } => value,
-            Vacant => unreachable_unchecked(),
+        for (w, &bits_word) in bits.iter().enumerate().take(self.back + 1).skip(self.front) {
+            let mut word = match w {
+                _ if w == self.front && w == self.back => self.front_word & self.back_word,
+                _ if w == self.front => self.front_word,
+                _ if w == self.back => self.back_word,
+                _ => bits_word,
+            };
+            while word != 0 {
+                acc = f(acc, w * BITS + word.trailing_zeros() as usize);
+                word &= word - 1;
+            }
         }
+        acc
     }
+}
 
-    pub fn into_option(self) -> Option<T> {
-        match self {
-            Occupied { value, .. } => Some(value),
-            Vacant => None,
+#[inline(always)]
+fn is_set(bits: &[u64], idx: usize) -> bool {
+    bits.get(idx / BITS)
+        .map_or(false, |word| word & (1 << (idx % BITS)) != 0)
+}
+
+// Returns the index of the first set bit in `bits` at or after `idx`, or
+// `end` if there is none before it.
+#[inline(always)]
+fn next_set(bits: &[u64], mut idx: usize, end: usize) -> usize {
+    while idx < end {
+        let word = bits[idx / BITS] >> (idx % BITS);
+        if word != 0 {
+            return (idx + word.trailing_zeros() as usize).min(end);
         }
+        idx = (idx / BITS + 1) * BITS;
     }
+    end
 }
 
 /// Secondary map, associate data with previously stored elements in a slot map.
@@ -91,11 +132,14 @@ impl<T> Slot<T> {
 /// has happened!
 ///
 /// Finally a note on memory complexity, the [`SecondaryMap`] can use memory for
-/// each slot in the primary slot map, and has to iterate over every slot during
-/// iteration, regardless of whether you have inserted an associative value at
-/// that key or not. If you have some property that you only expect to set for a
-/// minority of keys, use a [`SparseSecondaryMap`](crate::SparseSecondaryMap),
-/// which is backed by a [`HashMap`](std::collections::HashMap).
+/// each slot in the primary slot map, regardless of whether you have inserted
+/// an associative value at that key or not. Occupancy is tracked in a bitmap
+/// stored apart from the versions and the values, so a slot costs a `u32`
+/// version and an uninitialized `V` without any tag or padding, and iteration
+/// skips 64 vacant slots at a time. If you have some property that you only
+/// expect to set for a minority of keys, use a
+/// [`SparseSecondaryMap`](crate::SparseSecondaryMap), which is backed by a
+/// [`HashMap`](std::collections::HashMap).
 ///
 /// Example usage:
 ///
@@ -117,9 +161,14 @@ impl<T> Slot<T> {
 /// health[bob] -= ammo[alice] * 3;
 /// ammo[alice] = 0;
 /// ```
-#[derive(Debug, Clone)]
 pub struct SecondaryMap<K: Key, V> {
-    slots: Vec<Slot<V>>,
+    // Bit i is set if slot i holds a value.
+    occupied: Vec<u64>,
+    // The version of the value in each occupied slot. Stale for vacant slots,
+    // so lookups check the bitmap as well.
+    versions: Vec<u32>,
+    // Initialized exactly for the occupied slots.
+    values: Vec<MaybeUninit<V>>,
     num_elems: usize,
     _k: PhantomData<fn(K) -> K>,
 }
@@ -152,10 +201,17 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// let mut sec: SecondaryMap<DefaultKey, i32> = SecondaryMap::with_capacity(sm.capacity());
     /// ```
     pub fn with_capacity(capacity: usize) -> Self {
-        let mut slots = Vec::with_capacity(capacity + 1); // Sentinel.
-        slots.push(Slot::new_vacant());
+        let mut occupied = Vec::with_capacity((capacity + BITS) / BITS);
+        let mut versions = Vec::with_capacity(capacity + 1);
+        let mut values = Vec::with_capacity(capacity + 1);
+        // Sentinel.
+        occupied.push(0);
+        versions.push(0);
+        values.push(MaybeUninit::uninit());
         Self {
-            slots,
+            occupied,
+            versions,
+            values,
             num_elems: 0,
             _k: PhantomData,
         }
@@ -202,7 +258,7 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// assert!(sec.capacity() >= 10);
     /// ```
     pub fn capacity(&self) -> usize {
-        self.slots.capacity() - 1 // Sentinel.
+        self.values.capacity().min(self.versions.capacity()) - 1 // Sentinel.
     }
 
     /// Sets the capacity of the [`SecondaryMap`] to `new_capacity`, if it is
@@ -228,9 +284,11 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// ```
     pub fn set_capacity(&mut self, new_capacity: usize) {
         let new_capacity = new_capacity + 1; // Sentinel.
-        if new_capacity > self.slots.capacity() {
-            let needed = new_capacity - self.slots.len();
-            self.slots.reserve(needed);
+        if new_capacity > self.capacity() + 1 {
+            let needed = new_capacity - self.values.len();
+            self.occupied.reserve(((new_capacity + BITS - 1) / BITS).saturating_sub(self.occupied.len()));
+            self.versions.reserve(needed);
+            self.values.reserve(needed);
         }
     }
 
@@ -255,9 +313,11 @@ impl<K: Key, V> SecondaryMap<K, V> {
     #[cfg_attr(all(nightly, doc), doc(cfg(feature = "unstable")))]
     pub fn try_set_capacity(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
         let new_capacity = new_capacity + 1; // Sentinel.
-        if new_capacity > self.slots.capacity() {
-            let needed = new_capacity - self.slots.len();
-            self.slots.try_reserve(needed)
+        if new_capacity > self.capacity() + 1 {
+            let needed = new_capacity - self.values.len();
+            self.occupied.try_reserve(((new_capacity + BITS - 1) / BITS).saturating_sub(self.occupied.len()))?;
+            self.versions.try_reserve(needed)?;
+            self.values.try_reserve(needed)
         } else {
             Ok(())
         }
@@ -278,9 +338,8 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// ```
     pub fn contains_key(&self, key: K) -> bool {
         let kd = key.data();
-        self.slots
-            .get(kd.index() as usize)
-            .map_or(false, |slot| slot.version() == kd.version())
+        let idx = kd.index() as usize;
+        self.versions.get(idx) == Some(&kd.version()) && self.is_occupied(idx)
     }
 
     /// Inserts a value into the secondary map at the given `key`. Can silently
@@ -309,25 +368,32 @@ impl<K: Key, V> SecondaryMap<K, V> {
         }
 
         let kd = key.data();
-        self.slots
-            .extend((self.slots.len()..=kd.index() as usize).map(|_| Slot::new_vacant()));
-
-        let slot = &mut self.slots[kd.index() as usize];
-        if slot.version() == kd.version() {
-            // Is always occupied.
-            return Some(replace(unsafe { slot.get_unchecked_mut() }, value));
-        }
+        let idx = kd.index() as usize;
+        self.grow(idx);
+
+        // The slot exists after growing.
+        let word = unsafe { self.occupied.get_unchecked_mut(idx / BITS) };
+        let bit = 1 << (idx % BITS);
+        let version = unsafe { self.versions.get_unchecked_mut(idx) };
+        let slot = unsafe { self.values.get_unchecked_mut(idx) };
+        if *word & bit != 0 {
+            let old = unsafe { slot.assume_init_mut() };
+            if *version == kd.version() {
+                return Some(replace(old, value));
+            }
 
-        if slot.occupied() {
             // Don't replace existing newer values.
-            if is_older_version(kd.version(), slot.version()) {
+            if is_older_version(kd.version(), *version) {
                 return None;
             }
+            *old = value;
         } else {
+            *slot = MaybeUninit::new(value);
+            *word |= bit;
             self.num_elems += 1;
         }
 
-        *slot = Slot::new_occupied(kd.version(), value);
+        *version = kd.version();
         None
     }
 
@@ -358,15 +424,11 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// assert!(!squared.contains_key(k)); // Old key is no longer available.
     /// ```
     pub fn remove(&mut self, key: K) -> Option<V> {
-        let kd = key.data();
-        if let Some(slot) = self.slots.get_mut(kd.index() as usize) {
-            if slot.version() == kd.version() {
-                self.num_elems -= 1;
-                return replace(slot, Slot::new_vacant()).into_option();
-            }
+        if !self.contains_key(key) {
+            return None;
         }
 
-        None
+        Some(unsafe { self.take(key.data().index() as usize) })
     }
 
     /// Retains only the elements specified by the predicate.
@@ -399,13 +461,63 @@ impl<K: Key, V> SecondaryMap<K, V> {
     where
         F: FnMut(K, &mut V) -> bool,
     {
-        for (i, slot) in self.slots.iter_mut().enumerate() {
-            if let Occupied { value, version } = slot {
-                let key = unsafe { key_data(i as u32, *version).into() };
-                if !f(key, value) {
-                    self.num_elems -= 1;
-                    *slot = Slot::new_vacant();
-                }
+        let mut cursor = BitCursor::new(&self.occupied, self.num_elems);
+        while let Some(idx) = cursor.next(&self.occupied) {
+            let key = unsafe { key_data(idx as u32, self.versions[idx]).into() };
+            let value = unsafe { self.values[idx].assume_init_mut() };
+            if !f(key, value) {
+                drop(unsafe { self.take(idx) });
+            }
+        }
+    }
+
+    /// Removes every entry whose key is no longer valid in `primary`, which
+    /// can be any slot map. Entries of removed keys otherwise stay in the
+    /// secondary map until their slot is reused by an insertion.
+    ///
+    /// This takes one pass over whichever of the two is shorter: if `primary`
+    /// has fewer elements, the live entries are found through its keys and the
+    /// others are dropped without looking them up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut sec = SecondaryMap::new();
+    /// let a = sm.insert(1);
+    /// let b = sm.insert(2);
+    /// sec.insert(a, "a");
+    /// sec.insert(b, "b");
+    ///
+    /// sm.remove(a);
+    /// assert_eq!(sec.len(), 2);
+    /// sec.retain_live(&sm);
+    /// assert_eq!(sec.keys().collect::<Vec<_>>(), [b]);
+    /// ```
+    pub fn retain_live<P: PrimaryMap<K>>(&mut self, primary: &P) {
+        if self.len() <= primary.len() {
+            self.retain(|key, _| primary.contains_key(key));
+            return;
+        }
+
+        let mut live = alloc::vec![0u64; self.occupied.len()];
+        for key in primary.keys() {
+            if self.contains_key(key) {
+                let idx = key.data().index() as usize;
+                live[idx / BITS] |= 1 << (idx % BITS);
+            }
+        }
+
+        let occupied = mem::replace(&mut self.occupied, live);
+        for (word_idx, (&word, &keep)) in occupied.iter().zip(&self.occupied).enumerate() {
+            let mut dead = word & !keep;
+            while dead != 0 {
+                let idx = word_idx * BITS + dead.trailing_zeros() as usize;
+                dead &= dead - 1;
+                self.num_elems -= 1;
+                // This is safe because the slot was occupied.
+                unsafe { self.values[idx].assume_init_drop() };
             }
         }
     }
@@ -457,7 +569,175 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// assert_eq!(v, vec![(k, 1)]);
     /// ```
     pub fn drain(&mut self) -> Drain<K, V> {
-        Drain { cur: 0, sm: self }
+        Drain {
+            cursor: BitCursor::new(&self.occupied, self.num_elems),
+            sm: self,
+        }
+    }
+
+    /// Moves all entries of `other` into the secondary map. When both maps
+    /// have an entry for the same slot, the entry with the newest key is kept,
+    /// just like [`insert`](Self::insert) would. For equal keys the value of
+    /// `other` replaces the current one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let old_b = sm.insert(());
+    /// sm.remove(old_b);
+    /// let b = sm.insert(());
+    ///
+    /// let mut sec = SecondaryMap::new();
+    /// sec.insert(a, 1);
+    /// sec.insert(b, 2);
+    /// let mut snapshot = SecondaryMap::new();
+    /// snapshot.insert(a, 10);
+    /// snapshot.insert(old_b, 20);
+    ///
+    /// sec.union_with(snapshot);
+    /// assert_eq!(sec[a], 10);
+    /// assert_eq!(sec[b], 2);
+    /// ```
+    pub fn union_with(&mut self, other: Self) {
+        self.merge(other, |ours, theirs| {
+            match is_older_version(theirs.0.data().version(), ours.0.data().version()) {
+                true => ours,
+                false => theirs,
+            }
+        });
+    }
+
+    /// Moves all entries of `other` into the secondary map. When both maps
+    /// have an entry for the same slot, `resolve` is called with the entry of
+    /// the secondary map and the entry of `other`, and returns the entry to
+    /// keep. [`union_with`](Self::union_with) resolves conflicts in favor of
+    /// the newest key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `resolve` returns a key of another slot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let b = sm.insert(());
+    ///
+    /// let mut hits = SecondaryMap::new();
+    /// hits.insert(a, 1);
+    /// let mut other = SecondaryMap::new();
+    /// other.insert(a, 2);
+    /// other.insert(b, 5);
+    ///
+    /// hits.merge(other, |(key, ours), (_, theirs)| (key, ours + theirs));
+    /// assert_eq!(hits[a], 3);
+    /// assert_eq!(hits[b], 5);
+    /// ```
+    pub fn merge<F>(&mut self, other: Self, mut resolve: F)
+    where
+        F: FnMut((K, V), (K, V)) -> (K, V),
+    {
+        for (key, value) in other {
+            let idx = key.data().index() as usize;
+            if !self.is_occupied(idx) {
+                self.insert(key, value);
+                continue;
+            }
+
+            // This is safe because we checked that the slot is occupied.
+            let ours = unsafe { (key_data(idx as u32, self.versions[idx]).into(), self.take(idx)) };
+            let (key, value) = resolve(ours, (key, value));
+            assert!(
+                key.data().index() as usize == idx,
+                "merge resolved to a key of another slot"
+            );
+            self.insert(key, value);
+        }
+    }
+
+    /// Retains only the entries whose key is also in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let b = sm.insert(());
+    ///
+    /// let mut sec = SecondaryMap::new();
+    /// sec.insert(a, "a");
+    /// sec.insert(b, "b");
+    /// let mut visible = SecondaryMap::new();
+    /// visible.insert(b, true);
+    ///
+    /// sec.intersect_with(&visible);
+    /// assert_eq!(sec.keys().collect::<Vec<_>>(), [b]);
+    /// ```
+    pub fn intersect_with<W>(&mut self, other: &SecondaryMap<K, W>) {
+        self.retain(|key, _| other.contains_key(key));
+    }
+
+    /// An iterator visiting the key-value pairs whose key is not in `other`,
+    /// in order of their slot index. The iterator element type is
+    /// `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let b = sm.insert(());
+    ///
+    /// let mut sec = SecondaryMap::new();
+    /// sec.insert(a, "a");
+    /// sec.insert(b, "b");
+    /// let mut hidden = SecondaryMap::new();
+    /// hidden.insert(b, ());
+    ///
+    /// assert_eq!(sec.difference(&hidden).collect::<Vec<_>>(), [(a, &"a")]);
+    /// ```
+    pub fn difference<'a, W>(&'a self, other: &'a SecondaryMap<K, W>) -> Difference<'a, K, V, W> {
+        Difference {
+            inner: self.iter(),
+            other,
+        }
+    }
+
+    /// An iterator visiting the key-value pairs of either map whose key is not
+    /// in the other map, first those of the secondary map and then those of
+    /// `other`. The iterator element type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let b = sm.insert(());
+    /// let c = sm.insert(());
+    ///
+    /// let mut before = SecondaryMap::new();
+    /// before.insert(a, 1);
+    /// before.insert(b, 2);
+    /// let mut after = SecondaryMap::new();
+    /// after.insert(b, 2);
+    /// after.insert(c, 3);
+    ///
+    /// let changed: Vec<_> = before.symmetric_difference(&after).collect();
+    /// assert_eq!(changed, [(a, &1), (c, &3)]);
+    /// ```
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, K, V> {
+        SymmetricDifference {
+            ours: self.difference(other),
+            theirs: other.difference(self),
+        }
     }
 
     /// Returns a reference to the value corresponding to the key.
@@ -475,11 +755,54 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// assert_eq!(sec.get(key), None);
     /// ```
     pub fn get(&self, key: K) -> Option<&V> {
-        let kd = key.data();
-        self.slots
-            .get(kd.index() as usize)
-            .filter(|slot| slot.version() == kd.version())
-            .map(|slot| unsafe { slot.get_unchecked() })
+        if !self.contains_key(key) {
+            return None;
+        }
+
+        Some(unsafe { self.values[key.data().index() as usize].assume_init_ref() })
+    }
+
+    // Returns the key whose value occupies the slot of `key`, which may be an
+    // older or newer version of `key`.
+    pub(crate) fn occupant(&self, key: K) -> Option<K> {
+        let idx = key.data().index();
+        if !self.is_occupied(idx as usize) {
+            return None;
+        }
+
+        Some(unsafe { key_data(idx, self.versions[idx as usize]).into() })
+    }
+
+    #[inline(always)]
+    fn is_occupied(&self, idx: usize) -> bool {
+        is_set(&self.occupied, idx)
+    }
+
+    // Ensures slot `idx` exists.
+    #[inline(always)]
+    fn grow(&mut self, idx: usize) {
+        if idx >= self.values.len() {
+            self.grow_to(idx + 1);
+        }
+    }
+
+    // Adds slots a bitmap word at a time.
+    #[cold]
+    #[inline(never)]
+    fn grow_to(&mut self, len: usize) {
+        let len = (len + BITS - 1) / BITS * BITS;
+        self.occupied.resize(len / BITS, 0);
+        self.versions.resize(len, 0);
+        self.values.resize_with(len, MaybeUninit::uninit);
+    }
+
+    // Moves the value out of slot `idx`, which must be occupied, and marks it
+    // vacant.
+    unsafe fn take(&mut self, idx: usize) -> V {
+        debug_assert!(self.is_occupied(idx));
+        self.occupied[idx / BITS] &= !(1 << (idx % BITS));
+        self.num_elems -= 1;
+        self.values[idx].assume_init_read()
     }
 
     /// Returns a reference to the value corresponding to the key without
@@ -504,8 +827,7 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// ```
     pub unsafe fn get_unchecked(&self, key: K) -> &V {
         debug_assert!(self.contains_key(key));
-        let slot = self.slots.get_unchecked(key.data().index() as usize);
-        slot.get_unchecked()
+        self.values.get_unchecked(key.data().index() as usize).assume_init_ref()
     }
 
     /// Returns a mutable reference to the value corresponding to the key.
@@ -524,11 +846,11 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// assert_eq!(sec[key], 6.5);
     /// ```
     pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
-        let kd = key.data();
-        self.slots
-            .get_mut(kd.index() as usize)
-            .filter(|slot| slot.version() == kd.version())
-            .map(|slot| unsafe { slot.get_unchecked_mut() })
+        if !self.contains_key(key) {
+            return None;
+        }
+
+        Some(unsafe { self.values[key.data().index() as usize].assume_init_mut() })
     }
 
     /// Returns a mutable reference to the value corresponding to the key
@@ -554,8 +876,19 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// ```
     pub unsafe fn get_unchecked_mut(&mut self, key: K) -> &mut V {
         debug_assert!(self.contains_key(key));
-        let slot = self.slots.get_unchecked_mut(key.data().index() as usize);
-        slot.get_unchecked_mut()
+        self.values.get_unchecked_mut(key.data().index() as usize).assume_init_mut()
+    }
+
+    // Like `contains_key` and `get_unchecked_mut`, but through a raw pointer
+    // and without creating a reference to the values, so that references
+    // previously handed out to other values stay valid. Used by mutable joins.
+    pub(crate) unsafe fn raw_contains_key(this: *const Self, key: K) -> bool {
+        // The versions are stored apart from the values.
+        (*this).contains_key(key)
+    }
+
+    pub(crate) unsafe fn raw_get_unchecked_mut(this: *mut Self, key: K) -> *mut V {
+        (*this).values.as_mut_ptr().add(key.data().index() as usize).cast()
     }
 
     /// Returns mutable references to the values corresponding to the given
@@ -586,42 +919,26 @@ impl<K: Key, V> SecondaryMap<K, V> {
         // safe because the type we are claiming to have initialized here is a
         // bunch of `MaybeUninit`s, which do not require initialization.
         let mut ptrs: [MaybeUninit<*mut V>; N] = unsafe { MaybeUninit::uninit().assume_init() };
-        let mut slot_versions: [MaybeUninit<u32>; N] =
-            unsafe { MaybeUninit::uninit().assume_init() };
 
         let mut i = 0;
         while i < N {
-            let kd = keys[i].data();
-
-            match self.slots.get_mut(kd.index() as usize) {
-                Some(Occupied { version, value }) if *version == kd.version() => {
-                    // This key is valid, and the slot is occupied. Temporarily
-                    // set the version to 2 so duplicate keys would show up as
-                    // invalid, since keys always have an odd version. This
-                    // gives us a linear time disjointness check.
-                    ptrs[i] = MaybeUninit::new(&mut *value);
-                    slot_versions[i] = MaybeUninit::new(*version);
-					// 暂时设置为u32的最大值， 因此，如果真的有一个版本号u32最大值的key，这里实际上存在bug，无法正确比较key是否相交
-                    *version = std::u32::MAX;
-                },
-
-                _ => break,
+            let idx = keys[i].data().index() as usize;
+            if !self.contains_key(keys[i]) || !self.is_occupied(idx) {
+                break;
             }
 
+            // This key is valid, and the slot is occupied. Temporarily mark
+            // the slot vacant so duplicate keys would show up as invalid.
+            // This gives us a linear time disjointness check.
+            ptrs[i] = MaybeUninit::new(self.values[idx].as_mut_ptr());
+            self.occupied[idx / BITS] ^= 1 << (idx % BITS);
             i += 1;
         }
 
-        // Undo temporary unoccupied markings.
-        for j in 0..i {
-            let idx = keys[j].data().index() as usize;
-            unsafe {
-                match self.slots.get_mut(idx) {
-                    Some(Occupied { version, .. }) => {
-                        *version = slot_versions[j].assume_init();
-                    },
-                    _ => unreachable_unchecked(),
-                }
-            }
+        // Undo temporary vacant markings.
+        for key in &keys[..i] {
+            let idx = key.data().index() as usize;
+            self.occupied[idx / BITS] ^= 1 << (idx % BITS);
         }
 
         if i == N {
@@ -690,8 +1007,10 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// ```
     pub fn iter(&self) -> Iter<K, V> {
         Iter {
-            num_left: self.num_elems,
-            slots: self.slots.iter().enumerate(),
+            cursor: BitCursor::new(&self.occupied, self.num_elems),
+            occupied: &self.occupied,
+            versions: &self.versions,
+            values: &self.values,
             _k: PhantomData,
         }
     }
@@ -725,8 +1044,10 @@ impl<K: Key, V> SecondaryMap<K, V> {
     /// ```
     pub fn iter_mut(&mut self) -> IterMut<K, V> {
         IterMut {
-            num_left: self.num_elems,
-            slots: self.slots.iter_mut().enumerate(),
+            cursor: BitCursor::new(&self.occupied, self.num_elems),
+            occupied: &self.occupied,
+            versions: &self.versions,
+            values: &mut self.values,
             _k: PhantomData,
         }
     }
@@ -806,6 +1127,127 @@ impl<K: Key, V> SecondaryMap<K, V> {
         }
     }
 
+    /// An iterator visiting the key-value pairs stored in the slots with an
+    /// index in `range`, in slot order. The iterator element type is
+    /// `(K, &'a V)`.
+    ///
+    /// The slot index of a key is `key.data().index()`. Out of bounds parts of
+    /// `range` are ignored. Use [`Range::position`] to find where to resume
+    /// iteration later, even if the secondary map was modified in the
+    /// meantime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut sec = SecondaryMap::new();
+    /// let k0 = sm.insert(0); sec.insert(k0, 10);
+    /// let k1 = sm.insert(1); sec.insert(k1, 11);
+    /// let k2 = sm.insert(2); sec.insert(k2, 12);
+    ///
+    /// let idx = k1.data().index() as usize;
+    /// let v: Vec<_> = sec.range(idx..).collect();
+    /// assert_eq!(v, vec![(k1, &11), (k2, &12)]);
+    /// ```
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> Range<K, V> {
+        let (start, end) = slot_range(range, self.values.len());
+        Range {
+            cur: start,
+            end,
+            occupied: &self.occupied,
+            versions: &self.versions,
+            values: &self.values,
+            _k: PhantomData,
+        }
+    }
+
+    /// An iterator visiting the key-value pairs stored in the slots with an
+    /// index in `range`, in slot order, with mutable references to the values.
+    /// The iterator element type is `(K, &'a mut V)`.
+    ///
+    /// See [`range`](Self::range) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut sec = SecondaryMap::new();
+    /// let k0 = sm.insert(0); sec.insert(k0, 10);
+    /// let k1 = sm.insert(1); sec.insert(k1, 20);
+    ///
+    /// let idx = k1.data().index() as usize;
+    /// sec.range_mut(idx..=idx).for_each(|(_, v)| *v *= -1);
+    /// assert_eq!(sec[k0], 10);
+    /// assert_eq!(sec[k1], -20);
+    /// ```
+    pub fn range_mut<R: RangeBounds<usize>>(&mut self, range: R) -> RangeMut<K, V> {
+        let (start, end) = slot_range(range, self.values.len());
+        RangeMut {
+            cur: start,
+            end,
+            occupied: &self.occupied,
+            versions: &self.versions,
+            values: &mut self.values,
+            _k: PhantomData,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs stored at or after slot index
+    /// `position`, in slot order. The iterator element type is `(K, &'a V)`.
+    ///
+    /// This allows processing a secondary map in chunks, resuming where the
+    /// previous chunk left off through [`Range::position`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut sec = SecondaryMap::new();
+    /// for i in 0..4 {
+    ///     sec.insert(sm.insert(i), i);
+    /// }
+    ///
+    /// let mut it = sec.iter_from(0);
+    /// let first: Vec<_> = it.by_ref().take(2).map(|(_, v)| *v).collect();
+    /// let rest: Vec<_> = sec.iter_from(it.position()).map(|(_, v)| *v).collect();
+    /// assert_eq!(first, vec![0, 1]);
+    /// assert_eq!(rest, vec![2, 3]);
+    /// ```
+    pub fn iter_from(&self, position: usize) -> Range<K, V> {
+        self.range(position..)
+    }
+
+    /// An iterator visiting all key-value pairs stored at or after slot index
+    /// `position`, in slot order, with mutable references to the values. The
+    /// iterator element type is `(K, &'a mut V)`.
+    ///
+    /// See [`iter_from`](Self::iter_from) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut sec = SecondaryMap::new();
+    /// for i in 0..4 {
+    ///     sec.insert(sm.insert(i), i);
+    /// }
+    ///
+    /// let mut it = sec.iter_mut_from(0);
+    /// it.by_ref().take(2).for_each(|(_, v)| *v *= 10);
+    /// let position = it.position();
+    /// sec.iter_mut_from(position).for_each(|(_, v)| *v *= 100);
+    ///
+    /// let values: Vec<_> = sec.values().copied().collect();
+    /// assert_eq!(values, vec![0, 10, 200, 300]);
+    /// ```
+    pub fn iter_mut_from(&mut self, position: usize) -> RangeMut<K, V> {
+        self.range_mut(position..)
+    }
+
     /// Gets the given key's corresponding [`Entry`] in the map for in-place
     /// manipulation. May return [`None`] if the key was removed from the
     /// originating slot map.
@@ -829,17 +1271,23 @@ impl<K: Key, V> SecondaryMap<K, V> {
 
         // Ensure the slot exists so the Entry implementation can safely assume
         // the slot always exists without checking.
-        self.slots
-            .extend((self.slots.len()..=kd.index() as usize).map(|_| Slot::new_vacant()));
+        self.grow(kd.index() as usize);
 
-        let slot = unsafe { self.slots.get_unchecked(kd.index() as usize) };
-        if kd.version() == slot.version() {
+        let idx = kd.index() as usize;
+        let version = self.versions[idx];
+        if !self.is_occupied(idx) {
+            Some(Entry::Vacant(VacantEntry {
+                map: self,
+                kd,
+                _k: PhantomData,
+            }))
+        } else if kd.version() == version {
             Some(Entry::Occupied(OccupiedEntry {
                 map: self,
                 kd,
                 _k: PhantomData,
             }))
-        } else if is_older_version(kd.version(), slot.version()) {
+        } else if is_older_version(kd.version(), version) {
             None
         } else {
             Some(Entry::Vacant(VacantEntry {
@@ -857,6 +1305,39 @@ impl<K: Key, V> Default for SecondaryMap<K, V> {
     }
 }
 
+impl<K: Key, V: Clone> Clone for SecondaryMap<K, V> {
+    fn clone(&self) -> Self {
+        let values = (0..self.values.len())
+            .map(|idx| match self.is_occupied(idx) {
+                true => MaybeUninit::new(unsafe { self.values[idx].assume_init_ref() }.clone()),
+                false => MaybeUninit::uninit(),
+            })
+            .collect();
+
+        Self {
+            occupied: self.occupied.clone(),
+            versions: self.versions.clone(),
+            values,
+            num_elems: self.num_elems,
+            _k: PhantomData,
+        }
+    }
+}
+
+impl<K: Key, V> Drop for SecondaryMap<K, V> {
+    fn drop(&mut self) {
+        if mem::needs_drop::<V>() {
+            self.drain();
+        }
+    }
+}
+
+impl<K: Key, V: fmt::Debug> fmt::Debug for SecondaryMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
 impl<K: Key, V> Index<K> for SecondaryMap<K, V> {
     type Output = V;
 
@@ -1222,12 +1703,7 @@ impl<'a, K: Key, V> OccupiedEntry<'a, K, V> {
     /// }
     /// ```
     pub fn remove(self) -> V {
-        let slot = unsafe { self.map.slots.get_unchecked_mut(self.kd.index() as usize) };
-        self.map.num_elems -= 1;
-        match replace(slot, Slot::new_vacant()) {
-            Occupied { value, .. } => value,
-            Vacant => unsafe { unreachable_unchecked() },
-        }
+        unsafe { self.map.take(self.kd.index() as usize) }
     }
 }
 
@@ -1274,14 +1750,18 @@ impl<'a, K: Key, V> VacantEntry<'a, K, V> {
     /// }
     /// ```
     pub fn insert(self, value: V) -> &'a mut V {
-        let slot = unsafe { self.map.slots.get_unchecked_mut(self.kd.index() as usize) };
+        let idx = self.kd.index() as usize;
+        let map = self.map;
         // Despite the slot being considered Vacant for this entry, it might be occupied
         // with an outdated element.
-        match replace(slot, Slot::new_occupied(self.kd.version(), value)) {
-            Occupied { .. } => {},
-            Vacant => self.map.num_elems += 1,
+        if map.is_occupied(idx) {
+            drop(unsafe { map.take(idx) });
         }
-        unsafe { slot.get_unchecked_mut() }
+        map.values[idx] = MaybeUninit::new(value);
+        map.versions[idx] = self.kd.version();
+        map.occupied[idx / BITS] |= 1 << (idx % BITS);
+        map.num_elems += 1;
+        unsafe { map.values[idx].assume_init_mut() }
     }
 }
 
@@ -1292,7 +1772,7 @@ impl<'a, K: Key, V> VacantEntry<'a, K, V> {
 #[derive(Debug)]
 pub struct Drain<'a, K: Key + 'a, V: 'a> {
     sm: &'a mut SecondaryMap<K, V>,
-    cur: usize,
+    cursor: BitCursor,
 }
 
 /// An iterator that moves key-value pairs out of a [`SecondaryMap`].
@@ -1301,8 +1781,10 @@ pub struct Drain<'a, K: Key + 'a, V: 'a> {
 /// provided by the [`IntoIterator`] trait.
 #[derive(Debug)]
 pub struct IntoIter<K: Key, V> {
-    num_left: usize,
-    slots: Enumerate<alloc::vec::IntoIter<Slot<V>>>,
+    cursor: BitCursor,
+    occupied: Vec<u64>,
+    versions: Vec<u32>,
+    values: Vec<MaybeUninit<V>>,
     _k: PhantomData<fn(K) -> K>,
 }
 
@@ -1311,16 +1793,20 @@ pub struct IntoIter<K: Key, V> {
 /// This iterator is created by [`SecondaryMap::iter`].
 #[derive(Debug)]
 pub struct Iter<'a, K: Key + 'a, V: 'a> {
-    num_left: usize,
-    slots: Enumerate<core::slice::Iter<'a, Slot<V>>>,
+    cursor: BitCursor,
+    occupied: &'a [u64],
+    versions: &'a [u32],
+    values: &'a [MaybeUninit<V>],
     _k: PhantomData<fn(K) -> K>,
 }
 
 impl<'a, K: 'a + Key, V: 'a> Clone for Iter<'a, K, V> {
     fn clone(&self) -> Self {
         Iter {
-            num_left: self.num_left,
-            slots: self.slots.clone(),
+            cursor: self.cursor.clone(),
+            occupied: self.occupied,
+            versions: self.versions,
+            values: self.values,
             _k: self._k,
         }
     }
@@ -1331,8 +1817,10 @@ impl<'a, K: 'a + Key, V: 'a> Clone for Iter<'a, K, V> {
 /// This iterator is created by [`SecondaryMap::iter_mut`].
 #[derive(Debug)]
 pub struct IterMut<'a, K: Key + 'a, V: 'a> {
-    num_left: usize,
-    slots: Enumerate<core::slice::IterMut<'a, Slot<V>>>,
+    cursor: BitCursor,
+    occupied: &'a [u64],
+    versions: &'a [u32],
+    values: &'a mut [MaybeUninit<V>],
     _k: PhantomData<fn(K) -> K>,
 }
 
@@ -1376,21 +1864,110 @@ pub struct ValuesMut<'a, K: Key + 'a, V: 'a> {
     inner: IterMut<'a, K, V>,
 }
 
+/// An iterator over the key-value pairs in a range of slots of a
+/// [`SecondaryMap`].
+///
+/// This iterator is created by [`SecondaryMap::range`] and
+/// [`SecondaryMap::iter_from`].
+#[derive(Debug)]
+pub struct Range<'a, K: Key + 'a, V: 'a> {
+    cur: usize,
+    end: usize,
+    occupied: &'a [u64],
+    versions: &'a [u32],
+    values: &'a [MaybeUninit<V>],
+    _k: PhantomData<fn(K) -> K>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Range<'a, K, V> {
+    fn clone(&self) -> Self {
+        Range {
+            cur: self.cur,
+            end: self.end,
+            occupied: self.occupied,
+            versions: self.versions,
+            values: self.values,
+            _k: self._k,
+        }
+    }
+}
+
+impl<'a, K: 'a + Key, V: 'a> Range<'a, K, V> {
+    /// Returns the slot index from which iteration continues. Pass it to
+    /// [`SecondaryMap::iter_from`] to resume iterating later.
+    pub fn position(&self) -> usize {
+        self.cur
+    }
+}
+
+/// A mutable iterator over the key-value pairs in a range of slots of a
+/// [`SecondaryMap`].
+///
+/// This iterator is created by [`SecondaryMap::range_mut`] and
+/// [`SecondaryMap::iter_mut_from`].
+#[derive(Debug)]
+pub struct RangeMut<'a, K: Key + 'a, V: 'a> {
+    cur: usize,
+    end: usize,
+    occupied: &'a [u64],
+    versions: &'a [u32],
+    values: &'a mut [MaybeUninit<V>],
+    _k: PhantomData<fn(K) -> K>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> RangeMut<'a, K, V> {
+    /// Returns the slot index from which iteration continues. Pass it to
+    /// [`SecondaryMap::iter_mut_from`] to resume iterating later.
+    pub fn position(&self) -> usize {
+        self.cur
+    }
+}
+
+/// An iterator over the key-value pairs of a [`SecondaryMap`] whose key is
+/// not in another map.
+///
+/// This iterator is created by [`SecondaryMap::difference`].
+#[derive(Debug)]
+pub struct Difference<'a, K: Key + 'a, V: 'a, W: 'a> {
+    inner: Iter<'a, K, V>,
+    other: &'a SecondaryMap<K, W>,
+}
+
+impl<'a, K: 'a + Key, V: 'a, W: 'a> Clone for Difference<'a, K, V, W> {
+    fn clone(&self) -> Self {
+        Difference {
+            inner: self.inner.clone(),
+            other: self.other,
+        }
+    }
+}
+
+/// An iterator over the key-value pairs of two [`SecondaryMap`]s whose key is
+/// in only one of them.
+///
+/// This iterator is created by [`SecondaryMap::symmetric_difference`].
+#[derive(Debug)]
+pub struct SymmetricDifference<'a, K: Key + 'a, V: 'a> {
+    ours: Difference<'a, K, V, V>,
+    theirs: Difference<'a, K, V, V>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for SymmetricDifference<'a, K, V> {
+    fn clone(&self) -> Self {
+        SymmetricDifference {
+            ours: self.ours.clone(),
+            theirs: self.theirs.clone(),
+        }
+    }
+}
+
 impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
     type Item = (K, V);
 
     fn next(&mut self) -> Option<(K, V)> {
-        while let Some(slot) = self.sm.slots.get_mut(self.cur) {
-            let idx = self.cur;
-            self.cur += 1;
-            if let Occupied { value, version } = replace(slot, Slot::new_vacant()) {
-                self.sm.num_elems -= 1;
-                let key = unsafe { key_data(idx as u32, version).into() };
-                return Some((key, value));
-            }
-        }
-
-        None
+        let idx = self.cursor.next(&self.sm.occupied)?;
+        let key = unsafe { key_data(idx as u32, self.sm.versions[idx]).into() };
+        Some((key, unsafe { self.sm.take(idx) }))
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -1398,29 +1975,51 @@ impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V> DoubleEndedIterator for Drain<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        let idx = self.cursor.next_back(&self.sm.occupied)?;
+        let key = unsafe { key_data(idx as u32, self.sm.versions[idx]).into() };
+        Some((key, unsafe { self.sm.take(idx) }))
+    }
+}
+
 impl<'a, K: Key, V> Drop for Drain<'a, K, V> {
     fn drop(&mut self) {
         self.for_each(|_drop| {});
     }
 }
 
+impl<K: Key, V> IntoIter<K, V> {
+    // Moves the value out of slot `idx`, which the cursor just passed.
+    fn take(&mut self, idx: usize) -> (K, V) {
+        let key = unsafe { key_data(idx as u32, self.versions[idx]).into() };
+        (key, unsafe { self.values[idx].assume_init_read() })
+    }
+}
+
 impl<K: Key, V> Iterator for IntoIter<K, V> {
     type Item = (K, V);
 
     fn next(&mut self) -> Option<(K, V)> {
-        while let Some((idx, mut slot)) = self.slots.next() {
-            if let Occupied { value, version } = replace(&mut slot, Slot::new_vacant()) {
-                self.num_left -= 1;
-                let key = unsafe { key_data(idx as u32, version).into() };
-                return Some((key, value));
-            }
-        }
-
-        None
+        let idx = self.cursor.next(&self.occupied)?;
+        Some(self.take(idx))
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.num_left, Some(self.num_left))
+        (self.cursor.num_left, Some(self.cursor.num_left))
+    }
+}
+
+impl<K: Key, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        let idx = self.cursor.next_back(&self.occupied)?;
+        Some(self.take(idx))
+    }
+}
+
+impl<K: Key, V> Drop for IntoIter<K, V> {
+    fn drop(&mut self) {
+        self.for_each(|_drop| {});
     }
 }
 
@@ -1428,19 +2027,45 @@ impl<'a, K: Key, V> Iterator for Iter<'a, K, V> {
     type Item = (K, &'a V);
 
     fn next(&mut self) -> Option<(K, &'a V)> {
-        while let Some((idx, slot)) = self.slots.next() {
-            if let Occupied { value, version } = slot {
-                self.num_left -= 1;
-                let key = unsafe { key_data(idx as u32, *version).into() };
-                return Some((key, value));
-            }
+        let idx = self.cursor.next(self.occupied)?;
+        // The cursor only yields occupied slots, which exist.
+        unsafe {
+            let key = key_data(idx as u32, *self.versions.get_unchecked(idx)).into();
+            Some((key, self.values.get_unchecked(idx).assume_init_ref()))
         }
-
-        None
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.num_left, Some(self.num_left))
+        (self.cursor.num_left, Some(self.cursor.num_left))
+    }
+
+    fn fold<B, F: FnMut(B, Self::Item) -> B>(self, init: B, mut f: F) -> B {
+        let (versions, values) = (self.versions, self.values);
+        self.cursor.fold(self.occupied, init, |acc, idx| unsafe {
+            let key = key_data(idx as u32, *versions.get_unchecked(idx)).into();
+            f(acc, (key, values.get_unchecked(idx).assume_init_ref()))
+        })
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a V)> {
+        let idx = self.cursor.next_back(self.occupied)?;
+        let key = unsafe { key_data(idx as u32, self.versions[idx]).into() };
+        Some((key, unsafe { self.values[idx].assume_init_ref() }))
+    }
+}
+
+impl<'a, K: Key, V> IterMut<'a, K, V> {
+    // Unsafe necessary because Rust can't deduce that we won't return multiple
+    // references to the same value.
+    fn value_ref(&mut self, idx: usize) -> (K, &'a mut V) {
+        let key = unsafe { key_data(idx as u32, self.versions[idx]).into() };
+        let value_ref = unsafe {
+            let ptr: *mut V = self.values[idx].as_mut_ptr();
+            &mut *ptr
+        };
+        (key, value_ref)
     }
 }
 
@@ -1448,19 +2073,24 @@ impl<'a, K: Key, V> Iterator for IterMut<'a, K, V> {
     type Item = (K, &'a mut V);
 
     fn next(&mut self) -> Option<(K, &'a mut V)> {
-        while let Some((idx, slot)) = self.slots.next() {
-            if let Occupied { value, version } = slot {
-                let key = unsafe { key_data(idx as u32, *version).into() };
-                self.num_left -= 1;
-                return Some((key, value));
-            }
-        }
-
-        None
+        let idx = self.cursor.next(self.occupied)?;
+        Some(self.value_ref(idx))
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.num_left, Some(self.num_left))
+        (self.cursor.num_left, Some(self.cursor.num_left))
+    }
+
+    fn fold<B, F: FnMut(B, Self::Item) -> B>(mut self, init: B, mut f: F) -> B {
+        let (cursor, occupied) = (self.cursor.clone(), self.occupied);
+        cursor.fold(occupied, init, |acc, idx| f(acc, self.value_ref(idx)))
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a mut V)> {
+        let idx = self.cursor.next_back(self.occupied)?;
+        Some(self.value_ref(idx))
     }
 }
 
@@ -1474,6 +2104,16 @@ impl<'a, K: Key, V> Iterator for Keys<'a, K, V> {
     fn size_hint(&self) -> (usize, Option<usize>) {
         self.inner.size_hint()
     }
+
+    fn fold<B, F: FnMut(B, K) -> B>(self, init: B, mut f: F) -> B {
+        self.inner.fold(init, move |acc, (key, _)| f(acc, key))
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<K> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
 }
 
 impl<'a, K: Key, V> Iterator for Values<'a, K, V> {
@@ -1486,6 +2126,16 @@ impl<'a, K: Key, V> Iterator for Values<'a, K, V> {
     fn size_hint(&self) -> (usize, Option<usize>) {
         self.inner.size_hint()
     }
+
+    fn fold<B, F: FnMut(B, &'a V) -> B>(self, init: B, mut f: F) -> B {
+        self.inner.fold(init, move |acc, (_, value)| f(acc, value))
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
 }
 
 impl<'a, K: Key, V> Iterator for ValuesMut<'a, K, V> {
@@ -1498,6 +2148,88 @@ impl<'a, K: Key, V> Iterator for ValuesMut<'a, K, V> {
     fn size_hint(&self) -> (usize, Option<usize>) {
         self.inner.size_hint()
     }
+
+    fn fold<B, F: FnMut(B, &'a mut V) -> B>(self, init: B, mut f: F) -> B {
+        self.inner.fold(init, move |acc, (_, value)| f(acc, value))
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Range<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        let idx = next_set(self.occupied, self.cur, self.end);
+        self.cur = (idx + 1).min(self.end);
+        if idx == self.end {
+            return None;
+        }
+
+        let key = unsafe { key_data(idx as u32, self.versions[idx]).into() };
+        Some((key, unsafe { self.values[idx].assume_init_ref() }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.cur))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for RangeMut<'a, K, V> {
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<(K, &'a mut V)> {
+        let idx = next_set(self.occupied, self.cur, self.end);
+        self.cur = (idx + 1).min(self.end);
+        if idx == self.end {
+            return None;
+        }
+
+        let key = unsafe { key_data(idx as u32, self.versions[idx]).into() };
+
+        // Unsafe necessary because Rust can't deduce that we won't
+        // return multiple references to the same value.
+        let value_ref = unsafe {
+            let ptr: *mut V = self.values[idx].as_mut_ptr();
+            &mut *ptr
+        };
+        Some((key, value_ref))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.cur))
+    }
+}
+
+impl<'a, K: Key, V, W> Iterator for Difference<'a, K, V, W> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        let other = self.other;
+        self.inner.find(|&(key, _)| !other.contains_key(key))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
+}
+
+impl<'a, K: Key, V> Iterator for SymmetricDifference<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        self.ours.next().or_else(|| self.theirs.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let ours = self.ours.size_hint().1;
+        let theirs = self.theirs.size_hint().1;
+        (0, ours.zip(theirs).map(|(a, b)| a + b))
+    }
 }
 
 impl<'a, K: Key, V> IntoIterator for &'a SecondaryMap<K, V> {
@@ -1522,13 +2254,16 @@ impl<K: Key, V> IntoIterator for SecondaryMap<K, V> {
     type Item = (K, V);
     type IntoIter = IntoIter<K, V>;
 
-    fn into_iter(self) -> Self::IntoIter {
-        let len = self.len();
-        let mut it = self.slots.into_iter().enumerate();
-        it.next(); // Skip sentinel.
+    fn into_iter(mut self) -> Self::IntoIter {
+        // The values are moved to the iterator, leaving nothing for the map
+        // to drop.
+        let len = replace(&mut self.num_elems, 0);
+        let occupied = mem::take(&mut self.occupied);
         IntoIter {
-            num_left: len,
-            slots: it,
+            cursor: BitCursor::new(&occupied, len),
+            occupied,
+            versions: mem::take(&mut self.versions),
+            values: mem::take(&mut self.values),
             _k: PhantomData,
         }
     }
@@ -1541,6 +2276,10 @@ impl<'a, K: Key, V> FusedIterator for Values<'a, K, V> {}
 impl<'a, K: Key, V> FusedIterator for ValuesMut<'a, K, V> {}
 impl<'a, K: Key, V> FusedIterator for Drain<'a, K, V> {}
 impl<K: Key, V> FusedIterator for IntoIter<K, V> {}
+impl<'a, K: Key, V> FusedIterator for Range<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for RangeMut<'a, K, V> {}
+impl<'a, K: Key, V, W> FusedIterator for Difference<'a, K, V, W> {}
+impl<'a, K: Key, V> FusedIterator for SymmetricDifference<'a, K, V> {}
 
 impl<'a, K: Key, V> ExactSizeIterator for Iter<'a, K, V> {}
 impl<'a, K: Key, V> ExactSizeIterator for IterMut<'a, K, V> {}
@@ -1550,62 +2289,153 @@ impl<'a, K: Key, V> ExactSizeIterator for ValuesMut<'a, K, V> {}
 impl<'a, K: Key, V> ExactSizeIterator for Drain<'a, K, V> {}
 impl<K: Key, V> ExactSizeIterator for IntoIter<K, V> {}
 
-// Serialization with serde.
-#[cfg(feature = "serde")]
-mod serialize {
-    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+// Parallel iteration with rayon.
+#[cfg(feature = "rayon")]
+mod parallel {
+    use rayon::prelude::*;
 
     use super::*;
+    use crate::par::{ParBits, ParBitsMut};
+
+    impl<K: Key, V> SecondaryMap<K, V> {
+        /// A parallel iterator visiting all key-value pairs in arbitrary order.
+        /// The work is split such that every thread gets about the same number
+        /// of elements, regardless of how the vacant slots are spread out.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// let mut sec = SecondaryMap::new();
+        /// for i in 0..1000u64 {
+        ///     sec.insert(sm.insert(()), i);
+        /// }
+        /// let sum: u64 = sec.par_iter().map(|(_, &v)| v).sum();
+        /// assert_eq!(sum, 500 * 999);
+        /// ```
+        pub fn par_iter(&self) -> impl ParallelIterator<Item = (K, &V)>
+        where
+            K: Send,
+            V: Sync,
+        {
+            let versions = &self.versions;
+            ParBits::new(&self.occupied, &self.values).map(move |(idx, value)| {
+                let key = unsafe { key_data(idx as u32, versions[idx]).into() };
+                (key, unsafe { value.assume_init_ref() })
+            })
+        }
 
-    #[derive(Serialize, Deserialize)]
-    struct SerdeSlot<T> {
-        value: Option<T>,
-        version: u32,
-    }
+        /// A parallel iterator visiting all key-value pairs in arbitrary order,
+        /// with mutable references to the values.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// let mut sec = SecondaryMap::new();
+        /// for i in 0..1000u64 {
+        ///     sec.insert(sm.insert(()), i);
+        /// }
+        /// sec.par_iter_mut().for_each(|(_, v)| *v *= 2);
+        /// assert_eq!(sec.values().sum::<u64>(), 1000 * 999);
+        /// ```
+        pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (K, &mut V)>
+        where
+            K: Send,
+            V: Send,
+        {
+            let versions = &self.versions;
+            ParBitsMut::new(&self.occupied, &mut self.values).map(move |(idx, value)| {
+                let key = unsafe { key_data(idx as u32, versions[idx]).into() };
+                (key, unsafe { value.assume_init_mut() })
+            })
+        }
 
-    impl<T: Serialize> Serialize for Slot<T> {
-        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        /// A parallel iterator visiting all keys in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// let mut sec = SecondaryMap::new();
+        /// for i in 0..1000u64 {
+        ///     sec.insert(sm.insert(()), i);
+        /// }
+        /// assert_eq!(sec.par_keys().count(), 1000);
+        /// ```
+        pub fn par_keys(&self) -> impl ParallelIterator<Item = K> + '_
         where
-            S: Serializer,
+            K: Send,
+            V: Sync,
         {
-            let serde_slot = SerdeSlot {
-                version: self.version(),
-                value: match self {
-                    Occupied { value, .. } => Some(value),
-                    Vacant => None,
-                },
-            };
-            serde_slot.serialize(serializer)
+            self.par_iter().map(|(key, _)| key)
         }
-    }
 
-    impl<'de, T> Deserialize<'de> for Slot<T>
-    where
-        T: Deserialize<'de>,
-    {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        /// A parallel iterator visiting all values mutably in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// let mut sec = SecondaryMap::new();
+        /// for i in 0..1000u64 {
+        ///     sec.insert(sm.insert(()), i);
+        /// }
+        /// sec.par_values_mut().for_each(|v| *v += 1);
+        /// assert_eq!(sec.values().sum::<u64>(), 500 * 1001);
+        /// ```
+        pub fn par_values_mut(&mut self) -> impl ParallelIterator<Item = &mut V>
         where
-            D: Deserializer<'de>,
+            K: Send,
+            V: Send,
         {
-            let serde_slot: SerdeSlot<T> = Deserialize::deserialize(deserializer)?;
-            // let occupied = serde_slot.version % 2 == 1;
-            // if occupied ^ serde_slot.value.is_some() {
-            //     return Err(de::Error::custom(&"inconsistent occupation in Slot"));
-            // }
-
-            Ok(match serde_slot.value {
-                Some(value) => Self::new_occupied(serde_slot.version, value),
-                None => Self::new_vacant(),
-            })
+            self.par_iter_mut().map(|(_, value)| value)
         }
     }
+}
+
+// Serialization with serde.
+#[cfg(feature = "serde")]
+mod serialize {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct SerdeSlot<T> {
+        value: Option<T>,
+        version: u32,
+    }
 
     impl<K: Key, V: Serialize> Serialize for SecondaryMap<K, V> {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
             S: Serializer,
         {
-            self.slots.serialize(serializer)
+            // Leave out the vacant slots past the last value.
+            let len = BitCursor::new(&self.occupied, self.num_elems)
+                .next_back(&self.occupied)
+                .map_or(1, |idx| idx + 1);
+            // Vacant slots are written with version `u32::MAX`, as they were
+            // when slots were stored as an enum.
+            serializer.collect_seq((0..len).map(|idx| match self.is_occupied(idx) {
+                true => SerdeSlot {
+                    version: self.versions[idx],
+                    value: Some(unsafe { self.values[idx].assume_init_ref() }),
+                },
+                false => SerdeSlot {
+                    version: u32::MAX,
+                    value: None,
+                },
+            }))
         }
     }
 
@@ -1614,24 +2444,28 @@ mod serialize {
         where
             D: Deserializer<'de>,
         {
-            let mut slots: Vec<Slot<V>> = Deserialize::deserialize(deserializer)?;
+            let slots: Vec<SerdeSlot<V>> = Deserialize::deserialize(deserializer)?;
             if slots.len() >= (u32::max_value() - 1) as usize {
                 return Err(de::Error::custom(&"too many slots"));
             }
 
             // Ensure the first slot exists and is empty for the sentinel.
-            if slots.get(0).map_or(true, |slot| slot.occupied()) {
+            if slots.get(0).map_or(true, |slot| slot.value.is_some()) {
                 return Err(de::Error::custom(&"first slot not empty"));
             }
 
-            slots[0] = Slot::new_vacant();
-            let num_elems = slots.iter().map(|s| s.occupied() as usize).sum();
+            let mut map = Self::with_capacity(slots.len() - 1);
+            map.grow(slots.len() - 1);
+            for (idx, slot) in slots.into_iter().enumerate() {
+                if let Some(value) = slot.value {
+                    map.values[idx] = MaybeUninit::new(value);
+                    map.versions[idx] = slot.version;
+                    map.occupied[idx / BITS] |= 1 << (idx % BITS);
+                    map.num_elems += 1;
+                }
+            }
 
-            Ok(Self {
-                num_elems,
-                slots,
-                _k: PhantomData,
-            })
+            Ok(map)
         }
     }
 }
@@ -1691,6 +2525,138 @@ mod tests {
         }
     }
 
+    #[test]
+    fn resumable_range() {
+        let mut sm = SlotMap::new();
+        let mut sec = SecondaryMap::new();
+        for i in 0..50 {
+            let k = sm.insert(i);
+            if i % 4 != 0 {
+                sec.insert(k, i);
+            }
+        }
+
+        let mut seen = Vec::new();
+        let mut position = 0;
+        loop {
+            let mut it = sec.iter_from(position);
+            let chunk: Vec<_> = it.by_ref().take(5).map(|(_, v)| *v).collect();
+            position = it.position();
+            if chunk.is_empty() {
+                break;
+            }
+            seen.extend(chunk);
+        }
+        let expected: Vec<_> = (0..50).filter(|i| i % 4 != 0).collect();
+        assert_eq!(seen, expected);
+
+        sec.range_mut(..10).for_each(|(_, v)| *v = -*v);
+        assert!(sec.range(..10).all(|(_, v)| *v < 0));
+        assert!(sec.range(10..).all(|(_, v)| *v > 0));
+    }
+
+    #[test]
+    fn double_ended() {
+        let mut sm = SlotMap::new();
+        let mut sec = SecondaryMap::new();
+        for i in 0..30 {
+            let k = sm.insert(i);
+            if i % 3 != 0 && i < 25 {
+                sec.insert(k, i);
+            }
+        }
+
+        let forward: Vec<_> = sec.iter().map(|(k, &v)| (k, v)).collect();
+        assert!(sec.iter().rev().map(|(k, &v)| (k, v)).eq(forward.iter().rev().cloned()));
+        assert!(sec.keys().rev().eq(forward.iter().rev().map(|&(k, _)| k)));
+        sec.values_mut().rev().take(2).for_each(|v| *v = -*v);
+        assert_eq!(sec.values().rev().take(3).collect::<Vec<_>>(), [&-23, &-22, &20]);
+
+        let mut drain = sec.drain();
+        assert_eq!(drain.next_back().map(|(_, v)| v), Some(-23));
+        assert_eq!(drain.next().map(|(_, v)| v), Some(1));
+        drop(drain);
+        assert!(sec.is_empty());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_iter() {
+        use rayon::prelude::*;
+
+        // Only the last eighth of the slots is occupied.
+        let mut sm = SlotMap::new();
+        let mut sec = SecondaryMap::new();
+        let keys: Vec<_> = (0..16_000).map(|_| sm.insert(())).collect();
+        for (i, &k) in keys.iter().enumerate().skip(14_000) {
+            sec.insert(k, i);
+        }
+
+        let mut par: Vec<_> = sec.par_iter().map(|(k, &v)| (k, v)).collect();
+        par.sort();
+        let seq: Vec<_> = sec.iter().map(|(k, &v)| (k, v)).collect();
+        assert_eq!(par, seq);
+
+        sec.par_values_mut().for_each(|v| *v *= 2);
+        sec.par_iter_mut().for_each(|(_, v)| *v += 1);
+        assert!((14_000..16_000).all(|i| sec[keys[i]] == 2 * i + 1));
+        assert_eq!(sec.par_keys().count(), 2_000);
+    }
+
+    #[test]
+    fn sparse_words() {
+        use std::rc::Rc;
+
+        // Values spread over several bitmap words, with whole words vacant
+        // in between, some stale versions and values that track drops.
+        let tracker = Rc::new(());
+        let mut sm = SlotMap::new();
+        let mut sec = SecondaryMap::new();
+        let keys: Vec<_> = (0..300i32).map(|i| sm.insert(i)).collect();
+        for &k in keys.iter().filter(|&&k| sm[k] % 7 == 0 && !(64..192).contains(&sm[k])) {
+            sec.insert(k, (sm[k], tracker.clone()));
+        }
+        let stale = keys[7];
+        sm.remove(stale);
+        let fresh = sm.insert(7);
+        assert!(sec.insert(fresh, (7, tracker.clone())).is_none());
+        assert!(!sec.contains_key(stale));
+        assert!(sec.insert(stale, (-7, tracker.clone())).is_none());
+
+        let expected: Vec<_> = (0..300).filter(|i| i % 7 == 0 && !(64..192).contains(i)).collect();
+        assert_eq!(sec.len(), expected.len());
+        assert_eq!(Rc::strong_count(&tracker), expected.len() + 1);
+        assert!(sec.values().map(|v| v.0).eq(expected.iter().cloned()));
+        assert!(sec.values().rev().map(|v| v.0).eq(expected.iter().rev().cloned()));
+        assert!(sec.range(60..200).map(|(_, v)| v.0).eq([63, 196].iter().cloned()));
+
+        let mut it = sec.iter();
+        for _ in 0..expected.len() / 2 {
+            it.next();
+            it.next_back();
+        }
+        assert_eq!(it.len(), expected.len() % 2);
+        assert_eq!(it.count(), expected.len() % 2);
+
+        let mut it = sec.values_mut();
+        it.next_back();
+        it.next();
+        it.for_each(|v| v.0 = -v.0);
+        let negated: Vec<_> = sec.values().map(|v| v.0).collect();
+        assert_eq!(negated.iter().filter(|&&v| v < 0).count(), expected.len() - 2);
+        sec.values_mut().for_each(|v| v.0 = v.0.abs());
+
+        sec.retain(|_, v| v.0 % 2 == 0);
+        let cloned = sec.clone();
+        assert_eq!(Rc::strong_count(&tracker), 2 * sec.len() + 1);
+        let mut into_iter = cloned.into_iter();
+        assert_eq!(into_iter.next_back().map(|(_, v)| v.0), Some(294));
+        drop(into_iter);
+        assert_eq!(Rc::strong_count(&tracker), sec.len() + 1);
+        drop(sec);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
     quickcheck! {
         fn qc_secmap_equiv_hashmap(operations: Vec<(u8, u32)>) -> bool {
             let mut hm = HashMap::new();
@@ -1764,4 +2730,31 @@ mod tests {
             secv == hmv
         }
     }
+
+    #[test]
+    fn vacant_slots_match_no_key() {
+        // A key whose version wrapped around to the largest odd version.
+        let key = DefaultKey::from(KeyData::from_ffi((u64::from(u32::MAX) << 32) | 3));
+        let mut sec = SecondaryMap::with_capacity(8);
+        assert!(!sec.contains_key(key));
+        assert_eq!(sec.get(key), None);
+        assert!(matches!(sec.entry(key), Some(secondary::Entry::Vacant(_))));
+        sec.insert(key, 1);
+        assert_eq!(sec.remove(key), Some(1));
+        assert!(!sec.contains_key(key));
+
+        // Integer keys all have version 0, whatever their slot held before.
+        let mut sec: SecondaryMap<usize, String> = SecondaryMap::new();
+        sec.insert(10, "ten".to_string());
+        assert!(!sec.contains_key(3));
+        assert_eq!(sec.get(3), None);
+        assert_eq!(sec.get_mut(3), None);
+        assert_eq!(sec.remove(3), None);
+        assert!(matches!(sec.entry(3), Some(secondary::Entry::Vacant(_))));
+        assert_eq!(sec.remove(10).as_deref(), Some("ten"));
+        assert!(!sec.contains_key(10));
+        assert!(sec.iter().next().is_none());
+    }
+
+    crate::util::set_operation_tests!(SecondaryMap);
 }
@@ -0,0 +1,611 @@
+//! Contains the secondary index implementation, a map from attributes to the
+//! keys of a slot map that have them.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::ops::RangeBounds;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+
+use crate::primary::{PrimaryMap, PrimaryMapMut};
+use crate::SecondaryMap;
+use pi_key_alloter::{is_older_version, Key};
+
+/// The attribute to keys map backing a [`SecondaryIndex`], implemented for
+/// [`HashMap`] and [`BTreeMap`] with [`Vec<K>`] values.
+pub trait IndexStorage<A, K>: Default {
+    /// Returns the keys with attribute `attr`.
+    fn bucket(&self, attr: &A) -> Option<&Vec<K>>;
+
+    /// Returns the keys with attribute `attr`, mutably.
+    fn bucket_mut(&mut self, attr: &A) -> Option<&mut Vec<K>>;
+
+    /// Returns the keys with attribute `attr`, inserting an empty bucket if
+    /// there is none.
+    fn bucket_or_default(&mut self, attr: A) -> &mut Vec<K>;
+
+    /// Removes the bucket of attribute `attr`.
+    fn remove_bucket(&mut self, attr: &A);
+
+    /// Removes all buckets.
+    fn clear_buckets(&mut self);
+}
+
+impl<A: Hash + Eq, K, S: BuildHasher + Default> IndexStorage<A, K> for HashMap<A, Vec<K>, S> {
+    fn bucket(&self, attr: &A) -> Option<&Vec<K>> {
+        self.get(attr)
+    }
+
+    fn bucket_mut(&mut self, attr: &A) -> Option<&mut Vec<K>> {
+        self.get_mut(attr)
+    }
+
+    fn bucket_or_default(&mut self, attr: A) -> &mut Vec<K> {
+        self.entry(attr).or_default()
+    }
+
+    fn remove_bucket(&mut self, attr: &A) {
+        self.remove(attr);
+    }
+
+    fn clear_buckets(&mut self) {
+        self.clear();
+    }
+}
+
+impl<A: Ord, K> IndexStorage<A, K> for BTreeMap<A, Vec<K>> {
+    fn bucket(&self, attr: &A) -> Option<&Vec<K>> {
+        self.get(attr)
+    }
+
+    fn bucket_mut(&mut self, attr: &A) -> Option<&mut Vec<K>> {
+        self.get_mut(attr)
+    }
+
+    fn bucket_or_default(&mut self, attr: A) -> &mut Vec<K> {
+        self.entry(attr).or_default()
+    }
+
+    fn remove_bucket(&mut self, attr: &A) {
+        self.remove(attr);
+    }
+
+    fn clear_buckets(&mut self) {
+        self.clear();
+    }
+}
+
+// The attribute of a key and the position of the key in its bucket.
+#[derive(Debug, Clone)]
+struct Entry<A> {
+    attr: A,
+    pos: usize,
+}
+
+/// Secondary index, a companion to a slot map that maps attributes of its
+/// values to the keys that have them.
+///
+/// Every key has at most one attribute, and the keys with an attribute can be
+/// looked up with [`get`]. The attribute to keys map is stored in `M`, which
+/// defaults to a [`HashMap`]. An index created with [`with_btree`] stores it in
+/// a [`BTreeMap`] instead, which allows [`range`] queries.
+///
+/// The index is kept up to date by going through it to change the primary
+/// map, with [`insert_value`], [`modify`] and [`remove_value`], which work
+/// with any [`PrimaryMapMut`]. When that isn't possible the attribute of a key
+/// can be set directly with [`insert`] and [`remove`].
+///
+/// Queries returning keys take the primary map and skip the keys that are no
+/// longer in it, so keys removed behind the index's back are never returned.
+/// Their entries stay in the index until [`retain_live`] is called, or until a
+/// newer key for their slot is inserted, like in a [`SecondaryMap`].
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// struct Unit {
+///     team: u32,
+///     hp: u32,
+/// }
+///
+/// let mut units = SlotMap::new();
+/// let mut by_team: SecondaryIndex<_, u32> = SecondaryIndex::new();
+///
+/// let a = by_team.insert_value(&mut units, Unit { team: 1, hp: 10 }, 1);
+/// let b = by_team.insert_value(&mut units, Unit { team: 2, hp: 10 }, 2);
+/// assert!(by_team.get(&units, &1).eq([a]));
+///
+/// by_team.modify(&mut units, b, |unit, team| {
+///     unit.team = 1;
+///     *team = 1;
+/// });
+/// assert!(by_team.get(&units, &1).eq([a, b]));
+/// assert_eq!(by_team.get(&units, &2).count(), 0);
+///
+/// by_team.remove_value(&mut units, a);
+/// assert!(by_team.get(&units, &1).eq([b]));
+///
+/// // Removed without going through the index.
+/// units.remove(b);
+/// assert_eq!(by_team.get(&units, &1).count(), 0);
+/// ```
+///
+/// [`get`]: Self::get
+/// [`with_btree`]: Self::with_btree
+/// [`range`]: Self::range
+/// [`insert_value`]: Self::insert_value
+/// [`modify`]: Self::modify
+/// [`remove_value`]: Self::remove_value
+/// [`insert`]: Self::insert
+/// [`remove`]: Self::remove
+/// [`retain_live`]: Self::retain_live
+/// [`PrimaryMapMut`]: crate::primary::PrimaryMapMut
+#[derive(Debug, Clone)]
+pub struct SecondaryIndex<K: Key, A, M = HashMap<A, Vec<K>>> {
+    entries: SecondaryMap<K, Entry<A>>,
+    storage: M,
+}
+
+impl<K: Key, A: Hash + Eq> SecondaryIndex<K, A> {
+    /// Constructs a new, empty [`SecondaryIndex`] backed by a [`HashMap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let by_name: SecondaryIndex<DefaultKey, String> = SecondaryIndex::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_hasher(Default::default())
+    }
+}
+
+impl<K: Key, A: Hash + Eq, S: BuildHasher> SecondaryIndex<K, A, HashMap<A, Vec<K>, S>> {
+    /// Constructs a new, empty [`SecondaryIndex`] backed by a [`HashMap`]
+    /// which will use the given hash builder to hash attributes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::hash_map::RandomState;
+    /// # use slotmap::*;
+    /// let by_name: SecondaryIndex<DefaultKey, String, _> =
+    ///     SecondaryIndex::with_hasher(RandomState::new());
+    /// ```
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            entries: SecondaryMap::new(),
+            storage: HashMap::with_hasher(hash_builder),
+        }
+    }
+}
+
+impl<K: Key, A: Ord> SecondaryIndex<K, A, BTreeMap<A, Vec<K>>> {
+    /// Constructs a new, empty [`SecondaryIndex`] backed by a [`BTreeMap`],
+    /// which keeps the attributes ordered and allows [`range`](Self::range)
+    /// queries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let by_age: SecondaryIndex<DefaultKey, u32, _> = SecondaryIndex::with_btree();
+    /// ```
+    pub fn with_btree() -> Self {
+        Self {
+            entries: SecondaryMap::new(),
+            storage: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K: Key, A, M: IndexStorage<A, K>> SecondaryIndex<K, A, M> {
+    /// Returns the number of keys with an attribute, including keys removed
+    /// from the primary map behind the index's back until
+    /// [`retain_live`](Self::retain_live) is called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut index = SecondaryIndex::new();
+    /// index.insert(sm.insert(()), "x");
+    /// index.insert(sm.insert(()), "x");
+    /// assert_eq!(index.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns if no key has an attribute.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let index: SecondaryIndex<DefaultKey, u32> = SecondaryIndex::new();
+    /// assert!(index.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns [`true`] if `key` has an attribute.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut index = SecondaryIndex::new();
+    /// index.insert(k, 0);
+    /// assert!(index.contains_key(k));
+    /// index.remove(k);
+    /// assert!(!index.contains_key(k));
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns the attribute of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut index = SecondaryIndex::new();
+    /// index.insert(k, 'a');
+    /// assert_eq!(index.attr(k), Some(&'a'));
+    /// ```
+    pub fn attr(&self, key: K) -> Option<&A> {
+        self.entries.get(key).map(|entry| &entry.attr)
+    }
+
+    /// An iterator visiting the keys of `primary` with attribute `attr`, in
+    /// no particular order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b, c) = (sm.insert(()), sm.insert(()), sm.insert(()));
+    /// let mut index = SecondaryIndex::new();
+    /// index.insert(a, 1);
+    /// index.insert(b, 2);
+    /// index.insert(c, 1);
+    /// sm.remove(c);
+    /// assert_eq!(index.get(&sm, &1).collect::<Vec<_>>(), [a]);
+    /// assert_eq!(index.get(&sm, &3).count(), 0);
+    /// ```
+    pub fn get<'a, P: PrimaryMap<K>>(&'a self, primary: &'a P, attr: &A) -> impl Iterator<Item = K> + 'a {
+        let keys = self.storage.bucket(attr).map_or(&[][..], |keys| keys.as_slice());
+        keys.iter().copied().filter(move |&key| primary.contains_key(key))
+    }
+
+    /// Sets the attribute of `key` to `attr`, returning its old attribute.
+    /// Can silently fail and return [`None`] if `key` was removed from the
+    /// originating slot map, and a newer key of the same slot was inserted
+    /// into the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut index = SecondaryIndex::new();
+    /// assert_eq!(index.insert(k, 1), None);
+    /// assert_eq!(index.insert(k, 2), Some(1));
+    /// assert_eq!(index.get(&sm, &1).count(), 0);
+    /// assert!(index.get(&sm, &2).eq([k]));
+    /// ```
+    pub fn insert(&mut self, key: K, attr: A) -> Option<A>
+    where
+        A: Clone,
+    {
+        if key.is_null() {
+            return None;
+        }
+
+        let mut old_attr = None;
+        if let Some(old) = self.entries.occupant(key) {
+            if is_older_version(key.data().version(), old.data().version()) {
+                return None;
+            }
+            let attr = self.remove(old);
+            if old == key {
+                old_attr = attr;
+            }
+        }
+
+        let bucket = self.storage.bucket_or_default(attr.clone());
+        bucket.push(key);
+        let pos = bucket.len() - 1;
+        self.entries.insert(key, Entry { attr, pos });
+        old_attr
+    }
+
+    /// Removes the attribute of `key`, returning it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut index = SecondaryIndex::new();
+    /// index.insert(k, 1);
+    /// assert_eq!(index.remove(k), Some(1));
+    /// assert_eq!(index.remove(k), None);
+    /// assert_eq!(index.get(&sm, &1).count(), 0);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<A> {
+        let entry = self.entries.remove(key)?;
+        let bucket = self.storage.bucket_mut(&entry.attr).unwrap();
+        bucket.swap_remove(entry.pos);
+        if let Some(&moved) = bucket.get(entry.pos) {
+            self.entries.get_mut(moved).unwrap().pos = entry.pos;
+        } else if bucket.is_empty() {
+            self.storage.remove_bucket(&entry.attr);
+        }
+        Some(entry.attr)
+    }
+
+    /// Inserts `value` into `primary` and gives its key the attribute `attr`,
+    /// returning the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = HopSlotMap::new();
+    /// let mut index = SecondaryIndex::new();
+    /// let k = index.insert_value(&mut sm, "apple", 'a');
+    /// assert_eq!(sm[k], "apple");
+    /// assert!(index.get(&sm, &'a').eq([k]));
+    /// ```
+    pub fn insert_value<V, P>(&mut self, primary: &mut P, value: V, attr: A) -> K
+    where
+        A: Clone,
+        P: PrimaryMapMut<K, V>,
+    {
+        let key = primary.insert(value);
+        self.insert(key, attr);
+        key
+    }
+
+    /// Calls `f` with mutable references to the value of `key` in `primary`
+    /// and to a copy of its attribute, then updates the attribute. Returns
+    /// [`false`] if `key` isn't in both `primary` and the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut index = SecondaryIndex::new();
+    /// let k = index.insert_value(&mut sm, 3, false);
+    /// index.modify(&mut sm, k, |v, even| {
+    ///     *v += 1;
+    ///     *even = *v % 2 == 0;
+    /// });
+    /// assert!(index.get(&sm, &true).eq([k]));
+    /// ```
+    pub fn modify<V, P, F>(&mut self, primary: &mut P, key: K, f: F) -> bool
+    where
+        A: Clone + Eq,
+        P: PrimaryMapMut<K, V>,
+        F: FnOnce(&mut V, &mut A),
+    {
+        let (value, entry) = match (primary.get_mut(key), self.entries.get(key)) {
+            (Some(value), Some(entry)) => (value, entry),
+            _ => return false,
+        };
+        let mut attr = entry.attr.clone();
+        f(value, &mut attr);
+        if attr != entry.attr {
+            self.insert(key, attr);
+        }
+        true
+    }
+
+    /// Removes `key` from `primary` and the index, returning its value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut index = SecondaryIndex::new();
+    /// let k = index.insert_value(&mut sm, "pear", 'p');
+    /// assert_eq!(index.remove_value(&mut sm, k), Some("pear"));
+    /// assert!(!sm.contains_key(k));
+    /// assert_eq!(index.get(&sm, &'p').count(), 0);
+    /// ```
+    pub fn remove_value<V, P>(&mut self, primary: &mut P, key: K) -> Option<V>
+    where
+        P: PrimaryMapMut<K, V>,
+    {
+        self.remove(key);
+        primary.remove(key)
+    }
+
+    /// Removes the attributes of all keys that are no longer in `primary`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut index = SecondaryIndex::new();
+    /// let k = index.insert_value(&mut sm, "plum", 'p');
+    /// sm.remove(k);
+    /// assert_eq!(index.len(), 1);
+    /// index.retain_live(&sm);
+    /// assert!(index.is_empty());
+    /// ```
+    pub fn retain_live<P: PrimaryMap<K>>(&mut self, primary: &P) {
+        let dead: Vec<K> = self.entries.keys().filter(|&key| !primary.contains_key(key)).collect();
+        for key in dead {
+            self.remove(key);
+        }
+    }
+
+    /// Clears the index. Keeps the allocated memory of the key to attribute
+    /// map for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut index = SecondaryIndex::new();
+    /// index.insert(sm.insert(()), 5);
+    /// index.clear();
+    /// assert!(index.is_empty());
+    /// assert_eq!(index.get(&sm, &5).count(), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.storage.clear_buckets();
+    }
+
+    /// An iterator visiting all keys of `primary` with an attribute in order
+    /// of their slot index. The iterator element type is `(K, &'a A)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut index = SecondaryIndex::new();
+    /// index.insert(b, 2);
+    /// index.insert(a, 1);
+    /// assert_eq!(index.iter(&sm).collect::<Vec<_>>(), [(a, &1), (b, &2)]);
+    /// ```
+    pub fn iter<'a, P: PrimaryMap<K>>(&'a self, primary: &'a P) -> impl Iterator<Item = (K, &'a A)> + 'a {
+        self.entries
+            .iter()
+            .filter(move |&(key, _)| primary.contains_key(key))
+            .map(|(key, entry)| (key, &entry.attr))
+    }
+
+    /// Returns the attribute to keys map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut index = SecondaryIndex::new();
+    /// index.insert(sm.insert(()), "x");
+    /// index.insert(sm.insert(()), "y");
+    /// assert_eq!(index.storage().len(), 2);
+    /// ```
+    pub fn storage(&self) -> &M {
+        &self.storage
+    }
+}
+
+impl<K: Key, A: Ord> SecondaryIndex<K, A, BTreeMap<A, Vec<K>>> {
+    /// An iterator visiting the keys of `primary` whose attribute is in
+    /// `range`, in order of their attribute. The iterator element type is
+    /// `(&'a A, K)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut by_age = SecondaryIndex::with_btree();
+    /// let alice = by_age.insert_value(&mut sm, "alice", 31);
+    /// let bob = by_age.insert_value(&mut sm, "bob", 17);
+    /// let carol = by_age.insert_value(&mut sm, "carol", 45);
+    ///
+    /// let adults: Vec<_> = by_age.range(&sm, 18..).map(|(_, k)| sm[k]).collect();
+    /// assert_eq!(adults, ["alice", "carol"]);
+    /// ```
+    pub fn range<'a, P, R>(&'a self, primary: &'a P, range: R) -> impl Iterator<Item = (&'a A, K)> + 'a
+    where
+        P: PrimaryMap<K>,
+        R: RangeBounds<A>,
+    {
+        self.storage
+            .range(range)
+            .flat_map(|(attr, keys)| keys.iter().map(move |&key| (attr, key)))
+            .filter(move |&(_, key)| primary.contains_key(key))
+    }
+}
+
+impl<K: Key, A, M: IndexStorage<A, K>> Default for SecondaryIndex<K, A, M> {
+    fn default() -> Self {
+        Self {
+            entries: SecondaryMap::new(),
+            storage: M::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn never_returns_stale_keys() {
+        let mut sm = DenseSlotMap::new();
+        let mut index = SecondaryIndex::with_btree();
+        let keys: Vec<_> = (0..6).map(|i| index.insert_value(&mut sm, i, i % 3)).collect();
+        let get = |index: &SecondaryIndex<_, _, _>, sm: &DenseSlotMap<_, _>, attr| {
+            index.get(sm, &attr).collect::<Vec<_>>()
+        };
+        assert_eq!(get(&index, &sm, 0), [keys[0], keys[3]]);
+
+        // Swap removal keeps the positions of the moved keys up to date.
+        index.remove(keys[1]);
+        index.remove(keys[4]);
+        assert_eq!(get(&index, &sm, 1), []);
+        index.insert(keys[0], 2);
+        index.remove(keys[2]);
+        assert_eq!(get(&index, &sm, 2), [keys[0], keys[5]]);
+
+        // Removing behind the index's back hides the old key at once, and
+        // reusing its slot evicts it.
+        sm.remove(keys[3]);
+        assert_eq!(get(&index, &sm, 0), []);
+        assert!(index.range(&sm, ..).all(|(_, key)| key != keys[3]));
+        assert!(index.iter(&sm).all(|(key, _)| key != keys[3]));
+        let new = sm.insert(10);
+        assert!(index.insert(new, 2).is_none());
+        assert!(!index.contains_key(keys[3]));
+        assert_eq!(index.insert(keys[3], 0), None);
+        assert_eq!(get(&index, &sm, 0), []);
+
+        assert!(!index.modify(&mut sm, keys[3], |_, _| unreachable!()));
+        assert!(index.modify(&mut sm, new, |v, a| {
+            *v += 1;
+            *a = 1;
+        }));
+        let in_range: Vec<_> = index.range(&sm, 1..).collect();
+        assert_eq!(in_range, [(&1, new), (&2, keys[0]), (&2, keys[5])]);
+        assert_eq!(index.len(), 3);
+
+        for (key, attr) in index.iter(&sm) {
+            assert!(index.get(&sm, attr).any(|k| k == key));
+        }
+
+        // Live entries survive pruning, the others are dropped.
+        sm.remove(keys[5]);
+        assert_eq!(index.len(), 3);
+        index.retain_live(&sm);
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.attr(keys[5]), None);
+        assert_eq!(get(&index, &sm, 2), [keys[0]]);
+    }
+}
@@ -0,0 +1,539 @@
+//! Contains the secondary set implementation, a set of slot map keys backed by
+//! a bitset.
+
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::ops::{BitAnd, BitOr, Sub};
+
+use pi_key_alloter::{is_older_version, key_data, Key};
+
+const BITS: usize = u64::BITS as usize;
+
+/// Secondary set, a set of keys of a slot map.
+///
+/// A [`SecondarySet`] stores one occupancy bit and one version per slot,
+/// where a `SecondaryMap<K, ()>` stores a tagged slot per slot. Iteration
+/// checks the occupancy 64 slots at a time, skipping empty words of the
+/// bitset entirely.
+///
+/// Like a [`SecondaryMap`](crate::SecondaryMap), inserting a key replaces an
+/// older key of the same slot, and a key is ignored if a newer key of its
+/// slot is already in the set.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut sm = SlotMap::new();
+/// let keys: Vec<_> = (0..4).map(|i| sm.insert(i)).collect();
+///
+/// let mut selected = SecondarySet::new();
+/// selected.insert(keys[0]);
+/// selected.insert(keys[2]);
+/// let mut dirty: SecondarySet<_> = keys[1..].iter().copied().collect();
+///
+/// assert_eq!((&selected & &dirty).iter().collect::<Vec<_>>(), [keys[2]]);
+/// dirty.difference_with(&selected);
+/// assert_eq!(dirty.iter().collect::<Vec<_>>(), [keys[1], keys[3]]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SecondarySet<K: Key> {
+    bits: Vec<u64>,
+    versions: Vec<u32>,
+    num_elems: usize,
+    _k: PhantomData<fn(K) -> K>,
+}
+
+impl<K: Key> SecondarySet<K> {
+    /// Constructs a new, empty [`SecondarySet`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut set: SecondarySet<DefaultKey> = SecondarySet::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty [`SecondarySet`] with room for keys of the given
+    /// number of slots.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut set: SecondarySet<DefaultKey> = SecondarySet::with_capacity(100);
+    /// assert!(set.capacity() >= 100);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            bits: Vec::with_capacity((capacity + BITS - 1) / BITS),
+            versions: Vec::with_capacity(capacity),
+            num_elems: 0,
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns the number of keys in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut set = SecondarySet::new();
+    /// set.insert(sm.insert(()));
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.num_elems
+    }
+
+    /// Returns if the set is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let set: SecondarySet<DefaultKey> = SecondarySet::new();
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.num_elems == 0
+    }
+
+    /// Returns the number of slots the set can hold keys of without
+    /// reallocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let set: SecondarySet<DefaultKey> = SecondarySet::with_capacity(10);
+    /// assert!(set.capacity() >= 10);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.versions.capacity()
+    }
+
+    /// Returns [`true`] if the set contains `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut set = SecondarySet::new();
+    /// assert!(!set.contains(k));
+    /// set.insert(k);
+    /// assert!(set.contains(k));
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        let kd = key.data();
+        let idx = kd.index() as usize;
+        self.is_occupied(idx) && self.versions[idx] == kd.version()
+    }
+
+    /// Inserts `key` into the set, returning [`true`] if it wasn't in the set.
+    /// Can silently fail and return [`false`] if `key` was removed from the
+    /// originating slot map, and a newer key of the same slot is in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut set = SecondarySet::new();
+    /// assert!(set.insert(k));
+    /// assert!(!set.insert(k));
+    ///
+    /// sm.remove(k);
+    /// let new_k = sm.insert(());
+    /// assert!(set.insert(new_k));
+    /// assert!(!set.contains(k));
+    /// assert!(!set.insert(k));
+    /// ```
+    pub fn insert(&mut self, key: K) -> bool {
+        if key.is_null() {
+            return false;
+        }
+
+        let kd = key.data();
+        let idx = kd.index() as usize;
+        if idx >= self.versions.len() {
+            self.versions.resize(idx + 1, 0);
+            self.bits.resize((idx + BITS) / BITS, 0);
+        }
+
+        if self.is_occupied(idx) {
+            let version = self.versions[idx];
+            if version == kd.version() || is_older_version(kd.version(), version) {
+                return false;
+            }
+        } else {
+            self.bits[idx / BITS] |= 1 << (idx % BITS);
+            self.num_elems += 1;
+        }
+        self.versions[idx] = kd.version();
+        true
+    }
+
+    /// Removes `key` from the set, returning [`true`] if it was in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut set = SecondarySet::new();
+    /// set.insert(k);
+    /// assert!(set.remove(k));
+    /// assert!(!set.remove(k));
+    /// ```
+    pub fn remove(&mut self, key: K) -> bool {
+        if !self.contains(key) {
+            return false;
+        }
+        let idx = key.data().index() as usize;
+        self.bits[idx / BITS] &= !(1 << (idx % BITS));
+        self.num_elems -= 1;
+        true
+    }
+
+    /// Retains only the keys specified by the predicate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut set = SecondarySet::new();
+    /// for i in 0..10 {
+    ///     set.insert(sm.insert(i));
+    /// }
+    /// set.retain(|k| sm[k] % 2 == 0);
+    /// assert_eq!(set.len(), 5);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K) -> bool,
+    {
+        let removed: Vec<K> = self.iter().filter(|&key| !f(key)).collect();
+        for key in removed {
+            self.remove(key);
+        }
+    }
+
+    /// Clears the set. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut set = SecondarySet::new();
+    /// set.insert(sm.insert(()));
+    /// set.clear();
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.bits.clear();
+        self.versions.clear();
+        self.num_elems = 0;
+    }
+
+    /// Adds the keys of `other` to the set. Of two keys of the same slot the
+    /// newer one is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut set: SecondarySet<_> = [a].into_iter().collect();
+    /// set.union_with(&[b].into_iter().collect());
+    /// assert!(set.contains(a) && set.contains(b));
+    /// ```
+    pub fn union_with(&mut self, other: &Self) {
+        for key in other.iter() {
+            self.insert(key);
+        }
+    }
+
+    /// Removes the keys that aren't in `other` from the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut set: SecondarySet<_> = [a, b].into_iter().collect();
+    /// set.intersect_with(&[b].into_iter().collect());
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), [b]);
+    /// ```
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.bits.truncate(other.bits.len());
+        self.versions.truncate(other.versions.len());
+        for (word_idx, word) in self.bits.iter_mut().enumerate() {
+            let mut both = *word & other.bits[word_idx];
+            let mut bits = both;
+            while bits != 0 {
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                let idx = word_idx * BITS + bit;
+                if self.versions[idx] != other.versions[idx] {
+                    both &= !(1 << bit);
+                }
+            }
+            *word = both;
+        }
+        self.num_elems = self.count_ones();
+    }
+
+    /// Removes the keys that are in `other` from the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut set: SecondarySet<_> = [a, b].into_iter().collect();
+    /// set.difference_with(&[b].into_iter().collect());
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), [a]);
+    /// ```
+    pub fn difference_with(&mut self, other: &Self) {
+        for (word_idx, word) in self.bits.iter_mut().enumerate() {
+            let mut bits = *word & other.bits.get(word_idx).copied().unwrap_or(0);
+            while bits != 0 {
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                let idx = word_idx * BITS + bit;
+                if self.versions[idx] == other.versions[idx] {
+                    *word &= !(1 << bit);
+                    self.num_elems -= 1;
+                }
+            }
+        }
+    }
+
+    /// Returns [`true`] if every key of the set is in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let set: SecondarySet<_> = [a].into_iter().collect();
+    /// assert!(set.is_subset(&[a, b].into_iter().collect()));
+    /// assert!(!set.is_subset(&[b].into_iter().collect()));
+    /// ```
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.iter().all(|key| other.contains(key))
+    }
+
+    /// An iterator visiting all keys in order of their slot index. The
+    /// iterator element type is `K`.
+    ///
+    /// Empty words of the bitset are skipped without looking at their slots.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut set = SecondarySet::new();
+    /// set.insert(b);
+    /// set.insert(a);
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), [a, b]);
+    /// ```
+    pub fn iter(&self) -> Iter<K> {
+        Iter {
+            set: self,
+            word_idx: 0,
+            word: self.bits.first().copied().unwrap_or(0),
+            num_left: self.num_elems,
+        }
+    }
+
+    fn is_occupied(&self, idx: usize) -> bool {
+        self.bits
+            .get(idx / BITS)
+            .map_or(false, |word| word & (1 << (idx % BITS)) != 0)
+    }
+
+    fn count_ones(&self) -> usize {
+        self.bits.iter().map(|word| word.count_ones() as usize).sum()
+    }
+}
+
+impl<K: Key> Default for SecondarySet<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Key> PartialEq for SecondarySet<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.is_subset(other)
+    }
+}
+
+impl<K: Key> Eq for SecondarySet<K> {}
+
+impl<K: Key> Extend<K> for SecondarySet<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<K: Key> FromIterator<K> for SecondarySet<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<K: Key> BitOr for &SecondarySet<K> {
+    type Output = SecondarySet<K>;
+
+    fn bitor(self, rhs: Self) -> SecondarySet<K> {
+        let mut set = self.clone();
+        set.union_with(rhs);
+        set
+    }
+}
+
+impl<K: Key> BitAnd for &SecondarySet<K> {
+    type Output = SecondarySet<K>;
+
+    fn bitand(self, rhs: Self) -> SecondarySet<K> {
+        let mut set = self.clone();
+        set.intersect_with(rhs);
+        set
+    }
+}
+
+impl<K: Key> Sub for &SecondarySet<K> {
+    type Output = SecondarySet<K>;
+
+    fn sub(self, rhs: Self) -> SecondarySet<K> {
+        let mut set = self.clone();
+        set.difference_with(rhs);
+        set
+    }
+}
+
+// Iterators.
+/// An iterator over the keys in a [`SecondarySet`].
+///
+/// This iterator is created by [`SecondarySet::iter`].
+#[derive(Debug)]
+pub struct Iter<'a, K: Key + 'a> {
+    set: &'a SecondarySet<K>,
+    word_idx: usize,
+    word: u64,
+    num_left: usize,
+}
+
+impl<'a, K: 'a + Key> Clone for Iter<'a, K> {
+    fn clone(&self) -> Self {
+        Iter {
+            set: self.set,
+            word_idx: self.word_idx,
+            word: self.word,
+            num_left: self.num_left,
+        }
+    }
+}
+
+impl<'a, K: Key> Iterator for Iter<'a, K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        while self.word == 0 {
+            self.word_idx += 1;
+            self.word = *self.set.bits.get(self.word_idx)?;
+        }
+
+        let idx = self.word_idx * BITS + self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        self.num_left -= 1;
+        let version = self.set.versions[idx];
+        Some(unsafe { key_data(idx as u32, version) }.into())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.num_left, Some(self.num_left))
+    }
+}
+
+impl<'a, K: Key> IntoIterator for &'a SecondarySet<K> {
+    type Item = K;
+    type IntoIter = Iter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Key> FusedIterator for Iter<'a, K> {}
+
+impl<'a, K: Key> ExactSizeIterator for Iter<'a, K> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn set_operations() {
+        let mut sm = SlotMap::new();
+        let keys: Vec<_> = (0..200).map(|i| sm.insert(i)).collect();
+        let evens: SecondarySet<_> = keys.iter().copied().step_by(2).collect();
+        let threes: SecondarySet<_> = keys.iter().copied().step_by(3).collect();
+        assert_eq!(evens.len(), 100);
+
+        let both = &evens & &threes;
+        assert_eq!(both.len(), 34);
+        assert!(both.iter().all(|k| sm[k] % 6 == 0));
+        let either = &evens | &threes;
+        assert_eq!(either.len(), 100 + 67 - 34);
+        let only_evens = &evens - &threes;
+        assert_eq!(only_evens.len(), 100 - 34);
+        assert!(only_evens.is_subset(&evens));
+        assert_eq!(&only_evens | &both, evens);
+
+        // Keys of a reused slot don't match their older versions.
+        sm.remove(keys[6]);
+        let new = sm.insert(6);
+        let mut replaced = evens.clone();
+        assert!(replaced.insert(new));
+        assert!(!replaced.contains(keys[6]));
+        assert_eq!((&replaced & &threes).len(), 33);
+        assert_eq!((&replaced - &threes).len(), 67);
+        let mut expected = &threes | &evens;
+        expected.remove(keys[6]);
+        expected.insert(new);
+        assert_eq!(&threes | &replaced, expected);
+
+        // Sparse iteration only visits occupied slots.
+        let mut sparse = SecondarySet::new();
+        sparse.insert(keys[199]);
+        sparse.insert(keys[3]);
+        assert_eq!(sparse.iter().collect::<Vec<_>>(), [keys[3], keys[199]]);
+        assert_eq!(sparse.iter().len(), 2);
+    }
+}
@@ -0,0 +1,1777 @@
+//! Contains the structure-of-arrays slot map implementation.
+
+#[cfg(all(nightly, any(doc, feature = "unstable")))]
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::{DoubleEndedIterator, Enumerate, FusedIterator};
+use core::marker::PhantomData;
+use core::mem::{self, MaybeUninit};
+use core::ops::{Index, IndexMut, RangeBounds};
+
+use crate::util::{slot_range, Never, UnwrapUnchecked};
+use pi_key_alloter::{DefaultKey, Key, key_data};
+
+// Even = vacant, odd = occupied.
+#[inline(always)]
+fn occupied(version: &u32) -> bool {
+    version & 1 != 0
+}
+
+/// Structure-of-arrays slot map, a [`SlotMap`](crate::SlotMap) that stores
+/// the versions of its slots apart from the values.
+///
+/// A [`SlotMap`](crate::SlotMap) slot interleaves the version with the
+/// value, so checking a key pulls the value into the cache as well. This map
+/// keeps the versions in their own dense array of `u32`s and the values in a
+/// separate array, leaving vacant values uninitialized. Validating a key, and
+/// scans that only look at keys such as [`keys`](Self::keys) or the
+/// pre-filtering of [`retain`](Self::retain), touch 4 bytes per slot
+/// regardless of the size of the values. Vacant slots are tracked on a
+/// separate free list.
+///
+/// Accessing a value through a key reads from two arrays instead of one, so
+/// prefer [`SlotMap`](crate::SlotMap) for small values that are usually read
+/// right after their key is checked.
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut sm = SoaSlotMap::new();
+/// let foo = sm.insert([0u8; 256]);
+/// let bar = sm.insert([1u8; 256]);
+/// sm.remove(foo);
+/// assert!(!sm.contains_key(foo));
+/// assert_eq!(sm.keys().collect::<Vec<_>>(), [bar]);
+/// assert_eq!(sm[bar][0], 1);
+/// ```
+pub struct SoaSlotMap<K: Key, V> {
+    versions: Vec<u32>,
+    // Initialized exactly for the slots with an odd version.
+    values: Vec<MaybeUninit<V>>,
+    // Indices of the vacant slots, reused last in first out.
+    free: Vec<u32>,
+    num_elems: u32,
+    _k: PhantomData<fn(K) -> K>,
+}
+
+impl<V> SoaSlotMap<DefaultKey, V> {
+    /// Constructs a new, empty [`SoaSlotMap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm: SoaSlotMap<_, i32> = SoaSlotMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`SoaSlotMap`] with the given capacity.
+    ///
+    /// The slot map will not reallocate until it holds at least `capacity`
+    /// elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm: SoaSlotMap<_, i32> = SoaSlotMap::with_capacity(10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_key(capacity)
+    }
+}
+
+impl<K: Key, V> SoaSlotMap<K, V> {
+    /// Constructs a new, empty [`SoaSlotMap`] with a custom key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct PositionKey;
+    /// }
+    /// let mut positions: SoaSlotMap<PositionKey, i32> = SoaSlotMap::with_key();
+    /// ```
+    pub fn with_key() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`SoaSlotMap`] with the given capacity and a custom key
+    /// type.
+    ///
+    /// The slot map will not reallocate until it holds at least `capacity`
+    /// elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct MessageKey;
+    /// }
+    /// let mut messages = SoaSlotMap::with_capacity_and_key(3);
+    /// let welcome: MessageKey = messages.insert("Welcome");
+    /// let good_day = messages.insert("Good day");
+    /// let hello = messages.insert("Hello");
+    /// ```
+    pub fn with_capacity_and_key(capacity: usize) -> Self {
+        // Create slots with a sentinel at index 0, so keys have the same
+        // indices as they would in a SlotMap.
+        let mut versions = Vec::with_capacity(capacity + 1);
+        let mut values = Vec::with_capacity(capacity + 1);
+        versions.push(0);
+        values.push(MaybeUninit::uninit());
+
+        Self {
+            versions,
+            values,
+            free: Vec::new(),
+            num_elems: 0,
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns the number of elements in the slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::with_capacity(10);
+    /// sm.insert("len() counts actual elements, not capacity");
+    /// let key = sm.insert("removed elements don't count either");
+    /// sm.remove(key);
+    /// assert_eq!(sm.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.num_elems as usize
+    }
+
+    /// Returns if the slot map is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let key = sm.insert("dummy");
+    /// assert_eq!(sm.is_empty(), false);
+    /// sm.remove(key);
+    /// assert_eq!(sm.is_empty(), true);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.num_elems == 0
+    }
+
+    /// Returns the number of elements the [`SoaSlotMap`] can hold without
+    /// reallocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let sm: SoaSlotMap<_, f64> = SoaSlotMap::with_capacity(10);
+    /// assert_eq!(sm.capacity(), 10);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        // One slot is reserved for the sentinel.
+        self.versions.capacity().min(self.values.capacity()) - 1
+    }
+
+    /// Reserves capacity for at least `additional` more elements to be inserted
+    /// in the [`SoaSlotMap`]. The collection may reserve more space to avoid
+    /// frequent reallocations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new allocation size overflows [`usize`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// sm.insert("foo");
+    /// sm.reserve(32);
+    /// assert!(sm.capacity() >= 33);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        // One slot is reserved for the sentinel.
+        let needed = (self.len() + additional).saturating_sub(self.versions.len() - 1);
+        self.versions.reserve(needed);
+        self.values.reserve(needed);
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements to be
+    /// inserted in the [`SoaSlotMap`]. The collection may reserve more space to
+    /// avoid frequent reallocations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// sm.insert("foo");
+    /// sm.try_reserve(32).unwrap();
+    /// assert!(sm.capacity() >= 33);
+    /// ```
+    #[cfg(all(nightly, any(doc, feature = "unstable")))]
+    #[cfg_attr(all(nightly, doc), doc(cfg(feature = "unstable")))]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        // One slot is reserved for the sentinel.
+        let needed = (self.len() + additional).saturating_sub(self.versions.len() - 1);
+        self.versions.try_reserve(needed)?;
+        self.values.try_reserve(needed)
+    }
+
+    /// Returns [`true`] if the slot map contains `key`. Only the version
+    /// array is read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let key = sm.insert(42);
+    /// assert_eq!(sm.contains_key(key), true);
+    /// sm.remove(key);
+    /// assert_eq!(sm.contains_key(key), false);
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        let kd = key.data();
+        self.versions.get(kd.index() as usize) == Some(&kd.version())
+    }
+
+    /// Inserts a value into the slot map. Returns a unique key that can be used
+    /// to access this value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the slot map equals
+    /// 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let key = sm.insert(42);
+    /// assert_eq!(sm[key], 42);
+    /// ```
+    #[inline(always)]
+    pub fn insert(&mut self, value: V) -> K {
+        unsafe { self.try_insert_with_key::<_, Never>(move |_| Ok(value)).unwrap_unchecked_() }
+    }
+
+    /// Inserts a value given by `f` into the slot map. The key where the
+    /// value will be stored is passed into `f`. This is useful to store values
+    /// that contain their own key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the slot map equals
+    /// 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let key = sm.insert_with_key(|k| (k, 20));
+    /// assert_eq!(sm[key], (key, 20));
+    /// ```
+    #[inline(always)]
+    pub fn insert_with_key<F>(&mut self, f: F) -> K
+    where
+        F: FnOnce(K) -> V,
+    {
+        unsafe { self.try_insert_with_key::<_, Never>(move |k| Ok(f(k))).unwrap_unchecked_() }
+    }
+
+    /// Inserts a value given by `f` into the slot map. The key where the
+    /// value will be stored is passed into `f`. This is useful to store values
+    /// that contain their own key.
+    ///
+    /// If `f` returns `Err`, this method returns the error. The slotmap is untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the slot map equals
+    /// 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let key = sm.try_insert_with_key::<_, ()>(|k| Ok((k, 20))).unwrap();
+    /// assert_eq!(sm[key], (key, 20));
+    ///
+    /// sm.try_insert_with_key::<_, ()>(|k| Err(())).unwrap_err();
+    /// ```
+    pub fn try_insert_with_key<F, E>(&mut self, f: F) -> Result<K, E>
+    where
+        F: FnOnce(K) -> Result<V, E>,
+    {
+        // In case f panics, we don't make any changes until we have the value.
+        let new_num_elems = self.num_elems + 1;
+        if new_num_elems == u32::MAX {
+            panic!("SoaSlotMap number of elements overflow");
+        }
+
+        if let Some(&idx) = self.free.last() {
+            let occupied_version = self.versions[idx as usize] | 1;
+            let kd = unsafe { key_data(idx, occupied_version) };
+
+            // Get value first in case f panics or returns an error.
+            let value = f(kd.into())?;
+
+            // Update.
+            self.free.pop();
+            self.values[idx as usize] = MaybeUninit::new(value);
+            self.versions[idx as usize] = occupied_version;
+            self.num_elems = new_num_elems;
+            return Ok(kd.into());
+        }
+
+        let version = 1;
+        let kd = unsafe { key_data(self.versions.len() as u32, version) };
+
+        // Create new slot before adjusting the versions in case f or the
+        // allocation panics or errors.
+        self.values.push(MaybeUninit::new(f(kd.into())?));
+        self.versions.push(version);
+        self.num_elems = new_num_elems;
+        Ok(kd.into())
+    }
+
+    // Helper function to remove a value from a slot. Safe iff the slot is
+    // occupied. Returns the value removed.
+    #[inline(always)]
+    unsafe fn remove_from_slot(&mut self, idx: usize) -> V {
+        let version = self.versions.get_unchecked_mut(idx);
+        *version = version.wrapping_add(1);
+        self.free.push(idx as u32);
+        self.num_elems -= 1;
+        self.values.get_unchecked(idx).assume_init_read()
+    }
+
+    /// Removes a key from the slot map, returning the value at the key if the
+    /// key was not previously removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let key = sm.insert(42);
+    /// assert_eq!(sm.remove(key), Some(42));
+    /// assert_eq!(sm.remove(key), None);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let kd = key.data();
+        if self.contains_key(key) {
+            // This is safe because we know that the slot is occupied.
+            Some(unsafe { self.remove_from_slot(kd.index() as usize) })
+        } else {
+            None
+        }
+    }
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// In other words, remove all key-value pairs `(k, v)` such that
+    /// `f(k, &mut v)` returns false. This method invalidates any removed keys.
+    ///
+    /// This function must iterate over all slots, empty or not, but only reads
+    /// the version of a vacant slot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    ///
+    /// let k1 = sm.insert(0);
+    /// let k2 = sm.insert(1);
+    /// let k3 = sm.insert(2);
+    ///
+    /// sm.retain(|key, val| key == k1 || *val == 1);
+    ///
+    /// assert!(sm.contains_key(k1));
+    /// assert!(sm.contains_key(k2));
+    /// assert!(!sm.contains_key(k3));
+    ///
+    /// assert_eq!(2, sm.len());
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        for i in 1..self.versions.len() {
+            // This is safe because removing elements does not shrink slots.
+            let version = unsafe { *self.versions.get_unchecked(i) };
+            if !occupied(&version) {
+                continue;
+            }
+
+            let key = unsafe { key_data(i as u32, version) }.into();
+            let value = unsafe { self.values.get_unchecked_mut(i).assume_init_mut() };
+            if !f(key, value) {
+                // This is safe because we know that the slot was occupied.
+                drop(unsafe { self.remove_from_slot(i) });
+            }
+        }
+    }
+
+    /// Clears the slot map. Keeps the allocated memory for reuse.
+    ///
+    /// This function must iterate over all slots, empty or not. In the face of
+    /// many deleted elements it can be inefficient.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// for i in 0..10 {
+    ///     sm.insert(i);
+    /// }
+    /// assert_eq!(sm.len(), 10);
+    /// sm.clear();
+    /// assert_eq!(sm.len(), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        self.drain();
+    }
+
+    /// Clears the slot map, returning all key-value pairs in arbitrary order as
+    /// an iterator. Keeps the allocated memory for reuse.
+    ///
+    /// When the iterator is dropped all elements in the slot map are removed,
+    /// even if the iterator was not fully consumed. If the iterator is not
+    /// dropped (using e.g. [`std::mem::forget`]), only the elements that were
+    /// iterated over are removed.
+    ///
+    /// This function must iterate over all slots, empty or not. In the face of
+    /// many deleted elements it can be inefficient.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let k = sm.insert(0);
+    /// let v: Vec<_> = sm.drain().collect();
+    /// assert_eq!(sm.len(), 0);
+    /// assert_eq!(v, vec![(k, 0)]);
+    /// ```
+    pub fn drain(&mut self) -> Drain<K, V> {
+        Drain {
+            cur: 0,
+            end: self.versions.len(),
+            sm: self,
+        }
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let key = sm.insert("bar");
+    /// assert_eq!(sm.get(key), Some(&"bar"));
+    /// sm.remove(key);
+    /// assert_eq!(sm.get(key), None);
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        if !self.contains_key(key) {
+            return None;
+        }
+
+        Some(unsafe { self.get_unchecked(key) })
+    }
+
+    /// Returns a reference to the value corresponding to the key without
+    /// version or bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// This should only be used if `contains_key(key)` is true. Otherwise it is
+    /// potentially unsafe.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let key = sm.insert("bar");
+    /// assert_eq!(unsafe { sm.get_unchecked(key) }, &"bar");
+    /// sm.remove(key);
+    /// // sm.get_unchecked(key) is now dangerous!
+    /// ```
+    pub unsafe fn get_unchecked(&self, key: K) -> &V {
+        debug_assert!(self.contains_key(key));
+        self.values.get_unchecked(key.data().index() as usize).assume_init_ref()
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let key = sm.insert(3.5);
+    /// if let Some(x) = sm.get_mut(key) {
+    ///     *x += 3.0;
+    /// }
+    /// assert_eq!(sm[key], 6.5);
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        if !self.contains_key(key) {
+            return None;
+        }
+
+        Some(unsafe { self.get_unchecked_mut(key) })
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key
+    /// without version or bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// This should only be used if `contains_key(key)` is true. Otherwise it is
+    /// potentially unsafe.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let key = sm.insert("foo");
+    /// unsafe { *sm.get_unchecked_mut(key) = "bar" };
+    /// assert_eq!(sm[key], "bar");
+    /// sm.remove(key);
+    /// // sm.get_unchecked_mut(key) is now dangerous!
+    /// ```
+    pub unsafe fn get_unchecked_mut(&mut self, key: K) -> &mut V {
+        debug_assert!(self.contains_key(key));
+        self.values.get_unchecked_mut(key.data().index() as usize).assume_init_mut()
+    }
+
+    // Like `contains_key` and `get_unchecked_mut`, but through a raw pointer
+    // and without creating a reference to the values, so that references
+    // previously handed out to other values stay valid. Used by mutable joins.
+    pub(crate) unsafe fn raw_contains_key(this: *const Self, key: K) -> bool {
+        // The versions are stored apart from the values.
+        (*this).contains_key(key)
+    }
+
+    pub(crate) unsafe fn raw_get_unchecked_mut(this: *mut Self, key: K) -> *mut V {
+        (*this).values.as_mut_ptr().add(key.data().index() as usize).cast()
+    }
+
+    /// Returns mutable references to the values corresponding to the given
+    /// keys. All keys must be valid and disjoint, otherwise None is returned.
+    ///
+    /// Requires at least stable Rust version 1.51.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let ka = sm.insert("butter");
+    /// let kb = sm.insert("apples");
+    /// let kc = sm.insert("charlie");
+    /// sm.remove(kc); // Make key c invalid.
+    /// assert_eq!(sm.get_disjoint_mut([ka, kb, kc]), None); // Has invalid key.
+    /// assert_eq!(sm.get_disjoint_mut([ka, ka]), None); // Not disjoint.
+    /// let [a, b] = sm.get_disjoint_mut([ka, kb]).unwrap();
+    /// std::mem::swap(a, b);
+    /// assert_eq!(sm[ka], "apples");
+    /// assert_eq!(sm[kb], "butter");
+    /// ```
+    #[cfg(has_min_const_generics)]
+    pub fn get_disjoint_mut<const N: usize>(&mut self, keys: [K; N]) -> Option<[&mut V; N]> {
+        // Create an uninitialized array of `MaybeUninit`. The `assume_init` is
+        // safe because the type we are claiming to have initialized here is a
+        // bunch of `MaybeUninit`s, which do not require initialization.
+        let mut ptrs: [MaybeUninit<*mut V>; N] = unsafe { MaybeUninit::uninit().assume_init() };
+
+        let mut i = 0;
+        while i < N {
+            let kd = keys[i].data();
+            if !self.contains_key(kd.into()) {
+                break;
+            }
+
+            // This key is valid, and thus the slot is occupied. Temporarily
+            // mark it as unoccupied so duplicate keys would show up as invalid.
+            // This gives us a linear time disjointness check.
+            let idx = kd.index() as usize;
+            self.versions[idx] ^= 1;
+            ptrs[i] = MaybeUninit::new(self.values[idx].as_mut_ptr());
+            i += 1;
+        }
+
+        // Undo temporary unoccupied markings.
+        for k in &keys[..i] {
+            self.versions[k.data().index() as usize] ^= 1;
+        }
+
+        if i == N {
+            // All were valid and disjoint.
+            Some(unsafe { core::mem::transmute_copy::<_, [&mut V; N]>(&ptrs) })
+        } else {
+            None
+        }
+    }
+
+    /// Returns mutable references to the values corresponding to the given
+    /// keys. All keys must be valid and disjoint.
+    ///
+    /// Requires at least stable Rust version 1.51.
+    ///
+    /// # Safety
+    ///
+    /// This should only be used if `contains_key(key)` is true for every given
+    /// key and no two keys are equal. Otherwise it is potentially unsafe.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let ka = sm.insert("butter");
+    /// let kb = sm.insert("apples");
+    /// let [a, b] = unsafe { sm.get_disjoint_unchecked_mut([ka, kb]) };
+    /// std::mem::swap(a, b);
+    /// assert_eq!(sm[ka], "apples");
+    /// assert_eq!(sm[kb], "butter");
+    /// ```
+    #[cfg(has_min_const_generics)]
+    pub unsafe fn get_disjoint_unchecked_mut<const N: usize>(
+        &mut self,
+        keys: [K; N],
+    ) -> [&mut V; N] {
+        // Safe, see get_disjoint_mut.
+        let mut ptrs: [MaybeUninit<*mut V>; N] = MaybeUninit::uninit().assume_init();
+        for i in 0..N {
+            ptrs[i] = MaybeUninit::new(self.get_unchecked_mut(keys[i]));
+        }
+        core::mem::transmute_copy::<_, [&mut V; N]>(&ptrs)
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order. The
+    /// iterator element type is `(K, &'a V)`.
+    ///
+    /// This function must iterate over all slots, empty or not. In the face of
+    /// many deleted elements it can be inefficient.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let k0 = sm.insert(0);
+    /// let k1 = sm.insert(1);
+    /// let k2 = sm.insert(2);
+    ///
+    /// for (k, v) in sm.iter() {
+    ///     println!("key: {:?}, val: {}", k, v);
+    /// }
+    /// ```
+    pub fn iter(&self) -> Iter<K, V> {
+        Iter {
+            keys: self.keys(),
+            values: &self.values,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order, with
+    /// mutable references to the values. The iterator element type is
+    /// `(K, &'a mut V)`.
+    ///
+    /// This function must iterate over all slots, empty or not. In the face of
+    /// many deleted elements it can be inefficient.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    /// let k2 = sm.insert(30);
+    ///
+    /// for (k, v) in sm.iter_mut() {
+    ///     if k != k1 {
+    ///         *v *= -1;
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(sm[k0], -10);
+    /// assert_eq!(sm[k1], 20);
+    /// assert_eq!(sm[k2], -30);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<K, V> {
+        let mut versions = self.versions.iter().enumerate();
+        versions.next(); // Skip sentinel.
+        IterMut {
+            keys: Keys {
+                num_left: self.num_elems as usize,
+                versions,
+                _k: PhantomData,
+                _v: PhantomData,
+            },
+            values: &mut self.values,
+        }
+    }
+
+    /// An iterator visiting all keys in arbitrary order. The iterator element
+    /// type is `K`.
+    ///
+    /// Only the version array is read, so this is as fast for large values as
+    /// it is for small ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// # use std::collections::HashSet;
+    /// let mut sm = SoaSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    /// let k2 = sm.insert(30);
+    /// let keys: HashSet<_> = sm.keys().collect();
+    /// let check: HashSet<_> = vec![k0, k1, k2].into_iter().collect();
+    /// assert_eq!(keys, check);
+    /// ```
+    pub fn keys(&self) -> Keys<K, V> {
+        let mut versions = self.versions.iter().enumerate();
+        versions.next(); // Skip sentinel.
+        Keys {
+            num_left: self.len(),
+            versions,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    /// An iterator visiting all values in arbitrary order. The iterator element
+    /// type is `&'a V`.
+    ///
+    /// This function must iterate over all slots, empty or not. In the face of
+    /// many deleted elements it can be inefficient.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// # use std::collections::HashSet;
+    /// let mut sm = SoaSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    /// let k2 = sm.insert(30);
+    /// let values: HashSet<_> = sm.values().collect();
+    /// let check: HashSet<_> = vec![&10, &20, &30].into_iter().collect();
+    /// assert_eq!(values, check);
+    /// ```
+    pub fn values(&self) -> Values<K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// An iterator visiting all values mutably in arbitrary order. The iterator
+    /// element type is `&'a mut V`.
+    ///
+    /// This function must iterate over all slots, empty or not. In the face of
+    /// many deleted elements it can be inefficient.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// # use std::collections::HashSet;
+    /// let mut sm = SoaSlotMap::new();
+    /// sm.insert(1);
+    /// sm.insert(2);
+    /// sm.insert(3);
+    /// sm.values_mut().for_each(|n| { *n *= 3 });
+    /// let values: HashSet<_> = sm.into_iter().map(|(_k, v)| v).collect();
+    /// let check: HashSet<_> = vec![3, 6, 9].into_iter().collect();
+    /// assert_eq!(values, check);
+    /// ```
+    pub fn values_mut(&mut self) -> ValuesMut<K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    /// An iterator visiting the key-value pairs stored in the slots with an
+    /// index in `range`, in slot order. The iterator element type is
+    /// `(K, &'a V)`.
+    ///
+    /// The slot index of a key is `key.data().index()`. Out of bounds parts of
+    /// `range` are ignored. Use [`Range::position`] to find where to resume
+    /// iteration later, even if the slot map was modified in the meantime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let k0 = sm.insert(0);
+    /// let k1 = sm.insert(1);
+    /// let k2 = sm.insert(2);
+    ///
+    /// let idx = k1.data().index() as usize;
+    /// let v: Vec<_> = sm.range(idx..).collect();
+    /// assert_eq!(v, vec![(k1, &1), (k2, &2)]);
+    /// ```
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> Range<K, V> {
+        let (start, end) = slot_range(range, self.versions.len());
+        Range {
+            cur: start,
+            end,
+            versions: &self.versions,
+            values: &self.values,
+            _k: PhantomData,
+        }
+    }
+
+    /// An iterator visiting the key-value pairs stored in the slots with an
+    /// index in `range`, in slot order, with mutable references to the values.
+    /// The iterator element type is `(K, &'a mut V)`.
+    ///
+    /// See [`range`](Self::range) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// let k0 = sm.insert(10);
+    /// let k1 = sm.insert(20);
+    ///
+    /// let idx = k1.data().index() as usize;
+    /// sm.range_mut(idx..=idx).for_each(|(_, v)| *v *= -1);
+    /// assert_eq!(sm[k0], 10);
+    /// assert_eq!(sm[k1], -20);
+    /// ```
+    pub fn range_mut<R: RangeBounds<usize>>(&mut self, range: R) -> RangeMut<K, V> {
+        let (start, end) = slot_range(range, self.versions.len());
+        RangeMut {
+            cur: start,
+            end,
+            versions: &self.versions,
+            values: &mut self.values,
+            _k: PhantomData,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs stored at or after slot index
+    /// `position`, in slot order. The iterator element type is `(K, &'a V)`.
+    ///
+    /// This allows processing a slot map in chunks, resuming where the previous
+    /// chunk left off through [`Range::position`]. Elements inserted or
+    /// removed in between are handled gracefully: every element that stays in
+    /// the slot map is visited exactly once per pass.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// for i in 0..5 {
+    ///     sm.insert(i);
+    /// }
+    ///
+    /// let mut it = sm.iter_from(0);
+    /// let first: Vec<_> = it.by_ref().take(3).map(|(_, v)| *v).collect();
+    /// let position = it.position();
+    ///
+    /// sm.insert(5);
+    /// let rest: Vec<_> = sm.iter_from(position).map(|(_, v)| *v).collect();
+    /// assert_eq!(first, vec![0, 1, 2]);
+    /// assert_eq!(rest, vec![3, 4, 5]);
+    /// ```
+    pub fn iter_from(&self, position: usize) -> Range<K, V> {
+        self.range(position..)
+    }
+
+    /// An iterator visiting all key-value pairs stored at or after slot index
+    /// `position`, in slot order, with mutable references to the values. The
+    /// iterator element type is `(K, &'a mut V)`.
+    ///
+    /// See [`iter_from`](Self::iter_from) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SoaSlotMap::new();
+    /// for i in 0..4 {
+    ///     sm.insert(i);
+    /// }
+    ///
+    /// let mut it = sm.iter_mut_from(0);
+    /// it.by_ref().take(2).for_each(|(_, v)| *v *= 10);
+    /// let position = it.position();
+    /// sm.iter_mut_from(position).for_each(|(_, v)| *v *= 100);
+    ///
+    /// let values: Vec<_> = sm.values().copied().collect();
+    /// assert_eq!(values, vec![0, 10, 200, 300]);
+    /// ```
+    pub fn iter_mut_from(&mut self, position: usize) -> RangeMut<K, V> {
+        self.range_mut(position..)
+    }
+}
+
+impl<K: Key, V> Clone for SoaSlotMap<K, V>
+where
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        let values = self
+            .versions
+            .iter()
+            .zip(&self.values)
+            .map(|(version, value)| match occupied(version) {
+                true => MaybeUninit::new(unsafe { value.assume_init_ref() }.clone()),
+                false => MaybeUninit::uninit(),
+            })
+            .collect();
+
+        Self {
+            versions: self.versions.clone(),
+            values,
+            free: self.free.clone(),
+            ..*self
+        }
+    }
+}
+
+impl<K: Key, V> Drop for SoaSlotMap<K, V> {
+    fn drop(&mut self) {
+        if mem::needs_drop::<V>() {
+            for (version, value) in self.versions.iter().zip(&mut self.values) {
+                if occupied(version) {
+                    // This is safe because we checked that we're occupied.
+                    unsafe { value.assume_init_drop() };
+                }
+            }
+        }
+    }
+}
+
+impl<K: Key, V: fmt::Debug> fmt::Debug for SoaSlotMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Key, V> Default for SoaSlotMap<K, V> {
+    fn default() -> Self {
+        Self::with_key()
+    }
+}
+
+impl<K: Key, V> Index<K> for SoaSlotMap<K, V> {
+    type Output = V;
+
+    fn index(&self, key: K) -> &V {
+        match self.get(key) {
+            Some(r) => r,
+            None => panic!("invalid SoaSlotMap key used"),
+        }
+    }
+}
+
+impl<K: Key, V> IndexMut<K> for SoaSlotMap<K, V> {
+    fn index_mut(&mut self, key: K) -> &mut V {
+        match self.get_mut(key) {
+            Some(r) => r,
+            None => panic!("invalid SoaSlotMap key used"),
+        }
+    }
+}
+
+// Iterators.
+/// A draining iterator for [`SoaSlotMap`].
+///
+/// This iterator is created by [`SoaSlotMap::drain`].
+#[derive(Debug)]
+pub struct Drain<'a, K: 'a + Key, V: 'a> {
+    sm: &'a mut SoaSlotMap<K, V>,
+    cur: usize,
+    end: usize,
+}
+
+/// An iterator that moves key-value pairs out of a [`SoaSlotMap`].
+///
+/// This iterator is created by calling the `into_iter` method on
+/// [`SoaSlotMap`], provided by the [`IntoIterator`] trait.
+#[derive(Debug)]
+pub struct IntoIter<K: Key, V> {
+    num_left: usize,
+    versions: Enumerate<alloc::vec::IntoIter<u32>>,
+    values: Vec<MaybeUninit<V>>,
+    _k: PhantomData<fn(K) -> K>,
+}
+
+/// An iterator over the key-value pairs in a [`SoaSlotMap`].
+///
+/// This iterator is created by [`SoaSlotMap::iter`].
+#[derive(Debug)]
+pub struct Iter<'a, K: 'a + Key, V: 'a> {
+    keys: Keys<'a, K, V>,
+    values: &'a [MaybeUninit<V>],
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Iter<'a, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            keys: self.keys.clone(),
+            values: self.values,
+        }
+    }
+}
+
+/// A mutable iterator over the key-value pairs in a [`SoaSlotMap`].
+///
+/// This iterator is created by [`SoaSlotMap::iter_mut`].
+#[derive(Debug)]
+pub struct IterMut<'a, K: 'a + Key, V: 'a> {
+    keys: Keys<'a, K, V>,
+    values: &'a mut [MaybeUninit<V>],
+}
+
+/// An iterator over the keys in a [`SoaSlotMap`].
+///
+/// This iterator is created by [`SoaSlotMap::keys`].
+#[derive(Debug)]
+pub struct Keys<'a, K: 'a + Key, V: 'a> {
+    num_left: usize,
+    versions: Enumerate<core::slice::Iter<'a, u32>>,
+    _k: PhantomData<fn(K) -> K>,
+    _v: PhantomData<&'a V>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Keys<'a, K, V> {
+    fn clone(&self) -> Self {
+        Keys {
+            num_left: self.num_left,
+            versions: self.versions.clone(),
+            _k: self._k,
+            _v: self._v,
+        }
+    }
+}
+
+/// An iterator over the values in a [`SoaSlotMap`].
+///
+/// This iterator is created by [`SoaSlotMap::values`].
+#[derive(Debug)]
+pub struct Values<'a, K: 'a + Key, V: 'a> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Values<'a, K, V> {
+    fn clone(&self) -> Self {
+        Values {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// A mutable iterator over the values in a [`SoaSlotMap`].
+///
+/// This iterator is created by [`SoaSlotMap::values_mut`].
+#[derive(Debug)]
+pub struct ValuesMut<'a, K: 'a + Key, V: 'a> {
+    inner: IterMut<'a, K, V>,
+}
+
+/// An iterator over the key-value pairs in a range of slots of a
+/// [`SoaSlotMap`].
+///
+/// This iterator is created by [`SoaSlotMap::range`] and
+/// [`SoaSlotMap::iter_from`].
+#[derive(Debug)]
+pub struct Range<'a, K: 'a + Key, V: 'a> {
+    cur: usize,
+    end: usize,
+    versions: &'a [u32],
+    values: &'a [MaybeUninit<V>],
+    _k: PhantomData<fn(K) -> K>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Range<'a, K, V> {
+    fn clone(&self) -> Self {
+        Range {
+            cur: self.cur,
+            end: self.end,
+            versions: self.versions,
+            values: self.values,
+            _k: self._k,
+        }
+    }
+}
+
+impl<'a, K: 'a + Key, V: 'a> Range<'a, K, V> {
+    /// Returns the slot index from which iteration continues. Pass it to
+    /// [`SoaSlotMap::iter_from`] to resume iterating later.
+    pub fn position(&self) -> usize {
+        self.cur
+    }
+}
+
+/// A mutable iterator over the key-value pairs in a range of slots of a
+/// [`SoaSlotMap`].
+///
+/// This iterator is created by [`SoaSlotMap::range_mut`] and
+/// [`SoaSlotMap::iter_mut_from`].
+#[derive(Debug)]
+pub struct RangeMut<'a, K: 'a + Key, V: 'a> {
+    cur: usize,
+    end: usize,
+    versions: &'a [u32],
+    values: &'a mut [MaybeUninit<V>],
+    _k: PhantomData<fn(K) -> K>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> RangeMut<'a, K, V> {
+    /// Returns the slot index from which iteration continues. Pass it to
+    /// [`SoaSlotMap::iter_mut_from`] to resume iterating later.
+    pub fn position(&self) -> usize {
+        self.cur
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.cur < self.end {
+            let idx = self.cur;
+            self.cur += 1;
+
+            // This is safe because removing doesn't shrink slots.
+            unsafe {
+                let version = *self.sm.versions.get_unchecked(idx);
+                if occupied(&version) {
+                    let kd = key_data(idx as u32, version);
+                    return Some((kd.into(), self.sm.remove_from_slot(idx)));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.sm.len(), Some(self.sm.len()))
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Drain<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        while self.cur < self.end {
+            self.end -= 1;
+            let idx = self.end;
+
+            // This is safe because removing doesn't shrink slots.
+            unsafe {
+                let version = *self.sm.versions.get_unchecked(idx);
+                if occupied(&version) {
+                    let kd = key_data(idx as u32, version);
+                    return Some((kd.into(), self.sm.remove_from_slot(idx)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a, K: Key, V> Drop for Drain<'a, K, V> {
+    fn drop(&mut self) {
+        self.for_each(|_drop| {});
+    }
+}
+
+impl<K: Key, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        for (idx, version) in self.versions.by_ref() {
+            if occupied(&version) {
+                let kd = unsafe { key_data(idx as u32, version) };
+
+                // This is safe because we know the slot was occupied, and the
+                // version iterator won't yield it again.
+                let value = unsafe { self.values[idx].assume_init_read() };
+
+                self.num_left -= 1;
+                return Some((kd.into(), value));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.num_left, Some(self.num_left))
+    }
+}
+
+impl<K: Key, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        while let Some((idx, version)) = self.versions.next_back() {
+            if occupied(&version) {
+                let kd = unsafe { key_data(idx as u32, version) };
+
+                // This is safe because we know the slot was occupied, and the
+                // version iterator won't yield it again.
+                let value = unsafe { self.values[idx].assume_init_read() };
+
+                self.num_left -= 1;
+                return Some((kd.into(), value));
+            }
+        }
+
+        None
+    }
+}
+
+impl<K: Key, V> Drop for IntoIter<K, V> {
+    fn drop(&mut self) {
+        self.for_each(|_drop| {});
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Iter<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        let key = self.keys.next()?;
+        let value = unsafe { self.values.get_unchecked(key.data().index() as usize) };
+        Some((key, unsafe { value.assume_init_ref() }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a V)> {
+        let key = self.keys.next_back()?;
+        let value = unsafe { self.values.get_unchecked(key.data().index() as usize) };
+        Some((key, unsafe { value.assume_init_ref() }))
+    }
+}
+
+impl<'a, K: Key, V> IterMut<'a, K, V> {
+    // Unsafe necessary because Rust can't deduce that we won't return multiple
+    // references to the same value.
+    fn value_ref(&mut self, key: K) -> (K, &'a mut V) {
+        let value_ref = unsafe {
+            let ptr: *mut V = self.values.get_unchecked_mut(key.data().index() as usize).as_mut_ptr();
+            &mut *ptr
+        };
+        (key, value_ref)
+    }
+}
+
+impl<'a, K: Key, V> Iterator for IterMut<'a, K, V> {
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<(K, &'a mut V)> {
+        let key = self.keys.next()?;
+        Some(self.value_ref(key))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, &'a mut V)> {
+        let key = self.keys.next_back()?;
+        Some(self.value_ref(key))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Keys<'a, K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        for (idx, version) in self.versions.by_ref() {
+            if occupied(version) {
+                self.num_left -= 1;
+                return Some(unsafe { key_data(idx as u32, *version) }.into());
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.num_left, Some(self.num_left))
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<K> {
+        while let Some((idx, version)) = self.versions.next_back() {
+            if occupied(version) {
+                self.num_left -= 1;
+                return Some(unsafe { key_data(idx as u32, *version) }.into());
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K: Key, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K: Key, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Range<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        while self.cur < self.end {
+            let idx = self.cur;
+            self.cur += 1;
+
+            // This is safe because end never exceeds the number of slots.
+            let version = unsafe { *self.versions.get_unchecked(idx) };
+            if occupied(&version) {
+                let kd = unsafe { key_data(idx as u32, version) };
+                let value = unsafe { self.values.get_unchecked(idx).assume_init_ref() };
+                return Some((kd.into(), value));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.cur))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for RangeMut<'a, K, V> {
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<(K, &'a mut V)> {
+        while self.cur < self.end {
+            let idx = self.cur;
+            self.cur += 1;
+
+            let version = unsafe { *self.versions.get_unchecked(idx) };
+            if occupied(&version) {
+                let kd = unsafe { key_data(idx as u32, version) };
+
+                // Unsafe necessary because Rust can't deduce that we won't
+                // return multiple references to the same value.
+                let value_ref = unsafe {
+                    let ptr: *mut V = self.values.get_unchecked_mut(idx).as_mut_ptr();
+                    &mut *ptr
+                };
+                return Some((kd.into(), value_ref));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.cur))
+    }
+}
+
+impl<'a, K: Key, V> IntoIterator for &'a SoaSlotMap<K, V> {
+    type Item = (K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Key, V> IntoIterator for &'a mut SoaSlotMap<K, V> {
+    type Item = (K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K: Key, V> IntoIterator for SoaSlotMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        // The values are moved to the iterator, so the map must not drop them.
+        let len = self.len();
+        let mut versions = mem::take(&mut self.versions).into_iter().enumerate();
+        versions.next(); // Skip sentinel.
+        IntoIter {
+            num_left: len,
+            versions,
+            values: mem::take(&mut self.values),
+            _k: PhantomData,
+        }
+    }
+}
+
+impl<'a, K: Key, V> FusedIterator for Iter<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for IterMut<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for Keys<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for Values<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for ValuesMut<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for Drain<'a, K, V> {}
+impl<K: Key, V> FusedIterator for IntoIter<K, V> {}
+impl<'a, K: Key, V> FusedIterator for Range<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for RangeMut<'a, K, V> {}
+
+impl<'a, K: Key, V> ExactSizeIterator for Iter<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for IterMut<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for Keys<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for Values<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for ValuesMut<'a, K, V> {}
+impl<'a, K: Key, V> ExactSizeIterator for Drain<'a, K, V> {}
+impl<K: Key, V> ExactSizeIterator for IntoIter<K, V> {}
+
+// Parallel iteration with rayon.
+#[cfg(feature = "rayon")]
+mod parallel {
+    use rayon::prelude::*;
+
+    use super::*;
+    use crate::par::ParSlots;
+
+    // Lets the threads of `par_iter_mut` reach the values of the slots they
+    // were handed, which are disjoint.
+    struct ValuesPtr<V>(*mut MaybeUninit<V>);
+
+    unsafe impl<V: Send> Send for ValuesPtr<V> {}
+    unsafe impl<V: Send> Sync for ValuesPtr<V> {}
+
+    impl<V> ValuesPtr<V> {
+        unsafe fn get<'a>(&self, idx: usize) -> &'a mut V {
+            (*self.0.add(idx)).assume_init_mut()
+        }
+    }
+
+    impl<K: Key, V> SoaSlotMap<K, V> {
+        /// A parallel iterator visiting all key-value pairs in arbitrary order.
+        /// The work is split such that every thread gets about the same number
+        /// of elements, regardless of how the vacant slots are spread out.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SoaSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// let sum: u64 = sm.par_iter().map(|(_, &v)| v).sum();
+        /// assert_eq!(sum, 500 * 999);
+        /// ```
+        pub fn par_iter(&self) -> impl ParallelIterator<Item = (K, &V)>
+        where
+            K: Send,
+            V: Sync,
+        {
+            let values = &self.values;
+            ParSlots::new(&self.versions[1..], 1, occupied).map(move |(idx, &version)| {
+                let key = unsafe { key_data(idx as u32, version).into() };
+                (key, unsafe { values[idx].assume_init_ref() })
+            })
+        }
+
+        /// A parallel iterator visiting all key-value pairs in arbitrary order,
+        /// with mutable references to the values.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SoaSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// sm.par_iter_mut().for_each(|(_, v)| *v *= 2);
+        /// assert_eq!(sm.values().sum::<u64>(), 1000 * 999);
+        /// ```
+        pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (K, &mut V)>
+        where
+            K: Send,
+            V: Send,
+        {
+            let values = ValuesPtr(self.values.as_mut_ptr());
+            ParSlots::new(&self.versions[1..], 1, occupied).map(move |(idx, &version)| {
+                let key = unsafe { key_data(idx as u32, version).into() };
+                // Every occupied slot is yielded once.
+                (key, unsafe { values.get(idx) })
+            })
+        }
+
+        /// A parallel iterator visiting all keys in arbitrary order. Only the
+        /// version array is read.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SoaSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// assert_eq!(sm.par_keys().count(), 1000);
+        /// ```
+        pub fn par_keys(&self) -> impl ParallelIterator<Item = K> + '_
+        where
+            K: Send,
+        {
+            ParSlots::new(&self.versions[1..], 1, occupied)
+                .map(|(idx, &version)| unsafe { key_data(idx as u32, version).into() })
+        }
+
+        /// A parallel iterator visiting all values mutably in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SoaSlotMap::new();
+        /// for i in 0..1000u64 {
+        ///     sm.insert(i);
+        /// }
+        /// sm.par_values_mut().for_each(|v| *v += 1);
+        /// assert_eq!(sm.values().sum::<u64>(), 500 * 1001);
+        /// ```
+        pub fn par_values_mut(&mut self) -> impl ParallelIterator<Item = &mut V>
+        where
+            K: Send,
+            V: Send,
+        {
+            self.par_iter_mut().map(|(_, value)| value)
+        }
+    }
+}
+
+// Serialization with serde, in the same format as SlotMap.
+#[cfg(feature = "serde")]
+mod serialize {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct SerdeSlot<T> {
+        value: Option<T>,
+        version: u32,
+    }
+
+    impl<K: Key, V: Serialize> Serialize for SoaSlotMap<K, V> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_seq(self.versions.iter().zip(&self.values).map(|(version, value)| {
+                SerdeSlot {
+                    version: *version,
+                    value: match occupied(version) {
+                        true => Some(unsafe { value.assume_init_ref() }),
+                        false => None,
+                    },
+                }
+            }))
+        }
+    }
+
+    impl<'de, K, V> Deserialize<'de> for SoaSlotMap<K, V>
+    where
+        K: Key,
+        V: Deserialize<'de>,
+    {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let slots: Vec<SerdeSlot<V>> = Deserialize::deserialize(deserializer)?;
+            if slots.len() >= u32::MAX as usize {
+                return Err(de::Error::custom("too many slots"));
+            }
+
+            // Ensure the first slot exists and is empty for the sentinel.
+            if slots.first().map_or(true, |slot| occupied(&slot.version)) {
+                return Err(de::Error::custom("first slot not empty"));
+            }
+
+            let mut sm = Self::with_capacity_and_key(slots.len() - 1);
+            for (i, slot) in slots.into_iter().enumerate().skip(1) {
+                if occupied(&slot.version) ^ slot.value.is_some() {
+                    return Err(de::Error::custom("inconsistent occupation in Slot"));
+                }
+
+                // We have our slots, rebuild the free list.
+                match slot.value {
+                    Some(value) => {
+                        sm.values.push(MaybeUninit::new(value));
+                        sm.num_elems += 1;
+                    },
+                    None => {
+                        sm.values.push(MaybeUninit::uninit());
+                        sm.free.push(i as u32);
+                    },
+                }
+                sm.versions.push(slot.version);
+            }
+
+            Ok(sm)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::*;
+
+    #[derive(Clone)]
+    struct CountDrop<'a>(&'a RefCell<usize>, u32);
+
+    impl<'a> Drop for CountDrop<'a> {
+        fn drop(&mut self) {
+            *self.0.borrow_mut() += 1;
+        }
+    }
+
+    #[test]
+    fn columns_stay_consistent() {
+        let drops = RefCell::new(0usize);
+        let num_cloned;
+        {
+            let mut sm = SoaSlotMap::new();
+            let mut reference = SlotMap::new();
+            let mut keys = Vec::new();
+            for i in 0..100 {
+                let key = sm.insert(CountDrop(&drops, i));
+                assert_eq!(key, reference.insert(i));
+                keys.push(key);
+            }
+
+            // Same keys and slot reuse as SlotMap, through removal and retain.
+            for &key in keys.iter().step_by(3) {
+                assert_eq!(sm.remove(key).map(|v| v.1), reference.remove(key));
+            }
+            sm.retain(|_, v| v.1 % 5 != 0);
+            reference.retain(|_, v| *v % 5 != 0);
+            assert_eq!(*drops.borrow(), 100 - sm.len());
+            for i in 100..120 {
+                let key = sm.insert(CountDrop(&drops, i));
+                assert_eq!(key, reference.insert(i));
+            }
+
+            assert_eq!(sm.len(), reference.len());
+            assert!(sm.keys().eq(reference.keys()));
+            assert!(sm.keys().rev().eq(reference.keys().rev()));
+            assert!(sm.iter().map(|(k, v)| (k, v.1)).eq(reference.iter().map(|(k, v)| (k, *v))));
+            assert!(sm.iter_mut().rev().map(|(k, v)| (k, v.1)).eq(reference.iter().rev().map(|(k, v)| (k, *v))));
+            for &key in &keys {
+                assert_eq!(sm.get(key).map(|v| v.1), reference.get(key).copied());
+            }
+
+            let before = *drops.borrow();
+            let cloned = sm.clone();
+            num_cloned = cloned.len();
+            let mut into_iter = cloned.into_iter();
+            assert_eq!(into_iter.next_back().map(|(k, v)| (k, v.1)), reference.iter().next_back().map(|(k, v)| (k, *v)));
+            drop(into_iter);
+            assert_eq!(*drops.borrow(), before + sm.len());
+
+            let mut drain = sm.drain();
+            drain.next();
+            drop(drain);
+            assert!(sm.is_empty());
+            assert_eq!(*drops.borrow(), before + 2 * reference.len());
+        }
+        assert_eq!(*drops.borrow(), 120 + num_cloned);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_iter_mut_needs_only_send() {
+        use rayon::prelude::*;
+        use std::cell::Cell;
+
+        // Cell is Send but not Sync.
+        let mut sm = SoaSlotMap::new();
+        let keys: Vec<_> = (0..1_000).map(|i| sm.insert(Cell::new(i))).collect();
+        for &k in &keys[..500] {
+            sm.remove(k);
+        }
+        sm.par_values_mut().for_each(|c| *c.get_mut() *= 2);
+        sm.par_iter_mut().for_each(|(_, c)| *c.get_mut() += 1);
+        assert!((500..1_000).all(|i| sm[keys[i]].get() == 2 * i + 1));
+        let mut par_keys: Vec<_> = sm.par_keys().collect();
+        par_keys.sort();
+        assert_eq!(par_keys, keys[500..]);
+    }
+}
@@ -11,6 +11,7 @@ use std::marker::PhantomData;
 use std::ops::{Index, IndexMut};
 
 use pi_key_alloter::{Key, KeyData, key_data, is_older_version};
+use crate::primary::PrimaryMap;
 use crate::util::UnwrapUnchecked;
 
 #[derive(Debug, Clone)]
@@ -376,6 +377,50 @@ impl<K: Key, V, S: hash::BuildHasher> SparseSecondaryMap<K, V, S> {
         })
     }
 
+    /// Removes every entry whose key is no longer valid in `primary`, which
+    /// can be any slot map. Entries of removed keys otherwise stay in the
+    /// secondary map until their slot is reused by an insertion.
+    ///
+    /// This takes one pass over whichever of the two is shorter: if `primary`
+    /// has fewer elements, the live entries are found through its keys and the
+    /// others are dropped without looking them up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut sec = SparseSecondaryMap::new();
+    /// let a = sm.insert(1);
+    /// let b = sm.insert(2);
+    /// sec.insert(a, "a");
+    /// sec.insert(b, "b");
+    ///
+    /// sm.remove(a);
+    /// assert_eq!(sec.len(), 2);
+    /// sec.retain_live(&sm);
+    /// assert_eq!(sec.keys().collect::<Vec<_>>(), [b]);
+    /// ```
+    pub fn retain_live<P: PrimaryMap<K>>(&mut self, primary: &P) {
+        if self.len() <= primary.len() {
+            self.retain(|key, _| primary.contains_key(key));
+            return;
+        }
+
+        let live: Vec<_> = primary
+            .keys()
+            .filter_map(|key| {
+                let kd = key.data();
+                match self.slots.get(&kd.index()) {
+                    Some(slot) if slot.version == kd.version() => self.slots.remove_entry(&kd.index()),
+                    _ => None,
+                }
+            })
+            .collect();
+        self.slots.clear();
+        self.slots.extend(live);
+    }
+
     /// Clears the secondary map. Keeps the allocated memory for reuse.
     ///
     /// # Examples
@@ -423,6 +468,174 @@ impl<K: Key, V, S: hash::BuildHasher> SparseSecondaryMap<K, V, S> {
         }
     }
 
+    /// Moves all entries of `other` into the secondary map. When both maps
+    /// have an entry for the same slot, the entry with the newest key is kept,
+    /// just like [`insert`](Self::insert) would. For equal keys the value of
+    /// `other` replaces the current one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let old_b = sm.insert(());
+    /// sm.remove(old_b);
+    /// let b = sm.insert(());
+    ///
+    /// let mut sec = SparseSecondaryMap::new();
+    /// sec.insert(a, 1);
+    /// sec.insert(b, 2);
+    /// let mut snapshot = SparseSecondaryMap::new();
+    /// snapshot.insert(a, 10);
+    /// snapshot.insert(old_b, 20);
+    ///
+    /// sec.union_with(snapshot);
+    /// assert_eq!(sec[a], 10);
+    /// assert_eq!(sec[b], 2);
+    /// ```
+    pub fn union_with(&mut self, other: Self) {
+        self.merge(other, |ours, theirs| {
+            match is_older_version(theirs.0.data().version(), ours.0.data().version()) {
+                true => ours,
+                false => theirs,
+            }
+        });
+    }
+
+    /// Moves all entries of `other` into the secondary map. When both maps
+    /// have an entry for the same slot, `resolve` is called with the entry of
+    /// the secondary map and the entry of `other`, and returns the entry to
+    /// keep. [`union_with`](Self::union_with) resolves conflicts in favor of
+    /// the newest key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `resolve` returns a key of another slot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let b = sm.insert(());
+    ///
+    /// let mut hits = SparseSecondaryMap::new();
+    /// hits.insert(a, 1);
+    /// let mut other = SparseSecondaryMap::new();
+    /// other.insert(a, 2);
+    /// other.insert(b, 5);
+    ///
+    /// hits.merge(other, |(key, ours), (_, theirs)| (key, ours + theirs));
+    /// assert_eq!(hits[a], 3);
+    /// assert_eq!(hits[b], 5);
+    /// ```
+    pub fn merge<F>(&mut self, other: Self, mut resolve: F)
+    where
+        F: FnMut((K, V), (K, V)) -> (K, V),
+    {
+        for (key, value) in other {
+            let idx = key.data().index();
+            let (key, value) = match self.slots.remove(&idx) {
+                Some(slot) => {
+                    let ours = unsafe { key_data(idx, slot.version).into() };
+                    resolve((ours, slot.value), (key, value))
+                },
+                None => (key, value),
+            };
+            assert!(
+                key.data().index() == idx,
+                "merge resolved to a key of another slot"
+            );
+            self.insert(key, value);
+        }
+    }
+
+    /// Retains only the entries whose key is also in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let b = sm.insert(());
+    ///
+    /// let mut sec = SparseSecondaryMap::new();
+    /// sec.insert(a, "a");
+    /// sec.insert(b, "b");
+    /// let mut visible = SparseSecondaryMap::new();
+    /// visible.insert(b, true);
+    ///
+    /// sec.intersect_with(&visible);
+    /// assert_eq!(sec.keys().collect::<Vec<_>>(), [b]);
+    /// ```
+    pub fn intersect_with<W, T: hash::BuildHasher>(&mut self, other: &SparseSecondaryMap<K, W, T>) {
+        self.retain(|key, _| other.contains_key(key));
+    }
+
+    /// An iterator visiting the key-value pairs whose key is not in `other`,
+    /// in arbitrary order. The iterator element type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let b = sm.insert(());
+    ///
+    /// let mut sec = SparseSecondaryMap::new();
+    /// sec.insert(a, "a");
+    /// sec.insert(b, "b");
+    /// let mut hidden = SparseSecondaryMap::new();
+    /// hidden.insert(b, ());
+    ///
+    /// assert_eq!(sec.difference(&hidden).collect::<Vec<_>>(), [(a, &"a")]);
+    /// ```
+    pub fn difference<'a, W, T: hash::BuildHasher>(
+        &'a self,
+        other: &'a SparseSecondaryMap<K, W, T>,
+    ) -> Difference<'a, K, V, W, T> {
+        Difference {
+            inner: self.iter(),
+            other,
+        }
+    }
+
+    /// An iterator visiting the key-value pairs of either map whose key is not
+    /// in the other map, first those of the secondary map and then those of
+    /// `other`, each in arbitrary order. The iterator element type is
+    /// `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// # use std::collections::HashSet;
+    /// let mut sm = SlotMap::new();
+    /// let a = sm.insert(());
+    /// let b = sm.insert(());
+    /// let c = sm.insert(());
+    ///
+    /// let mut before = SparseSecondaryMap::new();
+    /// before.insert(a, 1);
+    /// before.insert(b, 2);
+    /// let mut after = SparseSecondaryMap::new();
+    /// after.insert(b, 2);
+    /// after.insert(c, 3);
+    ///
+    /// let changed: HashSet<_> = before.symmetric_difference(&after).collect();
+    /// assert_eq!(changed, [(a, &1), (c, &3)].into_iter().collect());
+    /// ```
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, K, V, S> {
+        SymmetricDifference {
+            ours: self.difference(other),
+            theirs: other.difference(self),
+        }
+    }
+
     /// Returns a reference to the value corresponding to the key.
     ///
     /// # Examples
@@ -1363,6 +1576,44 @@ pub struct ValuesMut<'a, K: Key + 'a, V: 'a> {
     inner: IterMut<'a, K, V>,
 }
 
+/// An iterator over the key-value pairs of a [`SparseSecondaryMap`] whose key
+/// is not in another map.
+///
+/// This iterator is created by [`SparseSecondaryMap::difference`].
+#[derive(Debug)]
+pub struct Difference<'a, K: Key + 'a, V: 'a, W: 'a, S: hash::BuildHasher + 'a> {
+    inner: Iter<'a, K, V>,
+    other: &'a SparseSecondaryMap<K, W, S>,
+}
+
+impl<'a, K: 'a + Key, V: 'a, W: 'a, S: hash::BuildHasher + 'a> Clone for Difference<'a, K, V, W, S> {
+    fn clone(&self) -> Self {
+        Difference {
+            inner: self.inner.clone(),
+            other: self.other,
+        }
+    }
+}
+
+/// An iterator over the key-value pairs of two [`SparseSecondaryMap`]s whose
+/// key is in only one of them.
+///
+/// This iterator is created by [`SparseSecondaryMap::symmetric_difference`].
+#[derive(Debug)]
+pub struct SymmetricDifference<'a, K: Key + 'a, V: 'a, S: hash::BuildHasher + 'a> {
+    ours: Difference<'a, K, V, V, S>,
+    theirs: Difference<'a, K, V, V, S>,
+}
+
+impl<'a, K: 'a + Key, V: 'a, S: hash::BuildHasher + 'a> Clone for SymmetricDifference<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        SymmetricDifference {
+            ours: self.ours.clone(),
+            theirs: self.theirs.clone(),
+        }
+    }
+}
+
 impl<'a, K: Key, V> Iterator for Drain<'a, K, V> {
     type Item = (K, V);
 
@@ -1465,6 +1716,33 @@ impl<'a, K: Key, V> Iterator for ValuesMut<'a, K, V> {
     }
 }
 
+impl<'a, K: Key, V, W, S: hash::BuildHasher> Iterator for Difference<'a, K, V, W, S> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        let other = self.other;
+        self.inner.find(|&(key, _)| !other.contains_key(key))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
+}
+
+impl<'a, K: Key, V, S: hash::BuildHasher> Iterator for SymmetricDifference<'a, K, V, S> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        self.ours.next().or_else(|| self.theirs.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let ours = self.ours.size_hint().1;
+        let theirs = self.theirs.size_hint().1;
+        (0, ours.zip(theirs).map(|(a, b)| a + b))
+    }
+}
+
 impl<'a, K, V, S> IntoIterator for &'a SparseSecondaryMap<K, V, S>
 where
     K: Key,
@@ -1514,6 +1792,8 @@ impl<'a, K: Key, V> FusedIterator for Values<'a, K, V> {}
 impl<'a, K: Key, V> FusedIterator for ValuesMut<'a, K, V> {}
 impl<'a, K: Key, V> FusedIterator for Drain<'a, K, V> {}
 impl<K: Key, V> FusedIterator for IntoIter<K, V> {}
+impl<'a, K: Key, V, W, S: hash::BuildHasher> FusedIterator for Difference<'a, K, V, W, S> {}
+impl<'a, K: Key, V, S: hash::BuildHasher> FusedIterator for SymmetricDifference<'a, K, V, S> {}
 
 impl<'a, K: Key, V> ExactSizeIterator for Iter<'a, K, V> {}
 impl<'a, K: Key, V> ExactSizeIterator for IterMut<'a, K, V> {}
@@ -1523,6 +1803,117 @@ impl<'a, K: Key, V> ExactSizeIterator for ValuesMut<'a, K, V> {}
 impl<'a, K: Key, V> ExactSizeIterator for Drain<'a, K, V> {}
 impl<K: Key, V> ExactSizeIterator for IntoIter<K, V> {}
 
+// Parallel iteration with rayon.
+#[cfg(feature = "rayon")]
+mod parallel {
+    use rayon::prelude::*;
+
+    use super::*;
+
+    impl<K: Key, V, S: hash::BuildHasher> SparseSecondaryMap<K, V, S> {
+        /// A parallel iterator visiting all key-value pairs in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// let mut sec = SparseSecondaryMap::new();
+        /// for i in 0..1000u64 {
+        ///     sec.insert(sm.insert(()), i);
+        /// }
+        /// let sum: u64 = sec.par_iter().map(|(_, &v)| v).sum();
+        /// assert_eq!(sum, 500 * 999);
+        /// ```
+        pub fn par_iter(&self) -> impl ParallelIterator<Item = (K, &V)>
+        where
+            K: Send,
+            V: Sync,
+            S: Sync,
+        {
+            self.slots.par_iter().map(|(&idx, slot)| {
+                let key = unsafe { key_data(idx, slot.version).into() };
+                (key, &slot.value)
+            })
+        }
+
+        /// A parallel iterator visiting all key-value pairs in arbitrary order,
+        /// with mutable references to the values.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// let mut sec = SparseSecondaryMap::new();
+        /// for i in 0..1000u64 {
+        ///     sec.insert(sm.insert(()), i);
+        /// }
+        /// sec.par_iter_mut().for_each(|(_, v)| *v *= 2);
+        /// assert_eq!(sec.values().sum::<u64>(), 1000 * 999);
+        /// ```
+        pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (K, &mut V)>
+        where
+            K: Send,
+            V: Send,
+            S: Send,
+        {
+            self.slots.par_iter_mut().map(|(&idx, slot)| {
+                let key = unsafe { key_data(idx, slot.version).into() };
+                (key, &mut slot.value)
+            })
+        }
+
+        /// A parallel iterator visiting all keys in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// let mut sec = SparseSecondaryMap::new();
+        /// for i in 0..1000u64 {
+        ///     sec.insert(sm.insert(()), i);
+        /// }
+        /// assert_eq!(sec.par_keys().count(), 1000);
+        /// ```
+        pub fn par_keys(&self) -> impl ParallelIterator<Item = K> + '_
+        where
+            K: Send,
+            V: Sync,
+            S: Sync,
+        {
+            self.par_iter().map(|(key, _)| key)
+        }
+
+        /// A parallel iterator visiting all values mutably in arbitrary order.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use slotmap::*;
+        /// use rayon::prelude::*;
+        /// let mut sm = SlotMap::new();
+        /// let mut sec = SparseSecondaryMap::new();
+        /// for i in 0..1000u64 {
+        ///     sec.insert(sm.insert(()), i);
+        /// }
+        /// sec.par_values_mut().for_each(|v| *v += 1);
+        /// assert_eq!(sec.values().sum::<u64>(), 500 * 1001);
+        /// ```
+        pub fn par_values_mut(&mut self) -> impl ParallelIterator<Item = &mut V>
+        where
+            V: Send,
+            S: Send,
+        {
+            self.slots.par_iter_mut().map(|(_, slot)| &mut slot.value)
+        }
+    }
+}
+
 // Serialization with serde.
 #[cfg(feature = "serde")]
 mod serialize {
@@ -1713,4 +2104,6 @@ mod tests {
             secv == hmv
         }
     }
+
+    crate::util::set_operation_tests!(SparseSecondaryMap);
 }
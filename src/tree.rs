@@ -0,0 +1,967 @@
+//! Contains the slot tree implementation, a forest of nodes addressed by slot
+//! map keys.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+use core::ops::{Index, IndexMut};
+
+use crate::{basic, SlotMap};
+use pi_key_alloter::{DefaultKey, Key};
+
+// A tree node together with the links to its relatives. Null keys mark
+// missing relatives.
+#[derive(Debug, Clone)]
+struct Node<K, V> {
+    value: V,
+    parent: K,
+    first_child: K,
+    last_child: K,
+    prev_sibling: K,
+    next_sibling: K,
+}
+
+/// Slot tree, a hierarchy of nodes backed by a [`SlotMap`].
+///
+/// The tree is really a forest: [`insert`](Self::insert) creates a new root,
+/// and nodes are attached under others with [`append_child`],
+/// [`insert_before`] and [`insert_after`]. Every node is addressed by the key
+/// returned when inserting it, and keys of removed nodes are rejected just
+/// like slot map keys. The children of a node are ordered, and attaching,
+/// detaching or removing a single node is O(1).
+///
+/// Removing a node with [`remove_subtree`] removes all of its descendants
+/// too, so the tree never contains orphaned nodes.
+///
+/// [`append_child`]: Self::append_child
+/// [`insert_before`]: Self::insert_before
+/// [`insert_after`]: Self::insert_after
+/// [`remove_subtree`]: Self::remove_subtree
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut tree = SlotTree::new();
+/// let root = tree.insert("root");
+/// let a = tree.append_child(root, "a").unwrap();
+/// let b = tree.append_child(root, "b").unwrap();
+/// let c = tree.append_child(a, "c").unwrap();
+///
+/// let order: Vec<_> = tree.dfs(root).map(|(_, v)| *v).collect();
+/// assert_eq!(order, ["root", "a", "c", "b"]);
+///
+/// assert!(tree.reparent(a, b));
+/// assert!(!tree.reparent(b, c)); // Would create a cycle.
+/// assert_eq!(tree.ancestors(c).map(|(k, _)| k).collect::<Vec<_>>(), [a, b, root]);
+///
+/// let removed = tree.remove_subtree(b);
+/// assert_eq!(removed.len(), 3);
+/// assert_eq!(tree.len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlotTree<K: Key, V> {
+    sm: SlotMap<K, Node<K, V>>,
+}
+
+impl<V> SlotTree<DefaultKey, V> {
+    /// Constructs a new, empty [`SlotTree`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree: SlotTree<_, i32> = SlotTree::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`SlotTree`] with the given capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree: SlotTree<_, i32> = SlotTree::with_capacity(10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_key(capacity)
+    }
+}
+
+impl<K: Key, V> SlotTree<K, V> {
+    /// Constructs a new, empty [`SlotTree`] with a custom key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct NodeKey;
+    /// }
+    /// let mut tree: SlotTree<NodeKey, i32> = SlotTree::with_key();
+    /// ```
+    pub fn with_key() -> Self {
+        Self::with_capacity_and_key(0)
+    }
+
+    /// Creates an empty [`SlotTree`] with the given capacity and a custom key
+    /// type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// new_key_type! {
+    ///     struct NodeKey;
+    /// }
+    /// let mut tree: SlotTree<NodeKey, i32> = SlotTree::with_capacity_and_key(10);
+    /// ```
+    pub fn with_capacity_and_key(capacity: usize) -> Self {
+        Self {
+            sm: SlotMap::with_capacity_and_key(capacity),
+        }
+    }
+
+    /// Returns the number of nodes in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert(0);
+    /// tree.append_child(root, 1);
+    /// assert_eq!(tree.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.sm.len()
+    }
+
+    /// Returns if the tree is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let key = tree.insert("dummy");
+    /// assert_eq!(tree.is_empty(), false);
+    /// tree.remove_subtree(key);
+    /// assert_eq!(tree.is_empty(), true);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.sm.is_empty()
+    }
+
+    /// Reserves capacity for at least `additional` more nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new allocation size overflows [`usize`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// tree.insert("foo");
+    /// tree.reserve(32);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.sm.reserve(additional)
+    }
+
+    /// Returns [`true`] if the tree contains `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let key = tree.insert(42);
+    /// assert_eq!(tree.contains_key(key), true);
+    /// tree.remove_subtree(key);
+    /// assert_eq!(tree.contains_key(key), false);
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.sm.contains_key(key)
+    }
+
+    /// Clears the tree. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// tree.insert(0);
+    /// tree.clear();
+    /// assert_eq!(tree.len(), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        self.sm.clear()
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let key = tree.insert("bar");
+    /// assert_eq!(tree.get(key), Some(&"bar"));
+    /// tree.remove_subtree(key);
+    /// assert_eq!(tree.get(key), None);
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.sm.get(key).map(|node| &node.value)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let key = tree.insert(3.5);
+    /// if let Some(x) = tree.get_mut(key) {
+    ///     *x += 3.0;
+    /// }
+    /// assert_eq!(tree[key], 6.5);
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.sm.get_mut(key).map(|node| &mut node.value)
+    }
+
+    /// Returns the key of the parent of the node of `key`, or [`None`] if it
+    /// is a root or `key` is not in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert(0);
+    /// let child = tree.append_child(root, 1).unwrap();
+    /// assert_eq!(tree.parent(child), Some(root));
+    /// assert_eq!(tree.parent(root), None);
+    /// ```
+    pub fn parent(&self, key: K) -> Option<K> {
+        self.link(key, |node| node.parent)
+    }
+
+    /// Returns the key of the first child of the node of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert(0);
+    /// let a = tree.append_child(root, 1).unwrap();
+    /// tree.append_child(root, 2);
+    /// assert_eq!(tree.first_child(root), Some(a));
+    /// ```
+    pub fn first_child(&self, key: K) -> Option<K> {
+        self.link(key, |node| node.first_child)
+    }
+
+    /// Returns the key of the last child of the node of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert(0);
+    /// tree.append_child(root, 1);
+    /// let b = tree.append_child(root, 2).unwrap();
+    /// assert_eq!(tree.last_child(root), Some(b));
+    /// ```
+    pub fn last_child(&self, key: K) -> Option<K> {
+        self.link(key, |node| node.last_child)
+    }
+
+    /// Returns the key of the sibling directly before the node of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert(0);
+    /// let a = tree.append_child(root, 1).unwrap();
+    /// let b = tree.append_child(root, 2).unwrap();
+    /// assert_eq!(tree.prev_sibling(b), Some(a));
+    /// assert_eq!(tree.prev_sibling(a), None);
+    /// ```
+    pub fn prev_sibling(&self, key: K) -> Option<K> {
+        self.link(key, |node| node.prev_sibling)
+    }
+
+    /// Returns the key of the sibling directly after the node of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert(0);
+    /// let a = tree.append_child(root, 1).unwrap();
+    /// let b = tree.append_child(root, 2).unwrap();
+    /// assert_eq!(tree.next_sibling(a), Some(b));
+    /// assert_eq!(tree.next_sibling(b), None);
+    /// ```
+    pub fn next_sibling(&self, key: K) -> Option<K> {
+        self.link(key, |node| node.next_sibling)
+    }
+
+    /// Inserts a value as a new root, returning its key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of nodes in the tree equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert("root");
+    /// assert_eq!(tree.parent(root), None);
+    /// ```
+    pub fn insert(&mut self, value: V) -> K {
+        self.sm.insert(Node {
+            value,
+            parent: K::null(),
+            first_child: K::null(),
+            last_child: K::null(),
+            prev_sibling: K::null(),
+            next_sibling: K::null(),
+        })
+    }
+
+    /// Inserts a value as the last child of the node of `parent`, returning
+    /// its key. Returns [`None`] and drops the value if `parent` is not in
+    /// the tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of nodes in the tree equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert('r');
+    /// tree.append_child(root, 'a');
+    /// tree.append_child(root, 'b');
+    /// let children: String = tree.children(root).map(|(_, v)| v).collect();
+    /// assert_eq!(children, "ab");
+    /// ```
+    pub fn append_child(&mut self, parent: K, value: V) -> Option<K> {
+        let prev = self.sm.get(parent)?.last_child;
+        let key = self.insert(value);
+        self.attach(key, parent, prev, K::null());
+        Some(key)
+    }
+
+    /// Inserts a value as the sibling directly before the node of `sibling`,
+    /// returning its key. Returns [`None`] and drops the value if `sibling`
+    /// is not in the tree or is a root, as roots have no siblings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of nodes in the tree equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert('r');
+    /// let b = tree.append_child(root, 'b').unwrap();
+    /// tree.insert_before(b, 'a');
+    /// assert_eq!(tree.insert_before(root, 'x'), None);
+    /// let children: String = tree.children(root).map(|(_, v)| v).collect();
+    /// assert_eq!(children, "ab");
+    /// ```
+    pub fn insert_before(&mut self, sibling: K, value: V) -> Option<K> {
+        let node = self.sm.get(sibling)?;
+        let (parent, prev) = (node.parent, node.prev_sibling);
+        if parent.is_null() {
+            return None;
+        }
+        let key = self.insert(value);
+        self.attach(key, parent, prev, sibling);
+        Some(key)
+    }
+
+    /// Inserts a value as the sibling directly after the node of `sibling`,
+    /// returning its key. Returns [`None`] and drops the value if `sibling`
+    /// is not in the tree or is a root, as roots have no siblings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of nodes in the tree equals 2<sup>32</sup> - 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert('r');
+    /// let a = tree.append_child(root, 'a').unwrap();
+    /// tree.insert_after(a, 'b');
+    /// let children: String = tree.children(root).map(|(_, v)| v).collect();
+    /// assert_eq!(children, "ab");
+    /// ```
+    pub fn insert_after(&mut self, sibling: K, value: V) -> Option<K> {
+        let node = self.sm.get(sibling)?;
+        let (parent, next) = (node.parent, node.next_sibling);
+        if parent.is_null() {
+            return None;
+        }
+        let key = self.insert(value);
+        self.attach(key, parent, sibling, next);
+        Some(key)
+    }
+
+    /// Detaches the node of `key` from its parent, making it a root together
+    /// with its descendants. Returns `false` if `key` is not in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert(0);
+    /// let child = tree.append_child(root, 1).unwrap();
+    /// assert!(tree.detach(child));
+    /// assert_eq!(tree.parent(child), None);
+    /// assert_eq!(tree.first_child(root), None);
+    /// ```
+    pub fn detach(&mut self, key: K) -> bool {
+        let node = match self.sm.get_mut(key) {
+            Some(node) => node,
+            None => return false,
+        };
+        let parent = core::mem::replace(&mut node.parent, K::null());
+        let prev = core::mem::replace(&mut node.prev_sibling, K::null());
+        let next = core::mem::replace(&mut node.next_sibling, K::null());
+
+        match self.sm.get_mut(prev) {
+            Some(node) => node.next_sibling = next,
+            None => {
+                if let Some(node) = self.sm.get_mut(parent) {
+                    node.first_child = next;
+                }
+            }
+        }
+        match self.sm.get_mut(next) {
+            Some(node) => node.prev_sibling = prev,
+            None => {
+                if let Some(node) = self.sm.get_mut(parent) {
+                    node.last_child = prev;
+                }
+            }
+        }
+        true
+    }
+
+    /// Moves the node of `key` with its descendants to be the last child of
+    /// the node of `new_parent`.
+    ///
+    /// Returns `false` and does nothing if either key is not in the tree, or
+    /// if `new_parent` is `key` itself or one of its descendants, as that
+    /// would create a cycle. Checking this walks the ancestors of
+    /// `new_parent`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let a = tree.insert('a');
+    /// let b = tree.insert('b');
+    /// assert!(tree.reparent(b, a));
+    /// assert_eq!(tree.parent(b), Some(a));
+    /// assert!(!tree.reparent(a, b));
+    /// ```
+    pub fn reparent(&mut self, key: K, new_parent: K) -> bool {
+        if !self.sm.contains_key(key) || !self.sm.contains_key(new_parent) {
+            return false;
+        }
+        if new_parent == key || self.ancestors(new_parent).any(|(k, _)| k == key) {
+            return false;
+        }
+
+        self.detach(key);
+        let prev = self.sm[new_parent].last_child;
+        self.attach(key, new_parent, prev, K::null());
+        true
+    }
+
+    /// Removes the node of `key` and all of its descendants, returning their
+    /// keys and values in depth-first pre-order. Returns an empty [`Vec`] if
+    /// `key` is not in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert('r');
+    /// let a = tree.append_child(root, 'a').unwrap();
+    /// let b = tree.append_child(a, 'b').unwrap();
+    /// tree.append_child(root, 'c');
+    ///
+    /// assert_eq!(tree.remove_subtree(a), [(a, 'a'), (b, 'b')]);
+    /// assert_eq!(tree.len(), 2);
+    /// assert!(tree.remove_subtree(b).is_empty());
+    /// ```
+    pub fn remove_subtree(&mut self, key: K) -> Vec<(K, V)> {
+        if !self.detach(key) {
+            return Vec::new();
+        }
+
+        let keys: Vec<K> = self.dfs(key).map(|(k, _)| k).collect();
+        keys.into_iter()
+            .map(|k| (k, self.sm.remove(k).unwrap().value))
+            .collect()
+    }
+
+    /// An iterator visiting all nodes in arbitrary order. The iterator element
+    /// type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert(1);
+    /// tree.append_child(root, 2);
+    /// tree.insert(3);
+    /// assert_eq!(tree.iter().map(|(_, v)| v).sum::<i32>(), 6);
+    /// ```
+    pub fn iter(&self) -> Iter<K, V> {
+        Iter {
+            inner: self.sm.iter(),
+        }
+    }
+
+    /// An iterator visiting all root nodes in arbitrary order. The iterator
+    /// element type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert(1);
+    /// tree.append_child(root, 2);
+    /// assert_eq!(tree.roots().collect::<Vec<_>>(), [(root, &1)]);
+    /// ```
+    pub fn roots(&self) -> impl Iterator<Item = (K, &V)> {
+        self.sm
+            .iter()
+            .filter(|(_, node)| node.parent.is_null())
+            .map(|(key, node)| (key, &node.value))
+    }
+
+    /// An iterator visiting the children of the node of `key` in order. The
+    /// iterator element type is `(K, &'a V)`. It is empty if `key` is not in
+    /// the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert(0);
+    /// tree.append_child(root, 1);
+    /// tree.append_child(root, 2);
+    /// assert!(tree.children(root).map(|(_, v)| *v).eq([1, 2]));
+    /// ```
+    pub fn children(&self, key: K) -> Children<K, V> {
+        Children {
+            sm: &self.sm,
+            cur: self.first_child(key).unwrap_or_else(K::null),
+        }
+    }
+
+    /// An iterator visiting the ancestors of the node of `key`, from its
+    /// parent up to its root. The iterator element type is `(K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert(0);
+    /// let a = tree.append_child(root, 1).unwrap();
+    /// let b = tree.append_child(a, 2).unwrap();
+    /// assert_eq!(tree.ancestors(b).collect::<Vec<_>>(), [(a, &1), (root, &0)]);
+    /// ```
+    pub fn ancestors(&self, key: K) -> Ancestors<K, V> {
+        Ancestors {
+            sm: &self.sm,
+            cur: self.parent(key).unwrap_or_else(K::null),
+        }
+    }
+
+    /// A depth-first iterator visiting the node of `key` and its descendants
+    /// in pre-order. The iterator element type is `(K, &'a V)`. It is empty
+    /// if `key` is not in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert('r');
+    /// let a = tree.append_child(root, 'a').unwrap();
+    /// tree.append_child(a, 'b');
+    /// tree.append_child(root, 'c');
+    /// assert_eq!(tree.dfs(root).map(|(_, v)| v).collect::<String>(), "rabc");
+    /// ```
+    pub fn dfs(&self, key: K) -> Dfs<K, V> {
+        let cur = if self.sm.contains_key(key) { key } else { K::null() };
+        Dfs {
+            sm: &self.sm,
+            root: key,
+            cur,
+        }
+    }
+
+    /// A breadth-first iterator visiting the node of `key` and its
+    /// descendants level by level. The iterator element type is
+    /// `(K, &'a V)`. It is empty if `key` is not in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut tree = SlotTree::new();
+    /// let root = tree.insert('r');
+    /// let a = tree.append_child(root, 'a').unwrap();
+    /// tree.append_child(a, 'b');
+    /// tree.append_child(root, 'c');
+    /// assert_eq!(tree.bfs(root).map(|(_, v)| v).collect::<String>(), "racb");
+    /// ```
+    pub fn bfs(&self, key: K) -> Bfs<K, V> {
+        let mut queue = VecDeque::new();
+        if self.sm.contains_key(key) {
+            queue.push_back(key);
+        }
+        Bfs { sm: &self.sm, queue }
+    }
+
+    fn link(&self, key: K, f: impl FnOnce(&Node<K, V>) -> K) -> Option<K> {
+        self.sm.get(key).map(f).filter(|k| !k.is_null())
+    }
+
+    // Attaches the root key under parent, in between the siblings prev and
+    // next, which must be neighbouring children of parent or null.
+    fn attach(&mut self, key: K, parent: K, prev: K, next: K) {
+        let node = &mut self.sm[key];
+        node.parent = parent;
+        node.prev_sibling = prev;
+        node.next_sibling = next;
+
+        match self.sm.get_mut(prev) {
+            Some(node) => node.next_sibling = key,
+            None => self.sm[parent].first_child = key,
+        }
+        match self.sm.get_mut(next) {
+            Some(node) => node.prev_sibling = key,
+            None => self.sm[parent].last_child = key,
+        }
+    }
+}
+
+impl<K: Key, V> Default for SlotTree<K, V> {
+    fn default() -> Self {
+        Self::with_key()
+    }
+}
+
+impl<K: Key, V> Index<K> for SlotTree<K, V> {
+    type Output = V;
+
+    fn index(&self, key: K) -> &V {
+        match self.get(key) {
+            Some(r) => r,
+            None => panic!("invalid SlotTree key used"),
+        }
+    }
+}
+
+impl<K: Key, V> IndexMut<K> for SlotTree<K, V> {
+    fn index_mut(&mut self, key: K) -> &mut V {
+        match self.get_mut(key) {
+            Some(r) => r,
+            None => panic!("invalid SlotTree key used"),
+        }
+    }
+}
+
+// Iterators.
+/// An iterator over the nodes in a [`SlotTree`], in arbitrary order.
+///
+/// This iterator is created by [`SlotTree::iter`].
+#[derive(Debug)]
+pub struct Iter<'a, K: Key + 'a, V: 'a> {
+    inner: basic::Iter<'a, K, Node<K, V>>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Iter<'a, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// An iterator over the children of a node in a [`SlotTree`].
+///
+/// This iterator is created by [`SlotTree::children`].
+#[derive(Debug)]
+pub struct Children<'a, K: Key + 'a, V: 'a> {
+    sm: &'a SlotMap<K, Node<K, V>>,
+    cur: K,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Children<'a, K, V> {
+    fn clone(&self) -> Self {
+        Children {
+            sm: self.sm,
+            cur: self.cur,
+        }
+    }
+}
+
+/// An iterator over the ancestors of a node in a [`SlotTree`].
+///
+/// This iterator is created by [`SlotTree::ancestors`].
+#[derive(Debug)]
+pub struct Ancestors<'a, K: Key + 'a, V: 'a> {
+    sm: &'a SlotMap<K, Node<K, V>>,
+    cur: K,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Ancestors<'a, K, V> {
+    fn clone(&self) -> Self {
+        Ancestors {
+            sm: self.sm,
+            cur: self.cur,
+        }
+    }
+}
+
+/// A depth-first pre-order iterator over a subtree of a [`SlotTree`].
+///
+/// This iterator is created by [`SlotTree::dfs`].
+#[derive(Debug)]
+pub struct Dfs<'a, K: Key + 'a, V: 'a> {
+    sm: &'a SlotMap<K, Node<K, V>>,
+    root: K,
+    cur: K,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Dfs<'a, K, V> {
+    fn clone(&self) -> Self {
+        Dfs {
+            sm: self.sm,
+            root: self.root,
+            cur: self.cur,
+        }
+    }
+}
+
+/// A breadth-first iterator over a subtree of a [`SlotTree`].
+///
+/// This iterator is created by [`SlotTree::bfs`].
+#[derive(Debug)]
+pub struct Bfs<'a, K: Key + 'a, V: 'a> {
+    sm: &'a SlotMap<K, Node<K, V>>,
+    queue: VecDeque<K>,
+}
+
+impl<'a, K: 'a + Key, V: 'a> Clone for Bfs<'a, K, V> {
+    fn clone(&self) -> Self {
+        Bfs {
+            sm: self.sm,
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Iter<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        self.inner.next().map(|(key, node)| (key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Children<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        let key = self.cur;
+        let node = self.sm.get(key)?;
+        self.cur = node.next_sibling;
+        Some((key, &node.value))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Ancestors<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        let key = self.cur;
+        let node = self.sm.get(key)?;
+        self.cur = node.parent;
+        Some((key, &node.value))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Dfs<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        let key = self.cur;
+        let node = self.sm.get(key)?;
+
+        // Descend into the first child, or else move on to the next sibling of
+        // the closest node that has one, without leaving the subtree.
+        self.cur = node.first_child;
+        let mut up = key;
+        while self.cur.is_null() && up != self.root {
+            let node = &self.sm[up];
+            self.cur = node.next_sibling;
+            up = node.parent;
+        }
+        Some((key, &node.value))
+    }
+}
+
+impl<'a, K: Key, V> Iterator for Bfs<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<(K, &'a V)> {
+        let key = self.queue.pop_front()?;
+        let node = &self.sm[key];
+        let mut child = node.first_child;
+        while let Some(child_node) = self.sm.get(child) {
+            self.queue.push_back(child);
+            child = child_node.next_sibling;
+        }
+        Some((key, &node.value))
+    }
+}
+
+impl<'a, K: Key, V> IntoIterator for &'a SlotTree<K, V> {
+    type Item = (K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Key, V> FusedIterator for Iter<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for Children<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for Ancestors<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for Dfs<'a, K, V> {}
+impl<'a, K: Key, V> FusedIterator for Bfs<'a, K, V> {}
+
+impl<'a, K: Key, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn check<K: Key, V>(tree: &SlotTree<K, V>) {
+        for (key, node) in tree.sm.iter() {
+            let children: Vec<_> = tree.children(key).map(|(k, _)| k).collect();
+            assert_eq!(children.first().copied(), tree.first_child(key));
+            assert_eq!(children.last().copied(), tree.last_child(key));
+            for w in children.windows(2) {
+                assert_eq!(tree.prev_sibling(w[1]), Some(w[0]));
+            }
+            for &child in &children {
+                assert_eq!(tree.parent(child), Some(key));
+            }
+            if node.parent.is_null() {
+                assert!(node.prev_sibling.is_null() && node.next_sibling.is_null());
+            }
+        }
+    }
+
+    #[test]
+    fn build_and_traverse() {
+        let mut tree = SlotTree::new();
+        let r = tree.insert('r');
+        let b = tree.append_child(r, 'b').unwrap();
+        let a = tree.insert_before(b, 'a').unwrap();
+        let d = tree.insert_after(b, 'd').unwrap();
+        let c = tree.append_child(b, 'c').unwrap();
+        let e = tree.append_child(d, 'e').unwrap();
+        let f = tree.append_child(e, 'f').unwrap();
+        check(&tree);
+
+        let dfs: String = tree.dfs(r).map(|(_, v)| *v).collect();
+        let bfs: String = tree.bfs(r).map(|(_, v)| *v).collect();
+        assert_eq!(dfs, "rabcdef");
+        assert_eq!(bfs, "rabdcef");
+        let sub: String = tree.dfs(b).map(|(_, v)| *v).collect();
+        assert_eq!(sub, "bc");
+        assert_eq!(tree.ancestors(f).map(|(k, _)| k).collect::<Vec<_>>(), [e, d, r]);
+        assert_eq!(tree.dfs(a).count(), 1);
+
+        // Reparenting keeps the order of the moved subtree.
+        assert!(tree.reparent(d, a));
+        assert!(!tree.reparent(a, f));
+        assert!(!tree.reparent(a, a));
+        check(&tree);
+        let dfs: String = tree.dfs(r).map(|(_, v)| *v).collect();
+        assert_eq!(dfs, "radefbc");
+
+        assert!(tree.detach(e));
+        check(&tree);
+        assert_eq!(tree.roots().count(), 2);
+        assert_eq!(tree.remove_subtree(e), [(e, 'e'), (f, 'f')]);
+        assert_eq!(tree.remove_subtree(r).len(), 5);
+        assert!(tree.is_empty());
+        assert!(!tree.contains_key(c));
+        assert!(!tree.detach(c));
+        assert_eq!(tree.append_child(c, 'x'), None);
+    }
+
+    #[test]
+    fn stale_keys_are_rejected() {
+        let mut tree = SlotTree::new();
+        let r = tree.insert(0);
+        let a = tree.append_child(r, 1).unwrap();
+        tree.remove_subtree(a);
+        let b = tree.append_child(r, 2).unwrap();
+        assert_ne!(a, b);
+        assert!(!tree.reparent(a, r));
+        assert_eq!(tree.dfs(a).count(), 0);
+        assert_eq!(tree.bfs(a).count(), 0);
+        assert_eq!(tree.ancestors(a).count(), 0);
+        assert_eq!(tree.children(r).count(), 1);
+        check(&tree);
+    }
+}
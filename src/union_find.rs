@@ -0,0 +1,660 @@
+//! Contains the union-find implementation, a disjoint-set forest over slot map
+//! keys.
+
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+use core::mem::replace;
+
+use crate::{SecondaryMap, SlotMap};
+use pi_null::Null;
+use pi_key_alloter::{is_older_version, DefaultKey, Key};
+
+// A node of the forest, owned by a member of its set or by nothing once that
+// member was evicted. Parent links form a tree per set, and next links form a
+// circular list of all nodes of the set. The root tracks the number of
+// members and nodes of the set, and the key representing it.
+#[derive(Debug, Clone)]
+struct Node<K> {
+    key: K,
+    parent: DefaultKey,
+    next: DefaultKey,
+    size: usize,
+    len: usize,
+    rep: K,
+}
+
+/// Union-find, a disjoint-set forest over the keys of a slot map.
+///
+/// Keys are added as singleton sets with [`make_set`], after which sets can
+/// be merged with [`union`] and queried with [`find`] and [`same_set`]. A
+/// [`SecondaryMap`] maps every key to its node in the forest, and [`find`]
+/// compresses paths while union merges by size, so these operations take
+/// nearly constant amortized time. The members of a set can be iterated in
+/// time linear in its size.
+///
+/// The union-find never sees the primary slot map, so keys removed from it
+/// are cleaned up lazily: they remain members of their set until they are
+/// evicted by [`remove`] or [`retain`], or until a newer key that reuses
+/// their slot is passed to [`make_set`]. Evicting a key keeps the other
+/// members together and takes constant amortized time, as the node of the
+/// key stays in the forest until more than half of the nodes of its set
+/// belong to evicted keys, at which point the set is rebuilt.
+///
+/// [`make_set`]: Self::make_set
+/// [`union`]: Self::union
+/// [`find`]: Self::find
+/// [`same_set`]: Self::same_set
+/// [`remove`]: Self::remove
+/// [`retain`]: Self::retain
+///
+/// # Examples
+///
+/// ```
+/// # use slotmap::*;
+/// let mut bodies = SlotMap::new();
+/// let a = bodies.insert("a");
+/// let b = bodies.insert("b");
+/// let c = bodies.insert("c");
+///
+/// let mut islands = UnionFind::new();
+/// for body in bodies.keys() {
+///     islands.make_set(body);
+/// }
+/// islands.union(a, b);
+/// assert!(islands.same_set(a, b));
+/// assert!(!islands.same_set(a, c));
+///
+/// bodies.remove(a);
+/// islands.retain(|k| bodies.contains_key(k));
+/// assert_eq!(islands.members(b).collect::<Vec<_>>(), [b]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct UnionFind<K: Key> {
+    nodes: SecondaryMap<K, DefaultKey>,
+    forest: SlotMap<DefaultKey, Node<K>>,
+}
+
+impl<K: Key> UnionFind<K> {
+    /// Constructs a new, empty [`UnionFind`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let uf: UnionFind<DefaultKey> = UnionFind::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty [`UnionFind`] with the given capacity of slots, like
+    /// [`SecondaryMap::with_capacity`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let sm: SlotMap<_, i32> = SlotMap::with_capacity(10);
+    /// let uf: UnionFind<DefaultKey> = UnionFind::with_capacity(sm.capacity());
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            nodes: SecondaryMap::with_capacity(capacity),
+            forest: SlotMap::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of keys in all sets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut uf = UnionFind::new();
+    /// uf.make_set(sm.insert(()));
+    /// uf.make_set(sm.insert(()));
+    /// assert_eq!(uf.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns if the union-find contains no keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let uf: UnionFind<DefaultKey> = UnionFind::new();
+    /// assert!(uf.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns [`true`] if `key` is in one of the sets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut uf = UnionFind::new();
+    /// assert!(!uf.contains_key(k));
+    /// uf.make_set(k);
+    /// assert!(uf.contains_key(k));
+    /// ```
+    pub fn contains_key(&self, key: K) -> bool {
+        self.nodes.contains_key(key)
+    }
+
+    /// Removes all keys and sets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let mut uf = UnionFind::new();
+    /// uf.make_set(sm.insert(()));
+    /// uf.clear();
+    /// assert!(uf.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.forest.clear();
+    }
+
+    /// Adds `key` as a new set containing only itself. Returns `false` and
+    /// does nothing if `key` is already in a set, or if a newer key from the
+    /// same slot is. An older key from the same slot is evicted first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let k = sm.insert(());
+    /// let mut uf = UnionFind::new();
+    /// assert!(uf.make_set(k));
+    /// assert!(!uf.make_set(k));
+    /// ```
+    pub fn make_set(&mut self, key: K) -> bool {
+        if key.is_null() {
+            return false;
+        }
+        if let Some(old) = self.nodes.occupant(key) {
+            if old == key || !is_older_version(old.data().version(), key.data().version()) {
+                return false;
+            }
+            self.remove(old);
+        }
+
+        let node = self.forest.insert_with_key(|node| Node {
+            key,
+            parent: node,
+            next: node,
+            size: 1,
+            len: 1,
+            rep: key,
+        });
+        self.nodes.insert(key, node);
+        true
+    }
+
+    /// Returns the representative of the set containing `key`, or [`None`] if
+    /// `key` is in no set. Two keys are in the same set exactly when they have
+    /// the same representative, as long as no sets are merged or keys evicted
+    /// in between.
+    ///
+    /// This compresses the path from `key` to the representative.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut uf = UnionFind::new();
+    /// uf.make_set(a);
+    /// uf.make_set(b);
+    /// assert_eq!(uf.find(a), Some(a));
+    /// uf.union(a, b);
+    /// assert_eq!(uf.find(a), uf.find(b));
+    /// ```
+    pub fn find(&mut self, key: K) -> Option<K> {
+        let node = *self.nodes.get(key)?;
+        let root = self.find_root(node);
+        Some(self.forest[root].rep)
+    }
+
+    /// Merges the sets containing `a` and `b`. Returns `true` if they were
+    /// different sets, and `false` if they already were the same set or
+    /// either key is in no set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut uf = UnionFind::new();
+    /// uf.make_set(a);
+    /// uf.make_set(b);
+    /// assert!(uf.union(a, b));
+    /// assert!(!uf.union(b, a));
+    /// ```
+    pub fn union(&mut self, a: K, b: K) -> bool {
+        let (a, b) = match (self.nodes.get(a), self.nodes.get(b)) {
+            (Some(&a), Some(&b)) => (self.find_root(a), self.find_root(b)),
+            _ => return false,
+        };
+        if a == b {
+            return false;
+        }
+
+        let (root, child) = if self.forest[a].len >= self.forest[b].len {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        // Join the node lists by exchanging the successors of the roots.
+        let root_next = self.forest[root].next;
+        let child_node = &mut self.forest[child];
+        child_node.parent = root;
+        let child_next = replace(&mut child_node.next, root_next);
+        let (size, len) = (child_node.size, child_node.len);
+        let root_node = &mut self.forest[root];
+        root_node.next = child_next;
+        root_node.size += size;
+        root_node.len += len;
+        true
+    }
+
+    /// Returns [`true`] if `a` and `b` are in the same set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b, c) = (sm.insert(()), sm.insert(()), sm.insert(()));
+    /// let mut uf = UnionFind::new();
+    /// for &k in &[a, b, c] {
+    ///     uf.make_set(k);
+    /// }
+    /// uf.union(a, b);
+    /// uf.union(b, c);
+    /// assert!(uf.same_set(a, c));
+    /// ```
+    pub fn same_set(&mut self, a: K, b: K) -> bool {
+        match (self.find(a), self.find(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Returns the number of keys in the set containing `key`, or [`None`] if
+    /// `key` is in no set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b) = (sm.insert(()), sm.insert(()));
+    /// let mut uf = UnionFind::new();
+    /// uf.make_set(a);
+    /// uf.make_set(b);
+    /// uf.union(a, b);
+    /// assert_eq!(uf.set_len(a), Some(2));
+    /// ```
+    pub fn set_len(&self, key: K) -> Option<usize> {
+        let root = self.root(*self.nodes.get(key)?);
+        Some(self.forest[root].size)
+    }
+
+    /// Removes `key` from its set, keeping the other members together.
+    /// Returns `false` if `key` is in no set.
+    ///
+    /// This takes constant amortized time. The node of `key` stays behind in
+    /// the forest until more than half of the nodes of the set are left
+    /// behind like this, and the set is rebuilt.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b, c) = (sm.insert(()), sm.insert(()), sm.insert(()));
+    /// let mut uf = UnionFind::new();
+    /// for &k in &[a, b, c] {
+    ///     uf.make_set(k);
+    /// }
+    /// uf.union(a, b);
+    /// uf.union(a, c);
+    /// assert!(uf.remove(a));
+    /// assert!(uf.same_set(b, c));
+    /// assert_eq!(uf.find(a), None);
+    /// ```
+    pub fn remove(&mut self, key: K) -> bool {
+        let node = match self.nodes.remove(key) {
+            Some(node) => node,
+            None => return false,
+        };
+
+        let root = self.find_root(node);
+        self.forest[node].key = K::null();
+        let root_node = &mut self.forest[root];
+        root_node.size -= 1;
+        let (size, len) = (root_node.size, root_node.len);
+
+        // Pass the representative on to the next member of the set.
+        if size > 0 && self.forest[root].rep == key {
+            let mut cur = self.forest[node].next;
+            while self.forest[cur].key.is_null() {
+                cur = self.forest[cur].next;
+            }
+            self.forest[root].rep = self.forest[cur].key;
+        }
+
+        if 2 * size < len {
+            self.rebuild(root);
+        }
+        true
+    }
+
+    /// Retains only the keys specified by the predicate, evicting the others
+    /// from their sets. This is typically used to drop keys that were removed
+    /// from the primary slot map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b, c) = (sm.insert(()), sm.insert(()), sm.insert(()));
+    /// let mut uf = UnionFind::new();
+    /// for &k in &[a, b, c] {
+    ///     uf.make_set(k);
+    /// }
+    /// uf.union(a, b);
+    /// uf.union(b, c);
+    ///
+    /// sm.remove(b);
+    /// uf.retain(|k| sm.contains_key(k));
+    /// assert_eq!(uf.len(), 2);
+    /// assert!(uf.same_set(a, c));
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K) -> bool,
+    {
+        let evicted: Vec<K> = self.nodes.keys().filter(|&k| !f(k)).collect();
+        for key in evicted {
+            self.remove(key);
+        }
+    }
+
+    /// An iterator visiting all members of the set containing `key`, starting
+    /// with `key` itself. It is empty if `key` is in no set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmap::*;
+    /// let mut sm = SlotMap::new();
+    /// let (a, b, c) = (sm.insert(()), sm.insert(()), sm.insert(()));
+    /// let mut uf = UnionFind::new();
+    /// for &k in &[a, b, c] {
+    ///     uf.make_set(k);
+    /// }
+    /// uf.union(a, c);
+    /// let mut members: Vec<_> = uf.members(c).collect();
+    /// members.sort();
+    /// assert_eq!(members, [a, c]);
+    /// ```
+    pub fn members(&self, key: K) -> Members<K> {
+        let first = self.nodes.get(key).copied().unwrap_or_else(DefaultKey::null);
+        Members {
+            forest: &self.forest,
+            first,
+            cur: first,
+        }
+    }
+
+    // Finds the root of the tree containing node without compressing paths.
+    fn root(&self, node: DefaultKey) -> DefaultKey {
+        let mut cur = node;
+        loop {
+            let parent = self.forest[cur].parent;
+            if parent == cur {
+                return cur;
+            }
+            cur = parent;
+        }
+    }
+
+    // Finds the root of the tree containing node and compresses the path to
+    // it.
+    fn find_root(&mut self, node: DefaultKey) -> DefaultKey {
+        let root = self.root(node);
+        let mut cur = node;
+        while cur != root {
+            cur = replace(&mut self.forest[cur].parent, root);
+        }
+        root
+    }
+
+    // Drops the nodes of evicted keys from the set with the given root, and
+    // points the nodes of all remaining members directly at a new root.
+    fn rebuild(&mut self, root: DefaultKey) {
+        let Node { size, rep, .. } = self.forest[root];
+        let mut new_root = DefaultKey::null();
+        let mut last = DefaultKey::null();
+        let mut cur = root;
+        loop {
+            let next = self.forest[cur].next;
+            if self.forest[cur].key.is_null() {
+                self.forest.remove(cur);
+            } else {
+                if new_root.is_null() {
+                    new_root = cur;
+                } else {
+                    self.forest[last].next = cur;
+                }
+                self.forest[cur].parent = new_root;
+                last = cur;
+            }
+
+            cur = next;
+            if cur == root {
+                break;
+            }
+        }
+
+        if !new_root.is_null() {
+            self.forest[last].next = new_root;
+            let root_node = &mut self.forest[new_root];
+            root_node.size = size;
+            root_node.len = size;
+            root_node.rep = rep;
+        }
+    }
+}
+
+impl<K: Key> Default for UnionFind<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Iterators.
+/// An iterator over the members of a set in a [`UnionFind`].
+///
+/// This iterator is created by [`UnionFind::members`].
+#[derive(Debug)]
+pub struct Members<'a, K: Key + 'a> {
+    forest: &'a SlotMap<DefaultKey, Node<K>>,
+    first: DefaultKey,
+    cur: DefaultKey,
+}
+
+impl<'a, K: 'a + Key> Clone for Members<'a, K> {
+    fn clone(&self) -> Self {
+        Members {
+            forest: self.forest,
+            first: self.first,
+            cur: self.cur,
+        }
+    }
+}
+
+impl<'a, K: Key> Iterator for Members<'a, K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        // Skip the nodes of evicted keys.
+        loop {
+            let node = self.forest.get(self.cur)?;
+            self.cur = if node.next == self.first {
+                DefaultKey::null()
+            } else {
+                node.next
+            };
+            if !node.key.is_null() {
+                return Some(node.key);
+            }
+        }
+    }
+}
+
+impl<'a, K: Key> FusedIterator for Members<'a, K> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use quickcheck::quickcheck;
+
+    fn check<K: Key>(uf: &mut UnionFind<K>) {
+        let keys: Vec<_> = uf.nodes.keys().collect();
+        for &k in &keys {
+            let members: Vec<_> = uf.members(k).collect();
+            assert_eq!(uf.set_len(k), Some(members.len()));
+            for &m in &members {
+                assert!(uf.same_set(k, m));
+            }
+            let outside = keys.iter().filter(|&&o| uf.same_set(k, o)).count();
+            assert_eq!(outside, members.len());
+        }
+    }
+
+    #[test]
+    fn slot_reuse_evicts_stale_keys() {
+        let mut sm = SlotMap::new();
+        let mut uf = UnionFind::new();
+        let keys: Vec<_> = (0..4).map(|i| sm.insert(i)).collect();
+        for &k in &keys {
+            uf.make_set(k);
+        }
+        uf.union(keys[0], keys[1]);
+        uf.union(keys[2], keys[1]);
+        uf.union(keys[3], keys[1]);
+        let root = uf.find(keys[3]).unwrap();
+
+        // Reusing the slot of the root evicts it, keeping the rest together.
+        sm.remove(root);
+        let new = sm.insert(4);
+        assert_eq!(new.data().index(), root.data().index());
+        assert!(uf.make_set(new));
+        assert!(!uf.contains_key(root));
+        assert_eq!(uf.find(root), None);
+        assert!(!uf.make_set(root));
+        check(&mut uf);
+
+        let rest: Vec<_> = keys.iter().copied().filter(|&k| k != root).collect();
+        assert_eq!(uf.set_len(rest[0]), Some(3));
+        assert!(uf.same_set(rest[0], rest[2]));
+        assert!(!uf.same_set(rest[0], new));
+    }
+
+    #[test]
+    fn eviction_drops_forest_nodes() {
+        let mut sm = SlotMap::new();
+        let mut uf = UnionFind::new();
+        let keys: Vec<_> = (0..1000).map(|_| sm.insert(())).collect();
+        for &k in &keys {
+            uf.make_set(k);
+        }
+        for &k in &keys[1..] {
+            uf.union(keys[0], k);
+        }
+
+        // Evicting the members one by one, starting with the root, never
+        // leaves more nodes behind than there are members.
+        for (i, &k) in keys[..999].iter().enumerate() {
+            assert!(uf.remove(k));
+            let left = 999 - i;
+            assert_eq!(uf.set_len(keys[999]), Some(left));
+            assert!(uf.forest.len() <= 2 * left);
+        }
+        assert_eq!(uf.find(keys[999]), Some(keys[999]));
+        assert_eq!(uf.members(keys[999]).collect::<Vec<_>>(), [keys[999]]);
+        check(&mut uf);
+
+        assert!(uf.remove(keys[999]));
+        assert!(uf.forest.is_empty());
+    }
+
+    quickcheck! {
+        fn qc_union_find_vs_labels(operations: Vec<(u8, u8, u8)>) -> bool {
+            let mut sm = SlotMap::new();
+            let mut uf = UnionFind::new();
+            let mut label = SecondaryMap::new();
+            let mut keys = Vec::new();
+            let mut next_label = 0;
+
+            for (op, a, b) in operations {
+                match op % 4 {
+                    0 => {
+                        let k = sm.insert(());
+                        keys.push(k);
+                        uf.make_set(k);
+                        label.insert(k, next_label);
+                        next_label += 1;
+                    }
+
+                    1 | 2 if !keys.is_empty() => {
+                        let (a, b) = (keys[a as usize % keys.len()], keys[b as usize % keys.len()]);
+                        let (la, lb) = (label.get(a).copied(), label.get(b).copied());
+                        let expected = matches!((la, lb), (Some(x), Some(y)) if x != y);
+                        if uf.union(a, b) != expected {
+                            return false;
+                        }
+                        if let (Some(la), Some(lb)) = (la, lb) {
+                            label.values_mut().filter(|l| **l == lb).for_each(|l| *l = la);
+                        }
+                    }
+
+                    3 if !keys.is_empty() => {
+                        let k = keys[a as usize % keys.len()];
+                        if sm.remove(k).is_some() {
+                            label.remove(k);
+                            uf.remove(k);
+                        }
+                    }
+
+                    _ => {}
+                }
+            }
+
+            check(&mut uf);
+            keys.iter().all(|&a| keys.iter().all(|&b| {
+                let expected = matches!((label.get(a), label.get(b)), (Some(x), Some(y)) if x == y);
+                uf.same_set(a, b) == expected
+            }))
+        }
+    }
+}
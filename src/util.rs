@@ -1,5 +1,6 @@
 use core::fmt::Debug;
 use core::hint::unreachable_unchecked;
+use core::ops::{Bound, RangeBounds};
 
 /// Internal stable replacement for !.
 #[derive(Debug)]
@@ -38,3 +39,155 @@ impl<T, E: Debug> UnwrapUnchecked<T> for Result<T, E> {
         }
     }
 }
+
+/// Resolves a slot index range into `(start, end)`, clamped to `1..len` so
+/// the sentinel slot at index 0 is never visited.
+pub(crate) fn slot_range<R: RangeBounds<usize>>(range: R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.saturating_add(1),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    let end = end.min(len);
+    (start.max(1).min(end), end)
+}
+
+// Tests of the set operations and merging shared by the secondary maps, which
+// are generated for the map type `$map` inside its test module.
+#[cfg(test)]
+macro_rules! set_operation_tests {
+    ($map:ident) => {
+        // Returns the keys of slots that were reused, paired as (old, new), and
+        // the keys of slots that were not.
+        fn reused_slots() -> (Vec<(DefaultKey, DefaultKey)>, Vec<DefaultKey>) {
+            let mut sm = SlotMap::new();
+            let old: Vec<_> = (0..20).map(|i| sm.insert(i)).collect();
+            for &key in old.iter().step_by(2) {
+                sm.remove(key);
+            }
+            let pairs = (0..10)
+                .map(|i| {
+                    let new = sm.insert(i);
+                    let old = *old.iter().find(|k| k.data().index() == new.data().index()).unwrap();
+                    (old, new)
+                })
+                .collect();
+            (pairs, old.into_iter().skip(1).step_by(2).collect())
+        }
+
+        fn sorted<'a>(entries: impl Iterator<Item = (DefaultKey, &'a u32)>) -> Vec<(DefaultKey, u32)> {
+            let mut entries: Vec<_> = entries.map(|(k, &v)| (k, v)).collect();
+            entries.sort();
+            entries
+        }
+
+        #[test]
+        fn set_operations() {
+            let (pairs, singles) = reused_slots();
+
+            // Per reused slot the maps hold the old and the new key in either
+            // order or only the new key; singles[0] is in both.
+            let mut a = $map::new();
+            let mut b = $map::new();
+            let mut only_a = Vec::new();
+            let mut only_b = Vec::new();
+            let mut union = Vec::new();
+            for (i, &(old, new)) in pairs.iter().enumerate() {
+                let (va, vb) = (i as u32, 100 + i as u32);
+                match i % 3 {
+                    0 => {
+                        a.insert(old, va);
+                        b.insert(new, vb);
+                        only_a.push((old, va));
+                        only_b.push((new, vb));
+                        union.push((new, vb));
+                    }
+                    1 => {
+                        a.insert(new, va);
+                        b.insert(old, vb);
+                        only_a.push((new, va));
+                        only_b.push((old, vb));
+                        union.push((new, va));
+                    }
+                    _ => {
+                        b.insert(new, vb);
+                        only_b.push((new, vb));
+                        union.push((new, vb));
+                    }
+                }
+            }
+            for (i, &key) in singles.iter().enumerate() {
+                let v = 200 + i as u32;
+                if i == 0 {
+                    a.insert(key, v);
+                    b.insert(key, v + 1);
+                    union.push((key, v + 1));
+                    continue;
+                }
+                match i % 2 {
+                    0 => a.insert(key, v),
+                    _ => b.insert(key, v),
+                };
+                match i % 2 {
+                    0 => only_a.push((key, v)),
+                    _ => only_b.push((key, v)),
+                };
+                union.push((key, v));
+            }
+            only_a.sort();
+            only_b.sort();
+            union.sort();
+
+            assert_eq!(sorted(a.difference(&b)), only_a);
+            assert_eq!(sorted(b.difference(&a)), only_b);
+            let mut either = [only_a, only_b].concat();
+            either.sort();
+            assert_eq!(sorted(a.symmetric_difference(&b)), either);
+
+            let mut both = a.clone();
+            both.intersect_with(&b);
+            assert_eq!(sorted(both.iter()), [(singles[0], 200)]);
+
+            a.union_with(b);
+            assert_eq!(sorted(a.iter()), union);
+        }
+
+        #[test]
+        fn merge_with_resolver() {
+            let (pairs, singles) = reused_slots();
+            let (old, new) = pairs[0];
+            let mut a = $map::new();
+            a.insert(old, 1);
+            a.insert(singles[0], 2);
+            let mut b = $map::new();
+            b.insert(new, 10);
+            b.insert(singles[0], 20);
+            b.insert(singles[1], 30);
+
+            // Keep our key, even when it is older, and add up the values.
+            a.merge(b, |(key, ours), (_, theirs)| (key, ours + theirs));
+            let mut expected = vec![(old, 11), (singles[0], 22), (singles[1], 30)];
+            expected.sort();
+            assert_eq!(sorted(a.iter()), expected);
+        }
+
+        #[test]
+        #[should_panic(expected = "merge resolved to a key of another slot")]
+        fn merge_rejects_key_of_another_slot() {
+            let (_, singles) = reused_slots();
+            let mut a = $map::new();
+            a.insert(singles[0], 1);
+            let mut b = $map::new();
+            b.insert(singles[0], 2);
+            a.merge(b, |ours, _| (singles[1], ours.1));
+        }
+    };
+}
+
+#[cfg(test)]
+pub(crate) use set_operation_tests;